@@ -0,0 +1,716 @@
+use crate::ollama::ChatOptions;
+use crate::templates::{ConversationTemplate, Profile, PromptTemplate, QuickPrompt};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// `--config`, in place of the default location for this run
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+/// Settings given on the command line. They apply to this run only and are
+/// never written back to the file.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    pub ollama_url: Option<String>,
+    pub default_model: Option<String>,
+    pub system_prompt: Option<String>,
+    pub theme: Option<String>,
+}
+
+impl Overrides {
+    fn apply(&self, config: &mut Config) {
+        if let Some(url) = &self.ollama_url {
+            config.ollama_url = url.clone();
+        }
+        if let Some(model) = &self.default_model {
+            config.default_model = Some(model.clone());
+        }
+        if let Some(prompt) = &self.system_prompt {
+            config.system_prompt = Some(prompt.clone());
+        }
+        if let Some(theme) = &self.theme {
+            config.theme = Some(theme.clone());
+        }
+    }
+
+    /// Put back the file's values wherever a field still holds the override,
+    /// so saving other settings doesn't persist it
+    fn revert(&self, config: &mut Config, saved: &Config) {
+        if self.ollama_url.as_ref() == Some(&config.ollama_url) {
+            config.ollama_url = saved.ollama_url.clone();
+        }
+        if self.default_model.is_some() && self.default_model == config.default_model {
+            config.default_model = saved.default_model.clone();
+        }
+        if self.system_prompt.is_some() && self.system_prompt == config.system_prompt {
+            config.system_prompt = saved.system_prompt.clone();
+        }
+        if self.theme.is_some() && self.theme == config.theme {
+            config.theme = saved.theme.clone();
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to determine config directory")]
+    NoConfigDir,
+    #[error("Failed to read config file: {0}")]
+    ReadError(#[from] std::io::Error),
+    #[error("Failed to parse config file: {0}")]
+    ParseError(#[from] toml::de::Error),
+    #[error("Failed to serialize config: {0}")]
+    SerializeError(#[from] toml::ser::Error),
+}
+
+/// How message times are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampFormat {
+    #[default]
+    Relative,
+    Absolute,
+}
+
+impl TimestampFormat {
+    pub const ALL: [TimestampFormat; 2] = [TimestampFormat::Relative, TimestampFormat::Absolute];
+}
+
+impl std::fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TimestampFormat::Relative => "5 min ago",
+            TimestampFormat::Absolute => "14:05",
+        })
+    }
+}
+
+/// Which key sends the message in the input box; the other one inserts a newline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmitKey {
+    /// Enter sends, Shift+Enter inserts a newline
+    #[default]
+    Enter,
+    /// Ctrl+Enter (Cmd+Enter on macOS) sends, Enter inserts a newline
+    CtrlEnter,
+}
+
+impl SubmitKey {
+    pub const ALL: [SubmitKey; 2] = [SubmitKey::Enter, SubmitKey::CtrlEnter];
+}
+
+impl std::fmt::Display for SubmitKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SubmitKey::Enter => "Enter",
+            SubmitKey::CtrlEnter => "Ctrl+Enter",
+        })
+    }
+}
+
+/// How much of the conversation is sent with each request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryLimit {
+    /// The last N questions and what followed them
+    Turns(usize),
+    /// As many recent messages as fit in this many (estimated) tokens
+    Tokens(u64),
+}
+
+impl HistoryLimit {
+    /// "8" or "8 turns", "2000 tokens" or "2000t"; empty means no limit
+    pub fn parse(input: &str) -> Result<Option<Self>, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+        let digits = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+        let (number, unit) = input.split_at(digits);
+        let invalid = || format!("History must be like \"8 turns\" or \"2000 tokens\", got \"{input}\"");
+        let number: u64 = number.parse().ok().filter(|n| *n > 0).ok_or_else(invalid)?;
+        match unit.trim() {
+            "" | "turn" | "turns" => Ok(Some(HistoryLimit::Turns(number as usize))),
+            "t" | "tok" | "token" | "tokens" => Ok(Some(HistoryLimit::Tokens(number))),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for HistoryLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryLimit::Turns(turns) => write!(f, "{turns} turns"),
+            HistoryLimit::Tokens(tokens) => write!(f, "{tokens} tokens"),
+        }
+    }
+}
+
+/// Defaults for one model, from a `[models."name"]` table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Options given here replace the `[options]` ones for this model
+    #[serde(flatten)]
+    pub options: ChatOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Ollama API URL
+    #[serde(default = "default_ollama_url")]
+    pub ollama_url: String,
+
+    /// Talk to a localhost Ollama directly even when a system proxy is set
+    /// (HTTP_PROXY etc.), since proxies tend to hold back streamed responses
+    #[serde(default = "default_proxy_bypass_local")]
+    pub proxy_bypass_local: bool,
+
+    /// More Ollama hosts to reach without the system proxy, e.g. a LAN GPU box
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+
+    /// Proxy for the server instead of HTTP_PROXY/HTTPS_PROXY, e.g.
+    /// `http://proxy:3128` or `socks5h://127.0.0.1:1080`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+
+    /// Proxy per server host, ahead of everything else; "direct" for none
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub host_proxies: BTreeMap<String, String>,
+
+    /// Default model to use
+    #[serde(default)]
+    pub default_model: Option<String>,
+
+    /// Default system prompt
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    /// Copy result to clipboard automatically
+    #[serde(default)]
+    pub auto_copy: bool,
+
+    /// Show GPU stats panel
+    #[serde(default = "default_show_gpu_stats")]
+    pub show_gpu_stats: bool,
+
+    /// RAM and CPU usage next to the GPU stats
+    #[serde(default = "default_show_system_stats")]
+    pub show_system_stats: bool,
+
+    /// When each message was sent and how long the answer took, under the bubbles
+    #[serde(default = "default_show_timestamps")]
+    pub show_timestamps: bool,
+
+    /// "relative" ("5 min ago") or "absolute" ("14:05")
+    #[serde(default)]
+    pub timestamp_format: TimestampFormat,
+
+    /// "enter" sends with Enter, "ctrl_enter" with Ctrl/Cmd+Enter
+    #[serde(default)]
+    pub submit_key: SubmitKey,
+
+    /// Name of a built-in iced theme, or "system" to follow the desktop's light/dark setting
+    #[serde(default)]
+    pub theme: Option<String>,
+
+    /// Bubble background for your messages, "#rrggbb"
+    #[serde(default)]
+    pub user_bubble_color: Option<String>,
+
+    /// Bubble background for responses, "#rrggbb"
+    #[serde(default)]
+    pub assistant_bubble_color: Option<String>,
+
+    /// Scale factor for the whole UI, 1.0 = 100%
+    #[serde(default = "default_ui_scale")]
+    pub ui_scale: f32,
+
+    /// Use a high-contrast color palette
+    #[serde(default)]
+    pub high_contrast: bool,
+
+    /// Disable typing indicators and other animated feedback
+    #[serde(default)]
+    pub reduced_motion: bool,
+
+    /// Keep the machine awake while a response is generating, through
+    /// `systemd-inhibit` on Linux
+    #[serde(default)]
+    pub inhibit_idle: bool,
+
+    /// Show reasoning from thinking models above their answers. It's never
+    /// part of what gets copied.
+    #[serde(default = "default_show_thinking")]
+    pub show_thinking: bool,
+
+    /// Record when each streamed chunk arrives and chart it under replies
+    #[serde(default)]
+    pub record_token_timing: bool,
+
+    /// Keep prompts recalled with Up/Down in the input box across restarts
+    #[serde(default)]
+    pub remember_prompts: bool,
+
+    /// Summarize older turns once the conversation grows past this many tokens
+    #[serde(default)]
+    pub summarize_after_tokens: Option<u64>,
+
+    /// Suggest summarizing or a fresh chat once a conversation has used this many tokens
+    #[serde(default)]
+    pub token_budget: Option<u64>,
+
+    /// Only send the most recent part of the conversation; the chat still shows all of it
+    #[serde(default)]
+    pub history_limit: Option<HistoryLimit>,
+
+    /// Stop a response once it has streamed this many tokens, keeping what it wrote
+    #[serde(default)]
+    pub response_soft_cap: Option<u64>,
+
+    /// Desktop notification when a response that took at least this many
+    /// seconds finishes while the window isn't focused
+    #[serde(default)]
+    pub notify_after_secs: Option<u64>,
+
+    /// How long Ollama keeps the model loaded after a request ("5m", "1h", -1 = forever)
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+
+    /// Global hotkey that pops up the quick-ask window, e.g. "Super+Space".
+    /// Keeps LocalLM running in the background after the main window closes.
+    #[serde(default)]
+    pub quick_ask_hotkey: Option<String>,
+
+    /// Sent as `Authorization: Bearer <key>`, for a server behind an authenticating proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+
+    /// Trust this CA certificate (PEM file) for an https `ollama_url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_certificate: Option<String>,
+
+    /// Skip TLS certificate checks, e.g. for a self-signed certificate. Insecure.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub accept_invalid_certs: bool,
+
+    /// Extra headers sent with every request to the server
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub server_headers: BTreeMap<String, String>,
+
+    /// Write prompts and answers to the debug log (`locallm.log` in the data
+    /// dir) instead of just their length
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub log_prompts: bool,
+
+    /// Seconds to wait for a connection to the server
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+
+    /// Seconds a response may go quiet before it's given up on. Streamed
+    /// answers have no overall limit, however long they take.
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+
+    /// Seconds a whole non-streamed request (titles, summaries, model info) may take
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+
+    /// Retries, with backoff, after a failed connection or a busy server
+    #[serde(default = "default_request_retries")]
+    pub request_retries: u32,
+
+    /// Start `ollama serve` when the server can't be reached
+    #[serde(default)]
+    pub manage_server: bool,
+
+    /// Ollama binary started by `manage_server`
+    #[serde(default = "default_ollama_binary")]
+    pub ollama_binary: String,
+
+    /// Environment for the started server, e.g. OLLAMA_HOST or HSA_OVERRIDE_GFX_VERSION
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub server_env: BTreeMap<String, String>,
+
+    /// Put in front of the clipboard by "Ask about clipboard", empty for none
+    #[serde(default = "default_clipboard_prompt")]
+    pub clipboard_prompt: String,
+
+    /// whisper.cpp ggml model for dictation; the 🎤 button shows when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub whisper_model: Option<String>,
+
+    /// whisper.cpp command line binary that transcribes dictation
+    #[serde(default = "default_whisper_binary")]
+    pub whisper_binary: String,
+
+    /// Word, character and token count under the input box
+    #[serde(default)]
+    pub input_stats: bool,
+
+    /// Hunspell dictionary the input is checked against: a language like
+    /// "en_US" or the path of a .dic file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spellcheck: Option<String>,
+
+    /// Address `locallm proxy` serves its OpenAI-compatible API on
+    #[serde(default)]
+    pub proxy_listen: Option<String>,
+
+    /// Regexes masked as [REDACTED] in anything sent to a model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact_patterns: Vec<String>,
+
+    /// Models asked to return their reasoning separately (`think: true`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub think_models: Vec<String>,
+
+    /// Folders indexed for "Use my documents" (text, markdown and PDF files)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub document_folders: Vec<String>,
+
+    /// Ollama model used to embed documents and questions
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+
+    /// Document chunks added to a question when "Use my documents" is on
+    #[serde(default = "default_document_top_k")]
+    pub document_top_k: usize,
+
+    /// Default generation options (max tokens, stop sequences, temperature, context size)
+    #[serde(default)]
+    pub options: ChatOptions,
+
+    /// System prompt and options per model, applied when it's picked
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub models: BTreeMap<String, ModelDefaults>,
+
+    /// Prompt templates selectable in the parameters panel
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_templates: Vec<PromptTemplate>,
+
+    /// Seeded conversations a new chat can start from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conversation_templates: Vec<ConversationTemplate>,
+
+    /// Buttons above the input box that wrap the message (or clipboard) and send it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quick_prompts: Vec<QuickPrompt>,
+
+    /// Model, system prompt, temperature and quick prompts switched together from the toolbar
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
+}
+
+fn default_ollama_url() -> String {
+    "http://127.0.0.1:11434".to_string()
+}
+
+fn default_show_gpu_stats() -> bool {
+    true
+}
+
+fn default_show_system_stats() -> bool {
+    true
+}
+
+fn default_show_timestamps() -> bool {
+    true
+}
+
+fn default_show_thinking() -> bool {
+    true
+}
+
+fn default_proxy_bypass_local() -> bool {
+    true
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_read_timeout_secs() -> u64 {
+    300
+}
+
+fn default_request_timeout_secs() -> u64 {
+    300
+}
+
+fn default_request_retries() -> u32 {
+    2
+}
+
+fn default_ollama_binary() -> String {
+    "ollama".to_string()
+}
+
+fn default_whisper_binary() -> String {
+    "whisper-cli".to_string()
+}
+
+fn default_clipboard_prompt() -> String {
+    "Explain this:".to_string()
+}
+
+fn default_embedding_model() -> String {
+    "nomic-embed-text".to_string()
+}
+
+fn default_document_top_k() -> usize {
+    4
+}
+
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
+/// Smallest and largest `ui_scale` the app accepts
+pub const UI_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            ollama_url: default_ollama_url(),
+            proxy_bypass_local: default_proxy_bypass_local(),
+            no_proxy: Vec::new(),
+            proxy: None,
+            host_proxies: BTreeMap::new(),
+            default_model: None,
+            system_prompt: None,
+            auto_copy: false,
+            show_gpu_stats: default_show_gpu_stats(),
+            show_system_stats: default_show_system_stats(),
+            show_timestamps: default_show_timestamps(),
+            timestamp_format: TimestampFormat::default(),
+            submit_key: SubmitKey::default(),
+            ui_scale: default_ui_scale(),
+            theme: None,
+            user_bubble_color: None,
+            assistant_bubble_color: None,
+            high_contrast: false,
+            reduced_motion: false,
+            inhibit_idle: false,
+            show_thinking: default_show_thinking(),
+            record_token_timing: false,
+            remember_prompts: false,
+            summarize_after_tokens: None,
+            token_budget: None,
+            history_limit: None,
+            response_soft_cap: None,
+            notify_after_secs: None,
+            keep_alive: None,
+            quick_ask_hotkey: None,
+            api_key: None,
+            ca_certificate: None,
+            accept_invalid_certs: false,
+            server_headers: BTreeMap::new(),
+            log_prompts: false,
+            connect_timeout_secs: default_connect_timeout_secs(),
+            read_timeout_secs: default_read_timeout_secs(),
+            request_timeout_secs: default_request_timeout_secs(),
+            request_retries: default_request_retries(),
+            manage_server: false,
+            ollama_binary: default_ollama_binary(),
+            server_env: BTreeMap::new(),
+            clipboard_prompt: default_clipboard_prompt(),
+            whisper_model: None,
+            input_stats: false,
+            spellcheck: None,
+            whisper_binary: default_whisper_binary(),
+            proxy_listen: None,
+            redact_patterns: Vec::new(),
+            think_models: Vec::new(),
+            document_folders: Vec::new(),
+            embedding_model: default_embedding_model(),
+            document_top_k: default_document_top_k(),
+            options: ChatOptions::default(),
+            models: BTreeMap::new(),
+            prompt_templates: Vec::new(),
+            conversation_templates: Vec::new(),
+            quick_prompts: Vec::new(),
+            profiles: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// `[models]` entry for a model, by full name or without its tag
+    /// ("llama3.2" covers "llama3.2:3b")
+    pub fn model_defaults(&self, model: &str) -> Option<&ModelDefaults> {
+        self.models.get(model).or_else(|| {
+            let (base, _) = model.split_once(':')?;
+            self.models.get(base)
+        })
+    }
+
+    /// Generation options for a model: its own where set, the global ones otherwise
+    pub fn options_for(&self, model: &str) -> ChatOptions {
+        let global = &self.options;
+        let Some(own) = self.model_defaults(model).map(|d| &d.options) else {
+            return global.clone();
+        };
+        ChatOptions {
+            num_predict: own.num_predict.or(global.num_predict),
+            stop: if own.stop.is_empty() { global.stop.clone() } else { own.stop.clone() },
+            temperature: own.temperature.or(global.temperature),
+            num_ctx: own.num_ctx.or(global.num_ctx),
+            ..global.clone()
+        }
+    }
+
+    pub fn system_prompt_for(&self, model: &str) -> Option<&str> {
+        self.model_defaults(model)
+            .and_then(|d| d.system_prompt.as_deref())
+            .or(self.system_prompt.as_deref())
+    }
+
+    /// Use another config file and override some of its settings for this run.
+    /// Call before the first `load`.
+    pub fn set_overrides(path: Option<PathBuf>, overrides: Overrides) {
+        if let Some(path) = path {
+            let _ = CONFIG_PATH.set(path);
+        }
+        let _ = OVERRIDES.set(overrides);
+    }
+
+    /// Get the config file path
+    pub fn config_path() -> Result<PathBuf, ConfigError> {
+        if let Some(path) = CONFIG_PATH.get() {
+            return Ok(path.clone());
+        }
+        ProjectDirs::from("com", "locallm", "locallm")
+            .map(|dirs| dirs.config_dir().join("config.toml"))
+            .ok_or(ConfigError::NoConfigDir)
+    }
+
+    /// Directory for app data (profiles, logs, saved state)
+    pub fn data_dir() -> Result<PathBuf, ConfigError> {
+        ProjectDirs::from("com", "locallm", "locallm")
+            .map(|dirs| dirs.data_dir().to_path_buf())
+            .ok_or(ConfigError::NoConfigDir)
+    }
+
+    /// Load config from disk, or create default if it doesn't exist.
+    /// Command-line overrides are applied on top.
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = Self::config_path()?;
+
+        let config = if path.exists() {
+            Self::read(&path)?
+        } else {
+            let config = Config::default();
+            config.save()?;
+            config
+        };
+        Ok(config.overridden())
+    }
+
+    /// With the command-line overrides applied
+    pub fn overridden(mut self) -> Self {
+        if let Some(overrides) = OVERRIDES.get() {
+            overrides.apply(&mut self);
+        }
+        self
+    }
+
+    fn read(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        let config: Self = toml::from_str(&content)?;
+        Ok(config.in_range())
+    }
+
+    /// Hand-edited values the app can't work with brought back in range
+    fn in_range(mut self) -> Self {
+        if !UI_SCALE_RANGE.contains(&self.ui_scale) {
+            let scale = if self.ui_scale.is_nan() {
+                default_ui_scale()
+            } else {
+                self.ui_scale.clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end())
+            };
+            tracing::warn!("ui_scale {} is out of range, using {scale}", self.ui_scale);
+            self.ui_scale = scale;
+        }
+        self
+    }
+
+    /// Save config to disk
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let path = Self::config_path()?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = match OVERRIDES.get() {
+            Some(overrides) => {
+                let saved = Self::read(&path).unwrap_or_default();
+                let mut config = self.clone();
+                overrides.revert(&mut config, &saved);
+                toml::to_string_pretty(&config)?
+            }
+            None => toml::to_string_pretty(self)?,
+        };
+        std::fs::write(&path, content)?;
+        Ok(())
+    }
+
+    /// Save a single setting: `change` is applied to the config on disk, so
+    /// nothing else changed in memory is written and edits made to the file
+    /// meanwhile are kept
+    pub fn save_with(change: impl FnOnce(&mut Config)) -> Result<(), ConfigError> {
+        let path = Self::config_path()?;
+        let mut saved = if path.exists() { Self::read(&path)? } else { Config::default() };
+        change(&mut saved);
+        saved.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_round_trips() {
+        let config = Config::default();
+        let text = toml::to_string_pretty(&config).unwrap();
+        let read: Config = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string_pretty(&read).unwrap(), text);
+    }
+
+    #[test]
+    fn ui_scale_is_clamped_on_load() {
+        let read = |text: &str| toml::from_str::<Config>(text).unwrap().in_range().ui_scale;
+        assert_eq!(read("ui_scale = 10.0"), 3.0);
+        assert_eq!(read("ui_scale = 0.0"), 0.5);
+        assert_eq!(read("ui_scale = nan"), 1.0);
+        assert_eq!(read("ui_scale = 1.25"), 1.25);
+    }
+
+    #[test]
+    fn overrides_are_not_saved() {
+        let overrides = Overrides {
+            ollama_url: Some(String::from("http://gpu-box:11434")),
+            theme: Some(String::from("Dark")),
+            ..Overrides::default()
+        };
+        let saved = Config::default();
+        let mut config = saved.clone();
+        overrides.apply(&mut config);
+        assert_eq!(config.ollama_url, "http://gpu-box:11434");
+
+        // Changed in settings since: kept
+        config.theme = Some(String::from("Light"));
+        overrides.revert(&mut config, &saved);
+        assert_eq!(config.ollama_url, saved.ollama_url);
+        assert_eq!(config.theme.as_deref(), Some("Light"));
+    }
+}
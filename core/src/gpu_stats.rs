@@ -0,0 +1,225 @@
+use tokio::process::Command;
+use std::process::Stdio;
+
+#[derive(Debug, Clone, Default)]
+pub struct GpuStats {
+    pub vram_used_mb: u64,
+    pub vram_total_mb: u64,
+    pub gpu_usage_percent: u8,
+    pub temperature_c: Option<u8>,
+    /// Marketing name, e.g. "AMD Radeon RX 7900 XTX", when the driver reports one
+    pub gpu_name: Option<String>,
+}
+
+impl GpuStats {
+    /// Name for the stats area, the card number when the driver has no name
+    pub fn label(&self, index: usize) -> String {
+        self.gpu_name.clone().unwrap_or_else(|| format!("GPU {index}"))
+    }
+
+    pub fn vram_usage_percent(&self) -> f32 {
+        if self.vram_total_mb == 0 {
+            return 0.0;
+        }
+        (self.vram_used_mb as f32 / self.vram_total_mb as f32) * 100.0
+    }
+}
+
+/// Samples kept for the status bar sparklines, two minutes at the focused poll rate
+pub const HISTORY_LEN: usize = 60;
+
+/// The last `HISTORY_LEN` readings of each stat, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct GpuHistory {
+    pub vram_percent: Vec<f32>,
+    pub usage_percent: Vec<f32>,
+    pub temperature_c: Vec<f32>,
+}
+
+impl GpuHistory {
+    pub fn push(&mut self, stats: &GpuStats) {
+        fn push_capped(series: &mut Vec<f32>, value: f32) {
+            if series.len() == HISTORY_LEN {
+                series.remove(0);
+            }
+            series.push(value);
+        }
+        push_capped(&mut self.vram_percent, stats.vram_usage_percent());
+        push_capped(&mut self.usage_percent, f32::from(stats.gpu_usage_percent));
+        if let Some(temperature) = stats.temperature_c {
+            push_capped(&mut self.temperature_c, f32::from(temperature));
+        }
+    }
+}
+
+/// Stats of every AMD GPU with dedicated VRAM, in card order.
+/// Ollama splits bigger models across cards, so all of them matter.
+pub async fn read_amd_gpu_stats() -> Vec<GpuStats> {
+    let mut cards = Vec::new();
+    for card_num in 0..8 {
+        if let Some(stats) = read_card_stats(card_num).await {
+            cards.push(stats);
+        }
+    }
+
+    // If sysfs didn't work, try rocm-smi
+    if cards.is_empty() {
+        cards = read_from_rocm_smi().await;
+    }
+
+    cards
+}
+
+async fn read_card_stats(card_num: u32) -> Option<GpuStats> {
+    let hwmon_base = format!("/sys/class/drm/card{}/device", card_num);
+
+    // Check if this is an AMD GPU by looking for mem_info_vram_total
+    let vram_total_path = format!("{}/mem_info_vram_total", hwmon_base);
+    let vram_total = tokio::fs::read_to_string(&vram_total_path)
+        .await
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(|b| b / 1024 / 1024)?; // Convert to MB
+
+    // If VRAM is very small (< 512MB), skip - likely not a discrete GPU
+    if vram_total < 512 {
+        return None;
+    }
+
+    let vram_used = tokio::fs::read_to_string(format!("{}/mem_info_vram_used", hwmon_base))
+        .await
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(|b| b / 1024 / 1024)
+        .unwrap_or(0);
+
+    let gpu_usage = tokio::fs::read_to_string(format!("{}/gpu_busy_percent", hwmon_base))
+        .await
+        .ok()
+        .and_then(|s| s.trim().parse::<u8>().ok())
+        .unwrap_or(0);
+
+    let temperature = find_gpu_temp(card_num).await;
+
+    let name = tokio::fs::read_to_string(format!("{}/product_name", hwmon_base))
+        .await
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    Some(GpuStats {
+        vram_used_mb: vram_used,
+        vram_total_mb: vram_total,
+        gpu_usage_percent: gpu_usage,
+        temperature_c: temperature,
+        gpu_name: name,
+    })
+}
+
+async fn find_gpu_temp(card_num: u32) -> Option<u8> {
+    // Try to find hwmon for this card
+    let hwmon_dir = format!("/sys/class/drm/card{}/device/hwmon", card_num);
+
+    if let Ok(mut entries) = tokio::fs::read_dir(&hwmon_dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let temp_path = entry.path().join("temp1_input");
+            if let Ok(temp_str) = tokio::fs::read_to_string(&temp_path).await {
+                if let Ok(temp_mc) = temp_str.trim().parse::<u32>() {
+                    return Some((temp_mc / 1000) as u8);
+                }
+            }
+        }
+    }
+
+    // Fallback: search all hwmon devices for amdgpu
+    let hwmon_dir = "/sys/class/hwmon";
+    if let Ok(mut entries) = tokio::fs::read_dir(hwmon_dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let name_path = path.join("name");
+            if let Ok(name) = tokio::fs::read_to_string(&name_path).await {
+                if name.trim() == "amdgpu" {
+                    let temp_path = path.join("temp1_input");
+                    if let Ok(temp_str) = tokio::fs::read_to_string(&temp_path).await {
+                        if let Ok(temp_mc) = temp_str.trim().parse::<u32>() {
+                            return Some((temp_mc / 1000) as u8);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+async fn read_from_rocm_smi() -> Vec<GpuStats> {
+    let Ok(output) = Command::new("rocm-smi")
+        .args(["--showmeminfo", "vram", "--showuse", "--showproductname", "--json"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+    else {
+        return Vec::new();
+    };
+
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    let json_str = String::from_utf8_lossy(&output.stdout);
+    let Ok(serde_json::Value::Object(json)) = serde_json::from_str(&json_str) else {
+        return Vec::new();
+    };
+
+    // One "cardN" object per GPU; rocm-smi output format varies, numbers may be strings
+    let number = |card: &serde_json::Value, key: &str| {
+        card.get(key).and_then(|v| v.as_u64().or_else(|| v.as_str()?.trim().parse().ok()))
+    };
+    let mut cards: Vec<(&String, &serde_json::Value)> =
+        json.iter().filter(|(key, _)| key.starts_with("card")).collect();
+    cards.sort_by_key(|(key, _)| key[4..].parse::<u32>().unwrap_or(u32::MAX));
+    cards
+        .into_iter()
+        .filter_map(|(_, card)| {
+            Some(GpuStats {
+                vram_used_mb: number(card, "VRAM Total Used Memory (B)")? / 1024 / 1024,
+                vram_total_mb: number(card, "VRAM Total Memory (B)")? / 1024 / 1024,
+                gpu_usage_percent: number(card, "GPU use (%)").unwrap_or(0).min(100) as u8,
+                temperature_c: None,
+                gpu_name: card
+                    .get("Card series")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_the_latest_readings() {
+        let mut history = GpuHistory::default();
+        for used in 0..HISTORY_LEN as u64 + 5 {
+            history.push(&GpuStats {
+                vram_used_mb: used,
+                vram_total_mb: 100,
+                ..GpuStats::default()
+            });
+        }
+        assert_eq!(history.vram_percent.len(), HISTORY_LEN);
+        assert_eq!(history.vram_percent.first(), Some(&5.0));
+        // No temperature sensor, no temperature series
+        assert!(history.temperature_c.is_empty());
+    }
+
+    #[test]
+    fn usage_without_total_is_zero() {
+        assert_eq!(GpuStats::default().vram_usage_percent(), 0.0);
+    }
+}
@@ -0,0 +1,387 @@
+//! Saved conversations, one JSON file each in the data dir's `conversations/`
+
+use crate::config::Config;
+use crate::ollama::ChatOptions;
+use crate::workspace::Attachment;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    /// Renamed by hand, so the generated title must not replace it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub renamed: bool,
+    /// Unix seconds
+    pub created_at: u64,
+    pub updated_at: u64,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Session override set with `/system`
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Session generation options ("Params"), restored with the conversation
+    #[serde(default)]
+    pub options: Option<ChatOptions>,
+    /// Notes from the scratchpad panel, and whether they're sent as context
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub scratchpad: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub scratchpad_in_context: bool,
+    /// Unsent text in the input box
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub draft: String,
+    pub messages: Vec<SavedMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedMessage {
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub excluded: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<Source>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<Alternative>,
+    /// Cut off by the token limit, can be continued
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Requested as JSON, shown formatted and checked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub json: bool,
+    /// Other versions of the conversation from this message on (regenerated
+    /// answers, edited questions), each the message and everything after it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Vec<SavedMessage>>,
+    /// Where this version sits among all of them
+    #[serde(default)]
+    pub variant_pos: usize,
+    /// Unix seconds when the message was sent or the answer finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// How long the answer took to generate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+/// Another model's answer shown under a response for comparison
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alternative {
+    pub model: String,
+    pub content: String,
+}
+
+/// A retrieved chunk, kept with the question it was added to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Source {
+    pub path: String,
+    pub text: String,
+}
+
+impl Source {
+    /// File name for the citation list
+    pub fn name(&self) -> &str {
+        self.path.rsplit(['/', '\\']).next().unwrap_or(&self.path)
+    }
+}
+
+/// A message in a saved conversation that matches a search
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub conversation: String,
+    pub title: String,
+    pub message: usize,
+    pub snippet: String,
+}
+
+fn dir() -> Result<PathBuf, String> {
+    Ok(Config::data_dir()
+        .map_err(|e| e.to_string())?
+        .join("conversations"))
+}
+
+/// Ids sort by creation time
+pub fn new_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("{millis}")
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// First line of the first user message, shortened
+pub fn title_from(messages: &[SavedMessage]) -> String {
+    const MAX_CHARS: usize = 48;
+    let first = messages
+        .iter()
+        .find(|m| m.role == "user")
+        .and_then(|m| m.content.lines().find(|line| !line.trim().is_empty()))
+        .map(str::trim)
+        .unwrap_or("New conversation");
+    if first.chars().count() > MAX_CHARS {
+        let short: String = first.chars().take(MAX_CHARS).collect();
+        format!("{}…", short.trim_end())
+    } else {
+        first.to_string()
+    }
+}
+
+/// Tidy a model-generated title: first line, no quotes, label or trailing period.
+/// None if nothing usable is left.
+pub fn clean_title(raw: &str) -> Option<String> {
+    const MAX_WORDS: usize = 8;
+    // Reasoning models may put their thoughts inline
+    let raw = raw.rsplit("</think>").next().unwrap_or(raw);
+    let line = raw.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("title:"))
+        .unwrap_or(line);
+    let line = line.trim_matches(|c: char| c.is_whitespace() || "\"'`*#.".contains(c));
+    let title = line.split_whitespace().take(MAX_WORDS).collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Every saved conversation, most recently updated first. Unreadable files are skipped.
+pub async fn load_all() -> Result<Vec<Conversation>, String> {
+    let dir = dir()?;
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {e}", dir.display())),
+    };
+
+    let mut conversations = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Conversation>(&json).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(conversation) => conversations.push(conversation),
+            Err(e) => tracing::warn!("Skipping conversation {}: {e}", path.display()),
+        }
+    }
+
+    conversations.sort_by_key(|c| std::cmp::Reverse(c.updated_at));
+    Ok(conversations)
+}
+
+/// Write atomically so a crash mid-save can't truncate the file
+pub async fn save(conversation: Conversation) -> Result<(), String> {
+    let dir = dir()?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+
+    let path = dir.join(format!("{}.json", conversation.id));
+    let tmp = temp_path(&path);
+    let json = serde_json::to_string_pretty(&conversation).map_err(|e| e.to_string())?;
+    let written = match tokio::fs::write(&tmp, json).await {
+        Ok(()) => tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| format!("Failed to write {}: {e}", path.display())),
+        Err(e) => Err(format!("Failed to write {}: {e}", tmp.display())),
+    };
+    if written.is_err() {
+        let _ = tokio::fs::remove_file(&tmp).await;
+    }
+    written
+}
+
+/// A temporary file next to `path` that no other save uses, even one of the
+/// same conversation in another instance or still in flight in this one
+fn temp_path(path: &Path) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{name}.{}-{n}.tmp", std::process::id()))
+}
+
+/// Where the draft and notes of a chat without messages yet are kept,
+/// there's no conversation file to put them in
+fn new_chat_path(name: &str) -> Result<PathBuf, String> {
+    Ok(dir()?.join(name))
+}
+
+/// Write one of the new chat's files; an empty text removes it
+async fn save_new_chat_file(name: &str, text: String) -> Result<(), String> {
+    let path = new_chat_path(name)?;
+    if text.trim().is_empty() {
+        return match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to delete {}: {e}", path.display()))
+            }
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    tokio::fs::write(&path, text)
+        .await
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Keep the new chat's draft; an empty one removes the file
+pub async fn save_draft(draft: String) -> Result<(), String> {
+    save_new_chat_file("draft.txt", draft).await
+}
+
+pub async fn load_draft() -> Option<String> {
+    tokio::fs::read_to_string(new_chat_path("draft.txt").ok()?).await.ok()
+}
+
+/// Keep the new chat's scratchpad; an empty one removes the file
+pub async fn save_scratchpad(notes: String) -> Result<(), String> {
+    save_new_chat_file("scratchpad.txt", notes).await
+}
+
+pub async fn load_scratchpad() -> Option<String> {
+    tokio::fs::read_to_string(new_chat_path("scratchpad.txt").ok()?).await.ok()
+}
+
+pub async fn delete(id: String) -> Result<(), String> {
+    let path = dir()?.join(format!("{id}.json"));
+    match tokio::fs::remove_file(&path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to delete {}: {e}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Case-insensitive search over every message of every conversation
+pub fn search(conversations: &[Conversation], query: &str, limit: usize) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    for conversation in conversations {
+        for (message, saved) in conversation.messages.iter().enumerate() {
+            if let Some(snippet) = snippet(&saved.content, query) {
+                hits.push(SearchHit {
+                    conversation: conversation.id.clone(),
+                    title: conversation.title.clone(),
+                    message,
+                    snippet,
+                });
+                if hits.len() >= limit {
+                    return hits;
+                }
+            }
+        }
+    }
+    hits
+}
+
+/// Byte ranges of every case-insensitive occurrence of `query` in `text`
+pub fn find_matches(text: &str, query: &str) -> Vec<(usize, usize)> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i + query.len() <= chars.len() {
+        let matched = chars[i..i + query.len()]
+            .iter()
+            .zip(&query)
+            .all(|((_, c), q)| c.to_lowercase().eq(std::iter::once(*q)));
+        if matched {
+            let start = chars[i].0;
+            let end = chars.get(i + query.len()).map_or(text.len(), |(offset, _)| *offset);
+            matches.push((start, end));
+            i += query.len();
+        } else {
+            i += 1;
+        }
+    }
+    matches
+}
+
+/// A line of context around the first match, None if there is none
+pub fn snippet(text: &str, query: &str) -> Option<String> {
+    const CONTEXT: usize = 40;
+    let (start, end) = *find_matches(text, query).first()?;
+
+    let before: String = {
+        let chars: Vec<char> = text[..start].chars().rev().take(CONTEXT).collect();
+        chars.into_iter().rev().collect()
+    };
+    let after: String = text[end..].chars().take(CONTEXT).collect();
+    let ellipsis = |cut: bool| if cut { "…" } else { "" };
+    let snippet = format!(
+        "{}{before}{}{after}{}",
+        ellipsis(before.len() < start),
+        &text[start..end],
+        ellipsis(end + after.len() < text.len()),
+    );
+    Some(snippet.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> SavedMessage {
+        serde_json::from_value(serde_json::json!({ "role": role, "content": content })).unwrap()
+    }
+
+    #[test]
+    fn title_is_the_first_question_line() {
+        let messages = [message("system", "Be brief"), message("user", "\n  How do lifetimes work?\nIn Rust")];
+        assert_eq!(title_from(&messages), "How do lifetimes work?");
+        assert_eq!(title_from(&[]), "New conversation");
+        let long = "word ".repeat(20);
+        assert!(title_from(&[message("user", &long)]).ends_with('…'));
+    }
+
+    #[test]
+    fn saves_write_to_their_own_temp_file() {
+        let path = Path::new("/data/conversations/1700000000000.json");
+        let (first, second) = (temp_path(path), temp_path(path));
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), path.parent());
+        // Not picked up as a conversation by `load_all`
+        assert_eq!(first.extension().and_then(|e| e.to_str()), Some("tmp"));
+    }
+
+    #[test]
+    fn matches_ignore_case() {
+        assert_eq!(find_matches("Rust and rust", "RUST"), vec![(0, 4), (9, 13)]);
+        assert_eq!(find_matches("Straße", "SSE"), Vec::new());
+        assert!(find_matches("anything", "").is_empty());
+    }
+
+    #[test]
+    fn snippet_keeps_context_around_the_match() {
+        let text = format!("{} needle {}", "a ".repeat(50), "b ".repeat(50));
+        let snippet = snippet(&text, "needle").unwrap();
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("needle"));
+        assert_eq!(super::snippet("short", "missing"), None);
+    }
+}
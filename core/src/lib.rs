@@ -0,0 +1,29 @@
+//! The parts of LocalLM that don't need a window: the streaming Ollama
+//! client, the config file, GPU readings and saved conversations. The app
+//! is built on these, and other tools can use them the same way.
+//!
+//! ```no_run
+//! use locallm_core::ollama::{ChatMessage, ChatOptions, OllamaClient};
+//!
+//! # async fn ask() -> Result<(), locallm_core::ollama::OllamaError> {
+//! let client = OllamaClient::new("http://localhost:11434");
+//! let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+//! let question = ChatMessage {
+//!     role: "user".into(),
+//!     content: "Why is the sky blue?".into(),
+//!     thinking: None,
+//! };
+//! let answer = client.chat_stream("llama3.2", vec![question], ChatOptions::default(), tx);
+//! # let _ = (answer.await?, rx.recv().await);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod config;
+pub mod gpu_stats;
+pub mod history;
+#[cfg(feature = "mock-server")]
+pub mod mock;
+pub mod ollama;
+pub mod templates;
+pub mod workspace;
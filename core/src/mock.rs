@@ -0,0 +1,257 @@
+//! A stand-in Ollama server for tests: canned replies per path, streamed
+//! line by line like the real one, and a record of every request.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// What the server answers on a path
+#[derive(Debug, Clone)]
+pub enum Reply {
+    Json { status: u16, body: Value },
+    /// NDJSON, one line per chunk, `delay` between them
+    Stream { lines: Vec<Value>, delay: Duration },
+    /// Anything else, like the HTML error page of a reverse proxy
+    Raw { status: u16, body: String },
+    /// A streamed body cut into these pieces wherever they fall, even inside
+    /// a line or a UTF-8 character, `delay` between them
+    Pieces { pieces: Vec<Vec<u8>>, delay: Duration },
+}
+
+impl Reply {
+    pub fn json(body: Value) -> Self {
+        Reply::Json { status: 200, body }
+    }
+
+    /// Ollama's error shape, `{"error": "..."}`
+    pub fn error(status: u16, message: &str) -> Self {
+        Reply::Json {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+
+    /// `/api/tags` listing these models
+    pub fn tags(models: &[&str]) -> Self {
+        let models: Vec<Value> = models
+            .iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "size": 2_019_393_189u64,
+                    "digest": format!("sha256:{name}"),
+                    "details": { "family": "llama", "parameter_size": "3.2B", "quantization_level": "Q4_K_M" },
+                })
+            })
+            .collect();
+        Reply::json(json!({ "models": models }))
+    }
+
+    /// A streamed `/api/chat` answer made of these chunks, then the done line
+    pub fn chat(model: &str, chunks: &[&str]) -> Self {
+        let mut lines: Vec<Value> = chunks
+            .iter()
+            .map(|chunk| json!({ "model": model, "message": { "role": "assistant", "content": chunk }, "done": false }))
+            .collect();
+        lines.push(json!({
+            "model": model,
+            "message": { "role": "assistant", "content": "" },
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": chunks.len(),
+            "eval_duration": 1_000_000 * chunks.len() as u64,
+        }));
+        Reply::Stream {
+            lines,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Stream lines this far apart, to cancel halfway or watch chunks arrive
+    pub fn paced(self, every: Duration) -> Self {
+        match self {
+            Reply::Stream { lines, .. } => Reply::Stream { lines, delay: every },
+            reply => reply,
+        }
+    }
+}
+
+/// A request as the server received it
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// `Null` when there was no JSON body
+    pub body: Value,
+}
+
+/// The one model the server starts out with
+pub const MODEL: &str = "mock:latest";
+
+/// The server, on a free localhost port until dropped.
+///
+/// It starts out as a server with one model, `mock:latest`, that answers
+/// every chat with "Hello from the mock"; [`MockOllama::route`] changes that.
+pub struct MockOllama {
+    url: String,
+    routes: Arc<Mutex<HashMap<String, Reply>>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+    server: JoinHandle<()>,
+}
+
+impl MockOllama {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind a localhost port");
+        let url = format!("http://{}", listener.local_addr().expect("bound address"));
+        let routes = Arc::new(Mutex::new(HashMap::from([
+            (String::from("/api/tags"), Reply::tags(&[MODEL])),
+            (String::from("/api/ps"), Reply::json(json!({ "models": [] }))),
+            (
+                String::from("/api/show"),
+                Reply::json(json!({ "parameters": "", "template": "{{ .Prompt }}", "model_info": { "llama.context_length": 8192 } })),
+            ),
+            (String::from("/api/chat"), Reply::chat(MODEL, &["Hello", " from", " the mock"])),
+        ])));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let server = tokio::spawn({
+            let routes = routes.clone();
+            let requests = requests.clone();
+            async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    tokio::spawn(serve(socket, routes.clone(), requests.clone()));
+                }
+            }
+        });
+        Self {
+            url,
+            routes,
+            requests,
+            server,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Answer `path` with `reply` from now on
+    pub fn route(&self, path: &str, reply: Reply) {
+        self.routes.lock().unwrap().insert(path.to_string(), reply);
+    }
+
+    /// Every request so far, oldest first
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The requests made to `path`
+    pub fn requests_to(&self, path: &str) -> Vec<Request> {
+        self.requests().into_iter().filter(|r| r.path == path).collect()
+    }
+}
+
+impl Drop for MockOllama {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn serve(mut socket: TcpStream, routes: Arc<Mutex<HashMap<String, Reply>>>, requests: Arc<Mutex<Vec<Request>>>) {
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+    let reply = routes.lock().unwrap().get(&request.path).cloned();
+    requests.lock().unwrap().push(request);
+
+    let reply = reply.unwrap_or_else(|| Reply::error(404, "404 page not found"));
+    // A write fails once the client hangs up, e.g. when it cancels a stream
+    let _ = respond(&mut socket, reply).await;
+}
+
+/// The request line, headers and a body of `content-length` bytes
+async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+    let header_end = loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let n = socket.read(&mut chunk).await.ok().filter(|n| *n > 0)?;
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    while buf.len() < header_end + length {
+        let n = socket.read(&mut chunk).await.ok().filter(|n| *n > 0)?;
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body = serde_json::from_slice(&buf[header_end..header_end + length]).unwrap_or(Value::Null);
+    Some(Request { method, path, body })
+}
+
+async fn respond(socket: &mut TcpStream, reply: Reply) -> std::io::Result<()> {
+    let (status, content_type, body) = match reply {
+        Reply::Json { status, body } => (status, "application/json", body.to_string()),
+        Reply::Raw { status, body } => (status, "text/html", body),
+        Reply::Stream { lines, delay } => {
+            let pieces = lines.iter().map(|line| format!("{line}\n").into_bytes()).collect();
+            return stream(socket, pieces, delay).await;
+        }
+        Reply::Pieces { pieces, delay } => return stream(socket, pieces, delay).await,
+    };
+    let head = format!(
+        "HTTP/1.1 {status} {}\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+        reason(status),
+        body.len()
+    );
+    socket.write_all(head.as_bytes()).await?;
+    socket.write_all(body.as_bytes()).await
+}
+
+/// An NDJSON body in chunked encoding, one HTTP chunk per piece
+async fn stream(socket: &mut TcpStream, pieces: Vec<Vec<u8>>, delay: Duration) -> std::io::Result<()> {
+    socket
+        .write_all(
+            b"HTTP/1.1 200 OK\r\ncontent-type: application/x-ndjson\r\n\
+              transfer-encoding: chunked\r\nconnection: close\r\n\r\n",
+        )
+        .await?;
+    for (i, piece) in pieces.iter().enumerate() {
+        if i > 0 && !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        socket.write_all(format!("{:x}\r\n", piece.len()).as_bytes()).await?;
+        socket.write_all(piece).await?;
+        socket.write_all(b"\r\n").await?;
+        socket.flush().await?;
+    }
+    socket.write_all(b"0\r\n\r\n").await
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
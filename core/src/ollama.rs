@@ -0,0 +1,1137 @@
+use crate::config::Config;
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Certificate, Client, ClientBuilder, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::pin::pin;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+#[derive(Error, Debug)]
+pub enum OllamaError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Ollama not running at {0}")]
+    NotRunning(String),
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+    #[error("Not authorized: {0}")]
+    Unauthorized(String),
+    #[error("Not enough memory for the model: {0}")]
+    OutOfMemory(String),
+    #[error("Prompt doesn't fit the context window: {0}")]
+    ContextExceeded(String),
+    #[error("Server timed out: {0}")]
+    Timeout(String),
+    #[error("Server error: {0}")]
+    ServerError(String),
+}
+
+/// What Ollama sends instead of a response when a request fails
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl OllamaError {
+    /// Short, stable name of the error kind for diagnostics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OllamaError::Http(e) if e.is_timeout() => "Timeout",
+            OllamaError::Http(e) if e.is_connect() => "ConnectionFailed",
+            OllamaError::Http(_) => "Http",
+            OllamaError::Json(_) => "Json",
+            OllamaError::NotRunning(_) => "NotRunning",
+            OllamaError::ModelNotFound(_) => "ModelNotFound",
+            OllamaError::Unauthorized(_) => "Unauthorized",
+            OllamaError::OutOfMemory(_) => "OutOfMemory",
+            OllamaError::ContextExceeded(_) => "ContextExceeded",
+            OllamaError::Timeout(_) => "Timeout",
+            OllamaError::ServerError(_) => "ServerError",
+        }
+    }
+
+    /// What the user can do about it
+    pub fn hint(&self) -> Option<&'static str> {
+        Some(match self.kind() {
+            "Timeout" => "The model may still be loading. Retry, or raise request_timeout_secs in the config.",
+            "ConnectionFailed" | "NotRunning" => "Start Ollama with `ollama serve`, or check the server URL in Settings.",
+            "ModelNotFound" => "Pull the model with `ollama pull`, or pick another one.",
+            "Unauthorized" => "Check the API key or auth headers in Settings.",
+            "OutOfMemory" => "Free memory by unloading other models, or use a smaller model, a smaller quant (e.g. q4_K_M) or a lower num_ctx.",
+            "ContextExceeded" => "Summarize the history, start a new chat, or raise num_ctx.",
+            "ServerError" => "Retry; if it keeps failing, the Ollama server log has the details.",
+            _ => return None,
+        })
+    }
+
+    /// A failed response, classified by status and the `{"error": ...}` body
+    pub fn from_response(status: StatusCode, body: &str, model: &str) -> Self {
+        let message = serde_json::from_str::<ErrorBody>(body)
+            .map(|body| body.error)
+            .unwrap_or_else(|_| body.trim().to_string());
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                OllamaError::Unauthorized(format!("HTTP {status}: {message}"))
+            }
+            StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => OllamaError::Timeout(message),
+            _ => match Self::from_message(&message, model) {
+                OllamaError::ServerError(message) => OllamaError::ServerError(format!("HTTP {status}: {message}")),
+                e => e,
+            },
+        }
+    }
+
+    /// An error Ollama reported by message alone, as in the middle of a stream
+    pub fn from_message(message: &str, model: &str) -> Self {
+        let lower = message.to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+        if has(&["unauthorized", "forbidden", "invalid api key"]) {
+            OllamaError::Unauthorized(message.to_string())
+        } else if has(&["requires more system memory", "out of memory", "unable to allocate", "insufficient memory"]) {
+            OllamaError::OutOfMemory(message.to_string())
+        } else if has(&["context length", "context window", "exceeds the context", "input length", "too long"]) {
+            OllamaError::ContextExceeded(message.to_string())
+        } else if has(&["timed out", "timeout"]) {
+            OllamaError::Timeout(message.to_string())
+        } else if has(&["file does not exist"]) || (lower.contains("model") && lower.contains("not found")) {
+            OllamaError::ModelNotFound(model.to_string())
+        } else {
+            OllamaError::ServerError(message.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub name: String,
+    pub size: u64,
+    pub digest: String,
+    #[serde(default)]
+    pub details: Option<ModelDetails>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelDetails {
+    #[serde(default)]
+    pub family: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+}
+
+/// Generation options passed through to Ollama's `options` field
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatOptions {
+    /// Maximum number of tokens to generate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i32>,
+
+    /// Stop generating when any of these strings is produced
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+
+    /// Sampling temperature, higher is more varied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    /// Context window in tokens, instead of the model's own default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+
+    /// Ask for separate reasoning output. Sent as the top-level `think`
+    /// flag rather than an option, and chosen per model, not in the config
+    #[serde(skip)]
+    pub think: bool,
+
+    /// Constrain the answer to JSON: `"json"` for any JSON, or a JSON schema
+    /// object. Sent as the top-level `format` field, per session like `think`
+    #[serde(skip)]
+    pub format: Option<serde_json::Value>,
+}
+
+impl ChatOptions {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// A piece of a streamed reply
+#[derive(Debug, Clone)]
+pub enum StreamChunk {
+    Content(String),
+    /// Reasoning from the separate `thinking` field
+    Thinking(String),
+}
+
+/// Split inline `<think>…</think>` reasoning (deepseek-r1 and others without
+/// `think` support) from the answer. While the block is still open the whole
+/// text is reasoning and the answer is empty.
+pub fn split_thinking(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content.trim_start().strip_prefix("<think>") else {
+        return (None, content);
+    };
+    match rest.split_once("</think>") {
+        Some((thinking, answer)) => (Some(thinking.trim()), answer.trim_start()),
+        None => (Some(rest.trim()), ""),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    #[serde(skip_serializing_if = "ChatOptions::is_empty")]
+    options: ChatOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    think: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
+}
+
+/// Raw completion request for `/api/generate`
+#[derive(Debug, Clone, Serialize)]
+struct GenerateRequest {
+    model: String,
+    prompt: String,
+    /// Text after the cursor, for fill-in-the-middle with models that support it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<String>,
+    /// Send the prompt as is, without the model's prompt template
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    raw: bool,
+    stream: bool,
+    #[serde(skip_serializing_if = "ChatOptions::is_empty")]
+    options: ChatOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<serde_json::Value>,
+}
+
+/// A line of a streamed `/api/generate` reply; the last one (`done`) has the stats
+#[derive(Debug, Clone, Deserialize)]
+pub struct GenerateResponse {
+    #[serde(default)]
+    pub response: String,
+    pub done: bool,
+    #[serde(default)]
+    pub done_reason: Option<String>,
+    /// Durations are in nanoseconds
+    #[serde(default)]
+    pub eval_count: Option<u64>,
+    #[serde(default)]
+    pub eval_duration: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// A model currently loaded by the server, from `/api/ps`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunningModel {
+    pub name: String,
+    /// Total size in bytes
+    #[serde(default)]
+    pub size: u64,
+    /// Bytes resident in VRAM, the rest is offloaded to system RAM
+    #[serde(default)]
+    pub size_vram: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RunningModelsResponse {
+    models: Vec<RunningModel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+    /// Reasoning returned separately when the request set `think`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatResponse {
+    /// Model that answered, as the server names it
+    #[serde(default)]
+    pub model: Option<String>,
+    pub message: Option<ChatMessage>,
+    pub done: bool,
+    /// Why generation ended: "stop", or "length" when it ran into
+    /// `num_predict` or the context window
+    #[serde(default)]
+    pub done_reason: Option<String>,
+    /// Durations are in nanoseconds
+    #[serde(default)]
+    pub total_duration: Option<u64>,
+    /// Time spent loading the model into memory
+    #[serde(default)]
+    pub load_duration: Option<u64>,
+    /// Tokens in the prompt (system prompt + history) that Ollama evaluated
+    #[serde(default)]
+    pub prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    pub prompt_eval_duration: Option<u64>,
+    /// Tokens generated for this response
+    #[serde(default)]
+    pub eval_count: Option<u64>,
+    #[serde(default)]
+    pub eval_duration: Option<u64>,
+}
+
+/// A progress line of `/api/pull`: "pulling manifest", "pulling <digest>"
+/// with byte counts while a layer downloads, "verifying sha256 digest", "success"
+#[derive(Debug, Clone, Deserialize)]
+pub struct PullProgress {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A model derived from another, as sent to `/api/create`
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateRequest {
+    pub model: String,
+    pub from: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Model metadata returned by `/api/show`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelShow {
+    /// Modelfile PARAMETER lines, e.g. "num_ctx 4096\nstop <|eot|>"
+    #[serde(default)]
+    pub parameters: Option<String>,
+    /// Prompt template (Go template syntax)
+    #[serde(default)]
+    pub template: Option<String>,
+    /// System prompt built into the model
+    #[serde(default)]
+    pub system: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub details: Option<ModelDetails>,
+    /// Architecture-prefixed keys like "llama.context_length"
+    #[serde(default)]
+    pub model_info: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ModelShow {
+    /// Context window: an explicit num_ctx parameter wins over the trained length
+    pub fn context_length(&self) -> Option<u64> {
+        let num_ctx = self.parameters.as_deref().and_then(|params| {
+            params.lines().find_map(|line| {
+                let mut parts = line.split_whitespace();
+                match (parts.next(), parts.next()) {
+                    (Some("num_ctx"), Some(value)) => value.parse().ok(),
+                    _ => None,
+                }
+            })
+        });
+
+        num_ctx.or_else(|| {
+            self.model_info
+                .iter()
+                .find(|(key, _)| key.ends_with(".context_length"))
+                .and_then(|(_, value)| value.as_u64())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ModelsResponse {
+    models: Vec<Model>,
+}
+
+/// First wait before retrying a request, doubled for each further attempt
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait for `/api/tags` when checking that the server is up
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Timeouts and retries, from the config
+#[derive(Debug, Clone, Copy)]
+pub struct NetworkPolicy {
+    pub connect_timeout: Duration,
+    /// Longest wait for the next bytes of a response. Streamed responses have
+    /// no overall limit, a long generation is fine as long as it keeps coming.
+    pub read_timeout: Duration,
+    /// Limit for a whole non-streamed request
+    pub request_timeout: Duration,
+    /// Extra attempts after a connection failure or "server busy"
+    pub retries: u32,
+}
+
+impl NetworkPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(config.connect_timeout_secs),
+            read_timeout: Duration::from_secs(config.read_timeout_secs),
+            request_timeout: Duration::from_secs(config.request_timeout_secs),
+            retries: config.request_retries,
+        }
+    }
+}
+
+impl Default for NetworkPolicy {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+/// Client for communicating with Ollama's HTTP API
+#[derive(Clone)]
+pub struct OllamaClient {
+    client: Client,
+    base_url: String,
+    keep_alive: Option<serde_json::Value>,
+    policy: NetworkPolicy,
+    /// Put prompts and answers in the debug log instead of their length
+    log_prompts: bool,
+    /// Receives every line streamed back by `/api/chat`, for the inspector
+    raw_lines: Option<mpsc::UnboundedSender<String>>,
+}
+
+/// Request fields holding what the user wrote, masked in the log unless `log_prompts` is on
+const CONTENT_FIELDS: &[&str] = &["content", "prompt", "suffix", "system", "input", "thinking"];
+
+impl OllamaClient {
+    pub fn new(base_url: &str) -> Self {
+        Self::with_proxy_bypass(base_url, true, &[])
+    }
+
+    /// Client that talks to the server directly instead of through the system
+    /// proxy when it's on localhost (if `bypass_local`) or one of `bypass_hosts`.
+    /// Proxies tend to buffer the streamed response until it's complete.
+    pub fn with_proxy_bypass(base_url: &str, bypass_local: bool, bypass_hosts: &[String]) -> Self {
+        let policy = NetworkPolicy::default();
+        let route = if bypasses_proxy(base_url, bypass_local, bypass_hosts) {
+            Route::Direct
+        } else {
+            Route::System
+        };
+        Self::build(client_builder(route, &policy), base_url, policy)
+    }
+
+    fn build(builder: ClientBuilder, base_url: &str, policy: NetworkPolicy) -> Self {
+        let client = builder.build().expect("Failed to create HTTP client");
+
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            keep_alive: None,
+            policy,
+            log_prompts: false,
+            raw_lines: None,
+        }
+    }
+
+    /// Client configured from the user's settings, including the credentials
+    /// and TLS options for servers behind a reverse proxy
+    pub fn from_config(config: &Config) -> Self {
+        let policy = NetworkPolicy::from_config(config);
+        let mut builder = client_builder(Route::from_config(config), &policy).default_headers(auth_headers(config));
+        if config.accept_invalid_certs {
+            tracing::warn!("Accepting invalid TLS certificates from {}", config.ollama_url);
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        if let Some(path) = config.ca_certificate.as_deref() {
+            match load_certificate(path) {
+                Ok(certificate) => builder = builder.add_root_certificate(certificate),
+                Err(e) => tracing::warn!("Ignoring ca_certificate: {e}"),
+            }
+        }
+        Self {
+            log_prompts: config.log_prompts,
+            ..Self::build(builder, &config.ollama_url, policy)
+        }
+        .with_keep_alive(config.keep_alive.as_deref())
+    }
+
+    /// How long the server keeps the model loaded after a request, e.g. "10m".
+    /// Plain numbers are seconds and negative values keep it loaded forever.
+    pub fn with_keep_alive(mut self, keep_alive: Option<&str>) -> Self {
+        self.keep_alive = keep_alive.map(keep_alive_value);
+        self
+    }
+
+    /// Also send the raw response lines of chat requests to `tx`
+    pub fn with_raw_lines(mut self, tx: mpsc::UnboundedSender<String>) -> Self {
+        self.raw_lines = Some(tx);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Send a request, retrying with backoff when the connection fails or the
+    /// server is busy (503). Neither means the server started on the request,
+    /// so sending it again is safe.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            self.log_request(&request);
+        }
+        let started = std::time::Instant::now();
+        let mut attempt = 0;
+        loop {
+            let Some(this_try) = request.try_clone() else {
+                return request.send().await;
+            };
+            let problem = match this_try.send().await {
+                Ok(resp) if resp.status() == StatusCode::SERVICE_UNAVAILABLE && attempt < self.policy.retries => {
+                    String::from("server busy")
+                }
+                Err(e) if e.is_connect() && attempt < self.policy.retries => e.to_string(),
+                result => {
+                    match &result {
+                        Ok(resp) => tracing::debug!("→ {} after {:?}", resp.status(), started.elapsed()),
+                        Err(e) => tracing::debug!("→ failed after {:?}: {e}", started.elapsed()),
+                    }
+                    return result;
+                }
+            };
+            let delay = RETRY_DELAY * 2u32.pow(attempt);
+            attempt += 1;
+            tracing::info!(
+                "Request to {} failed ({problem}), retry {attempt}/{} in {delay:?}",
+                self.base_url,
+                self.policy.retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Method, URL and JSON body of a request, with what the user wrote
+    /// replaced by its length unless `log_prompts` is on
+    fn log_request(&self, request: &RequestBuilder) {
+        let Some(Ok(request)) = request.try_clone().map(RequestBuilder::build) else {
+            return;
+        };
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(bytes).ok());
+        let body = match body {
+            Some(mut body) => {
+                if !self.log_prompts {
+                    mask_content(&mut body);
+                }
+                body.to_string()
+            }
+            None => String::new(),
+        };
+        tracing::debug!("{} {} {body}", request.method(), request.url());
+    }
+
+    /// Check if Ollama is running. A server that doesn't answer within
+    /// `HEALTH_CHECK_TIMEOUT` counts as down, retries aren't held up by it.
+    pub async fn health_check(&self) -> Result<bool, OllamaError> {
+        let url = format!("{}/api/tags", self.base_url);
+        match self.client.get(&url).timeout(HEALTH_CHECK_TIMEOUT).send().await {
+            Ok(resp) => Ok(resp.status().is_success()),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// List available models
+    pub async fn list_models(&self) -> Result<Vec<Model>, OllamaError> {
+        let url = format!("{}/api/tags", self.base_url);
+        let resp = self.send(self.client.get(&url).timeout(self.policy.request_timeout)).await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, "").await);
+        }
+
+        let models_resp: ModelsResponse = resp.json().await?;
+        Ok(models_resp.models)
+    }
+
+    /// List models currently loaded into memory
+    pub async fn list_running(&self) -> Result<Vec<RunningModel>, OllamaError> {
+        let url = format!("{}/api/ps", self.base_url);
+        let resp = self.send(self.client.get(&url).timeout(self.policy.request_timeout)).await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, "").await);
+        }
+
+        let running: RunningModelsResponse = resp.json().await?;
+        Ok(running.models)
+    }
+
+    /// Ask the server to evict a model from memory right away
+    pub async fn unload_model(&self, model: &str) -> Result<(), OllamaError> {
+        let url = format!("{}/api/generate", self.base_url);
+        let resp = self
+            .send(
+                self.client
+                    .post(&url)
+                    .timeout(self.policy.request_timeout)
+                    .json(&serde_json::json!({ "model": model, "keep_alive": 0 })),
+            )
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, model).await);
+        }
+        Ok(())
+    }
+
+    /// Remove an installed model; blobs other models share are kept
+    pub async fn delete_model(&self, model: &str) -> Result<(), OllamaError> {
+        let url = format!("{}/api/delete", self.base_url);
+        let resp = self
+            .send(
+                self.client
+                    .delete(&url)
+                    .timeout(self.policy.request_timeout)
+                    .json(&serde_json::json!({ "model": model })),
+            )
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, model).await);
+        }
+        Ok(())
+    }
+
+    /// Download a model from the registry, reporting progress as it goes
+    pub async fn pull_stream(
+        &self,
+        model: &str,
+        tx: mpsc::UnboundedSender<PullProgress>,
+    ) -> Result<(), OllamaError> {
+        let url = format!("{}/api/pull", self.base_url);
+        let resp = self
+            .send(self.client.post(&url).json(&serde_json::json!({ "model": model, "stream": true })))
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, model).await);
+        }
+
+        let mut lines = pin!(ndjson_lines(resp));
+        while let Some(line) = lines.next().await {
+            let line = line?;
+            let Ok(progress) = serde_json::from_str::<PullProgress>(&line) else {
+                continue;
+            };
+            // Unknown names fail here, after the request was accepted
+            if let Some(error) = progress.error {
+                return Err(if error.contains("not found") {
+                    OllamaError::ModelNotFound(model.to_string())
+                } else {
+                    OllamaError::from_message(&error, model)
+                });
+            }
+            let _ = tx.send(progress);
+        }
+        Ok(())
+    }
+
+    /// Build a model on top of another, reporting each step as it goes
+    pub async fn create_stream(
+        &self,
+        request: &CreateRequest,
+        tx: mpsc::UnboundedSender<PullProgress>,
+    ) -> Result<(), OllamaError> {
+        let url = format!("{}/api/create", self.base_url);
+        let mut body = serde_json::to_value(request)?;
+        body["stream"] = serde_json::Value::Bool(true);
+        let resp = self.send(self.client.post(&url).json(&body)).await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, &request.from).await);
+        }
+
+        let mut lines = pin!(ndjson_lines(resp));
+        while let Some(line) = lines.next().await {
+            let line = line?;
+            let Ok(progress) = serde_json::from_str::<PullProgress>(&line) else {
+                continue;
+            };
+            // A bad parameter or a missing base fails here, after the request was accepted
+            if let Some(error) = progress.error {
+                return Err(OllamaError::from_message(&error, &request.from));
+            }
+            let _ = tx.send(progress);
+        }
+        Ok(())
+    }
+
+    /// Embed each input with an embedding model, via `/api/embed`
+    pub async fn embed(&self, model: &str, input: &[String]) -> Result<Vec<Vec<f32>>, OllamaError> {
+        let url = format!("{}/api/embed", self.base_url);
+        let resp = self
+            .send(
+                self.client
+                    .post(&url)
+                    .timeout(self.policy.request_timeout)
+                    .json(&serde_json::json!({ "model": model, "input": input })),
+            )
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, model).await);
+        }
+        let embedded: EmbedResponse = resp.json().await?;
+        Ok(embedded.embeddings)
+    }
+
+    /// Fetch model metadata (parameters, context length, ...)
+    pub async fn show_model(&self, model: &str) -> Result<ModelShow, OllamaError> {
+        let url = format!("{}/api/show", self.base_url);
+        let resp = self
+            .send(
+                self.client
+                    .post(&url)
+                    .timeout(self.policy.request_timeout)
+                    .json(&serde_json::json!({ "model": model })),
+            )
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, model).await);
+        }
+
+        Ok(resp.json().await?)
+    }
+
+    fn chat_request(&self, model: &str, messages: Vec<ChatMessage>, options: ChatOptions) -> ChatRequest {
+        ChatRequest {
+            model: model.to_string(),
+            messages,
+            stream: true,
+            think: options.think,
+            format: options.format.clone(),
+            options,
+            keep_alive: self.keep_alive.clone(),
+        }
+    }
+
+    /// The body `chat_stream` posts for these arguments
+    pub fn chat_payload(&self, model: &str, messages: Vec<ChatMessage>, options: ChatOptions) -> serde_json::Value {
+        serde_json::to_value(self.chat_request(model, messages, options)).unwrap_or_default()
+    }
+
+    /// Send a chat message and stream the response
+    pub async fn chat_stream(
+        &self,
+        model: &str,
+        messages: Vec<ChatMessage>,
+        options: ChatOptions,
+        tx: mpsc::UnboundedSender<StreamChunk>,
+    ) -> Result<ChatResponse, OllamaError> {
+        let url = format!("{}/api/chat", self.base_url);
+        let request = self.chat_request(model, messages, options);
+
+        let resp = self.send(self.client.post(&url).json(&request)).await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, model).await);
+        }
+
+        let mut lines = pin!(ndjson_lines(resp));
+        let mut final_response = ChatResponse {
+            model: None,
+            message: None,
+            done: false,
+            done_reason: None,
+            total_duration: None,
+            load_duration: None,
+            prompt_eval_count: None,
+            prompt_eval_duration: None,
+            eval_count: None,
+            eval_duration: None,
+        };
+        let mut full_content = String::new();
+        let mut full_thinking = String::new();
+
+        // Each line is a JSON object
+        while let Some(line) = lines.next().await {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(raw) = &self.raw_lines {
+                let _ = raw.send(line.clone());
+            }
+            // A runner that crashes or runs out of memory mid-answer says so in place of a chunk
+            if let Ok(ErrorBody { error }) = serde_json::from_str(&line) {
+                return Err(OllamaError::from_message(&error, model));
+            }
+
+            if let Ok(response) = serde_json::from_str::<ChatResponse>(&line) {
+                if let Some(ref msg) = response.message {
+                    if let Some(ref thinking) = msg.thinking {
+                        full_thinking.push_str(thinking);
+                        let _ = tx.send(StreamChunk::Thinking(thinking.clone()));
+                    }
+                    // Thinking chunks and the final done line carry no content
+                    if !msg.content.is_empty() {
+                        full_content.push_str(&msg.content);
+                        let _ = tx.send(StreamChunk::Content(msg.content.clone()));
+                    }
+                }
+
+                if response.done {
+                    tracing::debug!(
+                        "chat done: reason={:?} prompt_tokens={:?} tokens={:?} load={:?}ns eval={:?}ns total={:?}ns",
+                        response.done_reason,
+                        response.prompt_eval_count,
+                        response.eval_count,
+                        response.load_duration,
+                        response.eval_duration,
+                        response.total_duration,
+                    );
+                    final_response = response;
+                    final_response.message = Some(ChatMessage {
+                        role: "assistant".to_string(),
+                        content: full_content.clone(),
+                        thinking: Some(full_thinking.clone()).filter(|t| !t.is_empty()),
+                    });
+                }
+            }
+        }
+
+        Ok(final_response)
+    }
+
+    /// Stream a raw completion of `prompt` (and `suffix`) from `/api/generate`.
+    /// Returns the final line, with the whole text in `response`.
+    pub async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: String,
+        suffix: Option<String>,
+        raw: bool,
+        options: ChatOptions,
+        tx: mpsc::UnboundedSender<StreamChunk>,
+    ) -> Result<GenerateResponse, OllamaError> {
+        let url = format!("{}/api/generate", self.base_url);
+
+        let request = GenerateRequest {
+            model: model.to_string(),
+            prompt,
+            suffix,
+            raw,
+            stream: true,
+            options,
+            keep_alive: self.keep_alive.clone(),
+        };
+
+        let resp = self.send(self.client.post(&url).json(&request)).await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, model).await);
+        }
+
+        let mut lines = pin!(ndjson_lines(resp));
+        let mut full_response = String::new();
+        let mut final_response = None;
+
+        while let Some(line) = lines.next().await {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(ErrorBody { error }) = serde_json::from_str(&line) {
+                return Err(OllamaError::from_message(&error, model));
+            }
+
+            if let Ok(response) = serde_json::from_str::<GenerateResponse>(&line) {
+                if !response.response.is_empty() {
+                    full_response.push_str(&response.response);
+                    let _ = tx.send(StreamChunk::Content(response.response.clone()));
+                }
+                if response.done {
+                    tracing::debug!(
+                        "generate done: reason={:?} tokens={:?} eval={:?}ns",
+                        response.done_reason,
+                        response.eval_count,
+                        response.eval_duration,
+                    );
+                    final_response = Some(response);
+                }
+            }
+        }
+
+        let mut final_response = final_response.unwrap_or(GenerateResponse {
+            response: String::new(),
+            done: false,
+            done_reason: None,
+            eval_count: None,
+            eval_duration: None,
+        });
+        final_response.response = full_response;
+        Ok(final_response)
+    }
+
+    /// Send a chat message (non-streaming)
+    pub async fn chat(
+        &self,
+        model: &str,
+        messages: Vec<ChatMessage>,
+        options: ChatOptions,
+    ) -> Result<ChatResponse, OllamaError> {
+        let url = format!("{}/api/chat", self.base_url);
+
+        let request = ChatRequest {
+            model: model.to_string(),
+            messages,
+            stream: false,
+            think: options.think,
+            format: options.format.clone(),
+            options,
+            keep_alive: self.keep_alive.clone(),
+        };
+
+        let resp = self
+            .send(self.client.post(&url).timeout(self.policy.request_timeout).json(&request))
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(failure(resp, model).await);
+        }
+
+        let response: ChatResponse = resp.json().await?;
+        Ok(response)
+    }
+}
+
+/// The error a non-2xx response stands for, from its status and body
+async fn failure(resp: Response, model: &str) -> OllamaError {
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    OllamaError::from_response(status, &body, model)
+}
+
+/// A streamed body as its NDJSON lines. A line, or a UTF-8 character in
+/// it, can be split over network chunks, so bytes are held until their
+/// line is complete; what's left when the body ends is the last line.
+fn ndjson_lines(resp: Response) -> impl futures::Stream<Item = Result<String, OllamaError>> {
+    futures::stream::unfold(
+        (resp.bytes_stream(), Vec::new(), false),
+        |(mut body, mut buf, mut ended)| async move {
+            loop {
+                if let Some(end) = buf.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = buf.drain(..=end).collect();
+                    let line = String::from_utf8_lossy(&line).trim_end().to_string();
+                    return Some((Ok(line), (body, buf, ended)));
+                }
+                if ended {
+                    if buf.is_empty() {
+                        return None;
+                    }
+                    let line = String::from_utf8_lossy(&std::mem::take(&mut buf)).into_owned();
+                    return Some((Ok(line), (body, buf, ended)));
+                }
+                match body.next().await {
+                    Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                    Some(Err(e)) => {
+                        buf.clear();
+                        return Some((Err(e.into()), (body, buf, true)));
+                    }
+                    None => ended = true,
+                }
+            }
+        },
+    )
+}
+
+/// How requests get to the server
+enum Route {
+    /// Whatever HTTP_PROXY/HTTPS_PROXY/ALL_PROXY say
+    System,
+    Direct,
+    Proxy(String),
+}
+
+impl Route {
+    /// `host_proxies` for the server's host wins, then the localhost and
+    /// `no_proxy` bypass, then `proxy`, then the environment
+    fn from_config(config: &Config) -> Self {
+        let host = reqwest::Url::parse(&config.ollama_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let for_host = config
+            .host_proxies
+            .iter()
+            .find(|(h, _)| h.eq_ignore_ascii_case(&host))
+            .map(|(_, proxy)| proxy.trim());
+        match for_host {
+            Some("direct") => Route::Direct,
+            Some(proxy) => Route::Proxy(proxy.to_string()),
+            None if bypasses_proxy(&config.ollama_url, config.proxy_bypass_local, &config.no_proxy) => Route::Direct,
+            None => match config.proxy.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+                Some(proxy) => Route::Proxy(proxy.to_string()),
+                None => Route::System,
+            },
+        }
+    }
+}
+
+fn client_builder(route: Route, policy: &NetworkPolicy) -> ClientBuilder {
+    let builder = Client::builder()
+        .connect_timeout(policy.connect_timeout)
+        .read_timeout(policy.read_timeout);
+    match route {
+        Route::System => builder,
+        Route::Direct => builder.no_proxy(),
+        Route::Proxy(url) => match reqwest::Proxy::all(&url) {
+            Ok(proxy) => builder.proxy(proxy),
+            Err(e) => {
+                if url.starts_with("socks") && !cfg!(feature = "socks") {
+                    tracing::warn!("Ignoring proxy {url}: SOCKS needs LocalLM built with the `socks` feature");
+                } else {
+                    tracing::warn!("Ignoring proxy {url}: {e}");
+                }
+                builder
+            }
+        },
+    }
+}
+
+/// `api_key` as a bearer token plus `server_headers`, sent with every request.
+/// Headers that aren't valid HTTP are skipped with a warning.
+fn auth_headers(config: &Config) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(key) = config.api_key.as_deref().filter(|k| !k.is_empty()) {
+        match HeaderValue::from_str(&format!("Bearer {key}")) {
+            Ok(mut value) => {
+                value.set_sensitive(true);
+                headers.insert(AUTHORIZATION, value);
+            }
+            Err(_) => tracing::warn!("Ignoring api_key: not valid in an HTTP header"),
+        }
+    }
+    for (name, value) in &config.server_headers {
+        match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            (Ok(name), Ok(mut value)) => {
+                value.set_sensitive(true);
+                headers.insert(name, value);
+            }
+            _ => tracing::warn!("Ignoring invalid header \"{name}\" in server_headers"),
+        }
+    }
+    headers
+}
+
+/// A PEM (or DER) CA certificate to trust besides the system ones
+fn load_certificate(path: &str) -> Result<Certificate, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+    Certificate::from_pem(&bytes)
+        .or_else(|_| Certificate::from_der(&bytes))
+        .map_err(|e| format!("{path} isn't a PEM or DER certificate: {e}"))
+}
+
+fn bypasses_proxy(base_url: &str, bypass_local: bool, bypass_hosts: &[String]) -> bool {
+    let Ok(url) = reqwest::Url::parse(base_url) else {
+        return false;
+    };
+    let host = url.host_str().unwrap_or_default();
+    let local = host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_matches(['[', ']'])
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback());
+    let listed = bypass_hosts.iter().any(|h| h.eq_ignore_ascii_case(host));
+    (local && bypass_local) || listed
+}
+
+fn mask_content(value: &mut serde_json::Value) {
+    use serde_json::Value;
+    let mask = |value: &mut Value| {
+        if let Value::String(text) = value {
+            *value = Value::from(format!("[{} chars]", text.chars().count()));
+        }
+    };
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                if !CONTENT_FIELDS.contains(&key.as_str()) {
+                    mask_content(value);
+                } else if let Value::Array(items) = value {
+                    items.iter_mut().for_each(mask);
+                } else {
+                    mask(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(mask_content),
+        _ => {}
+    }
+}
+
+/// Ollama takes keep_alive as seconds (number) or a Go duration string
+fn keep_alive_value(keep_alive: &str) -> serde_json::Value {
+    let keep_alive = keep_alive.trim();
+    match keep_alive.parse::<i64>() {
+        Ok(seconds) => serde_json::Value::from(seconds),
+        Err(_) => serde_json::Value::from(keep_alive),
+    }
+}
+
+impl Default for OllamaClient {
+    fn default() -> Self {
+        Self::new("http://127.0.0.1:11434")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_json_is_classified() {
+        let oom = r#"{"error":"model requires more system memory (12.3 GiB) than is available (8.0 GiB)"}"#;
+        let cases = [
+            (StatusCode::INTERNAL_SERVER_ERROR, oom, "OutOfMemory"),
+            (StatusCode::UNAUTHORIZED, r#"{"error":"unauthorized"}"#, "Unauthorized"),
+            (StatusCode::BAD_REQUEST, r#"{"error":"input length exceeds the context length"}"#, "ContextExceeded"),
+            (StatusCode::INTERNAL_SERVER_ERROR, r#"{"error":"timed out waiting for llama runner to start"}"#, "Timeout"),
+            (StatusCode::NOT_FOUND, r#"{"error":"model \"nope\" not found, try pulling it first"}"#, "ModelNotFound"),
+            (StatusCode::INTERNAL_SERVER_ERROR, r#"{"error":"llama runner process has terminated"}"#, "ServerError"),
+            (StatusCode::BAD_GATEWAY, "<html>bad gateway</html>", "ServerError"),
+        ];
+        for (status, body, kind) in cases {
+            assert_eq!(OllamaError::from_response(status, body, "nope").kind(), kind, "{body}");
+        }
+    }
+
+    #[test]
+    fn error_message_comes_from_the_json() {
+        let e = OllamaError::from_response(StatusCode::INTERNAL_SERVER_ERROR, r#"{"error":"boom"}"#, "m");
+        assert_eq!(e.to_string(), "Server error: HTTP 500 Internal Server Error: boom");
+        assert!(e.hint().is_some());
+    }
+
+    #[test]
+    fn inline_thinking_is_split_off() {
+        assert_eq!(split_thinking("<think>hmm</think>\n\nHi"), (Some("hmm"), "Hi"));
+        assert_eq!(split_thinking("<think>still going"), (Some("still going"), ""));
+        assert_eq!(split_thinking("Hi"), (None, "Hi"));
+    }
+}
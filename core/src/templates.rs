@@ -0,0 +1,156 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Reusable prompt with an `{input}` placeholder, defined in config.toml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+
+    /// Prompt text, `{input}` is replaced with the user's message
+    pub template: String,
+
+    /// Overrides the default system prompt while this template is used
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    /// Sample inputs with expectations, run from the template test screen
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<TemplateTest>,
+}
+
+impl PromptTemplate {
+    pub fn render(&self, input: &str) -> String {
+        fill(&self.template, input)
+    }
+}
+
+/// A button above the input box that wraps the typed message, or the
+/// clipboard when nothing is typed, and sends it right away
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickPrompt {
+    pub label: String,
+
+    /// Prompt text, `{input}` is replaced with the message or clipboard
+    pub template: String,
+}
+
+impl QuickPrompt {
+    pub fn render(&self, input: &str) -> String {
+        fill(&self.template, input)
+    }
+}
+
+/// A named setup ("Coder", "Translator DE") picked from the toolbar:
+/// model, system prompt, temperature and its own quick prompts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+
+    #[serde(default)]
+    pub model: Option<String>,
+
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    #[serde(default)]
+    pub temperature: Option<f32>,
+
+    /// Replace the global quick prompts while the profile is active
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quick_prompts: Vec<QuickPrompt>,
+}
+
+fn fill(template: &str, input: &str) -> String {
+    if template.contains("{input}") {
+        template.replace("{input}", input)
+    } else {
+        // Template without a placeholder acts as a prefix
+        format!("{template}\n\n{input}")
+    }
+}
+
+/// A conversation skeleton: system prompt plus seeded turns that a new
+/// session starts from, saved with `/template save <name>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationTemplate {
+    pub name: String,
+
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub turns: Vec<SeedTurn>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedTurn {
+    /// "user" or "assistant"
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateTest {
+    pub input: String,
+
+    #[serde(default, rename = "assert")]
+    pub assertions: Vec<Assertion>,
+}
+
+/// Expectation about a model's output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Assertion {
+    /// Output contains the value (case-insensitive)
+    Contains { value: String },
+    /// Output does not contain the value (case-insensitive)
+    NotContains { value: String },
+    /// Output matches the regular expression
+    Regex { pattern: String },
+    /// Output parses as JSON, optionally inside a ```json fence
+    JsonValid,
+}
+
+impl Assertion {
+    /// Check the output, returning a human-readable reason on failure
+    pub fn check(&self, output: &str) -> Result<(), String> {
+        match self {
+            Assertion::Contains { value } => {
+                if output.to_lowercase().contains(&value.to_lowercase()) {
+                    Ok(())
+                } else {
+                    Err(format!("expected to contain \"{value}\""))
+                }
+            }
+            Assertion::NotContains { value } => {
+                if output.to_lowercase().contains(&value.to_lowercase()) {
+                    Err(format!("expected not to contain \"{value}\""))
+                } else {
+                    Ok(())
+                }
+            }
+            Assertion::Regex { pattern } => {
+                let re = Regex::new(pattern).map_err(|e| format!("invalid regex: {e}"))?;
+                if re.is_match(output) {
+                    Ok(())
+                } else {
+                    Err(format!("expected to match /{pattern}/"))
+                }
+            }
+            Assertion::JsonValid => serde_json::from_str::<serde_json::Value>(strip_fence(output))
+                .map(|_| ())
+                .map_err(|e| format!("invalid JSON: {e}")),
+        }
+    }
+}
+
+/// Models love wrapping JSON in a markdown fence even when told not to
+fn strip_fence(output: &str) -> &str {
+    let trimmed = output.trim();
+    trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(str::trim)
+        .unwrap_or(trimmed)
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Directories that are never worth mentioning
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "__pycache__", "venv"];
+
+/// Stop scanning huge trees (home directories, monorepos) at this many files
+const MAX_FILES: usize = 20_000;
+
+/// Larger files are left out of the prompt rather than flooding the context
+const MAX_ATTACHMENT_BYTES: u64 = 200 * 1024;
+
+/// A project directory whose files can be mentioned as `#path` in the input
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    /// Paths relative to `root`, with forward slashes
+    files: Vec<String>,
+    index: HashSet<String>,
+}
+
+/// A mentioned or dropped file's contents, sent to the model along with the message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub path: String,
+    pub content: String,
+    /// Page count of a PDF
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<usize>,
+    /// Cut short to fit the context window
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl Attachment {
+    /// Chip label, e.g. "report.pdf (12 pages)"
+    pub fn label(&self) -> String {
+        let name = &self.path;
+        match (self.pages, self.truncated) {
+            (Some(1), false) => format!("{name} (1 page)"),
+            (Some(pages), false) => format!("{name} ({pages} pages)"),
+            (Some(pages), true) => format!("{name} ({pages} pages, truncated)"),
+            (None, true) => format!("{name} (truncated)"),
+            (None, false) => name.to_string(),
+        }
+    }
+}
+
+impl Workspace {
+    /// Walk `root` for files, skipping hidden and build directories. Blocking.
+    pub fn scan(root: &Path) -> Result<Workspace, String> {
+        let root = root
+            .canonicalize()
+            .map_err(|e| format!("Can't open workspace {}: {e}", root.display()))?;
+        if !root.is_dir() {
+            return Err(format!("{} is not a directory", root.display()));
+        }
+
+        let mut files = Vec::new();
+        let walker = WalkDir::new(&root).sort_by_file_name().into_iter().filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !(name.starts_with('.') || entry.file_type().is_dir() && SKIPPED_DIRS.contains(&name.as_ref()))
+        });
+        for entry in walker.filter_map(Result::ok) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            if let Ok(relative) = entry.path().strip_prefix(&root) {
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+            if files.len() >= MAX_FILES {
+                tracing::warn!("Workspace {} has over {MAX_FILES} files, ignoring the rest", root.display());
+                break;
+            }
+        }
+
+        let index = files.iter().cloned().collect();
+        Ok(Workspace { root, files, index })
+    }
+
+    pub async fn scan_async(root: PathBuf) -> Result<Workspace, String> {
+        tokio::task::spawn_blocking(move || Workspace::scan(&root))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    pub fn name(&self) -> String {
+        self.root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.root.display().to_string())
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Files for the autocomplete popup: file names starting with the query
+    /// first, then paths containing it
+    pub fn matches(&self, query: &str, limit: usize) -> Vec<&str> {
+        let query = query.to_lowercase();
+        let file_name = |path: &str| path.rsplit('/').next().unwrap_or(path).to_lowercase();
+
+        let mut prefix: Vec<&str> = Vec::new();
+        let mut contains: Vec<&str> = Vec::new();
+        for path in &self.files {
+            if file_name(path).starts_with(&query) {
+                prefix.push(path);
+            } else if path.to_lowercase().contains(&query) {
+                contains.push(path);
+            }
+            if prefix.len() >= limit {
+                break;
+            }
+        }
+        prefix.into_iter().chain(contains).take(limit).collect()
+    }
+
+    /// Read every file mentioned as `#path` in `text`. Unknown paths are
+    /// left alone, they may just be a hashtag or a markdown heading.
+    pub fn attachments(&self, text: &str) -> Result<Vec<Attachment>, String> {
+        let mut attachments: Vec<Attachment> = Vec::new();
+        for word in text.split_whitespace() {
+            let Some(path) = word.strip_prefix('#') else {
+                continue;
+            };
+            let path = path.trim_end_matches([',', '.', ':', ';', '?', '!', ')']);
+            if !self.index.contains(path) || attachments.iter().any(|a| a.path == path) {
+                continue;
+            }
+
+            let full = self.root.join(path);
+            let size = std::fs::metadata(&full).map(|m| m.len()).unwrap_or(0);
+            if size > MAX_ATTACHMENT_BYTES {
+                return Err(format!(
+                    "#{path} is {} KB, over the {} KB attachment limit",
+                    size / 1024,
+                    MAX_ATTACHMENT_BYTES / 1024
+                ));
+            }
+            let content = std::fs::read_to_string(&full)
+                .map_err(|e| format!("Can't attach #{path}: {e}"))?;
+            attachments.push(Attachment {
+                path: path.to_string(),
+                content,
+                pages: None,
+                truncated: false,
+            });
+        }
+        Ok(attachments)
+    }
+}
+
+/// The `#partial/path` being typed at the end of the input, if any
+pub fn mention_query(input: &str) -> Option<&str> {
+    // Editor text always ends in a newline, typing a space ends the mention
+    let input = input.strip_suffix('\n').unwrap_or(input);
+    if input.ends_with(char::is_whitespace) {
+        return None;
+    }
+    input.split_whitespace().next_back()?.strip_prefix('#')
+}
+
+/// Message text as the model sees it: the typed text followed by the mentioned files
+pub fn with_attachments(text: &str, attachments: &[Attachment]) -> String {
+    let mut prompt = text.to_string();
+    for attachment in attachments {
+        let note = if attachment.truncated { " (beginning only, it's too long to include whole)" } else { "" };
+        prompt.push_str(&format!(
+            "\n\nFile `{}`{note}:\n```\n{}\n```",
+            attachment.path,
+            attachment.content.trim_end()
+        ));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mention_at_the_end_of_editor_text() {
+        assert_eq!(mention_query("look at #src/ma\n"), Some("src/ma"));
+        assert_eq!(mention_query("look at #src/ma"), Some("src/ma"));
+        assert_eq!(mention_query("look at #src/main.rs \n"), None);
+        assert_eq!(mention_query("no mention\n"), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! The Ollama client against the mock server, no Ollama needed
+
+use locallm_core::mock::{MockOllama, Reply, MODEL};
+use locallm_core::ollama::{ChatMessage, ChatOptions, OllamaClient, StreamChunk};
+use std::time::Duration;
+
+fn question() -> Vec<ChatMessage> {
+    vec![ChatMessage {
+        role: String::from("user"),
+        content: String::from("Hi"),
+        thinking: None,
+    }]
+}
+
+async fn chat(server: &MockOllama) -> (Result<String, String>, Vec<String>) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let result = OllamaClient::new(server.url())
+        .chat_stream(MODEL, question(), ChatOptions::default(), tx)
+        .await
+        .map(|response| response.message.map(|m| m.content).unwrap_or_default())
+        .map_err(|e| e.kind().to_string());
+    let mut chunks = Vec::new();
+    while let Some(StreamChunk::Content(chunk)) = rx.recv().await {
+        chunks.push(chunk);
+    }
+    (result, chunks)
+}
+
+#[tokio::test]
+async fn lists_models() {
+    let server = MockOllama::start().await;
+    server.route("/api/tags", Reply::tags(&["llama3.2:3b", "qwen3:8b"]));
+
+    let models = OllamaClient::new(server.url()).list_models().await.unwrap();
+    let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["llama3.2:3b", "qwen3:8b"]);
+    assert_eq!(models[0].details.as_ref().unwrap().quantization_level.as_deref(), Some("Q4_K_M"));
+}
+
+#[tokio::test]
+async fn streams_a_chat_answer() {
+    let server = MockOllama::start().await;
+    server.route("/api/chat", Reply::chat(MODEL, &["Hel", "lo"]).paced(Duration::from_millis(10)));
+
+    let (answer, chunks) = chat(&server).await;
+    assert_eq!(answer.as_deref(), Ok("Hello"));
+    assert_eq!(chunks, ["Hel", "lo"]);
+
+    let sent = server.requests_to("/api/chat");
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].body["model"], MODEL);
+    assert_eq!(sent[0].body["stream"], true);
+    assert_eq!(sent[0].body["messages"][0]["content"], "Hi");
+}
+
+#[tokio::test]
+async fn inline_thinking_stays_in_the_content() {
+    let server = MockOllama::start().await;
+    server.route("/api/chat", Reply::chat(MODEL, &["<think>", "hmm", "</think>", "Yes"]));
+
+    let (answer, _) = chat(&server).await;
+    // Splitting it off is up to the caller, see `ollama::split_thinking`
+    assert_eq!(answer.as_deref(), Ok("<think>hmm</think>Yes"));
+}
+
+#[tokio::test]
+async fn classifies_error_responses() {
+    let cases = [
+        (Reply::error(500, "model requires more system memory (48.0 GiB) than is available (31.2 GiB)"), "OutOfMemory"),
+        (Reply::error(404, "model \"mock:latest\" not found, try pulling it first"), "ModelNotFound"),
+        (Reply::error(401, "unauthorized"), "Unauthorized"),
+        (Reply::error(400, "input length exceeds the context length"), "ContextExceeded"),
+        (Reply::Raw { status: 502, body: String::from("<html>Bad Gateway</html>") }, "ServerError"),
+    ];
+    let server = MockOllama::start().await;
+    for (reply, kind) in cases {
+        server.route("/api/chat", reply);
+        let (result, chunks) = chat(&server).await;
+        assert_eq!(result, Err(kind.to_string()));
+        assert!(chunks.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn error_in_the_middle_of_a_stream() {
+    let server = MockOllama::start().await;
+    server.route(
+        "/api/chat",
+        Reply::Stream {
+            lines: vec![
+                serde_json::json!({ "model": MODEL, "message": { "role": "assistant", "content": "Par" }, "done": false }),
+                serde_json::json!({ "error": "llama runner process has terminated: signal: killed" }),
+            ],
+            delay: Duration::ZERO,
+        },
+    );
+
+    let (result, chunks) = chat(&server).await;
+    assert_eq!(result, Err(String::from("ServerError")));
+    assert_eq!(chunks, ["Par"]);
+}
+
+#[tokio::test]
+async fn line_split_across_chunks() {
+    let server = MockOllama::start().await;
+    let lines = [
+        serde_json::json!({ "model": MODEL, "message": { "role": "assistant", "content": "Grüße" }, "done": false }),
+        serde_json::json!({ "model": MODEL, "message": { "role": "assistant", "content": "" }, "done": true }),
+    ];
+    let body: Vec<u8> = lines.iter().flat_map(|line| format!("{line}\n").into_bytes()).collect();
+    // Halfway through the "ü", and the last line without its newline
+    let split = body.iter().position(|&b| b == 0xC3).unwrap() + 1;
+    let pieces = vec![body[..split].to_vec(), body[split..body.len() - 1].to_vec()];
+    server.route("/api/chat", Reply::Pieces { pieces, delay: Duration::from_millis(20) });
+
+    let (answer, chunks) = chat(&server).await;
+    assert_eq!(answer.as_deref(), Ok("Grüße"));
+    assert_eq!(chunks, ["Grüße"]);
+}
+
+#[tokio::test]
+async fn unreachable_server() {
+    let url = {
+        let server = MockOllama::start().await;
+        server.url().to_string()
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let client = OllamaClient::new(&url);
+    assert!(!client.health_check().await.unwrap());
+}
+
+#[tokio::test]
+async fn unresponsive_server() {
+    // Accepts connections but never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = OllamaClient::new(&format!("http://{}", listener.local_addr().unwrap()));
+    let check = tokio::time::timeout(Duration::from_secs(5), client.health_check()).await;
+    assert!(!check.expect("the health check gave up on its own").unwrap());
+}
+
+#[tokio::test]
+async fn listing_errors_are_classified() {
+    let server = MockOllama::start().await;
+    let client = OllamaClient::new(server.url());
+    server.route("/api/tags", Reply::error(401, "unauthorized"));
+    assert_eq!(client.list_models().await.unwrap_err().kind(), "Unauthorized");
+
+    server.route("/api/ps", Reply::Raw { status: 502, body: String::from("<html>Bad Gateway</html>") });
+    assert_eq!(client.list_running().await.unwrap_err().kind(), "ServerError");
+
+    server.route("/api/chat", Reply::error(404, "model \"mock:latest\" not found, try pulling it first"));
+    let error = client.chat(MODEL, question(), ChatOptions::default()).await.unwrap_err();
+    assert_eq!(error.kind(), "ModelNotFound");
+}
@@ -53,6 +53,8 @@
           xorg.libXrandr
           xorg.libXi
           openssl
+          # Microphone capture, for `--features dictation`
+          alsa-lib
         ];
       in
       {
@@ -0,0 +1,189 @@
+//! `locallm ask`: one question from the command line or stdin, with the answer
+//! streamed to stdout. Uses the GUI's config for the server, default model,
+//! system prompt, redaction and options.
+
+use crate::config::Config;
+use crate::ollama::{self, ChatMessage, OllamaClient, StreamChunk};
+use crate::redact::Redactor;
+use std::io::{Read, Write};
+
+#[derive(Debug, clap::Args)]
+pub struct AskArgs {
+    /// The question; with --stdin it's put in front of the piped text
+    pub question: Option<String>,
+    /// Read the question (or the text it's about) from stdin
+    #[arg(long)]
+    pub stdin: bool,
+    /// Model to ask instead of `default_model`
+    #[arg(long, short)]
+    pub model: Option<String>,
+    /// System prompt instead of the configured one
+    #[arg(long, short)]
+    pub system: Option<String>,
+    /// Ask for a JSON answer and fail if it isn't valid JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Ask and print the answer, reasoning left out
+pub fn run(config: Config, args: AskArgs) -> Result<(), String> {
+    let mut question = args.question.unwrap_or_default();
+    if args.stdin {
+        let mut piped = String::new();
+        std::io::stdin()
+            .read_to_string(&mut piped)
+            .map_err(|e| format!("Failed to read stdin: {e}"))?;
+        if !question.is_empty() {
+            question.push_str("\n\n");
+        }
+        question.push_str(&piped);
+    }
+    if question.trim().is_empty() {
+        return Err(String::from("Nothing to ask: give a question or pipe one in with --stdin"));
+    }
+
+    let model = args
+        .model
+        .or_else(|| config.default_model.clone())
+        .ok_or("No --model given and no default_model configured")?;
+
+    let redactor = Redactor::new(&config.redact_patterns);
+    let mut messages = Vec::new();
+    if let Some(system) = args.system.or_else(|| config.system_prompt_for(&model).map(String::from)) {
+        messages.push(ChatMessage {
+            role: "system".to_string(),
+            content: system,
+            thinking: None,
+        });
+    }
+    messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: redactor.apply(question.trim()),
+        thinking: None,
+    });
+
+    let mut options = config.options_for(&model);
+    if args.json {
+        options.format = Some(serde_json::Value::from("json"));
+    }
+
+    let client = OllamaClient::from_config(&config);
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    let answer = runtime.block_on(async move {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let request = client.chat_stream(&model, messages, options, tx);
+        let print = async {
+            let mut answer = AnswerStream::default();
+            let mut stdout = std::io::stdout();
+            let mut print = |text: &str| {
+                if !text.is_empty() {
+                    let _ = stdout.write_all(text.as_bytes());
+                    let _ = stdout.flush();
+                }
+            };
+            while let Some(chunk) = rx.recv().await {
+                if let StreamChunk::Content(content) = chunk {
+                    print(answer.push(&content));
+                }
+            }
+            print(answer.finish());
+            answer.streamed
+        };
+        let (result, streamed) = futures::join!(request, print);
+        result
+            .map(|_| ollama::split_thinking(&streamed).1.to_string())
+            .map_err(|e| e.to_string())
+    })?;
+
+    if !answer.ends_with('\n') {
+        println!();
+    }
+    if args.json {
+        serde_json::from_str::<serde_json::Value>(&answer).map_err(|e| format!("Answer isn't valid JSON: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Streamed content handed out for printing as it comes, with an inline
+/// `<think>` block left out. A start that could still turn into `<think>` is
+/// held back until it's clear, the block itself until it's closed.
+#[derive(Debug, Default)]
+struct AnswerStream {
+    streamed: String,
+    /// How much of `streamed` has been handed out or skipped
+    printed: usize,
+}
+
+impl AnswerStream {
+    const OPEN: &str = "<think>";
+    const CLOSE: &str = "</think>";
+
+    /// Add `content` and return the answer text that's now safe to print
+    fn push(&mut self, content: &str) -> &str {
+        self.streamed.push_str(content);
+        match self.answer_start() {
+            Some(start) => self.take(start),
+            None => "",
+        }
+    }
+
+    /// Whatever was still held back, once the stream has ended
+    fn finish(&mut self) -> &str {
+        let answer = ollama::split_thinking(&self.streamed).1;
+        self.take(self.streamed.len() - answer.len())
+    }
+
+    /// Where the answer starts in `streamed`, if that's known yet
+    fn answer_start(&self) -> Option<usize> {
+        let trimmed = self.streamed.trim_start();
+        if Self::OPEN.starts_with(trimmed) {
+            return None;
+        }
+        let rest = match trimmed.strip_prefix(Self::OPEN) {
+            Some(rest) => rest,
+            None => return Some(0),
+        };
+        let (_, answer) = rest.split_once(Self::CLOSE)?;
+        Some(self.streamed.len() - answer.trim_start().len())
+    }
+
+    fn take(&mut self, start: usize) -> &str {
+        let from = self.printed.max(start);
+        self.printed = self.streamed.len();
+        &self.streamed[from..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnswerStream;
+
+    #[test]
+    fn think_tag_split_across_tokens() {
+        let mut answer = AnswerStream::default();
+        assert_eq!(answer.push("<thi"), "");
+        assert_eq!(answer.push("nk>x</think>y"), "y");
+        assert_eq!(answer.push(" z"), " z");
+        assert_eq!(answer.finish(), "");
+    }
+
+    #[test]
+    fn answer_after_the_block_is_printed_once() {
+        let mut answer = AnswerStream::default();
+        assert_eq!(answer.push("<think>hmm"), "");
+        assert_eq!(answer.push("</think>\n\n"), "");
+        assert_eq!(answer.push("Hi"), "Hi");
+        assert_eq!(answer.push(" there"), " there");
+        assert_eq!(answer.finish(), "");
+    }
+
+    #[test]
+    fn held_back_start_that_never_became_a_tag() {
+        let mut answer = AnswerStream::default();
+        assert_eq!(answer.push("<th"), "");
+        assert_eq!(answer.push("e"), "<the");
+        let mut answer = AnswerStream::default();
+        assert_eq!(answer.push("<"), "");
+        assert_eq!(answer.finish(), "<");
+    }
+}
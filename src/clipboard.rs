@@ -1,32 +1,117 @@
 use std::process::Stdio;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::process::Command;
 
-/// Copy text to clipboard using wl-copy (Wayland)
+/// Clipboard tools tried in order, first one that works wins
+const COPY_BACKENDS: &[(&str, &[&str])] = &[
+    // Wayland
+    ("wl-copy", &[]),
+    // X11
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    // macOS
+    ("pbcopy", &[]),
+    // Windows
+    ("clip.exe", &[]),
+];
+
+/// Tools that print the clipboard, tried in the same order
+const PASTE_BACKENDS: &[(&str, &[&str])] = &[
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("pbpaste", &[]),
+    ("powershell.exe", &["-NoProfile", "-Command", "Get-Clipboard"]),
+];
+
+/// Copy text to clipboard, trying wl-copy, then X11/macOS/Windows tools
 pub async fn copy_to_clipboard(text: &str) -> Result<(), String> {
-    let mut child = Command::new("wl-copy")
+    let mut errors = Vec::new();
+
+    for (program, args) in COPY_BACKENDS {
+        match copy_with(program, args, text).await {
+            Ok(()) => return Ok(()),
+            Err(CopyError::NotInstalled) => continue,
+            Err(CopyError::Failed(e)) => {
+                tracing::debug!("{program} failed, trying next clipboard tool: {e}");
+                errors.push(e);
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Err("No clipboard tool found (install wl-clipboard, xclip or xsel)".to_string())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Text on the clipboard, from the first clipboard tool that works
+pub async fn paste_from_clipboard() -> Result<String, String> {
+    let mut errors = Vec::new();
+
+    for (program, args) in PASTE_BACKENDS {
+        let output = match Command::new(program).args(*args).stdin(Stdio::null()).output().await {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                errors.push(format!("Failed to spawn {program}: {e}"));
+                continue;
+            }
+        };
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        tracing::debug!("{program} failed, trying next clipboard tool: {}", stderr.trim());
+        errors.push(format!("{program} failed: {}", stderr.trim()));
+    }
+
+    if errors.is_empty() {
+        Err("No clipboard tool found (install wl-clipboard, xclip or xsel)".to_string())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+enum CopyError {
+    NotInstalled,
+    Failed(String),
+}
+
+async fn copy_with(program: &str, args: &[&str], text: &str) -> Result<(), CopyError> {
+    let mut child = Command::new(program)
+        .args(args)
         .stdin(Stdio::piped())
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
         .spawn()
-        .map_err(|e| format!("Failed to spawn wl-copy: {e}"))?;
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => CopyError::NotInstalled,
+            _ => CopyError::Failed(format!("Failed to spawn {program}: {e}")),
+        })?;
 
     if let Some(mut stdin) = child.stdin.take() {
         stdin
             .write_all(text.as_bytes())
             .await
-            .map_err(|e| format!("Failed to write to wl-copy stdin: {e}"))?;
+            .map_err(|e| CopyError::Failed(format!("Failed to write to {program} stdin: {e}")))?;
     }
 
-    let output = child
-        .wait_with_output()
+    // Wait on the status only: wl-copy and xclip fork a daemon that can keep
+    // inherited pipes open, so waiting for output could block indefinitely
+    let status = child
+        .wait()
         .await
-        .map_err(|e| format!("Failed to wait for wl-copy: {e}"))?;
+        .map_err(|e| CopyError::Failed(format!("Failed to wait for {program}: {e}")))?;
 
-    if output.status.success() {
+    if status.success() {
         Ok(())
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("wl-copy failed: {stderr}"))
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr).await;
+        }
+        Err(CopyError::Failed(format!("{program} failed: {}", stderr.trim())))
     }
 }
@@ -0,0 +1,62 @@
+use crate::config::Config;
+use futures::{SinkExt, Stream};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::time::Duration;
+
+/// Editors save in bursts (truncate, write, rename), wait for them to settle
+const SETTLE: Duration = Duration::from_millis(250);
+
+/// `config.toml` as re-read after each edit on disk, or why it couldn't be.
+///
+/// The directory is watched rather than the file, since editors that save
+/// by renaming a temporary file over it would leave a file watch dangling.
+pub fn changes() -> impl Stream<Item = Result<Config, String>> {
+    iced::stream::channel(8, |mut output| async move {
+        let path = match Config::config_path() {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::warn!("Not watching the config: {e}");
+                return;
+            }
+        };
+        let Some(dir) = path.parent().map(|dir| dir.to_path_buf()) else {
+            return;
+        };
+
+        let (events, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let watched = path.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let relevant = event.is_ok_and(|event| {
+                matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event.paths.iter().any(|p| p.file_name() == watched.file_name())
+            });
+            if relevant {
+                let _ = events.send(());
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                tracing::warn!("Not watching the config: {e}");
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            tracing::warn!("Not watching {}: {e}", dir.display());
+            return;
+        }
+
+        while received.recv().await.is_some() {
+            tokio::time::sleep(SETTLE).await;
+            while received.try_recv().is_ok() {}
+            // Deleted, or mid-rename; the next event brings it back
+            if !path.exists() {
+                continue;
+            }
+            let config = Config::load().map_err(|e| e.to_string());
+            if output.send(config).await.is_err() {
+                break;
+            }
+        }
+    })
+}
@@ -0,0 +1,232 @@
+//! Conversations as text for pasting elsewhere, or as a page to share
+
+use crate::history::{self, SavedMessage};
+use crate::timestamps;
+use std::sync::LazyLock;
+use std::time::Duration;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+
+/// Markdown transcript with a bold `**User:**` / `**Assistant:**` header per
+/// message, followed by the model and times where they're known
+pub fn markdown(messages: &[SavedMessage]) -> String {
+    let now = history::unix_now();
+    let mut out = String::new();
+    for message in messages {
+        let role = match message.role.as_str() {
+            "user" => "User",
+            "assistant" => "Assistant",
+            "system" => "System",
+            other => other,
+        };
+        out.push_str(&format!("**{role}:**"));
+
+        let mut meta = Vec::new();
+        if let Some(model) = &message.model {
+            meta.push(model.clone());
+        }
+        if let Some(at) = message.created_at {
+            meta.push(timestamps::absolute(at, now));
+        }
+        if let Some(ms) = message.duration_ms {
+            meta.push(timestamps::duration(Duration::from_millis(ms)));
+        }
+        if !meta.is_empty() {
+            out.push_str(&format!(" _{}_", meta.join(" · ")));
+        }
+        out.push_str("\n\n");
+
+        out.push_str(message.content.trim());
+        out.push_str("\n\n");
+        for attachment in &message.attachments {
+            out.push_str(&format!("📎 {}\n\n", attachment.label()));
+        }
+    }
+    out.trim_end().to_string()
+}
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+const HTML_STYLE: &str = "
+body { font: 15px/1.5 system-ui, sans-serif; max-width: 820px; margin: 2em auto; padding: 0 1em;
+       background: #f6f7f9; color: #1f2328; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5em; }
+header h1 { font-size: 1.4em; margin: 0 0 .2em; }
+.meta { color: #656d76; font-size: .85em; }
+.message { margin: 1em 0; display: flex; flex-direction: column; }
+.message.user { align-items: flex-end; }
+.bubble { max-width: 85%; padding: .6em 1em; border-radius: 10px; background: #fff;
+          border: 1px solid #d0d7de; overflow-wrap: anywhere; }
+.user .bubble { background: #ddf4ff; border-color: #b6e3ff; }
+.bubble p { margin: .4em 0; }
+.bubble pre { padding: .8em; border-radius: 6px; overflow-x: auto; font-size: 13px; }
+.bubble code { font-family: ui-monospace, monospace; font-size: .9em; }
+.bubble :not(pre) > code { background: #eff1f3; padding: .1em .3em; border-radius: 4px; }
+.thinking { color: #656d76; font-size: .9em; }
+@media (prefers-color-scheme: dark) {
+  body { background: #0d1117; color: #e6edf3; }
+  header { border-color: #30363d; }
+  .bubble { background: #161b22; border-color: #30363d; }
+  .user .bubble { background: #0c2d4b; border-color: #1f6feb; }
+  .bubble :not(pre) > code { background: #2d333b; }
+}
+";
+
+/// A standalone HTML page of the conversation, styles inlined and code
+/// highlighted, for sharing with people who don't run the app
+pub fn html(title: &str, messages: &[SavedMessage]) -> String {
+    let now = history::unix_now();
+    let mut models: Vec<&str> = messages.iter().filter_map(|m| m.model.as_deref()).collect();
+    models.dedup();
+    let mut header_meta = vec![format!("Exported {}", timestamps::absolute(now, now))];
+    if let Some(started) = messages.iter().find_map(|m| m.created_at) {
+        header_meta.insert(0, format!("Started {}", timestamps::absolute(started, now)));
+    }
+    if !models.is_empty() {
+        header_meta.push(models.join(", "));
+    }
+    header_meta.push(format!("{} messages", messages.len()));
+
+    let mut body = String::new();
+    for message in messages {
+        let role = match message.role.as_str() {
+            "user" => "You",
+            "assistant" => "Assistant",
+            "system" => "System",
+            other => other,
+        };
+        let mut meta = vec![role.to_string()];
+        if let Some(model) = &message.model {
+            meta.push(model.clone());
+        }
+        if let Some(at) = message.created_at {
+            meta.push(timestamps::absolute(at, now));
+        }
+        if let Some(ms) = message.duration_ms {
+            meta.push(timestamps::duration(Duration::from_millis(ms)));
+        }
+
+        body.push_str(&format!(
+            "<div class=\"message {}\">\n<div class=\"meta\">{}</div>\n<div class=\"bubble\">\n",
+            escape(&message.role),
+            escape(&meta.join(" · "))
+        ));
+        if let Some(thinking) = message.thinking.as_deref().filter(|t| !t.trim().is_empty()) {
+            body.push_str(&format!(
+                "<details class=\"thinking\"><summary>Reasoning</summary>{}</details>\n",
+                prose_html(thinking)
+            ));
+        }
+        body.push_str(&markdown_html(&message.content));
+        for attachment in &message.attachments {
+            body.push_str(&format!("<p class=\"meta\">📎 {}</p>\n", escape(&attachment.label())));
+        }
+        body.push_str("</div>\n</div>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n\
+         <header><h1>{title}</h1><p class=\"meta\">{meta}</p></header>\n{body}</body>\n</html>\n",
+        title = escape(title),
+        meta = escape(&header_meta.join(" · ")),
+    )
+}
+
+/// Prose and ``` fenced code blocks, the code highlighted by syntect
+fn markdown_html(content: &str) -> String {
+    let mut out = String::new();
+    let mut prose = String::new();
+    let mut code: Option<(String, String)> = None;
+    for line in content.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (None, Some(tag)) => {
+                out.push_str(&prose_html(&prose));
+                prose.clear();
+                code = Some((tag.trim().to_string(), String::new()));
+            }
+            (Some(_), Some(_)) => {
+                if let Some((tag, body)) = code.take() {
+                    out.push_str(&code_html(&tag, &body));
+                }
+            }
+            (Some((_, body)), None) => {
+                body.push_str(line);
+                body.push('\n');
+            }
+            (None, None) => {
+                prose.push_str(line);
+                prose.push('\n');
+            }
+        }
+    }
+    // An unterminated fence runs to the end, as in the app
+    if let Some((tag, body)) = code {
+        out.push_str(&code_html(&tag, &body));
+    }
+    out.push_str(&prose_html(&prose));
+    out
+}
+
+fn code_html(tag: &str, code: &str) -> String {
+    let syntax = SYNTAXES
+        .find_syntax_by_token(tag)
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+    THEMES
+        .themes
+        .get("base16-ocean.dark")
+        .and_then(|theme| syntect::html::highlighted_html_for_string(code, &SYNTAXES, syntax, theme).ok())
+        .unwrap_or_else(|| format!("<pre><code>{}</code></pre>\n", escape(code)))
+}
+
+/// Paragraphs at blank lines, line breaks kept, `code` and **bold** inline
+fn prose_html(prose: &str) -> String {
+    let mut out = String::new();
+    for paragraph in prose.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let lines: Vec<String> = paragraph.lines().map(inline_html).collect();
+        out.push_str(&format!("<p>{}</p>\n", lines.join("<br>\n")));
+    }
+    out
+}
+
+fn inline_html(line: &str) -> String {
+    let mut out = String::new();
+    for (i, part) in line.split('`').enumerate() {
+        if i % 2 == 1 {
+            out.push_str(&format!("<code>{}</code>", escape(part)));
+            continue;
+        }
+        for (j, piece) in escape(part).split("**").enumerate() {
+            if j % 2 == 1 {
+                out.push_str(&format!("<strong>{piece}</strong>"));
+            } else {
+                out.push_str(piece);
+            }
+        }
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// File name for an exported conversation, from its title
+pub fn html_filename(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let stem = stem.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-");
+    if stem.is_empty() {
+        String::from("conversation.html")
+    } else {
+        format!("{stem}.html")
+    }
+}
@@ -0,0 +1,149 @@
+use futures::{SinkExt, Stream};
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+/// A system-wide hotkey, registered for as long as this is alive.
+///
+/// Works on X11, macOS and Windows. Wayland compositors don't let clients
+/// grab keys, so there the compositor binds `locallm --quick` instead,
+/// which reaches the running instance through [`remote_requests`].
+pub struct GlobalHotkey {
+    manager: GlobalHotKeyManager,
+    hotkey: HotKey,
+}
+
+impl GlobalHotkey {
+    /// Parse and register a hotkey like "Super+Space" or "Ctrl+Alt+L"
+    pub fn register(spec: &str) -> Result<Self, String> {
+        let hotkey: HotKey = spec
+            .parse()
+            .map_err(|e| format!("Invalid hotkey \"{spec}\": {e}"))?;
+        let manager =
+            GlobalHotKeyManager::new().map_err(|e| format!("Global hotkeys unavailable: {e}"))?;
+        manager
+            .register(hotkey)
+            .map_err(|e| format!("Failed to register {spec}: {e}"))?;
+
+        tracing::info!("Registered global hotkey {spec}");
+        Ok(Self { manager, hotkey })
+    }
+}
+
+impl Drop for GlobalHotkey {
+    fn drop(&mut self) {
+        let _ = self.manager.unregister(self.hotkey);
+    }
+}
+
+/// Fires once per press of a registered hotkey
+pub fn presses() -> impl Stream<Item = ()> {
+    iced::stream::channel(8, |mut output| async move {
+        loop {
+            // The event channel is a blocking crossbeam receiver
+            let event = tokio::task::spawn_blocking(|| GlobalHotKeyEvent::receiver().recv()).await;
+            match event {
+                Ok(Ok(event)) if event.state == HotKeyState::Pressed => {
+                    if output.send(()).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(Ok(_)) => {}
+                _ => break,
+            }
+        }
+    })
+}
+
+/// Socket a running instance listens on for `locallm --quick`. Per user:
+/// the runtime dir, or the data dir where there is none, never the shared /tmp.
+#[cfg(unix)]
+fn socket_path() -> Option<std::path::PathBuf> {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => Some(std::path::PathBuf::from(dir).join("locallm.sock")),
+        None => crate::config::Config::data_dir().ok().map(|dir| dir.join("locallm.sock")),
+    }
+}
+
+/// The socket this instance listens on, removed when it stops
+#[cfg(unix)]
+static LISTENING: std::sync::Mutex<Option<std::path::PathBuf>> = std::sync::Mutex::new(None);
+
+/// Remove the socket this instance listens on, if any. Called on shutdown,
+/// and when the listener goes away.
+pub fn remove_socket() {
+    #[cfg(unix)]
+    if let Some(path) = LISTENING.lock().unwrap().take() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Removes the socket when the listening stream is dropped
+#[cfg(unix)]
+struct Listening;
+
+#[cfg(unix)]
+impl Drop for Listening {
+    fn drop(&mut self) {
+        remove_socket();
+    }
+}
+
+/// Ask an already running instance to toggle its quick-ask popup.
+/// Returns false if no instance is listening.
+pub fn signal_running_instance() -> bool {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        socket_path().is_some_and(|path| {
+            std::os::unix::net::UnixStream::connect(path)
+                .and_then(|mut stream| stream.write_all(b"quick\n"))
+                .is_ok()
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+/// Quick-ask requests from other `locallm --quick` invocations
+pub fn remote_requests() -> impl Stream<Item = ()> {
+    iced::stream::channel(8, |mut output| async move {
+        #[cfg(unix)]
+        {
+            let Some(path) = socket_path() else {
+                tracing::warn!("No runtime or data directory for the quick-ask socket");
+                return;
+            };
+            if tokio::net::UnixStream::connect(&path).await.is_ok() {
+                tracing::warn!("Another instance owns {}, not listening", path.display());
+                return;
+            }
+            // Left behind by an instance that didn't exit cleanly
+            let _ = std::fs::remove_file(&path);
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+
+            let listener = match tokio::net::UnixListener::bind(&path) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::warn!("Failed to listen on {}: {e}", path.display());
+                    return;
+                }
+            };
+            *LISTENING.lock().unwrap() = Some(path);
+            let _listening = Listening;
+
+            while listener.accept().await.is_ok() {
+                if output.send(()).await.is_err() {
+                    break;
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = &mut output;
+        }
+    })
+}
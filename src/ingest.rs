@@ -0,0 +1,72 @@
+//! Text from files dropped on the window, attached to the next message
+
+use crate::tokens::estimate_tokens;
+use crate::workspace::Attachment;
+use std::path::{Path, PathBuf};
+
+/// File types whose text can be extracted
+pub const EXTENSIONS: &[&str] = &["txt", "md", "markdown", "pdf"];
+
+/// Plain text of a document and its page count for PDFs. Blocking.
+pub fn extract_text(path: &Path) -> Result<(String, Option<usize>), String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pdf" => {
+            let pages = pdf_extract::extract_text_by_pages(path).map_err(|e| e.to_string())?;
+            Ok((pages.join("\n\n"), Some(pages.len())))
+        }
+        ext if EXTENSIONS.contains(&ext) => {
+            let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+            Ok((text, None))
+        }
+        _ => Err(String::from("only .pdf, .txt and .md files can be attached")),
+    }
+}
+
+/// Read a dropped file into an attachment of at most `max_tokens`,
+/// cut at a paragraph break if it's longer
+pub async fn attach(path: PathBuf, max_tokens: u64) -> Result<Attachment, String> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let (text, pages) = tokio::task::spawn_blocking(move || extract_text(&path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Can't attach {name}: {e}"))?;
+    if text.trim().is_empty() {
+        return Err(format!("{name} has no extractable text (a scanned PDF?)"));
+    }
+
+    let (content, truncated) = fit(&text, max_tokens);
+    Ok(Attachment {
+        path: name,
+        content,
+        pages,
+        truncated,
+    })
+}
+
+/// The leading paragraphs of `text` that fit in `max_tokens`, and whether any were left out
+pub fn fit(text: &str, max_tokens: u64) -> (String, bool) {
+    if estimate_tokens(text) <= max_tokens {
+        return (text.to_string(), false);
+    }
+    let mut kept = String::new();
+    for paragraph in text.split("\n\n") {
+        if estimate_tokens(&kept) + estimate_tokens(paragraph) > max_tokens {
+            break;
+        }
+        kept.push_str(paragraph);
+        kept.push_str("\n\n");
+    }
+    // One huge paragraph: cut it by characters instead
+    if kept.is_empty() {
+        kept = text.chars().take(max_tokens as usize * 4).collect();
+    }
+    (kept.trim_end().to_string(), true)
+}
@@ -0,0 +1,49 @@
+use std::process::{Child, Command, Stdio};
+
+/// Whether there's a way to keep the machine awake here: logind, so Linux
+pub const SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Keeps the machine from idling or suspending while alive.
+///
+/// Holds a logind inhibitor lock through `systemd-inhibit`, which blocks
+/// suspend everywhere and screen blanking on compositors and idle daemons
+/// that honor logind (GNOME, KDE, swayidle with `-w`/idlehint setups).
+/// The lock is released when this is dropped.
+///
+/// Not the Wayland idle-inhibit protocol: that only holds while one of our
+/// windows is visible and never stops suspend, while generation usually
+/// runs with the window minimized or closed to the quick-ask popup. On
+/// compositors that ignore logind the screen may still blank.
+pub struct IdleInhibitor {
+    child: Child,
+}
+
+impl IdleInhibitor {
+    pub fn start(reason: &str) -> Result<Self, String> {
+        let child = Command::new("systemd-inhibit")
+            .args([
+                "--what=idle:sleep",
+                "--who=LocalLM",
+                &format!("--why={reason}"),
+                "--mode=block",
+                "sleep",
+                "infinity",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to spawn systemd-inhibit: {e}"))?;
+
+        tracing::debug!("Idle inhibitor started (pid {})", child.id());
+        Ok(Self { child })
+    }
+}
+
+impl Drop for IdleInhibitor {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        tracing::debug!("Idle inhibitor released");
+    }
+}
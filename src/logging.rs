@@ -0,0 +1,73 @@
+//! Log file in the data dir, written alongside stderr so connection problems
+//! can be looked into (Settings → View logs) without running from a terminal
+
+use crate::config::Config;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// The log moves to `locallm.log.1` once it grows past this, replacing the older one
+const MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Lines shown by the log viewer
+const TAIL_LINES: usize = 1000;
+
+pub fn log_path() -> Result<PathBuf, String> {
+    Ok(Config::data_dir().map_err(|e| e.to_string())?.join("locallm.log"))
+}
+
+/// Appends to the log file, rolling it over at `MAX_BYTES`
+pub struct RollingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl RollingFile {
+    pub fn open() -> Result<Self, String> {
+        let path = log_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self { path, file, written })
+    }
+
+    fn roll_over(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        std::fs::rename(&self.path, self.path.with_extension("log.1"))?;
+        self.file = File::create(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written + buf.len() as u64 > MAX_BYTES {
+            self.roll_over()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// The end of the log, for the viewer
+pub async fn read_tail() -> Result<String, String> {
+    let path = log_path()?;
+    let log = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let lines: Vec<&str> = log.lines().collect();
+    Ok(lines[lines.len().saturating_sub(TAIL_LINES)..].join("\n"))
+}
@@ -1,50 +1,160 @@
+mod ask;
 mod clipboard;
-mod config;
-mod gpu_stats;
-mod ollama;
+mod config_watch;
+mod export;
+mod hotkey;
+mod ingest;
+mod inhibit;
+mod logging;
+mod math;
+mod model_cache;
+mod notify;
+mod profile;
+mod prompt_history;
+mod proxy;
+mod rag;
+mod redact;
+mod server;
+mod snippets;
+mod system_stats;
+mod timestamps;
+mod tokens;
 mod ui;
+mod voice;
+mod vram_fit;
+mod window_state;
 
-use config::Config;
-use iced::window;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use clap::{Parser, Subcommand};
+use locallm_core::{config, gpu_stats, history, ollama, templates, workspace};
+use config::{Config, Overrides};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Show the quick-ask popup (of the running instance, if there is one)
+    #[arg(long)]
+    quick: bool,
+    /// Open a project so `#path` mentions attach its files
+    #[arg(long, value_name = "DIR")]
+    workspace: Option<PathBuf>,
+    /// Config file to use instead of the default one, e.g. one per server
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Ollama server URL for this run, instead of `ollama_url`
+    #[arg(long)]
+    url: Option<String>,
+    /// Model to select at startup, instead of `default_model`
+    #[arg(long)]
+    model: Option<String>,
+    /// System prompt for this run, instead of `system_prompt`
+    #[arg(long, value_name = "PROMPT")]
+    system_prompt: Option<String>,
+    /// Theme for this run, e.g. "Dark" or "system"
+    #[arg(long)]
+    theme: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Serve an OpenAI-compatible API in front of Ollama, without the window
+    Proxy {
+        /// Address to listen on, instead of `proxy_listen`
+        #[arg(long, value_name = "ADDR")]
+        listen: Option<String>,
+    },
+    /// Ask one question and stream the answer to stdout
+    Ask(ask::AskArgs),
+}
 
 fn main() -> iced::Result {
-    // Initialize logging
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "locallm=info".into()),
-        )
-        .init();
+    let cli = Cli::parse();
+
+    // Logs go to stderr so `locallm ask` output can be piped; it only reports problems
+    let default_filter = match cli.command {
+        Some(Command::Ask(_)) => "locallm=warn",
+        _ => "locallm=info",
+    };
+    let stderr_layer = tracing_subscriber::fmt::layer().with_writer(std::io::stderr).with_filter(
+        tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| default_filter.into()),
+    );
+    // The log file always gets everything down to debug, request details included
+    let (log_file, log_error) = match logging::RollingFile::open() {
+        Ok(file) => (Some(file), None),
+        Err(e) => (None, Some(e)),
+    };
+    let file_layer = log_file.map(|file| {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(Mutex::new(file))
+            .with_filter(tracing_subscriber::EnvFilter::new("locallm=debug"))
+    });
+    tracing_subscriber::registry().with(stderr_layer).with(file_layer).init();
+    if let Some(e) = log_error {
+        tracing::warn!("Logging to stderr only: {e}");
+    }
+
+    // `locallm --quick` toggles the popup of a running instance, if there is one
+    if cli.quick && hotkey::signal_running_instance() {
+        return Ok(());
+    }
 
     tracing::info!("Starting LocalLM");
 
-    // Load configuration
+    // Load configuration, with any overrides from the command line
+    Config::set_overrides(
+        cli.config,
+        Overrides {
+            ollama_url: cli.url,
+            default_model: cli.model,
+            system_prompt: cli.system_prompt,
+            theme: cli.theme,
+        },
+    );
     let config = match Config::load() {
         Ok(cfg) => cfg,
         Err(e) => {
             tracing::warn!("Failed to load config, using defaults: {e}");
-            Config::default()
+            Config::default().overridden()
         }
     };
 
     tracing::info!("Ollama URL: {}", config.ollama_url);
     tracing::info!("Config path: {:?}", Config::config_path());
 
-    // Create and run the application
-    iced::application(ui::App::title, ui::App::update, ui::App::view)
+    match cli.command {
+        // `locallm proxy [--listen ADDR]`: headless OpenAI-compatible API
+        Some(Command::Proxy { listen }) => {
+            if let Err(e) = proxy::run(config, listen) {
+                tracing::error!("{e}");
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Ask(args)) => {
+            if let Err(e) = ask::run(config, args) {
+                tracing::error!("{e}");
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // Create and run the application. A daemon so the quick-ask popup can
+    // live in its own window and outlast the main one.
+    // `--workspace DIR` opens a project for `#file` mentions
+    let quick = cli.quick;
+    let workspace = cli.workspace;
+    iced::daemon(ui::App::title, ui::App::update, ui::App::view)
         .subscription(ui::App::subscription)
         .theme(ui::App::theme)
-        .window(window::Settings {
-            size: iced::Size::new(800.0, 700.0),
-            min_size: Some(iced::Size::new(500.0, 400.0)),
-            resizable: true,
-            platform_specific: window::settings::PlatformSpecific {
-                application_id: String::from("locallm"),
-                ..Default::default()
-            },
-            ..Default::default()
-        })
-        .run_with(|| ui::App::new(config))
+        .scale_factor(ui::App::scale_factor)
+        .run_with(move || ui::App::new(config, quick, workspace))?;
+    hotkey::remove_socket();
+    Ok(())
 }
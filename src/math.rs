@@ -0,0 +1,888 @@
+//! LaTeX math in answers, without a TeX install: parsed into [`Node`]s that
+//! the chat lays out as stacked fractions, radicals, scripts and matrices,
+//! or flattened to Unicode text where one line reads the same (`x^2` as x²,
+//! `\alpha` as α).
+
+/// Part of a message: prose, inline `$…$` / `\(…\)` math or block `$$…$$` / `\[…\]` math
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Piece<'a> {
+    Text(&'a str),
+    Inline(&'a str),
+    Block(&'a str),
+}
+
+/// Split prose on math delimiters. A `$` only opens inline math when it's
+/// followed by a non-space, and only closes it after a non-space and before
+/// a non-digit on the same line, so prices like "$5 or $10" stay text.
+pub fn split(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut plain_start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with("\\$") {
+            i += 2;
+            continue;
+        }
+        let found = if let Some(body) = rest.strip_prefix("$$") {
+            body.find("$$").map(|end| (2, end, 2, true))
+        } else if let Some(body) = rest.strip_prefix("\\[") {
+            body.find("\\]").map(|end| (2, end, 2, true))
+        } else if let Some(body) = rest.strip_prefix("\\(") {
+            body.find("\\)").map(|end| (2, end, 2, false))
+        } else if let Some(body) = rest.strip_prefix('$') {
+            inline_end(body).map(|end| (1, end, 1, false))
+        } else {
+            None
+        };
+
+        match found {
+            Some((open, len, close, block)) if !rest[open..open + len].trim().is_empty() => {
+                if plain_start < i {
+                    pieces.push(Piece::Text(&text[plain_start..i]));
+                }
+                let body = &rest[open..open + len];
+                pieces.push(if block { Piece::Block(body.trim()) } else { Piece::Inline(body.trim()) });
+                i += open + len + close;
+                plain_start = i;
+            }
+            _ => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    if plain_start < text.len() {
+        pieces.push(Piece::Text(&text[plain_start..]));
+    }
+    pieces
+}
+
+/// Length of inline math after an opening `$`, if it closes on this line
+fn inline_end(body: &str) -> Option<usize> {
+    if body.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let line = body.split('\n').next().unwrap_or(body);
+    let mut prev = None;
+    for (pos, c) in line.char_indices() {
+        if c == '$' && prev.is_some_and(|p: char| !p.is_whitespace() && p != '\\') {
+            let next = line[pos + 1..].chars().next();
+            if !next.is_some_and(|n| n.is_ascii_digit()) {
+                return Some(pos);
+            }
+        }
+        prev = Some(c);
+    }
+    None
+}
+
+/// LaTeX math parsed for layout: what needs more than one line of text
+/// (fractions, radicals, stacked scripts, matrices) keeps its structure
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// Symbols and letters, already Unicode
+    Text(String),
+    Row(Vec<Node>),
+    Frac(Box<Node>, Box<Node>),
+    Sqrt {
+        degree: Option<Box<Node>>,
+        radicand: Box<Node>,
+    },
+    Binom(Box<Node>, Box<Node>),
+    Scripts {
+        base: Box<Node>,
+        sub: Option<Box<Node>>,
+        sup: Option<Box<Node>>,
+    },
+    /// A `matrix`/`cases`/`aligned`-like environment, or block math with
+    /// `\\` line breaks, between its delimiters
+    Table {
+        open: &'static str,
+        close: &'static str,
+        rows: Vec<Vec<Node>>,
+    },
+}
+
+impl Node {
+    /// Whether this reads the same as one line of Unicode text
+    pub fn is_flat(&self) -> bool {
+        match self {
+            Node::Text(_) => true,
+            Node::Row(nodes) => nodes.iter().all(Node::is_flat),
+            Node::Scripts { base, sub, sup } => {
+                let fits = |script: &Option<Box<Node>>, map: fn(char) -> Option<char>| {
+                    script.as_ref().is_none_or(|s| s.unicode().chars().filter(|c| *c != ' ').all(|c| map(c).is_some()))
+                };
+                base.is_flat() && fits(sub, subscript) && fits(sup, superscript) && (sub.is_none() || sup.is_none())
+            }
+            Node::Sqrt { degree: None, radicand } => matches!(**radicand, Node::Text(ref t) if wrap(t) == t.trim()),
+            _ => false,
+        }
+    }
+
+    /// The node as one line of Unicode text, `\frac{a}{b}` as a/b
+    fn unicode(&self) -> String {
+        match self {
+            Node::Text(t) => t.clone(),
+            Node::Row(nodes) => nodes.iter().map(Node::unicode).collect(),
+            Node::Frac(numerator, denominator) => {
+                format!("{}/{}", wrap(&numerator.unicode()), wrap(&denominator.unicode()))
+            }
+            Node::Sqrt { degree, radicand } => {
+                let radicand = wrap(&radicand.unicode());
+                match degree.as_ref().map(|d| d.unicode()).as_deref().map(str::trim) {
+                    None => format!("√{radicand}"),
+                    Some("3") => format!("∛{radicand}"),
+                    Some("4") => format!("∜{radicand}"),
+                    Some(n) => format!("{}√{radicand}", script(n, superscript, '^')),
+                }
+            }
+            Node::Binom(n, k) => format!("C({}, {})", n.unicode(), k.unicode()),
+            Node::Scripts { base, sub, sup } => {
+                let mut out = base.unicode();
+                if let Some(sub) = sub {
+                    out.push_str(&script(&sub.unicode(), subscript, '_'));
+                }
+                if let Some(sup) = sup {
+                    out.push_str(&script(&sup.unicode(), superscript, '^'));
+                }
+                out
+            }
+            Node::Table { open, close, rows } => {
+                let rows: Vec<String> = rows
+                    .iter()
+                    .map(|cells| cells.iter().map(|c| c.unicode().trim().to_string()).collect::<Vec<_>>().join(" "))
+                    .collect();
+                // Undelimited tables are lines of block math
+                let separator = if open.is_empty() && close.is_empty() { "\n" } else { "; " };
+                format!("{open}{}{close}", rows.join(separator))
+            }
+        }
+    }
+}
+
+/// Parse LaTeX math for layout
+pub fn parse(latex: &str) -> Node {
+    let mut parser = Parser {
+        chars: latex.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+    let mut rows = parser.table(true);
+    if rows.len() == 1 && rows[0].len() == 1 {
+        return rows.remove(0).remove(0);
+    }
+    Node::Table {
+        open: "",
+        close: "",
+        rows,
+    }
+}
+
+/// LaTeX math as plain Unicode text, for inline math and copying
+pub fn to_unicode(latex: &str) -> String {
+    let out = parse(latex).unicode();
+    // Collapse the runs of spaces left by dropped commands
+    let mut collapsed = String::with_capacity(out.len());
+    for c in out.chars() {
+        if c == ' ' && collapsed.ends_with(' ') {
+            continue;
+        }
+        collapsed.push(c);
+    }
+    collapsed.lines().map(str::trim).collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+/// Groups and arguments nested deeper than this are kept as they are, a
+/// model stuck repeating `{` mustn't overflow the stack
+const MAX_DEPTH: usize = 64;
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+/// What ended a run of math
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum End {
+    /// `}`, in a group
+    Group,
+    /// `&`, in a table
+    Cell,
+    /// `\\`, in a table
+    Line,
+    /// `\end{…}`, in a table
+    Environment,
+    Input,
+}
+
+/// One node for a run of them
+fn row(mut nodes: Vec<Node>) -> Node {
+    match nodes.len() {
+        0 => Node::Text(String::new()),
+        1 => nodes.remove(0),
+        _ => Node::Row(nodes),
+    }
+}
+
+/// Append text, to the text before it if there is some
+fn push_text(nodes: &mut Vec<Node>, s: &str) {
+    match nodes.last_mut() {
+        Some(Node::Text(t)) => t.push_str(s),
+        _ => nodes.push(Node::Text(s.to_string())),
+    }
+}
+
+/// Put a sub- or superscript on what comes before it: the last character
+/// of a text, or the last node
+fn attach(nodes: &mut Vec<Node>, script: Node, sup: bool) {
+    let base = match nodes.pop() {
+        Some(Node::Scripts { base, sub, sup: None }) if sup => {
+            nodes.push(Node::Scripts {
+                base,
+                sub,
+                sup: Some(Box::new(script)),
+            });
+            return;
+        }
+        Some(Node::Scripts { base, sub: None, sup: upper }) if !sup => {
+            nodes.push(Node::Scripts {
+                base,
+                sub: Some(Box::new(script)),
+                sup: upper,
+            });
+            return;
+        }
+        Some(Node::Text(mut t)) => {
+            let trimmed = t.trim_end().len();
+            t.truncate(trimmed);
+            match t.char_indices().last() {
+                Some((at, _)) if at > 0 => {
+                    let last = t.split_off(at);
+                    nodes.push(Node::Text(t));
+                    Node::Text(last)
+                }
+                _ => Node::Text(t),
+            }
+        }
+        Some(node) => node,
+        None => Node::Text(String::new()),
+    };
+    let script = Some(Box::new(script));
+    let (sub, sup) = if sup { (None, script) } else { (script, None) };
+    nodes.push(Node::Scripts {
+        base: Box::new(base),
+        sub,
+        sup,
+    });
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Parse one level deeper, or take the rest as it is past `MAX_DEPTH`
+    fn nested(&mut self, parse: fn(&mut Self) -> Node) -> Node {
+        if self.depth >= MAX_DEPTH {
+            let rest = self.chars.get(self.pos..).unwrap_or_default().iter().collect();
+            self.pos = self.chars.len();
+            return Node::Text(rest);
+        }
+        self.depth += 1;
+        let out = parse(self);
+        self.depth -= 1;
+        out
+    }
+
+    /// Everything up to the closing brace of a group (or the end)
+    fn group(&mut self) -> Node {
+        self.run(false).0
+    }
+
+    /// Math up to the end of the group, or in a table up to the end of the cell
+    fn run(&mut self, in_table: bool) -> (Node, End) {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.next() {
+            match c {
+                '}' if !in_table => return (row(nodes), End::Group),
+                '&' if in_table => return (row(nodes), End::Cell),
+                '\\' if in_table && self.peek() == Some('\\') => {
+                    self.pos += 1;
+                    return (row(nodes), End::Line);
+                }
+                '\\' if in_table && self.at_end() => return (row(nodes), End::Environment),
+                '{' => nodes.push(self.nested(Self::group)),
+                '\\' => {
+                    let node = self.command();
+                    match node {
+                        Node::Text(t) => push_text(&mut nodes, &t),
+                        node => nodes.push(node),
+                    }
+                }
+                '^' | '_' => {
+                    let script = self.argument();
+                    attach(&mut nodes, script, c == '^');
+                }
+                '&' | '~' => push_text(&mut nodes, " "),
+                c if c.is_whitespace() => push_text(&mut nodes, " "),
+                c => push_text(&mut nodes, c.encode_utf8(&mut [0; 4])),
+            }
+        }
+        (row(nodes), End::Input)
+    }
+
+    /// Whether `\end{…}` follows the backslash just read, consuming it if so
+    fn at_end(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        if self.chars[start..self.pos].iter().collect::<String>() == "end" {
+            self.argument();
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
+    /// Rows of `&`-separated cells, up to `\end{…}` or the end. At the top
+    /// a stray `\end` is skipped.
+    fn table(&mut self, top: bool) -> Vec<Vec<Node>> {
+        let mut rows = vec![Vec::new()];
+        loop {
+            let (cell, end) = self.run(true);
+            rows.last_mut().unwrap().push(cell);
+            match end {
+                End::Cell => {}
+                End::Line => rows.push(Vec::new()),
+                End::Environment if top => {}
+                _ => break,
+            }
+        }
+        // A trailing `\\` leaves an empty last row
+        if rows.len() > 1 && rows.last().is_some_and(|r| r.len() == 1 && r[0] == Node::Text(String::new())) {
+            rows.pop();
+        }
+        rows
+    }
+
+    /// A command's argument: a `{group}`, a command or a single character
+    fn argument(&mut self) -> Node {
+        self.skip_spaces();
+        match self.next() {
+            Some('{') => self.nested(Self::group),
+            Some('\\') => self.nested(Self::command),
+            Some(c) => Node::Text(c.to_string()),
+            None => Node::Text(String::new()),
+        }
+    }
+
+    /// An optional `[argument]`
+    fn optional(&mut self) -> Option<Node> {
+        self.skip_spaces();
+        if self.peek() != Some('[') {
+            return None;
+        }
+        self.pos += 1;
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c != ']') {
+            self.pos += 1;
+        }
+        let mut inner = Parser {
+            chars: self.chars[start..self.pos].to_vec(),
+            pos: 0,
+            depth: self.depth,
+        };
+        self.pos += 1;
+        Some(inner.nested(|p| p.run(false).0))
+    }
+
+    /// The argument as text
+    fn text_argument(&mut self) -> String {
+        self.argument().unicode()
+    }
+
+    fn command(&mut self) -> Node {
+        let name: String = if self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                self.pos += 1;
+            }
+            self.chars[start..self.pos].iter().collect()
+        } else {
+            self.next().map(String::from).unwrap_or_default()
+        };
+
+        let text = |s: &str| Node::Text(s.to_string());
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                Node::Frac(Box::new(numerator), Box::new(denominator))
+            }
+            "sqrt" => {
+                let degree = self.optional().map(Box::new);
+                let radicand = Box::new(self.argument());
+                Node::Sqrt { degree, radicand }
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.argument();
+                let k = self.argument();
+                Node::Binom(Box::new(n), Box::new(k))
+            }
+            "text" | "textrm" | "textbf" | "textit" | "textsf" | "texttt" | "mbox" | "mathrm" | "mathbf"
+            | "mathit" | "mathsf" | "mathtt" | "mathcal" | "boldsymbol" | "bm" | "operatorname" => self.argument(),
+            "mathbb" => Node::Text(self.text_argument().chars().map(double_struck).collect()),
+            "hat" | "widehat" => Node::Text(combine(&self.text_argument(), '\u{302}')),
+            "bar" | "overline" => Node::Text(combine(&self.text_argument(), '\u{305}')),
+            "tilde" | "widetilde" => Node::Text(combine(&self.text_argument(), '\u{303}')),
+            "vec" | "overrightarrow" => Node::Text(combine(&self.text_argument(), '\u{20D7}')),
+            "dot" => Node::Text(combine(&self.text_argument(), '\u{307}')),
+            "ddot" => Node::Text(combine(&self.text_argument(), '\u{308}')),
+            "pmod" => Node::Text(format!(" (mod {})", self.text_argument())),
+            "mod" | "bmod" => text(" mod "),
+            "left" | "right" | "bigl" | "bigr" | "Bigl" | "Bigr" | "big" | "Big" | "bigg" | "Bigg" => {
+                // `\left.` is an invisible delimiter
+                self.skip_spaces();
+                if self.peek() == Some('.') {
+                    self.pos += 1;
+                }
+                text("")
+            }
+            "begin" => {
+                let environment = self.text_argument();
+                if environment == "array" {
+                    // The column layout, `{cc|c}`
+                    self.argument();
+                }
+                let (open, close) = match environment.as_str() {
+                    "pmatrix" => ("(", ")"),
+                    "bmatrix" => ("[", "]"),
+                    "Bmatrix" => ("{", "}"),
+                    "vmatrix" => ("|", "|"),
+                    "Vmatrix" => ("‖", "‖"),
+                    "cases" => ("{", ""),
+                    _ => ("", ""),
+                };
+                if self.depth >= MAX_DEPTH {
+                    return self.nested(Self::group);
+                }
+                self.depth += 1;
+                let rows = self.table(false);
+                self.depth -= 1;
+                Node::Table { open, close, rows }
+            }
+            "end" => {
+                self.argument();
+                text("")
+            }
+            "displaystyle" | "textstyle" | "limits" | "nolimits" | "nonumber" | "notag" => text(""),
+            "\\" => text("\n"),
+            "," | ":" | ";" | ">" | " " => text(" "),
+            "!" => text(""),
+            "quad" => text("  "),
+            "qquad" => text("    "),
+            "{" | "}" | "$" | "%" | "&" | "#" | "_" => Node::Text(name),
+            "|" => text("‖"),
+            _ => match symbol(&name) {
+                Some(symbol) => text(symbol),
+                None if FUNCTIONS.contains(&name.as_str()) => Node::Text(name),
+                None => Node::Text(format!("\\{name}")),
+            },
+        }
+    }
+}
+
+/// Parenthesize anything longer than a number or a single symbol
+fn wrap(s: &str) -> String {
+    let s = s.trim();
+    if s.chars().count() <= 1 || s.chars().all(|c| c.is_alphanumeric() || c == '.') {
+        s.to_string()
+    } else {
+        format!("({s})")
+    }
+}
+
+/// Super- or subscript characters where Unicode has all of them, `^(…)` otherwise
+fn script(s: &str, map: fn(char) -> Option<char>, marker: char) -> String {
+    let s = s.trim();
+    let mapped: Option<String> = s.chars().filter(|c| *c != ' ').map(map).collect();
+    match mapped {
+        Some(mapped) if !mapped.is_empty() => mapped,
+        // `e^iπ` would read as e^i times π
+        _ if s.chars().count() > 1 => format!("{marker}({s})"),
+        _ => format!("{marker}{s}"),
+    }
+}
+
+/// Put a combining accent on every character
+fn combine(s: &str, accent: char) -> String {
+    s.chars().flat_map(|c| [c, accent]).collect()
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'T' => 'ᵀ',
+        '′' => '′',
+        '∗' | '*' => '*',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        'β' => 'ᵦ',
+        'γ' => 'ᵧ',
+        'ρ' => 'ᵨ',
+        'φ' => 'ᵩ',
+        'χ' => 'ᵪ',
+        _ => return None,
+    })
+}
+
+fn double_struck(c: char) -> char {
+    match c {
+        'R' => 'ℝ',
+        'N' => 'ℕ',
+        'Z' => 'ℤ',
+        'Q' => 'ℚ',
+        'C' => 'ℂ',
+        'P' => 'ℙ',
+        'E' => '𝔼',
+        other => other,
+    }
+}
+
+/// Operator names typeset upright, kept as they are
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh", "log", "ln",
+    "lg", "exp", "lim", "liminf", "limsup", "sup", "inf", "max", "min", "argmax", "argmin", "det", "gcd", "lcm",
+    "deg", "dim", "ker", "arg", "Pr", "Var", "Cov",
+];
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" => "ϵ",
+        "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "varpi" => "ϖ",
+        "rho" => "ρ",
+        "varrho" => "ϱ",
+        "sigma" => "σ",
+        "varsigma" => "ς",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "times" => "×",
+        "cdot" | "cdotp" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "•",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "le" | "leq" => "≤",
+        "ge" | "geq" => "≥",
+        "ne" | "neq" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "implies" => "⟹",
+        "impliedby" => "⟸",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "bigcup" => "⋃",
+        "bigcap" => "⋂",
+        "setminus" | "backslash" => "∖",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "ldots" | "dots" | "dotsc" | "dotsb" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "prime" => "′",
+        "angle" => "∠",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        "mid" => "∣",
+        "vert" | "lvert" | "rvert" => "|",
+        "Vert" | "lVert" | "rVert" => "‖",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "degree" => "°",
+        "hbar" => "ħ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "triangle" => "△",
+        "square" => "□",
+        "checkmark" => "✓",
+        "colon" => ":",
+        "lbrace" => "{",
+        "rbrace" => "}",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractions() {
+        assert_eq!(to_unicode(r"\frac{a}{b}"), "a/b");
+        assert_eq!(to_unicode(r"\frac{x+1}{2}"), "(x+1)/2");
+        assert_eq!(to_unicode(r"\dfrac12"), "1/2");
+        assert_eq!(to_unicode(r"\frac{1}{\frac{1}{x}}"), "1/(1/x)");
+    }
+
+    #[test]
+    fn sub_and_superscripts() {
+        assert_eq!(to_unicode("x^2 + y^{10}"), "x² + y¹⁰");
+        assert_eq!(to_unicode("a_{i+1}"), "aᵢ₊₁");
+        assert_eq!(to_unicode("x_1^2"), "x₁²");
+        // No superscript π in Unicode
+        assert_eq!(to_unicode(r"e^{i\pi}"), "e^(iπ)");
+        assert_eq!(to_unicode(r"\sqrt[3]{x}"), "∛x");
+    }
+
+    #[test]
+    fn nested_groups() {
+        assert_eq!(to_unicode("{{a}}"), "a");
+        assert_eq!(to_unicode(r"\sqrt{\frac{a}{b}}"), "√(a/b)");
+        assert_eq!(to_unicode(r"\left( \frac{n}{2} \right)^{2}"), "( n/2 )²");
+    }
+
+    #[test]
+    fn malformed_input_does_not_panic() {
+        for latex in [
+            r"\frac{a",
+            r"\frac",
+            "x^",
+            "_",
+            r"\sqrt[3",
+            r"\sqrt[",
+            "}",
+            "{",
+            "\\",
+            r"\left",
+            r"\begin{",
+            "^_^{_}",
+        ] {
+            to_unicode(latex);
+        }
+        to_unicode(&"{".repeat(100_000));
+        to_unicode(&r"\sqrt".repeat(100_000));
+        to_unicode(&r"\sqrt[".repeat(100_000));
+        assert_eq!(to_unicode(r"\unknown x"), r"\unknown x");
+    }
+
+    #[test]
+    fn keeps_the_layout() {
+        let text = |s: &str| Box::new(Node::Text(s.to_string()));
+        assert_eq!(parse(r"\frac{a}{b}"), Node::Frac(text("a"), text("b")));
+        assert_eq!(
+            parse(r"\sqrt{x+1}"),
+            Node::Sqrt {
+                degree: None,
+                radicand: text("x+1")
+            }
+        );
+        assert_eq!(
+            parse(r"\sum_{i=1}^{n} i"),
+            Node::Row(vec![
+                Node::Scripts {
+                    base: text("∑"),
+                    sub: Some(text("i=1")),
+                    sup: Some(text("n")),
+                },
+                Node::Text(String::from(" i")),
+            ])
+        );
+        let Node::Table { open, close, rows } = parse(r"\begin{pmatrix} a & b \\ c & d \end{pmatrix}") else {
+            panic!("not a table");
+        };
+        assert_eq!((open, close), ("(", ")"));
+        assert_eq!(rows.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2]);
+        assert_eq!(to_unicode(r"\begin{pmatrix} a & b \\ c & d \end{pmatrix}"), "(a b; c d)");
+        // Lines of block math
+        assert_eq!(to_unicode(r"x &= 1 \\ y &= 2"), "x = 1\ny = 2");
+    }
+
+    #[test]
+    fn flat_only_where_a_line_reads_the_same() {
+        for latex in ["x^2 + y^2", r"\alpha \le \beta", r"\sqrt{2}", "a_i"] {
+            assert!(parse(latex).is_flat(), "{latex}");
+        }
+        for latex in [r"\frac{a}{b}", r"\sqrt{x+1}", r"e^{i\pi}", "x_i^2", r"\begin{matrix} 1 \end{matrix}"] {
+            assert!(!parse(latex).is_flat(), "{latex}");
+        }
+    }
+
+    #[test]
+    fn splits_math_from_prose() {
+        assert_eq!(
+            split("Area $\\pi r^2$ and $$E=mc^2$$"),
+            [Piece::Text("Area "), Piece::Inline(r"\pi r^2"), Piece::Text(" and "), Piece::Block("E=mc^2")]
+        );
+        assert_eq!(split("costs $5 or $10"), [Piece::Text("costs $5 or $10")]);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::config::Config;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Last known model names per Ollama URL, in `models.json` in the data dir,
+/// shown at startup while the server is asked for the real list
+type Cache = BTreeMap<String, Vec<String>>;
+
+fn path() -> Option<PathBuf> {
+    Config::data_dir().ok().map(|dir| dir.join("models.json"))
+}
+
+fn read() -> Cache {
+    let Some(path) = path() else {
+        return Cache::new();
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .inspect_err(|e| tracing::warn!("Ignoring {}: {e}", path.display()))
+            .unwrap_or_default(),
+        Err(_) => Cache::new(),
+    }
+}
+
+/// Models last seen on `base_url`, empty if it was never listed
+pub fn load(base_url: &str) -> Vec<String> {
+    read().remove(base_url).unwrap_or_default()
+}
+
+/// Small enough to write synchronously; only called when the list changes
+pub fn save(base_url: &str, models: &[String]) -> Result<(), String> {
+    let path = path().ok_or("No data directory")?;
+    let mut cache = read();
+    cache.insert(base_url.to_string(), models.to_vec());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(&cache).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
@@ -0,0 +1,34 @@
+use tokio::process::Command;
+
+/// Show a desktop notification with `notify-send` (freedesktop, any Linux
+/// desktop) or `osascript` (macOS), whichever is there
+pub async fn notify(summary: String, body: String) -> Result<(), String> {
+    let status = match Command::new("notify-send")
+        .args(["--app-name=LocalLM", "--icon=dialog-information", &summary, &body])
+        .status()
+        .await
+    {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+            let script = format!(
+                "display notification \"{}\" with title \"{}\"",
+                quote(&body),
+                quote(&summary)
+            );
+            Command::new("osascript")
+                .args(["-e", &script])
+                .status()
+                .await
+                .map_err(|e| match e.kind() {
+                    std::io::ErrorKind::NotFound => "No notification tool found (install libnotify)".to_string(),
+                    _ => format!("Failed to run osascript: {e}"),
+                })?
+        }
+        result => result.map_err(|e| format!("Failed to run notify-send: {e}"))?,
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Notification failed ({status})"))
+    }
+}
@@ -0,0 +1,180 @@
+use crate::config::Config;
+use crate::gpu_stats::GpuStats;
+use crate::ollama::{ChatOptions, ChatResponse};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Collects GPU samples while a profiled request is in flight
+#[derive(Debug)]
+pub struct ProfileRecorder {
+    started: Instant,
+    started_at: u64,
+    request_id: u64,
+    model: String,
+    endpoint: String,
+    options: ChatOptions,
+    prompt_messages: usize,
+    prompt_chars: usize,
+    gpu_samples: Vec<GpuSample>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuSample {
+    pub t_ms: u64,
+    /// Index of the card among those read, in card order
+    pub gpu: usize,
+    pub vram_used_mb: u64,
+    pub vram_total_mb: u64,
+    pub gpu_usage_percent: u8,
+    pub temperature_c: Option<u8>,
+}
+
+/// Ollama's own accounting for the request, durations in milliseconds
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OllamaStats {
+    pub total_ms: Option<f64>,
+    pub load_ms: Option<f64>,
+    pub prompt_eval_count: Option<u64>,
+    pub prompt_eval_ms: Option<f64>,
+    pub eval_count: Option<u64>,
+    pub eval_ms: Option<f64>,
+    pub tokens_per_second: Option<f64>,
+}
+
+/// Everything recorded for one profiled generation, written as JSON
+#[derive(Debug, Serialize)]
+pub struct ProfileReport {
+    pub locallm_version: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub request_id: u64,
+    pub started_at: u64,
+    pub model: String,
+    pub endpoint: String,
+    pub options: ChatOptions,
+    pub prompt_messages: usize,
+    pub prompt_chars: usize,
+    pub wall_time_ms: u64,
+    pub error: Option<String>,
+    pub ollama: OllamaStats,
+    /// Arrival time of each streamed chunk, relative to the request
+    pub token_offsets_ms: Vec<f64>,
+    pub gpu_samples: Vec<GpuSample>,
+}
+
+impl ProfileRecorder {
+    pub fn new(
+        request_id: u64,
+        model: String,
+        endpoint: String,
+        options: ChatOptions,
+        prompt_messages: usize,
+        prompt_chars: usize,
+    ) -> Self {
+        Self {
+            started: Instant::now(),
+            started_at: unix_now(),
+            request_id,
+            model,
+            endpoint,
+            options,
+            prompt_messages,
+            prompt_chars,
+            gpu_samples: Vec::new(),
+        }
+    }
+
+    /// One sample per card, all with the same timestamp
+    pub fn record_gpu(&mut self, cards: &[GpuStats]) {
+        let t_ms = self.started.elapsed().as_millis() as u64;
+        for (gpu, stats) in cards.iter().enumerate() {
+            self.gpu_samples.push(GpuSample {
+                t_ms,
+                gpu,
+                vram_used_mb: stats.vram_used_mb,
+                vram_total_mb: stats.vram_total_mb,
+                gpu_usage_percent: stats.gpu_usage_percent,
+                temperature_c: stats.temperature_c,
+            });
+        }
+    }
+
+    pub fn finish(
+        self,
+        response: Result<&ChatResponse, &str>,
+        token_offsets: &[Duration],
+    ) -> ProfileReport {
+        let ms = |ns: Option<u64>| ns.map(|ns| ns as f64 / 1_000_000.0);
+
+        let (ollama, error) = match response {
+            Ok(resp) => (
+                OllamaStats {
+                    total_ms: ms(resp.total_duration),
+                    load_ms: ms(resp.load_duration),
+                    prompt_eval_count: resp.prompt_eval_count,
+                    prompt_eval_ms: ms(resp.prompt_eval_duration),
+                    eval_count: resp.eval_count,
+                    eval_ms: ms(resp.eval_duration),
+                    tokens_per_second: match (resp.eval_count, resp.eval_duration) {
+                        (Some(count), Some(ns)) if ns > 0 => {
+                            Some(count as f64 / (ns as f64 / 1_000_000_000.0))
+                        }
+                        _ => None,
+                    },
+                },
+                None,
+            ),
+            Err(e) => (OllamaStats::default(), Some(e.to_string())),
+        };
+
+        ProfileReport {
+            locallm_version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            request_id: self.request_id,
+            started_at: self.started_at,
+            model: self.model,
+            endpoint: self.endpoint,
+            options: self.options,
+            prompt_messages: self.prompt_messages,
+            prompt_chars: self.prompt_chars,
+            wall_time_ms: self.started.elapsed().as_millis() as u64,
+            error,
+            ollama,
+            token_offsets_ms: token_offsets
+                .iter()
+                .map(|d| d.as_secs_f64() * 1000.0)
+                .collect(),
+            gpu_samples: self.gpu_samples,
+        }
+    }
+}
+
+/// Write the report under `<data dir>/profiles/` and return its path
+pub async fn save_report(report: ProfileReport) -> Result<PathBuf, String> {
+    let dir = Config::data_dir()
+        .map_err(|e| e.to_string())?
+        .join("profiles");
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+
+    let path = dir.join(format!(
+        "profile-{}-{}.json",
+        report.started_at, report.request_id
+    ));
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+
+    Ok(path)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
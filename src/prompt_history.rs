@@ -0,0 +1,44 @@
+use crate::config::Config;
+use std::path::PathBuf;
+
+/// Sent prompts kept for Up/Down recall, oldest dropped first
+const MAX_PROMPTS: usize = 200;
+
+/// Prompts sent from the input box, oldest first, in `prompts.json` in the
+/// data dir when `remember_prompts` is on
+fn path() -> Option<PathBuf> {
+    Config::data_dir().ok().map(|dir| dir.join("prompts.json"))
+}
+
+pub fn load() -> Vec<String> {
+    let Some(path) = path() else {
+        return Vec::new();
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .inspect_err(|e| tracing::warn!("Ignoring {}: {e}", path.display()))
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Add a prompt unless it repeats the last one
+pub fn push(prompts: &mut Vec<String>, prompt: &str) {
+    if prompts.last().is_some_and(|last| last == prompt) {
+        return;
+    }
+    prompts.push(prompt.to_string());
+    if prompts.len() > MAX_PROMPTS {
+        prompts.drain(..prompts.len() - MAX_PROMPTS);
+    }
+}
+
+/// Small enough to write synchronously; called once per sent prompt
+pub fn save(prompts: &[String]) -> Result<(), String> {
+    let path = path().ok_or("No data directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(prompts).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
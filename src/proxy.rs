@@ -0,0 +1,366 @@
+//! `locallm proxy`: an OpenAI-compatible API in front of Ollama that applies
+//! the same system prompt, templates, redaction and options as the GUI.
+
+use crate::config::Config;
+use crate::ollama::{ChatMessage, ChatOptions, ChatResponse, OllamaClient, OllamaError, StreamChunk};
+use crate::redact::Redactor;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::future::Either;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:11435";
+
+/// Header selecting one of the configured `prompt_templates` by name
+const TEMPLATE_HEADER: &str = "x-locallm-template";
+
+struct Proxy {
+    config: Config,
+    client: OllamaClient,
+    redactor: Redactor,
+    next_id: AtomicU64,
+}
+
+/// Serve until the process is killed
+pub fn run(config: Config, listen: Option<String>) -> Result<(), String> {
+    let listen = listen
+        .or_else(|| config.proxy_listen.clone())
+        .unwrap_or_else(|| DEFAULT_LISTEN.to_string());
+
+    let proxy = Arc::new(Proxy {
+        client: OllamaClient::from_config(&config),
+        redactor: Redactor::new(&config.redact_patterns),
+        config,
+        next_id: AtomicU64::new(1),
+    });
+
+    let app = Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(chat_completions))
+        .with_state(proxy);
+
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async move {
+        let listener = tokio::net::TcpListener::bind(&listen)
+            .await
+            .map_err(|e| format!("Failed to listen on {listen}: {e}"))?;
+        tracing::info!("OpenAI-compatible proxy listening on http://{listen}/v1");
+        axum::serve(listener, app).await.map_err(|e| e.to_string())
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionRequest {
+    #[serde(default)]
+    model: Option<String>,
+    messages: Vec<RequestMessage>,
+    #[serde(default)]
+    stream: bool,
+    #[serde(default)]
+    max_tokens: Option<i32>,
+    #[serde(default)]
+    max_completion_tokens: Option<i32>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    stop: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestMessage {
+    role: String,
+    /// A string, or an array of content parts of which only text is used
+    #[serde(default)]
+    content: Value,
+}
+
+impl RequestMessage {
+    fn text(&self) -> String {
+        match &self.content {
+            Value::String(s) => s.clone(),
+            Value::Array(parts) => parts
+                .iter()
+                .filter_map(|part| part.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Error in OpenAI's response shape
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<OllamaError> for ApiError {
+    fn from(e: OllamaError) -> Self {
+        let status = match e {
+            OllamaError::ModelNotFound(_) => StatusCode::NOT_FOUND,
+            OllamaError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            OllamaError::ContextExceeded(_) => StatusCode::BAD_REQUEST,
+            OllamaError::OutOfMemory(_) => StatusCode::SERVICE_UNAVAILABLE,
+            OllamaError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            OllamaError::Http(ref e) if e.is_timeout() => StatusCode::GATEWAY_TIMEOUT,
+            OllamaError::NotRunning(_) | OllamaError::ServerError(_) => StatusCode::BAD_GATEWAY,
+            OllamaError::Http(ref e) if e.is_connect() => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": {
+                "message": self.message,
+                "type": if self.status.is_client_error() { "invalid_request_error" } else { "api_error" },
+            }
+        });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+async fn list_models(State(proxy): State<Arc<Proxy>>) -> Result<Json<Value>, ApiError> {
+    let models = proxy.client.list_models().await?;
+    let data: Vec<Value> = models
+        .into_iter()
+        .map(|m| json!({ "id": m.name, "object": "model", "created": 0, "owned_by": "ollama" }))
+        .collect();
+    Ok(Json(json!({ "object": "list", "data": data })))
+}
+
+async fn chat_completions(
+    State(proxy): State<Arc<Proxy>>,
+    headers: HeaderMap,
+    Json(request): Json<CompletionRequest>,
+) -> Result<Response, ApiError> {
+    let id = proxy.next_id.fetch_add(1, Ordering::Relaxed);
+    let template = headers.get(TEMPLATE_HEADER).and_then(|v| v.to_str().ok());
+    let (model, messages, options) = proxy.prepare(&request, template)?;
+    tracing::info!(
+        "Proxy #{id}: {model}, {} messages{}{}",
+        messages.len(),
+        template.map(|t| format!(", template \"{t}\"")).unwrap_or_default(),
+        if request.stream { ", streaming" } else { "" },
+    );
+
+    let completion_id = format!("chatcmpl-locallm-{id}");
+    let started = Instant::now();
+
+    if !request.stream {
+        let response = proxy
+            .client
+            .chat(&model, messages, options)
+            .await
+            .inspect_err(|e| tracing::warn!("Proxy #{id} failed: {e}"))?;
+        log_completion(id, &response, started);
+
+        let content = response.message.as_ref().map(|m| m.content.clone()).unwrap_or_default();
+        return Ok(Json(json!({
+            "id": completion_id,
+            "object": "chat.completion",
+            "created": unix_now(),
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": content },
+                "finish_reason": "stop",
+            }],
+            "usage": usage(&response),
+        }))
+        .into_response());
+    }
+
+    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+    let chunk = {
+        let completion_id = completion_id.clone();
+        let model = model.clone();
+        move |delta: Value, finish_reason: Option<&str>| {
+            Event::default().data(
+                json!({
+                    "id": completion_id,
+                    "object": "chat.completion.chunk",
+                    "created": unix_now(),
+                    "model": model,
+                    "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+                })
+                .to_string(),
+            )
+        }
+    };
+
+    tokio::spawn(async move {
+        let _ = events_tx.send(chunk(json!({ "role": "assistant", "content": "" }), None));
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let request = proxy.client.chat_stream(&model, messages, options, tx);
+        let forward = async {
+            // Reasoning goes in `reasoning_content`, as DeepSeek's API sends it
+            while let Some(token) = rx.recv().await {
+                let delta = match token {
+                    StreamChunk::Content(content) => json!({ "content": content }),
+                    StreamChunk::Thinking(thinking) => json!({ "reasoning_content": thinking }),
+                };
+                let _ = events_tx.send(chunk(delta, None));
+            }
+        };
+        let generation = async { futures::join!(request, forward).0 };
+        // The client hanging up ends the request, which stops Ollama generating
+        let result = match futures::future::select(pin!(generation), pin!(events_tx.closed())).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => {
+                tracing::warn!("Proxy #{id} failed: the client disconnected");
+                return;
+            }
+        };
+
+        match result {
+            Ok(response) => {
+                log_completion(id, &response, started);
+                let _ = events_tx.send(chunk(json!({}), Some("stop")));
+            }
+            Err(e) => {
+                tracing::warn!("Proxy #{id} failed: {e}");
+                let error = json!({ "error": { "message": e.to_string(), "type": "api_error" } });
+                let _ = events_tx.send(Event::default().data(error.to_string()));
+            }
+        }
+        let _ = events_tx.send(Event::default().data("[DONE]"));
+    });
+
+    let stream = futures::stream::poll_fn(move |cx| {
+        events_rx.poll_recv(cx).map(|event| event.map(Ok::<_, Infallible>))
+    });
+    Ok(Sse::new(stream).into_response())
+}
+
+impl Proxy {
+    /// Turn an OpenAI request into what LocalLM would send to Ollama
+    fn prepare(
+        &self,
+        request: &CompletionRequest,
+        template: Option<&str>,
+    ) -> Result<(String, Vec<ChatMessage>, ChatOptions), ApiError> {
+        let model = request
+            .model
+            .clone()
+            .filter(|m| !m.is_empty())
+            .or_else(|| self.config.default_model.clone())
+            .ok_or_else(|| ApiError::bad_request("No model given and no default_model configured"))?;
+
+        let template = match template {
+            Some(name) => Some(
+                self.config
+                    .prompt_templates
+                    .iter()
+                    .find(|t| t.name == name)
+                    .ok_or_else(|| ApiError::bad_request(format!("Unknown template \"{name}\"")))?,
+            ),
+            None => None,
+        };
+
+        let mut messages: Vec<ChatMessage> = request
+            .messages
+            .iter()
+            .map(|m| ChatMessage {
+                role: m.role.clone(),
+                content: self.redactor.apply(&m.text()),
+                thinking: None,
+            })
+            .collect();
+
+        // The template wraps the latest user message, like it does in the GUI
+        if let Some(template) = template {
+            if let Some(last) = messages.iter_mut().rev().find(|m| m.role == "user") {
+                last.content = template.render(&last.content);
+            }
+        }
+
+        let system_prompt = template
+            .and_then(|t| t.system_prompt.clone())
+            .or_else(|| self.config.system_prompt_for(&model).map(String::from));
+        if let Some(system) = system_prompt {
+            if !messages.iter().any(|m| m.role == "system") {
+                messages.insert(
+                    0,
+                    ChatMessage {
+                        role: "system".to_string(),
+                        content: system,
+                        thinking: None,
+                    },
+                );
+            }
+        }
+
+        let mut options = self.config.options_for(&model);
+        if let Some(temperature) = request.temperature {
+            options.temperature = Some(temperature);
+        }
+        if let Some(max) = request.max_completion_tokens.or(request.max_tokens) {
+            options.num_predict = Some(max);
+        }
+        match &request.stop {
+            Some(Value::String(stop)) => options.stop = vec![stop.clone()],
+            Some(Value::Array(stops)) => {
+                options.stop = stops
+                    .iter()
+                    .filter_map(|s| s.as_str().map(str::to_string))
+                    .collect();
+            }
+            _ => {}
+        }
+
+        Ok((model, messages, options))
+    }
+}
+
+fn usage(response: &ChatResponse) -> Value {
+    let prompt = response.prompt_eval_count.unwrap_or(0);
+    let completion = response.eval_count.unwrap_or(0);
+    json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": prompt + completion,
+    })
+}
+
+fn log_completion(id: u64, response: &ChatResponse, started: Instant) {
+    tracing::info!(
+        "Proxy #{id}: done, {} prompt + {} completion tokens in {:.1}s",
+        response.prompt_eval_count.unwrap_or(0),
+        response.eval_count.unwrap_or(0),
+        started.elapsed().as_secs_f32(),
+    );
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,332 @@
+//! "Use my documents": files from `document_folders`, split into chunks,
+//! embedded with Ollama and kept in `documents.sqlite` in the data dir.
+//! Questions are embedded the same way and answered with the closest chunks.
+
+use crate::config::Config;
+use crate::ingest;
+use crate::ollama::OllamaClient;
+pub use locallm_core::history::Source;
+use rusqlite::{params, Connection};
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+/// Chunk size in characters, with some overlap so a sentence cut in two
+/// still appears whole in one of the chunks
+const CHUNK_CHARS: usize = 1500;
+const CHUNK_OVERLAP: usize = 200;
+
+/// Chunks sent to `/api/embed` per request
+const EMBED_BATCH: usize = 16;
+
+/// What a (re)index run did
+#[derive(Debug, Clone)]
+pub struct IndexReport {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    /// Files that couldn't be read, with the reason
+    pub failed: Vec<String>,
+}
+
+/// A file found in the folders, with its modification time
+struct FoundFile {
+    path: PathBuf,
+    modified: i64,
+}
+
+fn db_path() -> Result<PathBuf, String> {
+    Ok(Config::data_dir()
+        .map_err(|e| e.to_string())?
+        .join("documents.sqlite"))
+}
+
+fn open() -> Result<Connection, String> {
+    let path = db_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    let conn = Connection::open(&path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS files (
+             path TEXT PRIMARY KEY,
+             modified INTEGER NOT NULL,
+             model TEXT NOT NULL
+         );
+         CREATE TABLE IF NOT EXISTS chunks (
+             path TEXT NOT NULL,
+             text TEXT NOT NULL,
+             embedding BLOB NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS chunks_path ON chunks (path);",
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| e.to_string())?
+}
+
+/// Bring the index up to date with the folders: new and modified files are
+/// embedded, deleted ones dropped. Changing the embedding model re-embeds everything.
+pub async fn index(client: OllamaClient, model: String, folders: Vec<String>) -> Result<IndexReport, String> {
+    if folders.is_empty() {
+        return Err(String::from("No document folders configured"));
+    }
+    let found = blocking(move || Ok(find_files(&folders))).await?;
+
+    let model_for_query = model.clone();
+    let known: Vec<(String, i64, String)> = blocking(move || {
+        let conn = open()?;
+        let mut stmt = conn
+            .prepare("SELECT path, modified, model FROM files")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    })
+    .await?;
+
+    let mut report = IndexReport {
+        indexed: 0,
+        unchanged: 0,
+        removed: 0,
+        failed: Vec::new(),
+    };
+
+    // Files that disappeared from the folders
+    let gone: Vec<String> = known
+        .iter()
+        .filter(|(path, _, _)| !found.iter().any(|f| f.path.to_string_lossy() == path.as_str()))
+        .map(|(path, _, _)| path.clone())
+        .collect();
+    report.removed = gone.len();
+    if !gone.is_empty() {
+        blocking(move || {
+            let conn = open()?;
+            for path in gone {
+                remove_file(&conn, &path)?;
+            }
+            Ok(())
+        })
+        .await?;
+    }
+
+    for file in found {
+        let path = file.path.to_string_lossy().into_owned();
+        let up_to_date = known
+            .iter()
+            .any(|(p, modified, m)| *p == path && *modified == file.modified && *m == model_for_query);
+        if up_to_date {
+            report.unchanged += 1;
+            continue;
+        }
+
+        let source = file.path.clone();
+        let text = match blocking(move || ingest::extract_text(&source)).await {
+            Ok((text, _)) => text,
+            Err(e) => {
+                tracing::warn!("Skipping {path}: {e}");
+                report.failed.push(format!("{path}: {e}"));
+                continue;
+            }
+        };
+        let chunks = chunk(&text);
+        let mut embeddings = Vec::with_capacity(chunks.len());
+        for batch in chunks.chunks(EMBED_BATCH) {
+            let embedded = client
+                .embed(&model, batch)
+                .await
+                .map_err(|e| format!("Embedding with {model} failed: {e}"))?;
+            embeddings.extend(embedded);
+        }
+
+        let model = model.clone();
+        let modified = file.modified;
+        blocking(move || {
+            let mut conn = open()?;
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            remove_file(&tx, &path)?;
+            for (text, embedding) in chunks.iter().zip(&embeddings) {
+                tx.execute(
+                    "INSERT INTO chunks (path, text, embedding) VALUES (?1, ?2, ?3)",
+                    params![path, text, to_bytes(embedding)],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            tx.execute(
+                "INSERT INTO files (path, modified, model) VALUES (?1, ?2, ?3)",
+                params![path, modified, model],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.commit().map_err(|e| e.to_string())
+        })
+        .await?;
+        report.indexed += 1;
+    }
+
+    Ok(report)
+}
+
+fn remove_file(conn: &Connection, path: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM chunks WHERE path = ?1", params![path])
+        .and_then(|_| conn.execute("DELETE FROM files WHERE path = ?1", params![path]))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// The `top_k` chunks closest to the question
+pub async fn retrieve(
+    client: OllamaClient,
+    model: String,
+    question: String,
+    top_k: usize,
+) -> Result<Vec<Source>, String> {
+    let query = client
+        .embed(&model, &[question])
+        .await
+        .map_err(|e| format!("Embedding with {model} failed: {e}"))?
+        .pop()
+        .ok_or("Ollama returned no embedding")?;
+
+    blocking(move || {
+        let conn = open()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT chunks.path, chunks.text, chunks.embedding FROM chunks
+                 JOIN files ON files.path = chunks.path WHERE files.model = ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![model], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Vec<u8>>(2)?))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut scored: Vec<(f32, Source)> = Vec::new();
+        for row in rows {
+            let (path, text, embedding) = row.map_err(|e| e.to_string())?;
+            let score = cosine(&query, &from_bytes(&embedding));
+            scored.push((score, Source { path, text }));
+        }
+        if scored.is_empty() {
+            return Err(String::from("No documents indexed yet, index them in Settings"));
+        }
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored.into_iter().take(top_k).map(|(_, source)| source).collect())
+    })
+    .await
+}
+
+/// The question with the retrieved chunks in front, numbered for citation
+pub fn with_sources(question: &str, sources: &[Source]) -> String {
+    if sources.is_empty() {
+        return question.to_string();
+    }
+    let mut prompt = String::from(
+        "Answer using these excerpts from my documents where they are relevant, \
+         and cite the ones you use as [1], [2], ...\n\n",
+    );
+    for (n, source) in sources.iter().enumerate() {
+        prompt.push_str(&format!("[{}] {}:\n{}\n\n", n + 1, source.name(), source.text.trim()));
+    }
+    prompt.push_str(&format!("Question: {question}"));
+    prompt
+}
+
+/// An earlier question as it's sent again: its excerpts were for its own
+/// answer, only their names are kept so its [n] citations still resolve
+pub fn with_citations(question: &str, sources: &[Source]) -> String {
+    if sources.is_empty() {
+        return question.to_string();
+    }
+    let cited: Vec<String> = sources
+        .iter()
+        .enumerate()
+        .map(|(n, source)| format!("[{}] {}", n + 1, source.name()))
+        .collect();
+    format!("{question}\n\n(Excerpts used: {})", cited.join(", "))
+}
+
+fn find_files(folders: &[String]) -> Vec<FoundFile> {
+    let mut files = Vec::new();
+    for folder in folders {
+        let walker = WalkDir::new(folder)
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.'));
+        for entry in walker.filter_map(Result::ok) {
+            let indexed_type = entry
+                .path()
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| ingest::EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()));
+            if !entry.file_type().is_file() || !indexed_type {
+                continue;
+            }
+            let modified = entry
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as i64);
+            files.push(FoundFile {
+                path: entry.into_path(),
+                modified,
+            });
+        }
+    }
+    files
+}
+
+/// Split text into overlapping chunks, preferring to break at paragraphs or lines
+pub fn chunk(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = (start + CHUNK_CHARS).min(chars.len());
+        if end < chars.len() {
+            // Back up to a paragraph or line break in the second half of the chunk
+            let window = &chars[start + CHUNK_CHARS / 2..end];
+            let text: String = window.iter().collect();
+            if let Some(at) = text.rfind("\n\n").or_else(|| text.rfind('\n')) {
+                end = start + CHUNK_CHARS / 2 + text[..at].chars().count();
+            }
+        }
+        let piece: String = chars[start..end].iter().collect();
+        if !piece.trim().is_empty() {
+            chunks.push(piece.trim().to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(CHUNK_OVERLAP).max(start + 1);
+    }
+    chunks
+}
+
+fn to_bytes(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
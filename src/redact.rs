@@ -0,0 +1,32 @@
+use regex::Regex;
+
+const MASK: &str = "[REDACTED]";
+
+/// Masks configured patterns (API keys, emails, hostnames...) before text is sent to a model
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Compile the `redact_patterns` from the config, skipping invalid ones
+    pub fn new(patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| {
+                Regex::new(pattern)
+                    .inspect_err(|e| tracing::warn!("Ignoring redact pattern {pattern:?}: {e}"))
+                    .ok()
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        self.patterns
+            .iter()
+            .fold(text.to_string(), |text, pattern| {
+                pattern.replace_all(&text, MASK).into_owned()
+            })
+    }
+}
@@ -0,0 +1,167 @@
+//! `ollama serve` run by LocalLM itself, for machines where Ollama isn't a
+//! service: started from the status bar or on launch (`manage_server`), and
+//! stopped with SIGTERM so it takes its model runners down with it.
+
+use crate::config::Config;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long `ollama serve` gets to shut down its runners before it's killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `ollama serve` started by LocalLM (`manage_server`), for setups that don't
+/// run Ollama as a service. Its output goes to `ollama-serve.log` in the data
+/// dir. [`stop`](Self::stop) waits for it to quit; dropping it only asks it
+/// to and leaves the waiting to a thread of its own.
+pub struct OllamaServer {
+    pid: u32,
+    /// Taken when it's dropped, by the thread that sees it stop
+    child: Option<Child>,
+}
+
+/// Entries of the server menu in the status bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerAction {
+    Start,
+    Restart,
+    Stop,
+}
+
+impl fmt::Display for ServerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ServerAction::Start => "Start ollama serve",
+            ServerAction::Restart => "Restart ollama serve",
+            ServerAction::Stop => "Stop ollama serve",
+        })
+    }
+}
+
+/// Where the server's output goes
+pub fn log_path() -> Result<PathBuf, String> {
+    Ok(Config::data_dir().map_err(|e| e.to_string())?.join("ollama-serve.log"))
+}
+
+impl OllamaServer {
+    pub fn start(binary: &str, env: &BTreeMap<String, String>) -> Result<Self, String> {
+        let log_path = log_path()?;
+        if let Some(dir) = log_path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        }
+        let log = File::create(&log_path).map_err(|e| format!("Failed to create {}: {e}", log_path.display()))?;
+        let log_err = log.try_clone().map_err(|e| e.to_string())?;
+
+        let child = Command::new(binary)
+            .arg("serve")
+            .envs(env)
+            .stdin(Stdio::null())
+            .stdout(log)
+            .stderr(log_err)
+            .spawn()
+            .map_err(|e| format!("Failed to start {binary} serve: {e}"))?;
+
+        tracing::info!("Started {binary} serve (pid {})", child.id());
+        Ok(Self {
+            pid: child.id(),
+            child: Some(child),
+        })
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// How it ended, None while it's still running
+    pub fn exit_status(&mut self) -> Option<String> {
+        self.child.as_mut().and_then(exit_status)
+    }
+
+    /// Stop it and wait until it has. Blocks for up to `STOP_TIMEOUT`, so
+    /// it's for a background thread, not the UI's.
+    pub fn stop(mut self) {
+        if let Some(mut child) = self.child.take() {
+            shut_down(&mut child);
+        }
+    }
+}
+
+fn exit_status(child: &mut Child) -> Option<String> {
+    match child.try_wait() {
+        Ok(Some(status)) => Some(status.to_string()),
+        Ok(None) => None,
+        Err(e) => Some(e.to_string()),
+    }
+}
+
+/// Ask it to quit (SIGTERM), so it stops its model runners too, and kill it if
+/// it takes longer than `STOP_TIMEOUT`
+fn shut_down(child: &mut Child) {
+    if exit_status(child).is_none() {
+        let terminated = terminate(child);
+        wait_or_kill(child, terminated);
+    }
+}
+
+/// Wait for it to quit after a SIGTERM (if one could be sent) and kill it
+/// once `STOP_TIMEOUT` is up
+fn wait_or_kill(child: &mut Child, terminated: bool) {
+    if terminated {
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while Instant::now() < deadline {
+            if exit_status(child).is_some() {
+                tracing::info!("ollama serve stopped");
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+    if let Err(e) = child.kill() {
+        tracing::warn!("Failed to kill ollama serve: {e}");
+    }
+    let _ = child.wait();
+    tracing::info!("ollama serve killed");
+}
+
+/// Send SIGTERM, false where that isn't possible
+#[cfg(unix)]
+fn terminate(child: &Child) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(child.id()) else {
+        return false;
+    };
+    // SAFETY: kill only sends a signal, to a child we haven't reaped yet
+    if unsafe { libc::kill(pid, libc::SIGTERM) } == 0 {
+        return true;
+    }
+    tracing::warn!("Failed to send SIGTERM to ollama serve: {}", std::io::Error::last_os_error());
+    false
+}
+
+#[cfg(not(unix))]
+fn terminate(_: &Child) -> bool {
+    false
+}
+
+impl Drop for OllamaServer {
+    /// Dropped on the UI thread (on quit, say), so only the signal is sent
+    /// here and a thread of its own waits for it to take. If LocalLM exits
+    /// first, ollama serve still shuts itself down on the SIGTERM.
+    fn drop(&mut self) {
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+        if exit_status(&mut child).is_some() {
+            return;
+        }
+        let terminated = terminate(&child);
+        let spawned = std::thread::Builder::new()
+            .name(String::from("ollama-serve-stop"))
+            .spawn(move || wait_or_kill(&mut child, terminated));
+        if let Err(e) = spawned {
+            tracing::warn!("Failed to wait for ollama serve to stop: {e}");
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A starred answer, kept in `snippets.json` in the data dir across conversations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Title of the conversation it was starred in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Unix seconds
+    pub created_at: u64,
+}
+
+impl Snippet {
+    /// First line, short enough for the sidebar
+    pub fn preview(&self) -> String {
+        let line = self.content.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+        let mut preview: String = line.chars().take(60).collect();
+        if line.chars().count() > 60 || self.content.trim().lines().nth(1).is_some() {
+            preview.push('…');
+        }
+        preview
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    Config::data_dir().ok().map(|dir| dir.join("snippets.json"))
+}
+
+/// Newest first; read once at startup
+pub fn load() -> Vec<Snippet> {
+    let Some(path) = path() else {
+        return Vec::new();
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .inspect_err(|e| tracing::warn!("Ignoring {}: {e}", path.display()))
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Write atomically, the whole collection lives in one file
+pub async fn save(snippets: Vec<Snippet>) -> Result<(), String> {
+    let path = path().ok_or("No data directory")?;
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let json = serde_json::to_string_pretty(&snippets).map_err(|e| e.to_string())?;
+    tokio::fs::write(&tmp, json)
+        .await
+        .map_err(|e| format!("Failed to write {}: {e}", tmp.display()))?;
+    tokio::fs::rename(&tmp, &path)
+        .await
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
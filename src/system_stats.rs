@@ -0,0 +1,62 @@
+/// RAM and CPU usage, for models that run partly or fully on the CPU
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemStats {
+    pub ram_used_mb: u64,
+    pub ram_total_mb: u64,
+    pub cpu: CpuTimes,
+}
+
+/// Cumulative CPU time from /proc/stat, in clock ticks; usage is the
+/// difference between two readings
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+impl CpuTimes {
+    /// Percentage of the time since `earlier` that all CPUs were busy
+    pub fn usage_since(&self, earlier: &CpuTimes) -> f32 {
+        let total = self.total.saturating_sub(earlier.total);
+        if total == 0 {
+            return 0.0;
+        }
+        self.busy.saturating_sub(earlier.busy) as f32 / total as f32 * 100.0
+    }
+}
+
+/// Read /proc/meminfo and /proc/stat (Linux only)
+pub async fn read_system_stats() -> Option<SystemStats> {
+    let meminfo = tokio::fs::read_to_string("/proc/meminfo").await.ok()?;
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            .map(|kb| kb / 1024)
+    };
+    let ram_total_mb = field("MemTotal")?;
+    // Page cache is reclaimable, so "available" is what's really free
+    let ram_used_mb = ram_total_mb.saturating_sub(field("MemAvailable")?);
+
+    let stat = tokio::fs::read_to_string("/proc/stat").await.ok()?;
+    // cpu  user nice system idle iowait irq softirq steal ...
+    let ticks: Vec<u64> = stat
+        .lines()
+        .next()?
+        .strip_prefix("cpu ")?
+        .split_whitespace()
+        .filter_map(|n| n.parse().ok())
+        .collect();
+    let total: u64 = ticks.iter().take(8).sum();
+    let idle = ticks.get(3).copied().unwrap_or(0) + ticks.get(4).copied().unwrap_or(0);
+
+    Some(SystemStats {
+        ram_used_mb,
+        ram_total_mb,
+        cpu: CpuTimes {
+            busy: total.saturating_sub(idle),
+            total,
+        },
+    })
+}
@@ -0,0 +1,111 @@
+//! Message times for the chat and exports, in local time
+
+use crate::config::TimestampFormat;
+use std::time::Duration;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Broken-down local time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalTime {
+    pub year: i32,
+    /// 0-based, like the day of the year
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub year_day: u32,
+}
+
+/// `unix` seconds in the local time zone (UTC where that isn't available)
+#[cfg(unix)]
+pub fn local_time(unix: u64) -> LocalTime {
+    let time = unix as libc::time_t;
+    // SAFETY: localtime_r only writes to the tm we pass it
+    let tm = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&time, &mut tm);
+        tm
+    };
+    LocalTime {
+        year: tm.tm_year + 1900,
+        month: tm.tm_mon as u32,
+        day: tm.tm_mday as u32,
+        hour: tm.tm_hour as u32,
+        minute: tm.tm_min as u32,
+        year_day: tm.tm_yday as u32,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn local_time(unix: u64) -> LocalTime {
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (unix / 86_400) as i64;
+    let secs = unix % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 2 } else { mp - 10 } as u32;
+    let year = (yoe + era * 400 + i64::from(month < 2)) as i32;
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let before = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334][month as usize];
+    LocalTime {
+        year,
+        month,
+        day,
+        hour: (secs / 3600) as u32,
+        minute: (secs % 3600 / 60) as u32,
+        year_day: before + day - 1 + u32::from(leap && month > 1),
+    }
+}
+
+/// "14:05" today, "Mar 3, 14:05" earlier this year, "Mar 3 2025, 14:05" before that
+pub fn absolute(unix: u64, now: u64) -> String {
+    let time = local_time(unix);
+    let today = local_time(now);
+    let clock = format!("{:02}:{:02}", time.hour, time.minute);
+    let month = MONTHS[time.month as usize % 12];
+    if time.year != today.year {
+        format!("{month} {} {}, {clock}", time.day, time.year)
+    } else if time.year_day != today.year_day {
+        format!("{month} {}, {clock}", time.day)
+    } else {
+        clock
+    }
+}
+
+/// "just now", "5 min ago", "3 h ago", then the absolute time after a day
+pub fn relative(unix: u64, now: u64) -> String {
+    let ago = now.saturating_sub(unix);
+    match ago {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{} min ago", ago / 60),
+        3600..86_400 => format!("{} h ago", ago / 3600),
+        _ => absolute(unix, now),
+    }
+}
+
+pub fn format(unix: u64, now: u64, format: TimestampFormat) -> String {
+    match format {
+        TimestampFormat::Relative => relative(unix, now),
+        TimestampFormat::Absolute => absolute(unix, now),
+    }
+}
+
+/// "850 ms", "4.2 s", "1 min 5 s"
+pub fn duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f32();
+    if secs < 1.0 {
+        format!("{} ms", duration.as_millis())
+    } else if secs < 60.0 {
+        format!("{secs:.1} s")
+    } else {
+        format!("{} min {} s", duration.as_secs() / 60, duration.as_secs() % 60)
+    }
+}
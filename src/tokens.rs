@@ -0,0 +1,16 @@
+/// Rough token estimate for when Ollama hasn't told us the real count.
+/// ~4 characters per token holds up well enough for English text and code.
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+/// Compact token count for the status bar, e.g. "850" or "3.2k"
+pub fn format_tokens(tokens: u64) -> String {
+    if tokens < 1000 {
+        tokens.to_string()
+    } else if tokens < 10_000 {
+        format!("{:.1}k", tokens as f64 / 1000.0)
+    } else {
+        format!("{}k", tokens / 1000)
+    }
+}
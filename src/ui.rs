@@ -1,48 +1,548 @@
+mod benchmark;
+mod code_blocks;
+mod commands;
+mod completion;
+mod diff_view;
+mod embeddings;
+mod error_card;
+mod inspector;
+mod logs;
+mod math_view;
+mod model_info;
+mod modelfile;
+mod onboarding;
+mod params;
+mod quick_ask;
+mod settings;
+mod shortcuts;
+mod sidebar;
+mod sparkline;
+#[cfg(feature = "spellcheck")]
+mod spellcheck;
+mod storage;
+mod template_tests;
+mod themes;
+mod timing;
+mod toast;
+
+#[cfg(test)]
+mod tests;
+
 use crate::clipboard;
-use crate::config::Config;
-use crate::gpu_stats::{read_amd_gpu_stats, GpuStats};
-use crate::ollama::{ChatMessage, OllamaClient};
+use crate::export;
+use crate::config_watch;
+use crate::config::{Config, HistoryLimit, SubmitKey, TimestampFormat, UI_SCALE_RANGE};
+use crate::gpu_stats::{read_amd_gpu_stats, GpuHistory, GpuStats};
+use crate::history::{self, Alternative, Conversation, SavedMessage};
+use crate::hotkey::{self, GlobalHotkey};
+use crate::ingest;
+use crate::inhibit::{self, IdleInhibitor};
+use crate::math;
+use crate::model_cache;
+use crate::notify;
+use crate::profile::{self, ProfileRecorder};
+use crate::prompt_history;
+use crate::rag::{self, Source};
+use crate::redact::Redactor;
+use crate::server::{OllamaServer, ServerAction};
+use crate::snippets::{self, Snippet};
+use crate::system_stats::{read_system_stats, SystemStats};
+use crate::ollama::{self, ChatMessage, ChatOptions, ChatResponse, ModelShow, OllamaClient, RunningModel, StreamChunk};
+use crate::timestamps;
+use crate::templates::{ConversationTemplate, Profile, PromptTemplate, QuickPrompt, SeedTurn};
+use crate::tokens::{estimate_tokens, format_tokens};
+use crate::voice::{self, Recording};
+use crate::vram_fit::{self, Fit};
+use crate::window_state::WindowState;
+use crate::workspace::{self, Attachment, Workspace};
 use iced::widget::{
-    button, column, container, horizontal_space, pick_list, row, scrollable, text, text_editor,
-    vertical_space, Column,
+    button, center, checkbox, column, container, hover, horizontal_space, mouse_area, opaque, rich_text, span, stack, pick_list, row, scrollable, text, text_editor, text_input,
+    tooltip, vertical_space, Column,
 };
 use iced::keyboard;
+use iced::window;
 use iced::{Element, Length, Subscription, Task, Theme};
+use benchmark::{BenchResult, BenchmarkRun};
+use commands::SlashCommand;
+use completion::{CompletionEvent, CompletionPad};
+use embeddings::{Comparison, EmbeddingPlayground};
+use error_card::ErrorReport;
+use inspector::{Inspector, InspectorTab};
+use logs::LogView;
+use modelfile::ModelForm;
+use onboarding::{Onboarding, PullEvent};
+use params::{JsonCheck, OutputFormat, ParamsForm};
+use quick_ask::{QuickAsk, QuickAskEvent};
+use settings::SettingsForm;
+use shortcuts::Shortcut;
+use sparkline::Sparkline;
+use storage::{SortBy, StorageView};
+use template_tests::{TemplateTestRun, TestOutcome};
+use toast::{Severity, Toasts};
+use timing::TokenTiming;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::LazyLock;
 use std::time::Duration;
 
+static CHAT_SCROLL: LazyLock<scrollable::Id> = LazyLock::new(|| scrollable::Id::new("chat"));
+static SEARCH_INPUT: LazyLock<text_input::Id> = LazyLock::new(|| text_input::Id::new("search"));
+
+/// Messages longer than this many lines are cut, with "Show more" below
+const COLLAPSE_LINES: usize = 40;
+/// Chats with more messages than this only build the bubbles near the viewport
+const VIRTUALIZE_ABOVE: usize = 100;
+/// How far above and below the viewport bubbles are still built, in pixels
+const OVERSCAN: f32 = 1500.0;
+
+/// While Ollama is down it's checked again after this long, doubling up to the max
+const RECONNECT_MIN: Duration = Duration::from_secs(2);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+
+/// The unsent input is saved once typing has paused this long
+const DRAFT_PAUSE: Duration = Duration::from_secs(3);
+
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Message {
     // Input
     InputChanged(text_editor::Action),
     Submit,
+    CompleteCommand(&'static str),
+    ModelCommandPicked(String),
+    /// Workspace file picked for the `#mention` being typed
+    MentionPicked(String),
+    WorkspaceScanned(Result<Workspace, String>),
+    FileDropped(window::Id, PathBuf),
+    FileAttached(Result<Attachment, String>),
+    RemoveDroppedFile(usize),
+    Retry,
+    /// Ask for the rest of a response cut off by the token limit
+    Continue,
+    /// Send the prompt behind an assistant message to another model, for comparison
+    RetryWith(usize, String),
+    /// Make an alternative answer the one kept in the conversation
+    UseAlternative(usize, usize),
+    DismissAlternative(usize, usize),
+    CancelGeneration,
+
+    // Saved conversations
+    ConversationsLoaded(Result<Vec<Conversation>, String>),
+    ConversationSaved(Result<(), String>),
+    ShowSnippets(bool),
+    ToggleStar(usize),
+    InsertSnippet(String),
+    CopySnippet(String),
+    RemoveSnippet(String),
+    SnippetsSaved(Result<(), String>),
+    /// Save the unsent input, a few seconds after typing
+    SaveDraft,
+    DraftTick,
+    DraftLoaded(Option<String>),
+    ScratchpadLoaded(Option<String>),
+    /// Open a saved conversation, scrolled to a message if given
+    OpenConversation(String, Option<usize>),
+    DeleteConversation(String),
+    ConversationDeleted(Result<(), String>),
+    /// Generated title for a conversation, by id
+    TitleGenerated(String, Result<String, String>),
+    StartRename(String),
+    RenameChanged(String),
+    SubmitRename,
+    CancelRename,
+    ToggleSidebar,
+    StartConversationTemplate(String),
+    /// Switch to a profile, starting a new chat with its setup
+    ProfileSelected(String),
+    ClearProfile,
+    /// Ctrl+F: open the search bar, or focus it if it's open
+    OpenSearch,
+    SearchChanged(String),
+    CloseSearch,
 
     // Ollama
-    ModelsLoaded(Result<Vec<String>, String>),
+    ConfigReloaded(Result<Box<Config>, String>),
+    ModelsLoaded(Result<Vec<ollama::Model>, String>),
+    /// Background re-list, doesn't touch the status bar
+    ModelsRefreshed(Result<Vec<ollama::Model>, String>),
     ModelSelected(String),
+    ModelInfoLoaded(String, Result<ModelShow, String>),
+    ToggleModelInfo,
+    UnloadModel,
+    /// Free memory for a model that didn't fit
+    UnloadAllModels,
+    ModelUnloaded(Result<String, String>),
+    RunningModelsTick,
+    RunningModelsLoaded(Result<Vec<RunningModel>, String>),
     RefreshModels,
+    /// Next frame of the refresh button's spinner
+    SpinnerTick,
     OllamaStatus(bool),
+    /// Check whether Ollama is up (again)
+    CheckConnection,
+    ServerAction(ServerAction),
+    /// The server we started has shut down, and should start again if true
+    ServerStopped(bool),
+
+    // Generation parameters
+    ToggleParams,
+    NumPredictChanged(String),
+    TemperatureChanged(String),
+    NumCtxChanged(String),
+    StopSequenceChanged(usize, String),
+    AddStopSequence,
+    RemoveStopSequence(usize),
+    TemplateSelected(Option<String>),
+    ThinkToggled(bool),
+    OutputFormatSelected(OutputFormat),
+    UseDocumentsToggled(bool),
+    /// Excerpts found for the question just sent, and the request to send it with
+    DocumentsRetrieved(String, ChatOptions, Result<Vec<Source>, String>),
+    FormatSchemaChanged(String),
+    HistoryLimitChanged(String),
+    ProfileNextToggled(bool),
+    InspectToggled(bool),
+    InspectorTab(InspectorTab),
+    InspectorLine(String),
+    CopyInspector,
+    ProfileGpuTick,
+    ProfileGpuSampled(Vec<GpuStats>),
+    ProfileSaved(Result<std::path::PathBuf, String>),
+
+    // Template tests
+    OpenTemplateTests,
+    CloseTemplateTests,
+    TemplateTestModelSelected(String),
+    RunTemplateTests,
+    TemplateTestFinished(TestOutcome),
+    TemplateTestsDone,
+
+    // Benchmark
+    OpenBenchmark,
+    CloseBenchmark,
+    BenchmarkModelToggled(String, bool),
+    BenchmarkPromptsAction(text_editor::Action),
+    RunBenchmark,
+    StopBenchmark,
+    BenchmarkFinished(BenchResult),
+    BenchmarkDone,
+    ExportBenchmark,
+    BenchmarkExported(Result<PathBuf, String>),
+
+    // Embeddings playground
+    OpenEmbeddings,
+    CloseEmbeddings,
+    EmbeddingsModelPicked(String),
+    EmbeddingsTextsAction(text_editor::Action),
+    RunEmbeddings,
+    EmbeddingsCompared(Result<Comparison, String>),
+
+    // Model storage
+    OpenStorage,
+    CloseStorage,
+    RefreshStorage,
+    StorageLoaded(Result<Vec<ollama::Model>, String>),
+    StorageSortPicked(SortBy),
+    ConfirmDeleteModel(String),
+    CancelDeleteModel,
+    DeleteModel(String),
+    ModelDeleted(Result<String, String>),
+
+    // Create model
+    OpenModelForm(Option<String>),
+    CloseModelForm,
+    ModelFormBasePicked(String),
+    ModelFormBaseLoaded(String, Result<ModelShow, String>),
+    ModelFormNameChanged(String),
+    ModelFormSystemAction(text_editor::Action),
+    ModelFormParametersAction(text_editor::Action),
+    ModelFormTemplateAction(text_editor::Action),
+    CreateModel,
+    CancelCreateModel,
+    CreateModelEvent(PullEvent),
+
+    // First-run setup
+    OnboardingUrlChanged(String),
+    OnboardingCheck,
+    OnboardingChecked(bool),
+    OnboardingCustomChanged(String),
+    OnboardingPull(String),
+    OnboardingCancelPull,
+    PullEvent(PullEvent),
+    CloseOnboarding,
+
+    // Log viewer
+    OpenLogs,
+    LogsLoaded(Result<String, String>),
+    RefreshLogs,
+    CopyLogs,
+    CloseLogs,
 
     // Streaming response
-    ResponseComplete(Result<String, String>),
+    ResponseChunk(StreamChunk),
+    /// Redraw the elapsed time while waiting for the first token
+    GenerationTick,
+    ResponseComplete(Result<(ChatResponse, Option<TokenTiming>), ErrorReport>),
+    CopyDiagnostics,
+    DismissError,
 
     // Chat management
     ClearChat,
+    ToggleLock,
+    TrimHistory,
+    SummarizeHistory,
+    SummaryComplete(Result<Summary, String>),
+    DismissBudgetAlert,
+    AutoCopyToggled(bool),
     CopyMessage(usize),
+    /// Message index and code block index within it
+    CopyCode(usize, usize),
+    SaveCode(usize, usize),
+    CodeSaved(Result<(PathBuf, Option<String>), String>),
+    /// Mouse entered or left a message bubble
+    HoverMessage(Option<usize>),
+    CopyHoveredMessage,
+    /// Put the clipboard in the input box as a code block
+    AskClipboard,
+    CopyConversation,
+    ExportHtml,
+    HtmlExported(Result<PathBuf, String>),
+    DismissToast(u64),
+    ToggleToastDetail(u64),
+    ToastTick,
+    ClipboardPasted(Result<String, String>),
+    ToggleDictation,
+    QuickPrompt(usize),
+    QuickPromptClipboard(usize, Result<String, String>),
+    ToggleScratchpad,
+    ScratchpadAction(text_editor::Action),
+    ScratchpadInContextToggled(bool),
+    ToggleCompletionMode,
+    CompletionPromptAction(text_editor::Action),
+    CompletionSuffixChanged(String),
+    CompletionRawToggled(bool),
+    RunCompletion,
+    StopCompletion,
+    AcceptCompletion,
+    CompletionEvent(CompletionEvent),
+    Transcribed(Result<String, String>),
+    SendQueued,
+    RemoveQueued(usize),
+    NewConversation,
+    DuplicateConversation,
+    FocusModelPicker,
+    ScrollChat(f32),
+    ToggleThinking(usize),
+    ToggleExpanded(usize),
+    ToggleDiff(usize),
+    CopyDiffVersion(usize, diff_view::Version),
+    ChatScrolled(scrollable::Viewport),
+    DeleteMessage(usize),
+    DeleteExchange(usize),
+    ToggleCurate,
+    SetExcluded(usize, bool),
+    MoveMessage(usize, isize),
+    /// Show the previous (-1) or next (1) version of a message and what followed it
+    SwitchVariant(usize, isize),
+    EditMessage(usize),
+    CancelEdit,
     CopyComplete(Result<(), String>),
 
     // GPU stats
     GpuStatsTick,
-    GpuStatsUpdated(Option<GpuStats>),
+    GpuStatsUpdated(Vec<GpuStats>),
+    SystemStatsTick,
+    SystemStatsUpdated(Option<SystemStats>),
+
+    /// New UI scale from the zoom shortcuts, saved right away
+    SetUiScale(f32),
+
+    // Follow-system theme
+    SystemThemeTick,
+    SystemThemeDetected(bool),
+
+    // Settings
+    OpenSettings,
+    CloseSettings,
+    SettingsUrlChanged(String),
+    SettingsModelChanged(String),
+    SettingsSystemPromptAction(text_editor::Action),
+    SettingsAutoCopyToggled(bool),
+    SettingsGpuStatsToggled(bool),
+    SettingsSystemStatsToggled(bool),
+    SettingsTimestampsToggled(bool),
+    SettingsTimestampFormatPicked(TimestampFormat),
+    SettingsSubmitKeyPicked(SubmitKey),
+    SettingsInputStatsToggled(bool),
+    SettingsSpellcheckChanged(String),
+    SettingsThemePicked(themes::ThemeChoice),
+    SettingsUiScaleChanged(String),
+    SettingsResponseSoftCapChanged(String),
+    SettingsNotifyAfterChanged(String),
+    Notified(Result<(), String>),
+    SettingsUserColorChanged(String),
+    SettingsAssistantColorChanged(String),
+    SettingsHighContrastToggled(bool),
+    SettingsProxyBypassToggled(bool),
+    SettingsManageServerToggled(bool),
+    SettingsApiKeyChanged(String),
+    SettingsProxyChanged(String),
+    SettingsOllamaBinaryChanged(String),
+    SettingsReducedMotionToggled(bool),
+    SettingsSummarizeAfterChanged(String),
+    SettingsTokenBudgetChanged(String),
+    SettingsTokenTimingToggled(bool),
+    SettingsRememberPromptsToggled(bool),
+    SettingsShowThinkingToggled(bool),
+    SettingsKeepAliveChanged(String),
+    SettingsInhibitIdleToggled(bool),
+    TestConnection,
+    ConnectionTested(bool),
+    ApplySettings,
     
+    SettingsQuickAskHotkeyChanged(String),
+    SettingsClipboardPromptChanged(String),
+    SettingsDocumentFoldersAction(text_editor::Action),
+    SettingsEmbeddingModelChanged(String),
+    SettingsDocumentTopKChanged(String),
+    IndexDocuments,
+    DocumentsIndexed(Result<rag::IndexReport, String>),
+
+    // Quick-ask popup
+    QuickAskToggle,
+    QuickAskInputChanged(String),
+    QuickAskSubmit,
+    QuickAskEvent(QuickAskEvent),
+    QuickAskCopied(Result<(), String>),
+    OpenMainWindow,
+
+    // Window
+    WindowEvent(window::Id, window::Event),
+    WindowMinimized(Option<bool>),
+    /// New main window size and whether it's maximized
+    WindowResized(iced::Size, bool),
+    WindowClosed(window::Id),
+
     // Keyboard
+    Shortcut(window::Id, Shortcut),
     ShiftPressed,
     ShiftReleased,
+    #[cfg(feature = "spellcheck")]
+    DictionaryLoaded(Result<std::sync::Arc<spellcheck::Dictionary>, String>),
+    /// Ctrl, or Cmd on macOS
+    CommandPressed,
+    CommandReleased,
 }
 
 #[derive(Debug, Clone)]
 pub struct ChatEntry {
     pub role: String,
     pub content: String,
+    /// Chunk arrival times, recorded when `record_token_timing` is on
+    pub timing: Option<TokenTiming>,
+    /// Reasoning the model returned apart from its answer
+    pub thinking: Option<String>,
+    pub show_thinking: bool,
+    /// A long message shown in full rather than cut at [`COLLAPSE_LINES`]
+    pub expanded: bool,
+    /// An answer shown as a diff against the text the question asked to revise
+    pub diff: Option<diff_view::Diff>,
+    /// Kept in the chat but left out of what the model sees
+    pub excluded: bool,
+    /// Workspace files mentioned with `#path`, sent along with the message
+    pub attachments: Vec<Attachment>,
+    /// Document excerpts added to a question by "Use my documents"
+    pub sources: Vec<Source>,
+    /// Model that wrote an assistant message
+    pub model: Option<String>,
+    /// Answers from other models to the same prompt, via "Retry with…"
+    pub alternatives: Vec<Alternative>,
+    /// Set for answers requested as JSON
+    pub json: Option<JsonCheck>,
+    /// Ended at the token limit rather than where the model wanted to stop
+    pub truncated: bool,
+    /// Other versions of the conversation from this message on, each the
+    /// message and everything after it; ◀ ▶ on the bubble switches to one
+    pub variants: Vec<Vec<ChatEntry>>,
+    /// Where this version sits among all of them
+    pub variant_pos: usize,
+    /// Unix seconds when it was sent or finished, unknown for older saves
+    pub created_at: Option<u64>,
+    /// Time the answer took, from the request to the last token
+    pub duration: Option<Duration>,
+}
+
+
+impl ChatEntry {
+    fn to_saved(&self) -> SavedMessage {
+        SavedMessage {
+            role: self.role.clone(),
+            content: self.content.clone(),
+            model: self.model.clone(),
+            thinking: self.thinking.clone(),
+            excluded: self.excluded,
+            attachments: self.attachments.clone(),
+            sources: self.sources.clone(),
+            alternatives: self.alternatives.clone(),
+            json: self.json.is_some(),
+            truncated: self.truncated,
+            variants: self
+                .variants
+                .iter()
+                .map(|tail| tail.iter().map(ChatEntry::to_saved).collect())
+                .collect(),
+            variant_pos: self.variant_pos,
+            created_at: self.created_at,
+            duration_ms: self.duration.map(|d| d.as_millis() as u64),
+        }
+    }
+}
+
+impl From<SavedMessage> for ChatEntry {
+    fn from(saved: SavedMessage) -> Self {
+        // The schema isn't saved, only whether it parses is checked again
+        let json = saved.json.then(|| params::check_json(&saved.content, None).1);
+        Self {
+            role: saved.role,
+            content: saved.content,
+            timing: None,
+            thinking: saved.thinking,
+            show_thinking: false,
+            expanded: false,
+            diff: None,
+            excluded: saved.excluded,
+            attachments: saved.attachments,
+            sources: saved.sources,
+            truncated: saved.truncated,
+            variants: saved
+                .variants
+                .into_iter()
+                .map(|tail| tail.into_iter().map(ChatEntry::from).collect())
+                .collect(),
+            variant_pos: saved.variant_pos,
+            created_at: saved.created_at,
+            duration: saved.duration_ms.map(Duration::from_millis),
+            model: saved.model,
+            alternatives: saved.alternatives,
+            json,
+        }
+    }
+}
+
+/// Add `old`, a replaced tail of the chat with its own branches, to the branches
+/// of `entry`, which starts the new tail and becomes the last version
+fn add_variant(entry: &mut ChatEntry, mut old: Vec<ChatEntry>) {
+    let mut variants = std::mem::take(&mut old[0].variants);
+    variants.insert(old[0].variant_pos.min(variants.len()), old);
+    entry.variant_pos = variants.len();
+    entry.variants = variants;
+}
+
+/// Condensed stand-in for the first `covers` history entries when talking to the model
+#[derive(Debug, Clone)]
+pub struct Summary {
+    covers: usize,
+    text: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -52,72 +552,491 @@ enum Status {
     Generating,
 }
 
+enum Screen {
+    Chat,
+    Settings(Box<SettingsForm>),
+    TemplateTests(TemplateTestRun),
+    Logs(LogView),
+    Onboarding(Onboarding),
+    Benchmark(BenchmarkRun),
+    Embeddings(EmbeddingPlayground),
+    Storage(StorageView),
+    ModelForm(ModelForm),
+}
+
 pub struct App {
     config: Config,
     client: OllamaClient,
+    /// Compiled `redact_patterns`, applied to everything we send
+    redactor: Redactor,
 
     // Models
     available_models: Vec<String>,
     selected_model: Option<String>,
+    /// /api/show response for the selected model
+    model_info: Option<ModelShow>,
+    /// Why the last /api/show failed, shown in place of the details
+    model_info_error: Option<String>,
+    show_model_info: bool,
+    /// Models the server currently holds in memory, from /api/ps
+    running_models: Vec<RunningModel>,
 
     // Chat state
     chat_history: Vec<ChatEntry>,
     input_content: text_editor::Content,
+    /// Last typed into, if that isn't saved yet
+    draft_edited_at: Option<std::time::Instant>,
+    /// Setup screen skipped, don't bring it back this session
+    onboarding_dismissed: bool,
     status: Status,
     status_message: String,
+    /// Token count Ollama reported after the last response, and how many
+    /// history entries it covered
+    measured_tokens: Option<(usize, u64)>,
+    /// Shown as a card at the end of the chat until the next request
+    last_error: Option<ErrorReport>,
+    next_request_id: u64,
+    summary: Option<Summary>,
+    summarizing: bool,
+    /// Set with `/system`, overrides the configured system prompt until restart
+    session_system_prompt: Option<String>,
+    /// Prompt + generated tokens Ollama reported over the whole conversation
+    tokens_used: u64,
+    /// The user closed the `token_budget` alert for this conversation
+    budget_dismissed: bool,
+    /// Read-only conversation: no new messages, no edits
+    locked: bool,
+    /// Show include checkboxes and move buttons on every message
+    curating: bool,
+    /// Held while generating when `inhibit_idle` is on, released on drop
+    idle_inhibitor: Option<IdleInhibitor>,
+    /// Recording a profiled request in flight
+    profiler: Option<ProfileRecorder>,
+    /// Aborts the request in flight
+    generation: Option<iced::task::Handle>,
+    /// The response so far and how many chunks (≈ tokens) it arrived in
+    streaming: String,
+    /// Reasoning streamed in the separate `thinking` field
+    streaming_thinking: String,
+    streamed_chunks: u64,
+    /// Bubble under the mouse, the target of Ctrl+C
+    hovered_message: Option<usize>,
+    /// Project whose files can be mentioned with `#path`
+    workspace: Option<Workspace>,
+    /// Files dropped on the window, sent with the next message
+    dropped_files: Vec<Attachment>,
+    /// Prompts sent while an answer was streaming, oldest first
+    queued: Vec<String>,
+    /// Installed model sizes in bytes, for the VRAM estimate
+    model_sizes: HashMap<String, u64>,
+    /// Model the user was warned won't fit in VRAM; sending again goes ahead
+    vram_warned: Option<String>,
+    /// Assistant message the request in flight answers again with another model
+    compare_target: Option<usize>,
+    /// The request in flight continues the last (truncated) answer
+    continuing: bool,
+    /// Answer the request in flight regenerates, kept as a branch of the new one
+    /// or put back if it fails
+    replaced: Option<Vec<ChatEntry>>,
+    /// User message being edited in the input box; sending it starts a new branch
+    editing: Option<usize>,
+    /// Model of the request in flight
+    generating_model: Option<String>,
+    /// When the current request was sent, for answers cut short before Ollama's stats
+    generation_started: Option<std::time::Instant>,
+    /// Time to the first token (reasoning included) of the request in flight
+    first_token_after: Option<Duration>,
+    /// The model was in memory when the request went out, so the wait
+    /// before the first token is prompt processing, not loading
+    model_was_loaded: bool,
+    /// Last scroll position of the chat, to build only the bubbles in view
+    chat_viewport: Option<scrollable::Viewport>,
+    /// `format` of the request in flight, its answer is checked against it
+    generating_format: Option<serde_json::Value>,
+    /// Saved conversations, most recently updated first
+    conversations: Vec<Conversation>,
+    /// Id the current chat is saved under, None until its first reply
+    conversation_id: Option<String>,
+    show_sidebar: bool,
+    /// Name of the profile whose setup new chats start with
+    active_profile: Option<String>,
+    /// Prompts sent from the input box, oldest first, for Up/Down recall
+    prompts: Vec<String>,
+    /// Which of them is in the input box while stepping through with Up/Down
+    recall: Option<usize>,
+    /// What was typed before Ctrl+K put `/model ` in the input, back once a
+    /// command runs
+    picker_draft: Option<String>,
+    /// Starred answers, newest first
+    snippets: Vec<Snippet>,
+    /// The sidebar lists snippets instead of conversations
+    show_snippets: bool,
+    /// Conversation being renamed in the sidebar and the new title so far
+    renaming: Option<(String, String)>,
+    /// A model list request is in flight
+    refreshing_models: bool,
+    spinner_frame: usize,
+    /// When the model list was last fetched, and why the latest refresh failed
+    /// if it did (the list shown may be out of date)
+    models_fetched_at: Option<std::time::Instant>,
+    models_error: Option<String>,
+    /// `ollama serve` started by us (`manage_server`)
+    server: Option<OllamaServer>,
+    /// Notes kept next to the chat, saved with the conversation and sent
+    /// ahead of the history when `scratchpad_in_context` is on
+    show_scratchpad: bool,
+    scratchpad: text_editor::Content,
+    /// Last chat request and its raw response, shown with `params.inspect`
+    inspector: Inspector,
+    scratchpad_in_context: bool,
+    /// Edited since the conversation was last saved
+    scratchpad_edited: bool,
+    /// Notifications stacked over the corner of the window
+    toasts: Toasts,
+    /// Toast for the copy in flight, shown once it succeeded
+    copy_notice: Option<String>,
+    /// Raw completions through `/api/generate`, shown instead of the chat when on
+    completion_mode: bool,
+    completion: CompletionPad,
+    /// Dictation being recorded, and whether the last one is being transcribed
+    recording: Option<Recording>,
+    transcribing: bool,
+    /// Starting it automatically was tried since the connection was lost
+    server_autostart_tried: bool,
+    /// Wait before the next health check while disconnected
+    reconnect_delay: Duration,
+    /// A health check is in flight
+    checking_connection: bool,
+    /// The list is from the on-disk cache, the server hasn't answered yet
+    models_cached: bool,
+    /// Search bar text, None while it's closed
+    search: Option<String>,
+
+    // Generation parameters panel
+    params: ParamsForm,
+    show_params: bool,
 
     // GPU stats
-    gpu_stats: Option<GpuStats>,
-    
+    /// Last RAM/CPU reading, and CPU usage since the one before
+    system_stats: Option<SystemStats>,
+    cpu_percent: Option<f32>,
+    /// One entry per card
+    gpu_stats: Vec<GpuStats>,
+    /// Recent samples of each card for the sparklines next to the stats
+    gpu_history: Vec<GpuHistory>,
+
+    screen: Screen,
+
+    // Windows; the main one can be closed while running in the background
+    main_window: Option<window::Id>,
+    quick_ask: Option<QuickAsk>,
+    /// Registered `quick_ask_hotkey`, unregistered on drop
+    hotkey: Option<GlobalHotkey>,
+    /// Keep running with no windows open so the quick-ask popup stays instant
+    background: bool,
+
+    // Window visibility, used to throttle background polling
+    window_focused: bool,
+    window_minimized: bool,
+    /// Main window geometry, saved when it closes
+    window_state: WindowState,
+
+    /// Desktop prefers dark colors, for `theme = "system"`
+    system_dark: bool,
+
     // Track if shift is held
     shift_held: bool,
+    command_held: bool,
+    /// Spellcheck dictionary for the input, once loaded
+    #[cfg(feature = "spellcheck")]
+    dictionary: Option<std::sync::Arc<spellcheck::Dictionary>>,
 }
 
 impl App {
-    pub fn new(config: Config) -> (Self, Task<Message>) {
-        let client = OllamaClient::new(&config.ollama_url);
+    /// Start with the main window, or only the quick-ask popup for `--quick`
+    pub fn new(config: Config, quick: bool, workspace: Option<PathBuf>) -> (Self, Task<Message>) {
+        let client = OllamaClient::from_config(&config);
+        themes::ThemeChoice::warn_if_unknown(config.theme.as_deref());
+        let mut params = ParamsForm::from_options(&config.options);
+        params.history_limit = config.history_limit.map(|l| l.to_string()).unwrap_or_default();
+        let prompts = if config.remember_prompts { prompt_history::load() } else { Vec::new() };
+        let hotkey = config
+            .quick_ask_hotkey
+            .as_deref()
+            .and_then(|spec| GlobalHotkey::register(spec).inspect_err(|e| tracing::warn!("{e}")).ok());
 
-        let app = Self {
+        let window_state = WindowState::load();
+        let (main_window, quick_ask, open_window) = if quick {
+            let (popup, task) = QuickAsk::open();
+            (None, Some(popup), task)
+        } else {
+            let (id, task) = open_main_window(&window_state);
+            (Some(id), None, task)
+        };
+
+        let mut app = Self {
+            redactor: Redactor::new(&config.redact_patterns),
             config,
             client: client.clone(),
             available_models: Vec::new(),
             selected_model: None,
+            model_info: None,
+            model_info_error: None,
+            show_model_info: false,
+            running_models: Vec::new(),
             chat_history: Vec::new(),
             input_content: text_editor::Content::new(),
+            draft_edited_at: None,
+            onboarding_dismissed: false,
             status: Status::Disconnected,
             status_message: String::from("Connecting to Ollama..."),
-            gpu_stats: None,
+            measured_tokens: None,
+            last_error: None,
+            next_request_id: 1,
+            streaming: String::new(),
+            streaming_thinking: String::new(),
+            streamed_chunks: 0,
+            summary: None,
+            summarizing: false,
+            session_system_prompt: None,
+            tokens_used: 0,
+            budget_dismissed: false,
+            locked: false,
+            curating: false,
+            idle_inhibitor: None,
+            profiler: None,
+            generation: None,
+            hovered_message: None,
+            workspace: None,
+            dropped_files: Vec::new(),
+            queued: Vec::new(),
+            model_sizes: HashMap::new(),
+            vram_warned: None,
+            compare_target: None,
+            continuing: false,
+            replaced: None,
+            editing: None,
+            generating_model: None,
+            generation_started: None,
+            first_token_after: None,
+            model_was_loaded: false,
+            chat_viewport: None,
+            generating_format: None,
+            conversations: Vec::new(),
+            conversation_id: None,
+            show_sidebar: false,
+            active_profile: None,
+            prompts,
+            recall: None,
+            picker_draft: None,
+            snippets: snippets::load(),
+            show_snippets: false,
+            renaming: None,
+            refreshing_models: false,
+            spinner_frame: 0,
+            models_fetched_at: None,
+            models_error: None,
+            server: None,
+            show_scratchpad: false,
+            scratchpad: text_editor::Content::new(),
+            inspector: Inspector::default(),
+            scratchpad_in_context: false,
+            scratchpad_edited: false,
+            toasts: Toasts::default(),
+            copy_notice: None,
+            completion_mode: false,
+            completion: CompletionPad::default(),
+            recording: None,
+            transcribing: false,
+            server_autostart_tried: false,
+            reconnect_delay: RECONNECT_MIN,
+            checking_connection: true,
+            models_cached: false,
+            search: None,
+            params,
+            show_params: false,
+            system_stats: None,
+            cpu_percent: None,
+            gpu_stats: Vec::new(),
+            gpu_history: Vec::new(),
+            screen: Screen::Chat,
+            main_window,
+            quick_ask,
+            background: quick || hotkey.is_some(),
+            hotkey,
+            window_focused: true,
+            window_minimized: main_window.is_none(),
+            window_state,
+            system_dark: true,
             shift_held: false,
+            command_held: false,
+            #[cfg(feature = "spellcheck")]
+            dictionary: None,
         };
 
+        app.show_cached_models();
+
         // Initial tasks: check Ollama status and load models
         let check_task = Task::perform(
             async move { client.health_check().await.unwrap_or(false) },
             Message::OllamaStatus,
         );
 
-        (app, check_task)
+        let theme_task = if app.follows_system_theme() {
+            Task::done(Message::SystemThemeTick)
+        } else {
+            Task::none()
+        };
+
+        let workspace_task = match workspace {
+            Some(dir) => Task::perform(Workspace::scan_async(dir), Message::WorkspaceScanned),
+            None => Task::none(),
+        };
+
+        let history_task = Task::perform(history::load_all(), Message::ConversationsLoaded);
+        let draft_task = Task::batch([
+            Task::perform(history::load_draft(), Message::DraftLoaded),
+            Task::perform(history::load_scratchpad(), Message::ScratchpadLoaded),
+        ]);
+        let dictionary_task = app.load_dictionary();
+
+        (
+            app,
+            Task::batch([
+                open_window,
+                check_task,
+                theme_task,
+                workspace_task,
+                history_task,
+                draft_task,
+                dictionary_task,
+            ]),
+        )
+    }
+
+    pub fn title(&self, window: window::Id) -> String {
+        if self.quick_ask.as_ref().is_some_and(|q| q.window == window) {
+            return String::from("LocalLM Quick Ask");
+        }
+        let current = self
+            .conversation_id
+            .as_ref()
+            .and_then(|id| self.conversations.iter().find(|c| &c.id == id));
+        match current {
+            Some(conversation) => format!("{} — LocalLM", conversation.title),
+            None => String::from("LocalLM"),
+        }
+    }
+
+    /// Applies to every window, so the quick-ask popup zooms along
+    pub fn scale_factor(&self, _window: window::Id) -> f64 {
+        f64::from(self.config.ui_scale)
     }
 
-    pub fn title(&self) -> String {
-        String::from("LocalLM")
+    pub fn theme(&self, _window: window::Id) -> Theme {
+        themes::resolve(self.config.theme.as_deref(), self.config.high_contrast, self.system_dark)
     }
 
-    pub fn theme(&self) -> Theme {
-        Theme::TokyoNightStorm
+    fn follows_system_theme(&self) -> bool {
+        themes::ThemeChoice::from_config(self.config.theme.as_deref()) == themes::ThemeChoice::System
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        let gpu_sub = if self.config.show_gpu_stats {
-            iced::time::every(Duration::from_secs(2)).map(|_| Message::GpuStatsTick)
+        // Poll GPU stats less often in the background, not at all while minimized
+        let gpu_sub = if self.config.show_gpu_stats && !self.window_minimized {
+            let interval = if self.window_focused { 2 } else { 10 };
+            iced::time::every(Duration::from_secs(interval)).map(|_| Message::GpuStatsTick)
+        } else {
+            Subscription::none()
+        };
+        let system_sub = if self.config.show_system_stats && !self.window_minimized {
+            let interval = if self.window_focused { 2 } else { 10 };
+            iced::time::every(Duration::from_secs(interval)).map(|_| Message::SystemStatsTick)
         } else {
             Subscription::none()
         };
 
-        // Track shift key state
+        // Desktops switch between light and dark on a schedule
+        let theme_sub = if self.follows_system_theme() {
+            iced::time::every(Duration::from_secs(30)).map(|_| Message::SystemThemeTick)
+        } else {
+            Subscription::none()
+        };
+
+        // Sample the GPU densely while a profiled request runs
+        let profile_sub = if self.profiler.is_some() {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::ProfileGpuTick)
+        } else {
+            Subscription::none()
+        };
+
+        // Which models hold VRAM changes behind our back (keep_alive expiry, other clients)
+        let ps_sub = if self.status != Status::Disconnected && !self.window_minimized {
+            let interval = if self.window_focused { 5 } else { 30 };
+            iced::time::every(Duration::from_secs(interval)).map(|_| Message::RunningModelsTick)
+        } else {
+            Subscription::none()
+        };
+
+        let reconnect_sub = self.reconnect_subscription();
+
+        let toast_sub = if self.toasts.is_empty() {
+            Subscription::none()
+        } else {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::ToastTick)
+        };
+
+        // Unsent input survives a crash, saved once typing pauses
+        let draft_sub = if self.draft_edited_at.is_some() {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::DraftTick)
+        } else {
+            Subscription::none()
+        };
+
+        let waiting_sub = if self.status == Status::Generating && self.first_token_after.is_none() {
+            let interval = if self.config.reduced_motion { 1000 } else { 100 };
+            iced::time::every(Duration::from_millis(interval)).map(|_| Message::GenerationTick)
+        } else {
+            Subscription::none()
+        };
+
+        let spinner_sub = if self.refreshing_models && !self.config.reduced_motion {
+            iced::time::every(Duration::from_millis(100)).map(|_| Message::SpinnerTick)
+        } else {
+            Subscription::none()
+        };
+
+        // Only the window events we care about, RedrawRequested would fire every frame
+        let window_sub = iced::event::listen_with(|event, _status, id| match event {
+            iced::Event::Window(
+                event @ (window::Event::Focused
+                | window::Event::Unfocused
+                | window::Event::Resized(_)
+                | window::Event::Moved(_)),
+            ) => Some(Message::WindowEvent(id, event)),
+            iced::Event::Window(window::Event::FileDropped(path)) => Some(Message::FileDropped(id, path)),
+            _ => None,
+        });
+        let close_sub = window::close_events().map(Message::WindowClosed);
+
+        // Quick-ask triggers: our own hotkey, or `locallm --quick` from a compositor binding
+        let hotkey_sub = if self.hotkey.is_some() {
+            Subscription::run(hotkey::presses).map(|()| Message::QuickAskToggle)
+        } else {
+            Subscription::none()
+        };
+        let remote_sub = Subscription::run(hotkey::remote_requests).map(|()| Message::QuickAskToggle);
+
+        // Hand edits to config.toml are applied without a restart
+        let config_sub = Subscription::run(config_watch::changes).map(|config| Message::ConfigReloaded(config.map(Box::new)));
+
+        // Track Shift and Ctrl/Cmd, which decide what Enter does in the input
         let shift_sub = keyboard::on_key_press(|key, _| {
             match key {
                 keyboard::Key::Named(keyboard::key::Named::Shift) => Some(Message::ShiftPressed),
+                keyboard::Key::Named(keyboard::key::Named::Control | keyboard::key::Named::Super) => {
+                    Some(Message::CommandPressed)
+                }
                 _ => None,
             }
         });
@@ -125,368 +1044,5264 @@ impl App {
         let shift_release_sub = keyboard::on_key_release(|key, _| {
             match key {
                 keyboard::Key::Named(keyboard::key::Named::Shift) => Some(Message::ShiftReleased),
+                keyboard::Key::Named(keyboard::key::Named::Control | keyboard::key::Named::Super) => {
+                    Some(Message::CommandReleased)
+                }
                 _ => None,
             }
         });
 
-        Subscription::batch([gpu_sub, shift_sub, shift_release_sub])
+        Subscription::batch([
+            gpu_sub,
+            system_sub,
+            theme_sub,
+            profile_sub,
+            ps_sub,
+            reconnect_sub,
+            spinner_sub,
+            toast_sub,
+            draft_sub,
+            waiting_sub,
+            window_sub,
+            close_sub,
+            shortcuts::listen(),
+            hotkey_sub,
+            remote_sub,
+            config_sub,
+            shift_sub,
+            shift_release_sub,
+        ])
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::InputChanged(action) => {
-                // Check if this is an Enter key - submit if Shift is NOT held
+                // Enter submits or inserts a newline depending on `submit_key` and the modifiers held
                 let is_enter = matches!(
                     action,
                     text_editor::Action::Edit(text_editor::Edit::Enter)
                 );
                 
-                if is_enter && !self.shift_held {
+                let submits = match self.config.submit_key {
+                    SubmitKey::Enter => !self.shift_held,
+                    SubmitKey::CtrlEnter => self.command_held,
+                };
+                if is_enter && submits {
                     // Submit instead of inserting newline
                     return self.update(Message::Submit);
                 }
+
+                if let text_editor::Action::Move(motion @ (text_editor::Motion::Up | text_editor::Motion::Down)) = action {
+                    if self.recall_prompt(motion == text_editor::Motion::Up) {
+                        return Task::none();
+                    }
+                }
                 
+                if action.is_edit() {
+                    self.draft_edited_at = Some(std::time::Instant::now());
+                    self.recall = None;
+                }
                 self.input_content.perform(action);
                 Task::none()
             }
 
             Message::Submit => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
                 let input_text = self.input_content.text();
                 if input_text.trim().is_empty() {
                     return Task::none();
                 }
-                if self.status == Status::Generating {
+                // Sent while an answer streams: waits its turn, remembered once it goes out
+                if self.status == Status::Generating && commands::parse(&input_text).is_none() {
+                    self.queued.push(input_text.trim().to_string());
+                    self.input_content = text_editor::Content::new();
+                    self.status_message = format!("Queued, {} waiting", self.queued.len());
                     return Task::none();
                 }
+                self.remember_prompt(input_text.trim());
+                if commands::parse(&input_text).is_none() {
+                    self.picker_draft = None;
+                }
 
-                let Some(model) = self.selected_model.clone() else {
-                    self.status_message = String::from("No model selected");
+                // Slash commands never reach the model
+                match commands::parse(&input_text) {
+                    Some(Ok(command)) => {
+                        self.input_content = self.input_after_command();
+                        return self.run_command(command);
+                    }
+                    Some(Err(e)) => {
+                        self.status_message = e;
+                        return Task::none();
+                    }
+                    None => {}
+                }
+
+                let Some((model, options)) = self.request_settings() else {
                     return Task::none();
                 };
+                // Only before it's loaded, a model already running on the CPU isn't news
+                let loaded = self.running_models.iter().any(|r| r.name == model);
+                if !loaded && self.vram_fit(&model) == Some(Fit::CpuOnly) && self.vram_warned.as_ref() != Some(&model) {
+                    let size = self.model_sizes.get(&model).copied().unwrap_or_default();
+                    self.toasts.push(
+                        Severity::Warning,
+                        format!(
+                            "{model} ({}) is far bigger than the free VRAM and will run on the CPU, slowly. Send again to go ahead.",
+                            format_bytes(size)
+                        ),
+                    );
+                    self.vram_warned = Some(model);
+                    return Task::none();
+                }
+
+                let attachments = match &self.workspace {
+                    Some(workspace) => match workspace.attachments(&input_text) {
+                        Ok(attachments) => attachments,
+                        Err(e) => {
+                            self.status_message = e;
+                            return Task::none();
+                        }
+                    },
+                    None => Vec::new(),
+                };
+                let attachments = attachments
+                    .into_iter()
+                    .chain(std::mem::take(&mut self.dropped_files))
+                    .map(|a| Attachment {
+                        content: self.redactor.apply(&a.content),
+                        ..a
+                    })
+                    .collect();
 
-                // Add user message to history
-                let user_msg = input_text.trim().to_string();
+                // Add user message to history, wrapped in the selected template
+                let input_text = self.redactor.apply(commands::unescape(input_text.trim()));
+                let user_msg = match self.active_template() {
+                    Some(template) => template.render(&input_text),
+                    None => input_text.clone(),
+                };
+                // An edited message replaces the original and everything after it,
+                // which stay reachable as another branch
+                let replaced = self
+                    .editing
+                    .take()
+                    .filter(|&idx| idx < self.chat_history.len())
+                    .map(|idx| self.split_history(idx));
                 self.chat_history.push(ChatEntry {
                     role: "user".to_string(),
                     content: user_msg.clone(),
+                    timing: None,
+                    thinking: None,
+                    show_thinking: false,
+                    expanded: false,
+                    diff: None,
+                    excluded: false,
+                    attachments,
+                    sources: Vec::new(),
+                    model: None,
+                    alternatives: Vec::new(),
+                    json: None,
+                    truncated: false,
+                    variants: Vec::new(),
+                    variant_pos: 0,
+                    created_at: Some(history::unix_now()),
+                    duration: None,
                 });
+                if let (Some(old), Some(entry)) = (replaced, self.chat_history.last_mut()) {
+                    add_variant(entry, old);
+                }
                 self.input_content = text_editor::Content::new();
-                self.status = Status::Generating;
-                self.status_message = String::from("Generating...");
-
-                // Build messages for API
-                let mut messages: Vec<ChatMessage> = Vec::new();
 
-                // Add system prompt if configured
-                if let Some(ref sys) = self.config.system_prompt {
-                    messages.push(ChatMessage {
-                        role: "system".to_string(),
-                        content: sys.clone(),
-                    });
+                if !self.params.use_documents {
+                    return self.generate(model, options);
                 }
+                // Generating already, so nothing else is sent while the documents are searched
+                self.status = Status::Generating;
+                self.status_message = String::from("Searching your documents...");
+                let (task, handle) = Task::perform(
+                    rag::retrieve(
+                        self.client.clone(),
+                        self.config.embedding_model.clone(),
+                        input_text,
+                        self.config.document_top_k,
+                    ),
+                    move |result| Message::DocumentsRetrieved(model.clone(), options.clone(), result),
+                )
+                .abortable();
+                self.generation = Some(handle);
+                task
+            }
 
-                // Add chat history
-                for entry in &self.chat_history {
-                    messages.push(ChatMessage {
-                        role: entry.role.clone(),
-                        content: entry.content.clone(),
-                    });
+            // The oldest queued prompt goes out as if typed, leaving what's in the input alone
+            Message::SendQueued => {
+                if self.queued.is_empty() || self.status == Status::Generating {
+                    return Task::none();
                 }
+                let prompt = self.queued.remove(0);
+                let draft = std::mem::replace(&mut self.input_content, text_editor::Content::with_text(&prompt));
+                let task = self.update(Message::Submit);
+                if self.status != Status::Generating {
+                    // Not sent (no model, locked, ...): back to the front of the line
+                    self.queued.insert(0, prompt);
+                }
+                self.input_content = draft;
+                task
+            }
 
-                let client = self.client.clone();
-                Task::perform(
-                    async move {
-                        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
-
-                        // Spawn the streaming request
-                        let handle = tokio::spawn(async move {
-                            client.chat_stream(&model, messages, tx).await
-                        });
+            Message::RemoveQueued(idx) => {
+                if idx < self.queued.len() {
+                    self.queued.remove(idx);
+                }
+                Task::none()
+            }
 
-                        // Collect all tokens
-                        let mut full_response = String::new();
-                        while let Some(token) = rx.recv().await {
-                            full_response.push_str(&token);
+            Message::DocumentsRetrieved(model, options, result) => {
+                if self.status != Status::Generating {
+                    return Task::none();
+                }
+                let note = match result {
+                    Ok(sources) => {
+                        let note = format!("Generating with {} document excerpts...", sources.len());
+                        if let Some(entry) = self.chat_history.last_mut().filter(|e| e.role == "user") {
+                            entry.sources = sources;
                         }
+                        note
+                    }
+                    Err(e) => {
+                        tracing::warn!("Document search failed: {e}");
+                        format!("Answering without documents: {e}")
+                    }
+                };
+                let task = self.generate(model, options);
+                self.status_message = note;
+                task
+            }
 
-                        // Wait for completion
-                        match handle.await {
-                            Ok(Ok(_)) => Ok(full_response),
-                            Ok(Err(e)) => Err(e.to_string()),
-                            Err(e) => Err(e.to_string()),
-                        }
-                    },
-                    Message::ResponseComplete,
-                )
+            Message::CompleteCommand(name) => {
+                let takes_argument = commands::COMMANDS
+                    .iter()
+                    .any(|(command, args, _)| *command == name && !args.is_empty());
+                let text = if takes_argument { format!("{name} ") } else { name.to_string() };
+                self.input_content = text_editor::Content::with_text(&text);
+                self.input_content
+                    .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+                Task::none()
             }
 
-            Message::ResponseComplete(result) => {
-                self.status = Status::Connected;
+            Message::MentionPicked(path) => {
+                // Replace the partial `#mention` at the end of the input
+                let input = self.input_content.text();
+                let input = input.trim_end_matches('\n');
+                let start = input.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+                let text = format!("{}#{path} ", &input[..start]);
+                self.input_content = text_editor::Content::with_text(&text);
+                self.input_content
+                    .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+                Task::none()
+            }
+
+            Message::WorkspaceScanned(result) => {
                 match result {
-                    Ok(response) => {
-                        if !response.is_empty() {
-                            self.chat_history.push(ChatEntry {
-                                role: "assistant".to_string(),
-                                content: response,
-                            });
-                        }
-                        self.status_message = String::from("Ready");
-                    }
-                    Err(e) => {
-                        self.status_message = format!("Error: {e}");
+                    Ok(workspace) => {
+                        self.status_message = format!(
+                            "📁 Workspace {}: {} files, type # to mention one",
+                            workspace.name(),
+                            workspace.file_count()
+                        );
+                        self.workspace = Some(workspace);
                     }
+                    Err(e) => self.status_message = e,
                 }
                 Task::none()
             }
 
-            Message::OllamaStatus(connected) => {
-                if connected {
-                    self.status = Status::Connected;
-                    self.status_message = String::from("Connected to Ollama");
-
-                    // Load models
-                    let client = self.client.clone();
-                    Task::perform(
-                        async move {
-                            client
-                                .list_models()
-                                .await
-                                .map(|models| {
-                                    models.into_iter().map(|m| m.name).collect::<Vec<String>>()
-                                })
-                                .map_err(|e| e.to_string())
-                        },
-                        Message::ModelsLoaded,
-                    )
-                } else {
-                    self.status = Status::Disconnected;
-                    self.status_message = String::from("Ollama not running");
-                    Task::none()
+            Message::FileDropped(window, path) => {
+                // Files dropped on the quick-ask popup have nowhere to go
+                if self.main_window != Some(window) {
+                    return Task::none();
+                }
+                const RESERVED_FOR_REPLY: u64 = 1024;
+                const DEFAULT_CONTEXT: u64 = 8192;
+                let pending: u64 = self.dropped_files.iter().map(|a| estimate_tokens(&a.content)).sum();
+                let room = self
+                    .context_length()
+                    .unwrap_or(DEFAULT_CONTEXT)
+                    .saturating_sub(self.context_tokens() + pending + RESERVED_FOR_REPLY);
+                if room < 256 {
+                    self.status_message = format!("No room left in the context window for {}", path.display());
+                    return Task::none();
                 }
+                self.status_message = format!("Reading {}...", path.display());
+                Task::perform(ingest::attach(path, room), Message::FileAttached)
             }
 
-            Message::ModelsLoaded(result) => {
+            Message::FileAttached(result) => {
                 match result {
-                    Ok(models) => {
-                        self.available_models = models;
-
-                        // Select default model or first available
-                        if self.selected_model.is_none() {
-                            self.selected_model = self
-                                .config
-                                .default_model
-                                .clone()
-                                .filter(|m| self.available_models.contains(m))
-                                .or_else(|| self.available_models.first().cloned());
-                        }
-
-                        if self.available_models.is_empty() {
-                            self.status_message = String::from("No models found. Run: ollama pull <model>");
-                        } else {
-                            self.status_message = format!("{} models available", self.available_models.len());
-                        }
-                    }
-                    Err(e) => {
-                        self.status_message = format!("Failed to load models: {e}");
+                    Ok(attachment) => {
+                        self.status_message = match attachment.truncated {
+                            true => format!("Attached {}, cut to fit the context window", attachment.path),
+                            false => format!("Attached {}", attachment.path),
+                        };
+                        self.dropped_files.push(attachment);
                     }
+                    Err(e) => self.status_message = e,
                 }
                 Task::none()
             }
 
-            Message::ModelSelected(model) => {
-                self.selected_model = Some(model);
+            Message::RemoveDroppedFile(idx) => {
+                if idx < self.dropped_files.len() {
+                    self.dropped_files.remove(idx);
+                }
                 Task::none()
             }
 
-            Message::RefreshModels => {
-                let client = self.client.clone();
-                Task::perform(
-                    async move {
-                        client
-                            .list_models()
-                            .await
-                            .map(|models| {
-                                models.into_iter().map(|m| m.name).collect::<Vec<String>>()
-                            })
-                            .map_err(|e| e.to_string())
-                    },
-                    Message::ModelsLoaded,
-                )
+            Message::ModelCommandPicked(model) => {
+                self.input_content = self.input_after_command();
+                self.status_message = format!("Switched to {model}");
+                self.update(Message::ModelSelected(model))
             }
 
-            Message::ClearChat => {
-                self.chat_history.clear();
-                self.input_content = text_editor::Content::new();
-                self.status_message = String::from("Chat cleared");
+            Message::CancelGeneration => {
+                let Some(handle) = self.generation.take() else {
+                    return Task::none();
+                };
+                handle.abort();
+                self.status = Status::Connected;
+                self.streaming.clear();
+                self.streaming_thinking.clear();
+                self.compare_target = None;
+                self.continuing = false;
+                self.restore_replaced();
+                self.generating_model = None;
+                self.status_message = String::from("Generation cancelled");
+                self.idle_inhibitor = None;
+                self.profiler = None;
+                tracing::info!("Generation cancelled");
                 Task::none()
             }
 
-            Message::CopyMessage(idx) => {
-                if let Some(entry) = self.chat_history.get(idx) {
-                    let content = entry.content.clone();
-                    let role = entry.role.clone();
-                    self.status_message = format!("📋 Copied {} message!", role);
-                    Task::perform(
-                        async move { clipboard::copy_to_clipboard(&content).await },
-                        Message::CopyComplete,
-                    )
-                } else {
-                    Task::none()
+            Message::Continue => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                if !self.chat_history.last().is_some_and(|e| e.role == "assistant" && e.truncated) {
+                    return Task::none();
                 }
+                let Some((model, options)) = self.request_settings() else {
+                    return Task::none();
+                };
+                self.continuing = true;
+                self.generate(model, options)
             }
 
-            Message::CopyComplete(result) => {
-                if let Err(e) = result {
-                    self.status_message = format!("Copy failed: {e}");
+            Message::Retry => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
                 }
-                // On success, keep the message we already set
-                Task::none()
+                let Some((model, options)) = self.request_settings() else {
+                    return Task::none();
+                };
+
+                if self.chat_history.last().is_some_and(|e| e.role == "assistant") {
+                    self.replaced = Some(self.split_history(self.chat_history.len() - 1));
+                }
+                if self.chat_history.last().is_none_or(|e| e.role != "user") {
+                    self.restore_replaced();
+                    self.status_message = String::from("Nothing to retry");
+                    return Task::none();
+                }
+                self.generate(model, options)
             }
 
-            Message::GpuStatsTick => {
-                Task::perform(async { read_amd_gpu_stats().await }, Message::GpuStatsUpdated)
+            Message::RetryWith(idx, model) => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                if self.status == Status::Generating
+                    || self.chat_history.get(idx).is_none_or(|e| e.role != "assistant")
+                {
+                    return Task::none();
+                }
+                let mut options = match self.params.to_options() {
+                    Ok(options) => options,
+                    Err(e) => {
+                        self.show_params = true;
+                        self.status_message = e;
+                        return Task::none();
+                    }
+                };
+                // Thinking is chosen per model, not carried over from the selected one
+                options.think = self.config.think_models.contains(&model);
+                self.compare_target = Some(idx);
+                self.generate(model, options)
             }
 
-            Message::GpuStatsUpdated(stats) => {
-                self.gpu_stats = stats;
+            Message::UseAlternative(idx, alt) => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                if let Some(entry) = self.chat_history.get_mut(idx) {
+                    if let Some(alternative) = entry.alternatives.get_mut(alt) {
+                        std::mem::swap(&mut entry.content, &mut alternative.content);
+                        let previous = entry.model.replace(alternative.model.clone());
+                        alternative.model = previous.unwrap_or_default();
+                        entry.thinking = None;
+                        entry.timing = None;
+                        self.measured_tokens = None;
+                        self.drop_summary_covering(idx);
+                    }
+                }
+                self.refresh_diffs();
+                self.save_conversation()
+            }
+
+            Message::DismissAlternative(idx, alt) => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                if let Some(entry) = self.chat_history.get_mut(idx) {
+                    if alt < entry.alternatives.len() {
+                        entry.alternatives.remove(alt);
+                    }
+                }
+                self.save_conversation()
+            }
+
+            Message::ConversationsLoaded(result) => {
+                match result {
+                    Ok(conversations) => self.conversations = conversations,
+                    Err(e) => tracing::warn!("Failed to load saved conversations: {e}"),
+                }
+                Task::none()
+            }
+
+            Message::SaveDraft => {
+                self.draft_edited_at = None;
+                if self.chat_history.is_empty() {
+                    let notes = self.save_conversation();
+                    Task::batch([Task::perform(history::save_draft(self.draft_text()), Message::ConversationSaved), notes])
+                } else {
+                    self.save_draft_in_conversation()
+                }
+            }
+
+            Message::DraftTick => {
+                if self.draft_edited_at.is_some_and(|at| at.elapsed() >= DRAFT_PAUSE) {
+                    return self.update(Message::SaveDraft);
+                }
+                Task::none()
+            }
+
+            Message::DraftLoaded(draft) => {
+                // Only into an empty box, something may have been typed already
+                if let Some(draft) = draft.filter(|_| self.chat_history.is_empty() && self.draft_text().is_empty()) {
+                    self.input_content = text_editor::Content::with_text(&draft);
+                }
+                Task::none()
+            }
+
+            Message::ScratchpadLoaded(notes) => {
+                let notes = notes.filter(|notes| !notes.trim().is_empty());
+                if let Some(notes) = notes.filter(|_| self.chat_history.is_empty() && self.scratchpad_text().is_empty()) {
+                    self.scratchpad = text_editor::Content::with_text(&notes);
+                    self.show_scratchpad = true;
+                }
+                Task::none()
+            }
+
+            Message::ConversationSaved(result) => {
+                if let Err(e) = result {
+                    tracing::warn!("Failed to save conversation: {e}");
+                    self.status_message = format!("Conversation not saved: {e}");
+                }
+                Task::none()
+            }
+
+            Message::ShowSnippets(show) => {
+                self.show_snippets = show;
+                Task::none()
+            }
+
+            Message::ToggleStar(idx) => {
+                let Some(entry) = self.chat_history.get(idx) else {
+                    return Task::none();
+                };
+                if let Some(pos) = self.snippets.iter().position(|s| s.content == entry.content) {
+                    self.snippets.remove(pos);
+                    self.status_message = String::from("Removed from snippets");
+                } else {
+                    let source = self
+                        .conversation_id
+                        .as_ref()
+                        .and_then(|id| self.conversations.iter().find(|c| &c.id == id))
+                        .map(|c| c.title.clone());
+                    self.snippets.insert(
+                        0,
+                        Snippet {
+                            id: history::new_id(),
+                            content: entry.content.clone(),
+                            model: entry.model.clone(),
+                            source,
+                            created_at: history::unix_now(),
+                        },
+                    );
+                    self.status_message = String::from("★ Saved to snippets");
+                }
+                Task::perform(snippets::save(self.snippets.clone()), Message::SnippetsSaved)
+            }
+
+            Message::InsertSnippet(id) => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                if let Some(snippet) = self.snippets.iter().find(|s| s.id == id) {
+                    self.input_content.perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                        std::sync::Arc::new(snippet.content.clone()),
+                    )));
+                    self.draft_edited_at = Some(std::time::Instant::now());
+                }
+                Task::none()
+            }
+
+            Message::CopySnippet(id) => match self.snippets.iter().find(|s| s.id == id) {
+                Some(snippet) => {
+                    let content = snippet.content.clone();
+                    self.copy_notice = Some(String::from("📋 Copied snippet"));
+                    Task::perform(
+                        async move { clipboard::copy_to_clipboard(&content).await },
+                        Message::CopyComplete,
+                    )
+                }
+                None => Task::none(),
+            },
+
+            Message::RemoveSnippet(id) => {
+                self.snippets.retain(|s| s.id != id);
+                Task::perform(snippets::save(self.snippets.clone()), Message::SnippetsSaved)
+            }
+
+            Message::SnippetsSaved(result) => {
+                if let Err(e) = result {
+                    tracing::warn!("Failed to save snippets: {e}");
+                    self.toasts.push(Severity::Error, format!("Snippets not saved: {e}"));
+                }
+                Task::none()
+            }
+
+            Message::OpenConversation(id, jump_to) => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                let Some(conversation) = self.conversations.iter().find(|c| c.id == id).cloned() else {
+                    return Task::none();
+                };
+                let _ = self.update(Message::CancelGeneration);
+                let save_notes = self.update(Message::ClearChat);
+
+                self.chat_history = conversation.messages.into_iter().map(ChatEntry::from).collect();
+                self.conversation_id = Some(conversation.id);
+                self.session_system_prompt = conversation.system_prompt;
+                self.scratchpad = text_editor::Content::with_text(&conversation.scratchpad);
+                self.scratchpad_in_context = conversation.scratchpad_in_context;
+                self.show_scratchpad |= !conversation.scratchpad.is_empty();
+                self.input_content = text_editor::Content::with_text(&conversation.draft);
+                self.status_message = format!("Opened \"{}\"", conversation.title);
+
+                // Back to the model the chat was held with, even if it's gone, so the
+                // missing-model warning offers a replacement instead of switching silently
+                let model_task = match conversation.model {
+                    Some(model) if self.selected_model.as_ref() != Some(&model) => {
+                        self.update(Message::ModelSelected(model))
+                    }
+                    _ => Task::none(),
+                };
+                // After the switch, the chat's own options win over the model's defaults
+                if let Some(options) = conversation.options {
+                    self.params.set_options(&options);
+                }
+                let missing = self.selected_model_missing() && !self.available_models.is_empty();
+                if let Some(model) = self.selected_model.as_deref().filter(|_| missing) {
+                    self.status_message = format!(
+                        "Opened \"{}\", but {model} is no longer on the server",
+                        conversation.title
+                    );
+                }
+
+                // Scroll to the hit, counting only the bubbles the search leaves visible
+                let scroll = match jump_to {
+                    Some(message) => {
+                        let visible: Vec<usize> = (0..self.chat_history.len())
+                            .filter(|idx| self.search_matches(*idx))
+                            .collect();
+                        let position = visible.iter().position(|idx| *idx == message).unwrap_or(0);
+                        let y = position as f32 / visible.len().saturating_sub(1).max(1) as f32;
+                        scrollable::snap_to(CHAT_SCROLL.clone(), scrollable::RelativeOffset { x: 0.0, y })
+                    }
+                    None => scrollable::snap_to(CHAT_SCROLL.clone(), scrollable::RelativeOffset::END),
+                };
+                Task::batch([save_notes, model_task, scroll])
+            }
+
+            Message::DeleteConversation(id) => {
+                self.conversations.retain(|c| c.id != id);
+                if self.conversation_id.as_ref() == Some(&id) {
+                    // The chat stays on screen, saved again under a new id if it continues
+                    self.conversation_id = None;
+                }
+                Task::perform(history::delete(id), Message::ConversationDeleted)
+            }
+
+            Message::TitleGenerated(id, result) => {
+                let title = match result.map(|raw| history::clean_title(&raw)) {
+                    Ok(Some(title)) => title,
+                    Ok(None) => return Task::none(),
+                    Err(e) => {
+                        tracing::warn!("Couldn't generate a conversation title: {e}");
+                        return Task::none();
+                    }
+                };
+                // Renamed by hand while the request ran
+                let Some(conversation) = self.conversations.iter_mut().find(|c| c.id == id && !c.renamed) else {
+                    return Task::none();
+                };
+                conversation.title = title;
+                Task::perform(history::save(conversation.clone()), Message::ConversationSaved)
+            }
+
+            Message::StartRename(id) => {
+                let Some(conversation) = self.conversations.iter().find(|c| c.id == id) else {
+                    return Task::none();
+                };
+                self.renaming = Some((id, conversation.title.clone()));
+                text_input::focus(sidebar::RENAME_INPUT.clone())
+            }
+
+            Message::RenameChanged(title) => {
+                if let Some((_, draft)) = &mut self.renaming {
+                    *draft = title;
+                }
+                Task::none()
+            }
+
+            Message::SubmitRename => {
+                let Some((id, title)) = self.renaming.take() else {
+                    return Task::none();
+                };
+                let title = title.trim();
+                let Some(conversation) = self.conversations.iter_mut().find(|c| c.id == id) else {
+                    return Task::none();
+                };
+                if title.is_empty() || title == conversation.title {
+                    return Task::none();
+                }
+                conversation.title = title.to_string();
+                conversation.renamed = true;
+                Task::perform(history::save(conversation.clone()), Message::ConversationSaved)
+            }
+
+            Message::CancelRename => {
+                self.renaming = None;
+                Task::none()
+            }
+
+            Message::ConversationDeleted(result) => {
+                self.status_message = match result {
+                    Ok(()) => String::from("Conversation deleted"),
+                    Err(e) => e,
+                };
+                Task::none()
+            }
+
+            Message::StartConversationTemplate(name) => {
+                let Some(template) = self.config.conversation_templates.iter().find(|t| t.name == name).cloned()
+                else {
+                    self.status_message = format!("No conversation template named \"{name}\"");
+                    return Task::none();
+                };
+                let task = self.update(Message::NewConversation);
+                if self.locked {
+                    return task;
+                }
+                self.session_system_prompt = template.system_prompt;
+                self.chat_history = template
+                    .turns
+                    .into_iter()
+                    .map(|turn| ChatEntry {
+                        role: turn.role,
+                        content: turn.content,
+                        timing: None,
+                        thinking: None,
+                        show_thinking: false,
+                        expanded: false,
+                        diff: None,
+                        excluded: false,
+                        attachments: Vec::new(),
+                        sources: Vec::new(),
+                        model: None,
+                        alternatives: Vec::new(),
+                        json: None,
+                        truncated: false,
+                        variants: Vec::new(),
+                        variant_pos: 0,
+                        created_at: None,
+                        duration: None,
+                    })
+                    .collect();
+                self.status_message = format!("Started from template \"{name}\"");
+                task
+            }
+
+            Message::ProfileSelected(name) => {
+                let Some(profile) = self.config.profiles.iter().find(|p| p.name == name).cloned() else {
+                    return Task::none();
+                };
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                // Set first, the new conversation takes the profile's system prompt
+                self.active_profile = Some(name);
+                let task = self.update(Message::NewConversation);
+                let mut notes = Vec::new();
+                let model_task = match profile.model {
+                    Some(model) if self.available_models.contains(&model) => self.update(Message::ModelSelected(model)),
+                    Some(model) => {
+                        notes.push(format!("{model} isn't installed"));
+                        Task::none()
+                    }
+                    None => Task::none(),
+                };
+                if let Some(temperature) = profile.temperature {
+                    self.params.temperature = temperature.to_string();
+                }
+                notes.insert(0, format!("Profile \"{}\"", profile.name));
+                self.status_message = notes.join(", ");
+                Task::batch([task, model_task])
+            }
+
+            Message::ClearProfile => {
+                self.active_profile = None;
+                self.session_system_prompt = None;
+                self.status_message = String::from("No profile");
+                Task::none()
+            }
+
+            Message::ToggleSidebar => {
+                self.show_sidebar = !self.show_sidebar;
+                Task::none()
+            }
+
+            Message::OpenSearch => {
+                if self.search.is_none() {
+                    self.search = Some(String::new());
+                }
+                text_input::focus(SEARCH_INPUT.clone())
+            }
+
+            Message::SearchChanged(query) => {
+                self.search = Some(query);
+                Task::none()
+            }
+
+            Message::CloseSearch => {
+                self.search = None;
+                Task::none()
+            }
+
+            Message::ResponseChunk(chunk) => {
+                if self.generation.is_none() {
+                    return Task::none();
+                }
+                if self.first_token_after.is_none() {
+                    self.first_token_after = self.generation_started.map(|started| started.elapsed());
+                    tracing::debug!("First token after {:?}", self.first_token_after.unwrap_or_default());
+                }
+                let chunk = match chunk {
+                    StreamChunk::Content(chunk) => chunk,
+                    StreamChunk::Thinking(thinking) => {
+                        self.streaming_thinking.push_str(&thinking);
+                        return Task::none();
+                    }
+                };
+                self.streaming.push_str(&chunk);
+                self.streamed_chunks += 1;
+
+                let cap = self.config.response_soft_cap;
+                if cap.is_some_and(|cap| self.streamed_chunks > cap) {
+                    return self.stop_at_soft_cap();
+                }
+                Task::none()
+            }
+
+            Message::GenerationTick => Task::none(),
+
+            Message::ResponseComplete(result) => {
+                self.generation = None;
+                self.generating_model = None;
+                self.streaming.clear();
+                self.streaming_thinking.clear();
+                self.status = Status::Connected;
+                self.idle_inhibitor = None;
+
+                let mut tasks = Vec::new();
+                if let Some(recorder) = self.profiler.take() {
+                    let report = match result {
+                        Ok((ref response, ref timing)) => recorder.finish(
+                            Ok(response),
+                            timing.as_ref().map(|t| t.offsets()).unwrap_or_default(),
+                        ),
+                        Err(ref report) => recorder.finish(Err(&report.message), &[]),
+                    };
+                    tasks.push(Task::perform(profile::save_report(report), Message::ProfileSaved));
+                }
+                let result = result.map(|(response, timing)| {
+                    (response, timing.filter(|_| self.config.record_token_timing))
+                });
+
+                let took = self.generation_started.take().map(|started| started.elapsed());
+                match result {
+                    Ok((chat_response, timing)) => {
+                        self.status_message = String::from("Ready");
+                        let (response, thinking) = chat_response
+                            .message
+                            .map(|m| (m.content, m.thinking))
+                            .unwrap_or_default();
+
+                        // Someone who switched away from a slow model wants to know it's done
+                        let slow = took
+                            .zip(self.config.notify_after_secs)
+                            .is_some_and(|(took, after)| took.as_secs() >= after);
+                        if slow && !self.window_focused {
+                            let model = chat_response.model.clone().unwrap_or_default();
+                            let preview: String = response.trim().chars().take(120).collect();
+                            tasks.push(Task::perform(
+                                notify::notify(format!("Response ready ({model})"), preview),
+                                Message::Notified,
+                            ));
+                        }
+                        let (response, json) = match self.generating_format.take() {
+                            Some(format) if !response.is_empty() => {
+                                let (pretty, check) = params::check_json(&response, Some(&format));
+                                if let JsonCheck::Invalid(ref problem) = check {
+                                    self.status_message = format!("⚠ Answer is {problem}");
+                                }
+                                (pretty, Some(check))
+                            }
+                            _ => (response, None),
+                        };
+
+                        let continued = std::mem::take(&mut self.continuing)
+                            .then(|| self.chat_history.last_mut().filter(|e| e.role == "assistant"))
+                            .flatten();
+                        if let Some(entry) = continued {
+                            entry.content.push_str(&response);
+                            entry.truncated = chat_response.done_reason.as_deref() == Some("length");
+                            entry.timing = None;
+                            self.measured_tokens = None;
+                            self.tokens_used += chat_response.prompt_eval_count.unwrap_or(0)
+                                + chat_response.eval_count.unwrap_or(0);
+                            tasks.push(self.save_conversation());
+                            tasks.push(self.load_running_models());
+                        } else if let Some(target) = self.compare_target.take() {
+                            let model = chat_response.model.unwrap_or_default();
+                            self.status_message = format!("Compared with {model}");
+                            if let Some(entry) = self.chat_history.get_mut(target) {
+                                entry.alternatives.push(Alternative {
+                                    model,
+                                    content: response,
+                                });
+                            }
+                            tasks.push(self.save_conversation());
+                            tasks.push(self.load_running_models());
+                        } else if !response.is_empty() {
+                            self.chat_history.push(ChatEntry {
+                                role: "assistant".to_string(),
+                                content: response.clone(),
+                                timing,
+                                thinking,
+                                show_thinking: false,
+                                expanded: false,
+                                diff: None,
+                                excluded: false,
+                                attachments: Vec::new(),
+                                sources: Vec::new(),
+                                model: chat_response.model,
+                                alternatives: Vec::new(),
+                                json,
+                                truncated: chat_response.done_reason.as_deref() == Some("length"),
+                                variants: Vec::new(),
+                                variant_pos: 0,
+                                created_at: Some(history::unix_now()),
+                                duration: chat_response.total_duration.map(Duration::from_nanos),
+                            });
+                            self.attach_replaced();
+
+                            if let (Some(prompt), Some(eval)) =
+                                (chat_response.prompt_eval_count, chat_response.eval_count)
+                            {
+                                self.measured_tokens = Some((self.chat_history.len(), prompt + eval));
+                            }
+
+                            let was_over = self.over_token_budget();
+                            self.tokens_used += chat_response.prompt_eval_count.unwrap_or(0)
+                                + chat_response.eval_count.unwrap_or(0);
+                            if self.over_token_budget() && !was_over {
+                                tracing::info!("Conversation passed its token budget ({} tokens)", self.tokens_used);
+                            }
+
+                            let over_budget = self
+                                .config
+                                .summarize_after_tokens
+                                .is_some_and(|budget| self.context_tokens() > budget);
+                            if over_budget {
+                                tasks.push(self.summarize_history());
+                            }
+                            tasks.push(self.save_conversation());
+                            tasks.push(self.load_running_models());
+                            let replies = self.chat_history.iter().filter(|e| e.role == "assistant").count();
+                            if replies == 1 {
+                                tasks.push(self.generate_title());
+                            }
+
+                            if self.config.auto_copy {
+                                self.copy_notice = Some(String::from("📋 Response copied"));
+                                tasks.push(Task::perform(
+                                    async move { clipboard::copy_to_clipboard(&response).await },
+                                    Message::CopyComplete,
+                                ));
+                            }
+                        }
+                        // An empty answer doesn't replace the one it regenerated
+                        self.restore_replaced();
+                        if !self.queued.is_empty() {
+                            tasks.push(Task::done(Message::SendQueued));
+                        }
+                    }
+                    Err(report) => {
+                        self.compare_target = None;
+                        self.continuing = false;
+                        self.restore_replaced();
+                        tracing::warn!("Request #{} failed: {}", report.request_id, report.message);
+                        self.status_message = String::from("Request failed");
+                        // Find out whether it was removed for good and offer a replacement
+                        if report.kind == "ModelNotFound" {
+                            self.toasts.push_with_detail(
+                                Severity::Error,
+                                format!("{} isn't on the server", report.model),
+                                Some(report.message.clone()),
+                            );
+                            tasks.push(self.load_models(Message::ModelsRefreshed));
+                        } else {
+                            self.toasts.push_with_detail(
+                                Severity::Error,
+                                format!("Request #{} failed: {}", report.request_id, report.kind),
+                                Some(match report.hint {
+                                    Some(hint) => format!("{}\n{hint}", report.message),
+                                    None => report.message.clone(),
+                                }),
+                            );
+                        }
+                        self.last_error = Some(report);
+                    }
+                }
+                Task::batch(tasks)
+            }
+
+            Message::CopyDiagnostics => {
+                let Some(ref report) = self.last_error else {
+                    return Task::none();
+                };
+                let diagnostics = report.diagnostics();
+                self.copy_notice = Some(String::from("📋 Copied diagnostics"));
+                Task::perform(
+                    async move { clipboard::copy_to_clipboard(&diagnostics).await },
+                    Message::CopyComplete,
+                )
+            }
+
+            Message::DismissError => {
+                self.last_error = None;
+                Task::none()
+            }
+
+            Message::CheckConnection => {
+                if self.checking_connection {
+                    return Task::none();
+                }
+                self.checking_connection = true;
+                let client = self.client.clone();
+                Task::perform(
+                    async move { client.health_check().await.unwrap_or(false) },
+                    Message::OllamaStatus,
+                )
+            }
+
+            Message::ServerAction(ServerAction::Start) => {
+                self.server_autostart_tried = true;
+                if self.server.is_none() {
+                    self.start_server();
+                }
+                Task::none()
+            }
+
+            Message::ServerAction(action) => {
+                let Some(server) = self.server.take() else {
+                    return Task::none();
+                };
+                let restart = action == ServerAction::Restart;
+                self.status_message = String::from("Stopping ollama serve...");
+                // Waits for the runners to shut down, off the UI thread
+                Task::perform(
+                    async move {
+                        let _ = tokio::task::spawn_blocking(move || server.stop()).await;
+                    },
+                    move |()| Message::ServerStopped(restart),
+                )
+            }
+
+            Message::ServerStopped(restart) => {
+                // Stopped by hand, don't bring it straight back
+                self.server_autostart_tried = true;
+                if restart {
+                    self.start_server();
+                } else {
+                    self.status_message = String::from("ollama serve stopped");
+                }
+                self.update(Message::CheckConnection)
+            }
+
+            Message::OllamaStatus(connected) => {
+                self.checking_connection = false;
+                if connected {
+                    self.reconnect_delay = RECONNECT_MIN;
+                    self.server_autostart_tried = false;
+                    if self.status == Status::Generating {
+                        return Task::none();
+                    }
+                    // Models were fetched before, so the connection had been lost
+                    if self.status == Status::Disconnected && self.models_fetched_at.is_some() {
+                        self.toasts.push(Severity::Info, "Reconnected to Ollama");
+                    }
+                    self.status = Status::Connected;
+                    self.status_message = String::from("Connected to Ollama");
+                    if let Screen::Onboarding(setup) = &mut self.screen {
+                        setup.reachable = Some(true);
+                    }
+
+                    // Load models
+                    self.refreshing_models = true;
+                    self.load_models(Message::ModelsLoaded)
+                } else {
+                    // Back off from the delay of the check that just failed
+                    if self.status == Status::Disconnected {
+                        self.reconnect_delay = (self.reconnect_delay * 2).min(RECONNECT_MAX);
+                    } else {
+                        self.reconnect_delay = RECONNECT_MIN;
+                        tracing::warn!("Lost connection to Ollama");
+                        let url = self.config.ollama_url.clone();
+                        self.toasts.push_with_detail(Severity::Warning, "Lost connection to Ollama", Some(url));
+                    }
+                    self.status = Status::Disconnected;
+                    self.status_message = format!(
+                        "Ollama not running, retrying in {}s",
+                        self.reconnect_delay.as_secs()
+                    );
+                    // Never connected and nothing cached: most likely a first run
+                    if self.models_fetched_at.is_none() && self.available_models.is_empty() {
+                        self.start_onboarding(false);
+                    }
+
+                    if let Some(exit) = self.server.as_mut().and_then(OllamaServer::exit_status) {
+                        self.server = None;
+                        tracing::warn!("ollama serve exited: {exit}");
+                        self.status_message = format!("ollama serve exited ({exit}), see ollama-serve.log");
+                    }
+                    if self.config.manage_server && self.server.is_none() && !self.server_autostart_tried {
+                        self.server_autostart_tried = true;
+                        self.start_server();
+                    }
+                    Task::none()
+                }
+            }
+
+            Message::ModelsLoaded(result) => {
+                self.refreshing_models = false;
+                match result.map(|models| self.record_model_sizes(models)) {
+                    Ok(models) => {
+                        self.models_fetched_at = Some(std::time::Instant::now());
+                        self.models_error = None;
+                        // A pick made from a stale cache is redone, not warned about
+                        let cached_pick_gone = self.models_cached
+                            && self.chat_history.is_empty()
+                            && self.selected_model.as_ref().is_some_and(|m| !models.contains(m));
+                        if cached_pick_gone {
+                            self.selected_model = None;
+                        }
+                        self.set_available_models(models);
+
+                        // Select default model or first available
+                        let mut load_context = Task::none();
+                        if self.selected_model.is_none() {
+                            self.select_default_model();
+                            load_context = self.load_model_info();
+                        } else if self.model_info.is_none() {
+                            // Picked from the cache before the server was up
+                            load_context = self.load_model_info();
+                        }
+
+                        if self.available_models.is_empty() {
+                            self.status_message = String::from("No models found. Run: ollama pull <model>");
+                            self.start_onboarding(true);
+                        } else {
+                            self.status_message = format!("{} models available", self.available_models.len());
+                            // Found some on the server the setup screen was pointed at
+                            if matches!(&self.screen, Screen::Onboarding(setup) if setup.pull.is_none()) {
+                                self.screen = Screen::Chat;
+                            }
+                        }
+                        return Task::batch([load_context, self.load_running_models()]);
+                    }
+                    // Keep the list we have, it's most likely still right
+                    Err(e) if !self.available_models.is_empty() => {
+                        self.status_message = format!("Couldn't refresh models, showing the last known list: {e}");
+                        self.models_error = Some(e);
+                    }
+                    Err(e) => {
+                        self.status_message = format!("Failed to load models: {e}");
+                    }
+                }
+                Task::none()
+            }
+
+            Message::ModelSelected(model) => {
+                self.params.think = self.config.think_models.contains(&model);
+                self.params.set_options(&self.config.options_for(&model));
+                self.selected_model = Some(model);
+                self.load_model_info()
+            }
+
+            Message::ModelInfoLoaded(model, result) => {
+                if self.selected_model.as_ref() == Some(&model) {
+                    match result {
+                        Ok(info) => self.model_info = Some(info),
+                        Err(e) => {
+                            tracing::warn!("Failed to load details of {model}: {e}");
+                            self.model_info_error = Some(e);
+                        }
+                    }
+                }
+                Task::none()
+            }
+
+            Message::UnloadModel => {
+                let Some(model) = self.selected_model.clone() else {
+                    return Task::none();
+                };
+                self.status_message = format!("Unloading {model}...");
+                let client = self.client.clone();
+                Task::perform(
+                    async move {
+                        client
+                            .unload_model(&model)
+                            .await
+                            .map(|_| model)
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::ModelUnloaded,
+                )
+            }
+
+            Message::UnloadAllModels => {
+                if self.running_models.is_empty() {
+                    return Task::none();
+                }
+                self.status_message = String::from("Unloading models...");
+                Task::batch(self.running_models.iter().map(|running| {
+                    let client = self.client.clone();
+                    let model = running.name.clone();
+                    Task::perform(
+                        async move {
+                            client
+                                .unload_model(&model)
+                                .await
+                                .map(|_| model)
+                                .map_err(|e| e.to_string())
+                        },
+                        Message::ModelUnloaded,
+                    )
+                }))
+            }
+
+            Message::ModelUnloaded(result) => {
+                self.status_message = match result {
+                    Ok(model) => format!("Unloaded {model}"),
+                    Err(e) => format!("Unload failed: {e}"),
+                };
+                self.load_running_models()
+            }
+
+            // Also re-list installed models so deletions and renames don't go unnoticed
+            Message::RunningModelsTick => Task::batch([
+                self.load_running_models(),
+                self.load_models(Message::ModelsRefreshed),
+            ]),
+
+            Message::ModelsRefreshed(result) => {
+                // Transient failures are left to the health check, only flagged here
+                match result.map(|models| self.record_model_sizes(models)) {
+                    Ok(models) => {
+                        self.models_fetched_at = Some(std::time::Instant::now());
+                        self.models_error = None;
+                        self.set_available_models(models);
+                    }
+                    Err(e) if !self.available_models.is_empty() => self.models_error = Some(e),
+                    Err(_) => {}
+                }
+                Task::none()
+            }
+
+            Message::RunningModelsLoaded(result) => {
+                // Keep the last known list if the server hiccups, but find out if it's gone
+                match result {
+                    Ok(models) => {
+                        self.running_models = models;
+                        Task::none()
+                    }
+                    Err(_) if self.status == Status::Connected => self.update(Message::CheckConnection),
+                    Err(_) => Task::none(),
+                }
+            }
+
+            Message::ToggleModelInfo => {
+                self.show_model_info = !self.show_model_info;
+                // The first fetch may have failed, e.g. while the server was starting
+                if self.show_model_info && self.model_info.is_none() {
+                    return self.load_model_info();
+                }
+                Task::none()
+            }
+
+            Message::RefreshModels => {
+                if self.refreshing_models {
+                    return Task::none();
+                }
+                self.refreshing_models = true;
+                self.spinner_frame = 0;
+                self.load_models(Message::ModelsLoaded)
+            }
+
+            Message::SpinnerTick => {
+                self.spinner_frame = self.spinner_frame.wrapping_add(1);
+                Task::none()
+            }
+
+            Message::QuickPrompt(idx) => {
+                let typed = self.input_content.text();
+                if typed.trim().is_empty() {
+                    // Nothing typed, so it's about the clipboard
+                    return Task::perform(clipboard::paste_from_clipboard(), move |result| {
+                        Message::QuickPromptClipboard(idx, result)
+                    });
+                }
+                self.update(Message::QuickPromptClipboard(idx, Ok(typed)))
+            }
+
+            Message::QuickPromptClipboard(idx, result) => {
+                let Some(prompt) = self.quick_prompts().get(idx).cloned() else {
+                    return Task::none();
+                };
+                let input = match result {
+                    Ok(text) if !text.trim().is_empty() => text,
+                    Ok(_) => {
+                        self.status_message = String::from("Type a message or copy something first");
+                        return Task::none();
+                    }
+                    Err(e) => {
+                        self.status_message = format!("Paste failed: {e}");
+                        return Task::none();
+                    }
+                };
+                self.input_content = text_editor::Content::with_text(&prompt.render(input.trim()));
+                self.update(Message::Submit)
+            }
+
+            Message::ToggleScratchpad => {
+                self.show_scratchpad = !self.show_scratchpad;
+                if !self.show_scratchpad && self.scratchpad_edited {
+                    return self.save_conversation();
+                }
+                Task::none()
+            }
+
+            Message::ScratchpadAction(action) => {
+                self.scratchpad_edited |= action.is_edit();
+                self.scratchpad.perform(action);
+                Task::none()
+            }
+
+            Message::ScratchpadInContextToggled(enabled) => {
+                self.scratchpad_in_context = enabled;
+                self.save_conversation()
+            }
+
+            Message::ToggleCompletionMode => {
+                self.completion_mode = !self.completion_mode;
+                Task::none()
+            }
+
+            Message::CompletionPromptAction(action) => {
+                self.completion.prompt.perform(action);
+                Task::none()
+            }
+
+            Message::CompletionSuffixChanged(value) => {
+                self.completion.suffix = value;
+                Task::none()
+            }
+
+            Message::CompletionRawToggled(raw) => {
+                self.completion.raw = raw;
+                Task::none()
+            }
+
+            Message::RunCompletion => {
+                let prompt = self.completion.prompt.text();
+                let prompt = prompt.strip_suffix('\n').unwrap_or(&prompt);
+                if prompt.trim().is_empty() {
+                    return Task::none();
+                }
+                let Some((model, options)) = self.request_settings() else {
+                    return Task::none();
+                };
+                let prompt = self.redactor.apply(prompt);
+                tracing::info!("Completion with {model} (raw: {})", self.completion.raw);
+                self.completion.start(self.client.clone(), model, prompt, options)
+            }
+
+            Message::StopCompletion => {
+                self.completion.stop();
+                Task::none()
+            }
+
+            Message::AcceptCompletion => {
+                self.completion.accept();
+                Task::none()
+            }
+
+            Message::CompletionEvent(event) => {
+                self.completion.handle(event);
+                Task::none()
+            }
+
+            Message::ToggleParams => {
+                self.show_params = !self.show_params;
+                Task::none()
+            }
+
+            Message::NumPredictChanged(value) => {
+                self.params.num_predict = value;
+                Task::none()
+            }
+
+            Message::TemperatureChanged(value) => {
+                self.params.temperature = value;
+                Task::none()
+            }
+
+            Message::NumCtxChanged(value) => {
+                self.params.num_ctx = value;
+                Task::none()
+            }
+
+            Message::StopSequenceChanged(idx, value) => {
+                if let Some(stop) = self.params.stop.get_mut(idx) {
+                    *stop = value;
+                }
+                Task::none()
+            }
+
+            Message::AddStopSequence => {
+                self.params.stop.push(String::new());
+                Task::none()
+            }
+
+            Message::RemoveStopSequence(idx) => {
+                if idx < self.params.stop.len() {
+                    self.params.stop.remove(idx);
+                }
+                Task::none()
+            }
+
+            Message::HistoryLimitChanged(value) => {
+                self.params.history_limit = value;
+                Task::none()
+            }
+
+            Message::ProfileNextToggled(enabled) => {
+                self.params.profile_next = enabled;
+                Task::none()
+            }
+
+            Message::InspectToggled(enabled) => {
+                self.params.inspect = enabled;
+                Task::none()
+            }
+
+            Message::InspectorTab(tab) => {
+                self.inspector.tab = tab;
+                Task::none()
+            }
+
+            Message::InspectorLine(line) => {
+                self.inspector.push_line(&line);
+                Task::none()
+            }
+
+            Message::CopyInspector => {
+                let text = self.inspector.current().to_string();
+                self.copy_notice = Some(String::from("📋 Copied from the inspector"));
+                Task::perform(
+                    async move { clipboard::copy_to_clipboard(&text).await },
+                    Message::CopyComplete,
+                )
+            }
+
+            Message::ProfileGpuTick => {
+                Task::perform(async { read_amd_gpu_stats().await }, Message::ProfileGpuSampled)
+            }
+
+            Message::ProfileGpuSampled(stats) => {
+                if let Some(recorder) = &mut self.profiler {
+                    recorder.record_gpu(&stats);
+                }
+                Task::none()
+            }
+
+            Message::ProfileSaved(result) => {
+                self.status_message = match result {
+                    Ok(path) => format!("Profile saved to {}", path.display()),
+                    Err(e) => format!("Failed to save profile: {e}"),
+                };
+                Task::none()
+            }
+
+            Message::UseDocumentsToggled(enabled) => {
+                self.params.use_documents = enabled;
+                if enabled && self.config.document_folders.is_empty() {
+                    self.status_message = String::from("Add document folders in Settings and index them first");
+                }
+                Task::none()
+            }
+
+            Message::OutputFormatSelected(format) => {
+                self.params.format = format;
+                Task::none()
+            }
+
+            Message::FormatSchemaChanged(schema) => {
+                self.params.schema = schema;
+                Task::none()
+            }
+
+            Message::ThinkToggled(enabled) => {
+                self.params.think = enabled;
+                let Some(model) = self.selected_model.clone() else {
+                    return Task::none();
+                };
+
+                // Remembered per model, a reasoning model stays a reasoning model
+                self.config.think_models.retain(|m| *m != model);
+                if enabled {
+                    self.config.think_models.push(model);
+                }
+                let think_models = self.config.think_models.clone();
+                if let Err(e) = Config::save_with(|config| config.think_models = think_models) {
+                    tracing::warn!("Failed to save think setting: {e}");
+                }
+                Task::none()
+            }
+
+            Message::ToggleThinking(idx) => {
+                if let Some(entry) = self.chat_history.get_mut(idx) {
+                    entry.show_thinking = !entry.show_thinking;
+                }
+                Task::none()
+            }
+
+            Message::ToggleExpanded(idx) => {
+                if let Some(entry) = self.chat_history.get_mut(idx) {
+                    entry.expanded = !entry.expanded;
+                }
+                Task::none()
+            }
+
+            Message::ToggleDiff(idx) => {
+                let diff = match self.chat_history.get(idx) {
+                    Some(entry) if entry.diff.is_none() => {
+                        self.diff_sides(idx).map(|(original, revised)| diff_view::Diff::new(original, revised))
+                    }
+                    _ => None,
+                };
+                if let Some(entry) = self.chat_history.get_mut(idx) {
+                    entry.diff = diff;
+                }
+                Task::none()
+            }
+
+            Message::CopyDiffVersion(idx, version) => {
+                let Some((original, revised)) = self.diff_sides(idx) else {
+                    return Task::none();
+                };
+                let (content, notice) = match version {
+                    diff_view::Version::Original => (original.to_string(), "📋 Copied the original"),
+                    diff_view::Version::Revised => (revised.to_string(), "📋 Copied the revised version"),
+                };
+                self.copy_notice = Some(String::from(notice));
+                Task::perform(
+                    async move { clipboard::copy_to_clipboard(&content).await },
+                    Message::CopyComplete,
+                )
+            }
+
+            Message::ChatScrolled(viewport) => {
+                self.chat_viewport = Some(viewport);
+                Task::none()
+            }
+
+            Message::TemplateSelected(name) => {
+                self.params.template = name;
+                Task::none()
+            }
+
+            Message::OpenTemplateTests => {
+                self.screen = Screen::TemplateTests(TemplateTestRun::new(self.selected_model.clone()));
+                Task::none()
+            }
+
+            Message::CloseTemplateTests => {
+                if let Screen::TemplateTests(run) = &mut self.screen {
+                    run.stop();
+                }
+                self.screen = Screen::Chat;
+                Task::none()
+            }
+
+            Message::OpenBenchmark => {
+                self.screen = Screen::Benchmark(BenchmarkRun::new(self.selected_model.clone()));
+                Task::none()
+            }
+
+            Message::CloseBenchmark => {
+                if let Screen::Benchmark(run) = &mut self.screen {
+                    run.stop();
+                }
+                self.screen = Screen::Chat;
+                Task::none()
+            }
+
+            Message::BenchmarkModelToggled(model, on) => {
+                if let Screen::Benchmark(run) = &mut self.screen {
+                    if on {
+                        run.models.insert(model);
+                    } else {
+                        run.models.remove(&model);
+                    }
+                }
+                Task::none()
+            }
+
+            Message::BenchmarkPromptsAction(action) => {
+                if let Screen::Benchmark(run) = &mut self.screen {
+                    run.prompts.perform(action);
+                }
+                Task::none()
+            }
+
+            Message::RunBenchmark => match &mut self.screen {
+                Screen::Benchmark(run) => run.start(self.client.clone()),
+                _ => Task::none(),
+            },
+
+            Message::StopBenchmark => {
+                if let Screen::Benchmark(run) = &mut self.screen {
+                    run.stop();
+                }
+                Task::none()
+            }
+
+            Message::BenchmarkFinished(result) => {
+                if let Screen::Benchmark(run) = &mut self.screen {
+                    run.results.push(result);
+                }
+                Task::none()
+            }
+
+            Message::BenchmarkDone => {
+                if let Screen::Benchmark(run) = &mut self.screen {
+                    run.running = false;
+                    run.handle = None;
+                }
+                Task::none()
+            }
+
+            Message::ExportBenchmark => {
+                let Screen::Benchmark(run) = &self.screen else {
+                    return Task::none();
+                };
+                Task::perform(benchmark::export_csv(run.results.clone()), Message::BenchmarkExported)
+            }
+
+            Message::BenchmarkExported(result) => {
+                match result {
+                    Ok(path) => self.toasts.push(Severity::Success, format!("Saved {}", path.display())),
+                    Err(e) => self.toasts.push(Severity::Error, e),
+                }
+                Task::none()
+            }
+
+            Message::OpenEmbeddings => {
+                // The documents' embedding model if it's installed
+                let wanted = self.config.embedding_model.as_str();
+                let model = self
+                    .available_models
+                    .iter()
+                    .find(|m| *m == wanted || m.strip_prefix(wanted).is_some_and(|tag| tag.starts_with(':')))
+                    .cloned();
+                self.screen = Screen::Embeddings(EmbeddingPlayground::new(model));
+                Task::none()
+            }
+
+            Message::CloseEmbeddings => {
+                self.screen = Screen::Chat;
+                Task::none()
+            }
+
+            Message::EmbeddingsModelPicked(model) => {
+                if let Screen::Embeddings(playground) = &mut self.screen {
+                    playground.model = Some(model);
+                }
+                Task::none()
+            }
+
+            Message::EmbeddingsTextsAction(action) => {
+                if let Screen::Embeddings(playground) = &mut self.screen {
+                    playground.texts.perform(action);
+                }
+                Task::none()
+            }
+
+            Message::RunEmbeddings => match &mut self.screen {
+                Screen::Embeddings(playground) => playground.start(self.client.clone()),
+                _ => Task::none(),
+            },
+
+            Message::EmbeddingsCompared(result) => {
+                if let Screen::Embeddings(playground) = &mut self.screen {
+                    playground.running = false;
+                    playground.result = Some(result);
+                }
+                Task::none()
+            }
+
+            Message::OpenStorage => {
+                self.screen = Screen::Storage(StorageView::new());
+                self.load_models(Message::StorageLoaded)
+            }
+
+            Message::CloseStorage => {
+                self.screen = Screen::Chat;
+                Task::none()
+            }
+
+            Message::RefreshStorage => {
+                if let Screen::Storage(storage) = &mut self.screen {
+                    storage.loading = true;
+                }
+                self.load_models(Message::StorageLoaded)
+            }
+
+            Message::StorageLoaded(result) => {
+                if let Screen::Storage(storage) = &mut self.screen {
+                    storage.loading = false;
+                    match result {
+                        Ok(models) => {
+                            storage.models = models;
+                            storage.error = None;
+                        }
+                        Err(e) => storage.error = Some(format!("Couldn't list models: {e}")),
+                    }
+                }
+                Task::none()
+            }
+
+            Message::StorageSortPicked(sort) => {
+                if let Screen::Storage(storage) = &mut self.screen {
+                    storage.sort = sort;
+                }
+                Task::none()
+            }
+
+            Message::ConfirmDeleteModel(model) => {
+                if let Screen::Storage(storage) = &mut self.screen {
+                    storage.confirm_delete = Some(model);
+                }
+                Task::none()
+            }
+
+            Message::CancelDeleteModel => {
+                if let Screen::Storage(storage) = &mut self.screen {
+                    storage.confirm_delete = None;
+                }
+                Task::none()
+            }
+
+            Message::DeleteModel(model) => {
+                let Screen::Storage(storage) = &mut self.screen else {
+                    return Task::none();
+                };
+                storage.confirm_delete = None;
+                storage.deleting = Some(model.clone());
+                let client = self.client.clone();
+                Task::perform(
+                    async move {
+                        client
+                            .delete_model(&model)
+                            .await
+                            .map(|_| model)
+                            .map_err(|e| e.to_string())
+                    },
+                    Message::ModelDeleted,
+                )
+            }
+
+            Message::ModelDeleted(result) => {
+                if let Screen::Storage(storage) = &mut self.screen {
+                    storage.deleting = None;
+                }
+                match result {
+                    Ok(model) => {
+                        tracing::info!("Deleted {model}");
+                        self.toasts.push(Severity::Success, format!("Deleted {model}"));
+                    }
+                    Err(e) => self.toasts.push_with_detail(Severity::Error, "Delete failed", Some(e)),
+                }
+                // The picker's list changes too
+                Task::batch([
+                    self.load_models(Message::StorageLoaded),
+                    self.load_models(Message::ModelsRefreshed),
+                ])
+            }
+
+            Message::OpenModelForm(base) => {
+                self.show_model_info = false;
+                self.screen = Screen::ModelForm(ModelForm::new(base.clone()));
+                match base {
+                    Some(base) => self.update(Message::ModelFormBasePicked(base)),
+                    None => Task::none(),
+                }
+            }
+
+            Message::CloseModelForm => {
+                if let Screen::ModelForm(form) = &mut self.screen {
+                    form.cancel();
+                }
+                self.screen = Screen::Chat;
+                Task::none()
+            }
+
+            Message::ModelFormBasePicked(base) => {
+                let Screen::ModelForm(form) = &mut self.screen else {
+                    return Task::none();
+                };
+                form.base = Some(base.clone());
+                let client = self.client.clone();
+                Task::perform(
+                    async move {
+                        let show = client.show_model(&base).await.map_err(|e| e.to_string());
+                        (base, show)
+                    },
+                    |(base, show)| Message::ModelFormBaseLoaded(base, show),
+                )
+            }
+
+            Message::ModelFormBaseLoaded(base, result) => {
+                if let Screen::ModelForm(form) = &mut self.screen {
+                    // Picked another base while this one loaded
+                    if form.base.as_ref() != Some(&base) {
+                        return Task::none();
+                    }
+                    match result {
+                        Ok(show) => form.fill_from(&show),
+                        Err(e) => form.error = Some(format!("Couldn't read {base}: {e}")),
+                    }
+                }
+                Task::none()
+            }
+
+            Message::ModelFormNameChanged(name) => {
+                if let Screen::ModelForm(form) = &mut self.screen {
+                    form.name = name;
+                }
+                Task::none()
+            }
+
+            Message::ModelFormSystemAction(action) => {
+                if let Screen::ModelForm(form) = &mut self.screen {
+                    form.system.perform(action);
+                }
+                Task::none()
+            }
+
+            Message::ModelFormParametersAction(action) => {
+                if let Screen::ModelForm(form) = &mut self.screen {
+                    form.parameters.perform(action);
+                }
+                Task::none()
+            }
+
+            Message::ModelFormTemplateAction(action) => {
+                if let Screen::ModelForm(form) = &mut self.screen {
+                    form.template.perform(action);
+                }
+                Task::none()
+            }
+
+            Message::CreateModel => match &mut self.screen {
+                Screen::ModelForm(form) => form.start(self.client.clone()),
+                _ => Task::none(),
+            },
+
+            Message::CancelCreateModel => {
+                if let Screen::ModelForm(form) = &mut self.screen {
+                    form.cancel();
+                }
+                Task::none()
+            }
+
+            Message::CreateModelEvent(event) => {
+                let Screen::ModelForm(form) = &mut self.screen else {
+                    return Task::none();
+                };
+                match form.handle(event) {
+                    Some(model) => {
+                        tracing::info!("Created model {model}");
+                        self.toasts.push(Severity::Success, format!("Created {model}"));
+                        self.load_models(Message::ModelsRefreshed)
+                    }
+                    None => Task::none(),
+                }
+            }
+
+            Message::OnboardingUrlChanged(url) => {
+                if let Screen::Onboarding(setup) = &mut self.screen {
+                    setup.url = url;
+                    setup.reachable = None;
+                }
+                Task::none()
+            }
+
+            Message::OnboardingCheck => {
+                let Screen::Onboarding(setup) = &mut self.screen else {
+                    return Task::none();
+                };
+                let url = match settings::validate_url(&setup.url) {
+                    Ok(url) => url,
+                    Err(e) => {
+                        setup.error = Some(e);
+                        return Task::none();
+                    }
+                };
+                setup.error = None;
+                setup.checking = true;
+                if url != self.config.ollama_url {
+                    self.config.ollama_url = url;
+                    if let Err(e) = self.config.save() {
+                        setup.error = Some(format!("Server address not saved: {e}"));
+                    }
+                    self.client = OllamaClient::from_config(&self.config);
+                }
+                let client = self.client.clone();
+                Task::perform(
+                    async move { client.health_check().await.unwrap_or(false) },
+                    Message::OnboardingChecked,
+                )
+            }
+
+            Message::OnboardingChecked(ok) => {
+                let Screen::Onboarding(setup) = &mut self.screen else {
+                    return Task::none();
+                };
+                setup.checking = false;
+                setup.reachable = Some(ok);
+                if ok {
+                    self.reconnect_delay = RECONNECT_MIN;
+                    self.update(Message::CheckConnection)
+                } else {
+                    Task::none()
+                }
+            }
+
+            Message::OnboardingCustomChanged(name) => {
+                if let Screen::Onboarding(setup) = &mut self.screen {
+                    setup.custom = name;
+                }
+                Task::none()
+            }
+
+            Message::OnboardingPull(model) => match &mut self.screen {
+                Screen::Onboarding(setup) => {
+                    tracing::info!("Pulling {model}");
+                    setup.start_pull(self.client.clone(), model)
+                }
+                _ => Task::none(),
+            },
+
+            Message::OnboardingCancelPull => {
+                if let Screen::Onboarding(setup) = &mut self.screen {
+                    setup.cancel_pull();
+                }
+                Task::none()
+            }
+
+            Message::PullEvent(event) => {
+                let Screen::Onboarding(setup) = &mut self.screen else {
+                    return Task::none();
+                };
+                let Some(model) = setup.handle(event) else {
+                    return Task::none();
+                };
+                self.toasts.push(Severity::Success, format!("Downloaded {model}"));
+                self.params.set_options(&self.config.options_for(&model));
+                self.selected_model = Some(model);
+                self.model_info = None;
+                self.update(Message::RefreshModels)
+            }
+
+            Message::CloseOnboarding => {
+                if let Screen::Onboarding(setup) = &mut self.screen {
+                    setup.cancel_pull();
+                }
+                self.onboarding_dismissed = true;
+                self.screen = Screen::Chat;
+                Task::none()
+            }
+
+            Message::OpenLogs => {
+                let settings = match std::mem::replace(&mut self.screen, Screen::Chat) {
+                    Screen::Settings(form) => Some(form),
+                    _ => None,
+                };
+                let mut logs = LogView::new(settings);
+                let task = logs.load();
+                self.screen = Screen::Logs(logs);
+                task
+            }
+
+            Message::LogsLoaded(result) => {
+                if let Screen::Logs(logs) = &mut self.screen {
+                    logs.loaded(result);
+                }
+                Task::none()
+            }
+
+            Message::RefreshLogs => match &mut self.screen {
+                Screen::Logs(logs) => logs.load(),
+                _ => Task::none(),
+            },
+
+            Message::CopyLogs => {
+                let Screen::Logs(logs) = &self.screen else {
+                    return Task::none();
+                };
+                let text = logs.text.clone();
+                self.copy_notice = Some(String::from("📋 Copied the log"));
+                Task::perform(
+                    async move { clipboard::copy_to_clipboard(&text).await },
+                    Message::CopyComplete,
+                )
+            }
+
+            Message::CloseLogs => {
+                if let Screen::Logs(logs) = std::mem::replace(&mut self.screen, Screen::Chat) {
+                    if let Some(form) = logs.settings {
+                        self.screen = Screen::Settings(form);
+                    }
+                }
+                Task::none()
+            }
+
+            Message::TemplateTestModelSelected(model) => {
+                if let Screen::TemplateTests(run) = &mut self.screen {
+                    run.model = Some(model);
+                }
+                Task::none()
+            }
+
+            Message::RunTemplateTests => {
+                let Screen::TemplateTests(run) = &mut self.screen else {
+                    return Task::none();
+                };
+                let system_prompt = match &run.model {
+                    Some(model) => self.config.system_prompt_for(model),
+                    None => self.config.system_prompt.as_deref(),
+                };
+                run.start(
+                    self.client.clone(),
+                    self.config.prompt_templates.clone(),
+                    system_prompt.map(String::from),
+                )
+            }
+
+            Message::TemplateTestFinished(outcome) => {
+                if let Screen::TemplateTests(run) = &mut self.screen {
+                    run.results.push(outcome);
+                }
+                Task::none()
+            }
+
+            Message::TemplateTestsDone => {
+                if let Screen::TemplateTests(run) = &mut self.screen {
+                    run.finished();
+                }
+                Task::none()
+            }
+
+            Message::TrimHistory => {
+                if self.locked {
+                    return Task::none();
+                }
+                // Drop the oldest exchange: everything up to and including the first reply
+                let end = self
+                    .chat_history
+                    .iter()
+                    .position(|e| e.role == "assistant")
+                    .map_or(self.chat_history.len().min(1), |i| i + 1);
+                self.chat_history.drain(..end);
+                self.measured_tokens = None;
+                self.drop_summary_covering(0);
+                self.status_message = String::from("Removed oldest exchange");
+                Task::none()
+            }
+
+            Message::SummarizeHistory if self.locked => Task::none(),
+            Message::SummarizeHistory => self.summarize_history(),
+
+            Message::SummaryComplete(result) => {
+                self.summarizing = false;
+                match result {
+                    // History may have been cleared or trimmed in the meantime
+                    Ok(summary) if summary.covers <= self.chat_history.len() => {
+                        self.status_message = format!("Summarized {} older messages", summary.covers);
+                        self.summary = Some(summary);
+                        self.measured_tokens = None;
+                        // The context is small again, that's what the alert asked for
+                        self.budget_dismissed = true;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        self.status_message = format!("Summarization failed: {e}");
+                    }
+                }
+                Task::none()
+            }
+
+            Message::DismissBudgetAlert => {
+                self.budget_dismissed = true;
+                Task::none()
+            }
+
+            Message::ToggleLock => {
+                self.locked = !self.locked;
+                self.curating = false;
+                if self.locked {
+                    // A read-only conversation doesn't get the rest of the answer
+                    let _ = self.update(Message::CancelGeneration);
+                }
+                self.status_message = if self.locked {
+                    String::from("🔒 Conversation locked (read-only)")
+                } else {
+                    String::from("🔓 Conversation unlocked")
+                };
+                Task::none()
+            }
+
+            Message::ClearChat if self.locked => {
+                self.status_message = String::from("🔒 Conversation is locked");
+                Task::none()
+            }
+
+            Message::ClearChat => {
+                if self.compare_target.is_some() {
+                    let _ = self.update(Message::CancelGeneration);
+                }
+                // Notes and input typed since the last save would be lost with the conversation
+                let save_notes = if self.draft_edited_at.is_some() {
+                    self.update(Message::SaveDraft)
+                } else if self.scratchpad_edited {
+                    self.save_conversation()
+                } else {
+                    Task::none()
+                };
+                self.scratchpad = text_editor::Content::new();
+                self.scratchpad_in_context = false;
+                self.scratchpad_edited = false;
+                self.chat_history.clear();
+                self.queued.clear();
+                self.replaced = None;
+                self.editing = None;
+                self.conversation_id = None;
+                self.measured_tokens = None;
+                self.summary = None;
+                self.last_error = None;
+                self.tokens_used = 0;
+                self.budget_dismissed = false;
+                self.input_content = text_editor::Content::new();
+                self.status_message = String::from("Chat cleared");
+                save_notes
+            }
+
+            Message::AutoCopyToggled(enabled) => {
+                self.config.auto_copy = enabled;
+                self.status_message = if enabled {
+                    String::from("Auto-copy enabled")
+                } else {
+                    String::from("Auto-copy disabled")
+                };
+                Task::none()
+            }
+
+            Message::DeleteMessage(_) | Message::DeleteExchange(_) if self.locked => {
+                self.status_message = String::from("🔒 Conversation is locked");
+                Task::none()
+            }
+
+            Message::ToggleCurate => {
+                self.curating = !self.curating && !self.locked;
+                Task::none()
+            }
+
+            Message::SetExcluded(..)
+            | Message::MoveMessage(..)
+            | Message::SwitchVariant(..)
+            | Message::EditMessage(_)
+                if self.locked =>
+            {
+                self.status_message = String::from("🔒 Conversation is locked");
+                Task::none()
+            }
+
+            Message::SetExcluded(idx, excluded) => {
+                if let Some(entry) = self.chat_history.get_mut(idx) {
+                    entry.excluded = excluded;
+                    self.measured_tokens = None;
+                    self.drop_summary_covering(idx);
+                }
+                Task::none()
+            }
+
+            Message::MoveMessage(idx, offset) => {
+                if self.compare_target.is_some() {
+                    self.status_message = String::from("Wait for the comparison to finish");
+                    return Task::none();
+                }
+                let target = idx.checked_add_signed(offset);
+                // Summarized messages are sent as one block, they stay put
+                let first_movable = self.summary.as_ref().map_or(0, |s| s.covers);
+                if let Some(target) = target.filter(|t| {
+                    *t < self.chat_history.len() && *t >= first_movable && idx >= first_movable
+                }) {
+                    self.chat_history.swap(idx, target);
+                    self.measured_tokens = None;
+                    // The message being edited moves with it
+                    self.editing = self.editing.map(|e| match e {
+                        e if e == idx => target,
+                        e if e == target => idx,
+                        e => e,
+                    });
+                    self.refresh_diffs();
+                }
+                Task::none()
+            }
+
+            Message::SwitchVariant(idx, offset) => {
+                if self.status == Status::Generating {
+                    return Task::none();
+                }
+                let Some(entry) = self.chat_history.get(idx).filter(|e| !e.variants.is_empty()) else {
+                    return Task::none();
+                };
+                let count = entry.variants.len() + 1;
+                let next = (entry.variant_pos as isize + offset).rem_euclid(count as isize) as usize;
+
+                // Swap the shown tail for the chosen one, which takes over the list of the others
+                if self.editing.is_some_and(|e| e >= idx) {
+                    self.editing = None;
+                }
+                let mut tail = self.split_history(idx);
+                let mut variants = std::mem::take(&mut tail[0].variants);
+                variants.insert(tail[0].variant_pos.min(variants.len()), tail);
+                let mut chosen = variants.remove(next);
+                chosen[0].variants = variants;
+                chosen[0].variant_pos = next;
+                self.chat_history.extend(chosen);
+                self.status_message = format!("Branch {}/{count}", next + 1);
+                self.save_conversation()
+            }
+
+            Message::EditMessage(idx) => {
+                let Some(entry) = self.chat_history.get(idx).filter(|e| e.role == "user") else {
+                    return Task::none();
+                };
+                self.input_content = text_editor::Content::with_text(&entry.content);
+                self.input_content
+                    .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+                self.editing = Some(idx);
+                Task::none()
+            }
+
+            Message::CancelEdit => {
+                self.editing = None;
+                self.input_content = text_editor::Content::new();
+                Task::none()
+            }
+
+            Message::DeleteMessage(idx) => {
+                self.remove_entries(idx, 1);
+                self.save_conversation()
+            }
+
+            Message::DeleteExchange(idx) => {
+                // A user message together with the reply that follows it
+                let count = match self.chat_history.get(idx + 1) {
+                    Some(next) if next.role == "assistant" => 2,
+                    _ => 1,
+                };
+                self.remove_entries(idx, count);
+                self.save_conversation()
+            }
+
+            Message::CopyMessage(idx) => {
+                if let Some(entry) = self.chat_history.get(idx) {
+                    let content = entry.content.clone();
+                    let role = entry.role.clone();
+                    self.copy_notice = Some(format!("📋 Copied {role} message"));
+                    Task::perform(
+                        async move { clipboard::copy_to_clipboard(&content).await },
+                        Message::CopyComplete,
+                    )
+                } else {
+                    Task::none()
+                }
+            }
+
+            Message::CopyConversation => {
+                if self.chat_history.is_empty() {
+                    return Task::none();
+                }
+                let messages: Vec<SavedMessage> = self.chat_history.iter().map(ChatEntry::to_saved).collect();
+                let transcript = export::markdown(&messages);
+                self.copy_notice = Some(format!("📋 Copied {} messages as markdown", messages.len()));
+                Task::perform(
+                    async move { clipboard::copy_to_clipboard(&transcript).await },
+                    Message::CopyComplete,
+                )
+            }
+
+            Message::ExportHtml => {
+                if self.chat_history.is_empty() {
+                    self.status_message = String::from("Nothing to export yet");
+                    return Task::none();
+                }
+                let title = self
+                    .conversation_id
+                    .as_ref()
+                    .and_then(|id| self.conversations.iter().find(|c| &c.id == id))
+                    .map_or_else(|| String::from("Conversation"), |c| c.title.clone());
+                let messages: Vec<SavedMessage> = self.chat_history.iter().map(ChatEntry::to_saved).collect();
+                let page = export::html(&title, &messages);
+                Task::perform(
+                    code_blocks::save(code_blocks::save_dir(), export::html_filename(&title), page),
+                    Message::HtmlExported,
+                )
+            }
+
+            Message::HtmlExported(result) => {
+                match result {
+                    Ok(path) => self.toasts.push(Severity::Success, format!("Exported to {}", path.display())),
+                    Err(e) => self.toasts.push_with_detail(Severity::Error, "Export failed", Some(e)),
+                }
+                Task::none()
+            }
+
+            Message::CopyCode(idx, block) => {
+                let Some((_, code)) = self
+                    .chat_history
+                    .get(idx)
+                    .and_then(|entry| code_blocks::nth_block(&entry.content, block))
+                else {
+                    return Task::none();
+                };
+                let code = code.to_string();
+                self.copy_notice = Some(String::from("📋 Copied code block"));
+                Task::perform(
+                    async move { clipboard::copy_to_clipboard(&code).await },
+                    Message::CopyComplete,
+                )
+            }
+
+            Message::SaveCode(idx, block) => {
+                let Some((tag, code)) = self
+                    .chat_history
+                    .get(idx)
+                    .and_then(|entry| code_blocks::nth_block(&entry.content, block))
+                else {
+                    return Task::none();
+                };
+                let (language, _) = code_blocks::language(tag, code);
+                let filename = code_blocks::suggested_filename(code, language, block + 1);
+                let run = language.and_then(|l| l.run);
+                let code = code.to_string();
+                Task::perform(
+                    async move {
+                        let path = code_blocks::save(code_blocks::save_dir(), filename, code).await?;
+                        let hint = run.map(|run| run.replace("{file}", &path.display().to_string()));
+                        Ok((path, hint))
+                    },
+                    Message::CodeSaved,
+                )
+            }
+
+            Message::CodeSaved(result) => {
+                self.status_message = match result {
+                    Ok((path, Some(hint))) => format!("💾 Saved {}, run with: {hint}", path.display()),
+                    Ok((path, None)) => format!("💾 Saved {}", path.display()),
+                    Err(e) => e,
+                };
+                Task::none()
+            }
+
+            Message::HoverMessage(idx) => {
+                self.hovered_message = idx;
+                Task::none()
+            }
+
+            Message::CopyHoveredMessage => {
+                // With a selection in the input box, Ctrl+C already copied that
+                if self.input_content.selection().is_some() {
+                    return Task::none();
+                }
+                match self.hovered_message {
+                    Some(idx) => self.update(Message::CopyMessage(idx)),
+                    None => Task::none(),
+                }
+            }
+
+            Message::AskClipboard => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                Task::perform(clipboard::paste_from_clipboard(), Message::ClipboardPasted)
+            }
+
+            Message::ClipboardPasted(result) => {
+                let pasted = match result {
+                    Ok(text) if !text.trim().is_empty() => text,
+                    Ok(_) => {
+                        self.status_message = String::from("Clipboard is empty");
+                        return Task::none();
+                    }
+                    Err(e) => {
+                        self.status_message = format!("Paste failed: {e}");
+                        return Task::none();
+                    }
+                };
+                // Tag the fence when the language is recognizable, for highlighting in the bubble
+                let tag = code_blocks::detect(&pasted).map_or("", |language| language.name);
+                let block = format!("```{tag}\n{}\n```", pasted.trim_matches('\n'));
+                let prompt = match self.config.clipboard_prompt.trim() {
+                    "" => block,
+                    prefix => format!("{prefix}\n\n{block}"),
+                };
+                // Added after anything already typed
+                let typed = self.input_content.text();
+                let text = match typed.trim_end() {
+                    "" => prompt,
+                    typed => format!("{typed}\n\n{prompt}"),
+                };
+                self.input_content = text_editor::Content::with_text(&text);
+                self.input_content
+                    .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+                self.status_message = String::from("📋 Clipboard added, press Enter to ask");
+                Task::none()
+            }
+
+            Message::ToggleDictation => {
+                if let Some(recording) = self.recording.take() {
+                    let Some(model) = self.config.whisper_model.clone() else {
+                        return Task::none();
+                    };
+                    let binary = self.config.whisper_binary.clone();
+                    self.transcribing = true;
+                    self.status_message = String::from("Transcribing...");
+                    return Task::perform(
+                        async move {
+                            let path = recording.finish().await.map_err(|e| format!("Recording failed: {e}"))?;
+                            voice::transcribe(binary, model, path)
+                                .await
+                                .map_err(|e| format!("Transcription failed: {e}"))
+                        },
+                        Message::Transcribed,
+                    );
+                }
+                match Recording::start() {
+                    Ok(recording) => {
+                        self.recording = Some(recording);
+                        self.status_message = String::from("🎤 Listening, click ⏹ when done");
+                    }
+                    Err(e) => self.status_message = format!("Recording failed: {e}"),
+                }
+                Task::none()
+            }
+
+            Message::Transcribed(result) => {
+                self.transcribing = false;
+                match result {
+                    Ok(text) if !text.is_empty() => {
+                        // Inserted at the cursor, spaced from the word before it
+                        let (line, column) = self.input_content.cursor_position();
+                        let before = self
+                            .input_content
+                            .line(line)
+                            .and_then(|line| line.get(..column).and_then(|s| s.chars().last()));
+                        let text = match before {
+                            Some(c) if !c.is_whitespace() => format!(" {text}"),
+                            _ => text,
+                        };
+                        self.input_content
+                            .perform(text_editor::Action::Edit(text_editor::Edit::Paste(std::sync::Arc::new(text))));
+                        self.status_message = String::new();
+                    }
+                    Ok(_) => self.status_message = String::from("Heard nothing"),
+                    Err(e) => self.status_message = e,
+                }
+                Task::none()
+            }
+
+            Message::NewConversation => {
+                if self.locked {
+                    self.status_message = String::from("🔒 Conversation is locked");
+                    return Task::none();
+                }
+                let _ = self.update(Message::CancelGeneration);
+                self.session_system_prompt = self.profile().and_then(|p| p.system_prompt.clone());
+                self.params.template = None;
+                let task = self.update(Message::ClearChat);
+                self.status_message = String::from("New conversation");
+                task
+            }
+
+            // The copy becomes the current chat; the original stays as it was in the sidebar
+            Message::DuplicateConversation => {
+                if self.chat_history.is_empty() || self.status == Status::Generating {
+                    return Task::none();
+                }
+                let save_original = self.save_conversation();
+                let Some(original) = self
+                    .conversation_id
+                    .as_ref()
+                    .and_then(|id| self.conversations.iter().find(|c| &c.id == id))
+                    .cloned()
+                else {
+                    return save_original;
+                };
+                // Ids are creation millis, and the original may have been saved just now
+                let mut id = history::new_id();
+                while self.conversations.iter().any(|c| c.id == id) {
+                    id = (id.parse::<u128>().unwrap_or_default() + 1).to_string();
+                }
+                let now = history::unix_now();
+                let copy = Conversation {
+                    id: id.clone(),
+                    title: format!("{} (copy)", original.title),
+                    created_at: now,
+                    updated_at: now,
+                    ..original
+                };
+                self.conversation_id = Some(id);
+                self.status_message = format!("Duplicated as \"{}\"", copy.title);
+                self.conversations.insert(0, copy.clone());
+                Task::batch([save_original, Task::perform(history::save(copy), Message::ConversationSaved)])
+            }
+
+            Message::FocusModelPicker => {
+                let typed = self.input_content.text();
+                let typed = typed.trim_end_matches('\n');
+                if !typed.trim().is_empty() && !typed.trim_start().starts_with('/') {
+                    self.picker_draft = Some(typed.to_string());
+                }
+                self.update(Message::CompleteCommand("/model"))
+            }
+
+            Message::ScrollChat(pages) => scrollable::scroll_by(
+                CHAT_SCROLL.clone(),
+                scrollable::AbsoluteOffset { x: 0.0, y: pages * 400.0 },
+            ),
+
+            Message::CopyComplete(result) => {
+                let notice = self.copy_notice.take();
+                match result {
+                    Ok(()) => {
+                        if let Some(notice) = notice {
+                            self.toasts.push(Severity::Success, notice);
+                        }
+                    }
+                    Err(e) => self.toasts.push_with_detail(Severity::Error, "Copy failed", Some(e)),
+                }
+                Task::none()
+            }
+
+            Message::DismissToast(id) => {
+                self.toasts.dismiss(id);
+                Task::none()
+            }
+
+            Message::ToggleToastDetail(id) => {
+                self.toasts.toggle_detail(id);
+                Task::none()
+            }
+
+            Message::ToastTick => {
+                self.toasts.expire();
+                Task::none()
+            }
+
+            Message::SystemStatsTick => Task::perform(read_system_stats(), Message::SystemStatsUpdated),
+
+            Message::SystemStatsUpdated(stats) => {
+                self.cpu_percent = match (&self.system_stats, &stats) {
+                    (Some(earlier), Some(now)) => Some(now.cpu.usage_since(&earlier.cpu)),
+                    _ => None,
+                };
+                self.system_stats = stats;
+                Task::none()
+            }
+
+            Message::GpuStatsTick => {
+                Task::perform(async { read_amd_gpu_stats().await }, Message::GpuStatsUpdated)
+            }
+
+            Message::GpuStatsUpdated(stats) => {
+                // A card appearing or vanishing makes the old histories meaningless
+                if stats.len() != self.gpu_history.len() {
+                    self.gpu_history = vec![GpuHistory::default(); stats.len()];
+                }
+                for (history, card) in self.gpu_history.iter_mut().zip(&stats) {
+                    history.push(card);
+                }
+                self.gpu_stats = stats;
+                Task::none()
+            }
+
+            Message::SetUiScale(scale) => {
+                // Round so repeated steps don't drift to 1.0999999
+                let scale = (scale * 10.0).round() / 10.0;
+                self.config.ui_scale = scale.clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end());
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.ui_scale = format!("{:.0}", self.config.ui_scale * 100.0);
+                }
+                self.status_message = match self.config.save() {
+                    Ok(()) => format!("Zoom {:.0}%", self.config.ui_scale * 100.0),
+                    Err(e) => format!("Zoom {:.0}% (not saved: {e})", self.config.ui_scale * 100.0),
+                };
+                Task::none()
+            }
+
+            Message::SystemThemeTick => Task::perform(
+                async {
+                    tokio::task::spawn_blocking(themes::detect_system_dark)
+                        .await
+                        .unwrap_or(true)
+                },
+                Message::SystemThemeDetected,
+            ),
+
+            Message::SystemThemeDetected(dark) => {
+                self.system_dark = dark;
+                Task::none()
+            }
+            
+            Message::OpenSettings => {
+                self.screen = Screen::Settings(Box::new(SettingsForm::from_config(&self.config)));
+                Task::none()
+            }
+
+            Message::CloseSettings => {
+                self.screen = Screen::Chat;
+                Task::none()
+            }
+
+            Message::SettingsUrlChanged(url) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.ollama_url = url;
+                    form.connection_ok = None;
+                    form.error = None;
+                }
+                Task::none()
+            }
+
+            Message::SettingsModelChanged(model) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.default_model = model;
+                }
+                Task::none()
+            }
+
+            Message::SettingsTimestampsToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.show_timestamps = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsInputStatsToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.input_stats = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsSpellcheckChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.spellcheck = value;
+                }
+                Task::none()
+            }
+
+            #[cfg(feature = "spellcheck")]
+            Message::DictionaryLoaded(result) => {
+                match result {
+                    Ok(dictionary) => self.dictionary = Some(dictionary),
+                    Err(e) => {
+                        tracing::warn!("Spellcheck off: {e}");
+                        self.toasts.push(Severity::Warning, format!("Spellcheck off: {e}"));
+                    }
+                }
+                Task::none()
+            }
+
+            Message::SettingsSubmitKeyPicked(key) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.submit_key = key;
+                }
+                Task::none()
+            }
+
+            Message::SettingsTimestampFormatPicked(format) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.timestamp_format = format;
+                }
+                Task::none()
+            }
+
+            Message::SettingsSystemPromptAction(action) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.system_prompt.perform(action);
+                }
+                Task::none()
+            }
+
+            Message::SettingsAutoCopyToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.auto_copy = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsSystemStatsToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.show_system_stats = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsGpuStatsToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.show_gpu_stats = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsThemePicked(choice) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.theme = choice;
+                }
+                Task::none()
+            }
+
+            Message::SettingsResponseSoftCapChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.response_soft_cap = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsNotifyAfterChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.notify_after_secs = value;
+                }
+                Task::none()
+            }
+
+            Message::Notified(result) => {
+                if let Err(e) = result {
+                    tracing::warn!("Desktop notification failed: {e}");
+                }
+                Task::none()
+            }
+
+            Message::SettingsUiScaleChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.ui_scale = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsUserColorChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.user_bubble_color = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsAssistantColorChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.assistant_bubble_color = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsApiKeyChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.api_key = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsProxyChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.proxy = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsManageServerToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.manage_server = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsOllamaBinaryChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.ollama_binary = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsProxyBypassToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.proxy_bypass_local = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsHighContrastToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.high_contrast = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsReducedMotionToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.reduced_motion = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsSummarizeAfterChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.summarize_after_tokens = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsTokenBudgetChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.token_budget = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsInhibitIdleToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.inhibit_idle = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsKeepAliveChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.keep_alive = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsShowThinkingToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.show_thinking = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsRememberPromptsToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.remember_prompts = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsTokenTimingToggled(enabled) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.record_token_timing = enabled;
+                }
+                Task::none()
+            }
+
+            Message::SettingsQuickAskHotkeyChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.quick_ask_hotkey = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsClipboardPromptChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.clipboard_prompt = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsDocumentFoldersAction(action) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.document_folders.perform(action);
+                }
+                Task::none()
+            }
+
+            Message::SettingsEmbeddingModelChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.embedding_model = value;
+                }
+                Task::none()
+            }
+
+            Message::SettingsDocumentTopKChanged(value) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.document_top_k = value;
+                }
+                Task::none()
+            }
+
+            // Indexes what the form shows, so folders can be tried before applying
+            Message::IndexDocuments => {
+                let Screen::Settings(form) = &mut self.screen else {
+                    return Task::none();
+                };
+                let folders: Vec<String> = form
+                    .folders()
+                    .iter()
+                    .map(|folder| expand_home(folder).to_string_lossy().into_owned())
+                    .collect();
+                form.indexing = true;
+                form.index_status = None;
+                Task::perform(
+                    rag::index(self.client.clone(), form.embedding_model.trim().to_string(), folders),
+                    Message::DocumentsIndexed,
+                )
+            }
+
+            Message::DocumentsIndexed(result) => {
+                let status = match result {
+                    Ok(report) => {
+                        let mut status = format!(
+                            "Indexed {} files, {} unchanged, {} removed",
+                            report.indexed, report.unchanged, report.removed
+                        );
+                        if !report.failed.is_empty() {
+                            status.push_str(&format!(", {} unreadable (see log)", report.failed.len()));
+                        }
+                        status
+                    }
+                    Err(e) => format!("Indexing failed: {e}"),
+                };
+                tracing::info!("{status}");
+                match &mut self.screen {
+                    Screen::Settings(form) => {
+                        form.indexing = false;
+                        form.index_status = Some(status);
+                    }
+                    _ => self.status_message = status,
+                }
+                Task::none()
+            }
+
+            Message::TestConnection => {
+                let Screen::Settings(form) = &mut self.screen else {
+                    return Task::none();
+                };
+
+                match settings::validate_url(&form.ollama_url) {
+                    Ok(url) => {
+                        form.testing = true;
+                        form.connection_ok = None;
+                        form.error = None;
+                        // Test with what the form would apply, credentials included
+                        let mut config = self.config.clone();
+                        config.ollama_url = url;
+                        config.proxy_bypass_local = form.proxy_bypass_local;
+                        config.api_key = Some(form.api_key.trim().to_string()).filter(|k| !k.is_empty());
+                        config.proxy = Some(form.proxy.trim().to_string()).filter(|p| !p.is_empty());
+                        let client = OllamaClient::from_config(&config);
+                        Task::perform(
+                            async move { client.health_check().await.unwrap_or(false) },
+                            Message::ConnectionTested,
+                        )
+                    }
+                    Err(e) => {
+                        form.error = Some(e);
+                        Task::none()
+                    }
+                }
+            }
+
+            Message::ConnectionTested(ok) => {
+                if let Screen::Settings(form) = &mut self.screen {
+                    form.testing = false;
+                    form.connection_ok = Some(ok);
+                }
+                Task::none()
+            }
+
+            Message::ApplySettings => {
+                let Screen::Settings(form) = &mut self.screen else {
+                    return Task::none();
+                };
+
+                let new_config = match form.to_config(&self.config) {
+                    Ok(cfg) => cfg,
+                    Err(e) => {
+                        form.error = Some(e);
+                        return Task::none();
+                    }
+                };
+
+                self.status_message = match new_config.save() {
+                    Ok(()) => String::from("Settings saved"),
+                    Err(e) => format!("Settings applied but not saved: {e}"),
+                };
+                self.screen = Screen::Chat;
+                self.apply_config(new_config)
+            }
+
+            Message::ConfigReloaded(Ok(config)) => {
+                // Our own saves come back through the watcher too
+                let unchanged = toml::to_string(&config).ok() == toml::to_string(&self.config).ok();
+                if unchanged {
+                    return Task::none();
+                }
+                tracing::info!("config.toml changed on disk, reloading");
+                self.toasts.push(Severity::Info, "Reloaded config.toml");
+                self.apply_config(*config)
+            }
+
+            Message::ConfigReloaded(Err(e)) => {
+                self.toasts.push_with_detail(Severity::Warning, "config.toml not reloaded", Some(e));
+                Task::none()
+            }
+
+            Message::QuickAskToggle => match self.quick_ask.take() {
+                Some(popup) => window::close(popup.window),
+                None => {
+                    let (popup, task) = QuickAsk::open();
+                    self.quick_ask = Some(popup);
+                    task
+                }
+            },
+
+            Message::QuickAskInputChanged(value) => {
+                if let Some(popup) = &mut self.quick_ask {
+                    popup.input = value;
+                }
+                Task::none()
+            }
+
+            Message::QuickAskSubmit => {
+                let model = self
+                    .selected_model
+                    .clone()
+                    .or_else(|| self.config.default_model.clone());
+                let Some(popup) = &mut self.quick_ask else {
+                    return Task::none();
+                };
+                let Some(model) = model else {
+                    popup.error = Some(String::from("No model selected"));
+                    return Task::none();
+                };
+                let system_prompt = self.config.system_prompt_for(&model).map(String::from);
+                popup.ask(self.client.clone(), model, system_prompt, &self.redactor)
+            }
+
+            Message::QuickAskEvent(event) => {
+                let Some(popup) = &mut self.quick_ask else {
+                    return Task::none();
+                };
+                match event {
+                    QuickAskEvent::Token(token) => popup.answer.push_str(&token),
+                    QuickAskEvent::Done(Ok(())) => {
+                        popup.generating = false;
+                        let answer = ollama::split_thinking(&popup.answer).1.to_string();
+                        if self.config.auto_copy && !answer.is_empty() {
+                            return Task::perform(
+                                async move { clipboard::copy_to_clipboard(&answer).await },
+                                Message::QuickAskCopied,
+                            );
+                        }
+                    }
+                    QuickAskEvent::Done(Err(e)) => {
+                        popup.generating = false;
+                        popup.error = Some(e);
+                    }
+                }
+                Task::none()
+            }
+
+            Message::QuickAskCopied(result) => {
+                if let Some(popup) = &mut self.quick_ask {
+                    match result {
+                        Ok(()) => popup.copied = true,
+                        Err(e) => popup.error = Some(e),
+                    }
+                }
+                Task::none()
+            }
+
+            Message::OpenMainWindow => {
+                let close_popup = match self.quick_ask.take() {
+                    Some(popup) => window::close(popup.window),
+                    None => Task::none(),
+                };
+                let show_main = match self.main_window {
+                    Some(id) => window::gain_focus(id),
+                    None => {
+                        let (id, task) = open_main_window(&self.window_state);
+                        self.main_window = Some(id);
+                        self.window_minimized = false;
+                        task
+                    }
+                };
+                Task::batch([close_popup, show_main])
+            }
+
+            Message::WindowClosed(id) => {
+                if self.main_window == Some(id) {
+                    self.main_window = None;
+                    // Nothing to show, pause polling like when minimized
+                    self.window_minimized = true;
+                    if let Err(e) = self.window_state.save() {
+                        tracing::warn!("Failed to save window geometry: {e}");
+                    }
+                } else if self.quick_ask.as_ref().is_some_and(|q| q.window == id) {
+                    self.quick_ask = None;
+                }
+
+                if self.main_window.is_none() && self.quick_ask.is_none() && !self.background {
+                    iced::exit()
+                } else {
+                    Task::none()
+                }
+            }
+
+            Message::Shortcut(window, Shortcut::Escape)
+                if self.quick_ask.as_ref().is_some_and(|q| q.window == window) =>
+            {
+                self.quick_ask = None;
+                window::close(window)
+            }
+
+            Message::Shortcut(_, shortcut @ (Shortcut::ZoomIn | Shortcut::ZoomOut | Shortcut::ZoomReset)) => {
+                let scale = match shortcut {
+                    Shortcut::ZoomIn => self.config.ui_scale + 0.1,
+                    Shortcut::ZoomOut => self.config.ui_scale - 0.1,
+                    _ => 1.0,
+                };
+                self.update(Message::SetUiScale(scale))
+            }
+
+            Message::Shortcut(window, _) if self.main_window != Some(window) => Task::none(),
+
+            Message::Shortcut(_, shortcut) => match (&self.screen, shortcut) {
+                (Screen::Settings(_), Shortcut::Escape) => self.update(Message::CloseSettings),
+                (Screen::TemplateTests(_), Shortcut::Escape) => {
+                    self.update(Message::CloseTemplateTests)
+                }
+                (Screen::Logs(_), Shortcut::Escape) => self.update(Message::CloseLogs),
+                (Screen::Benchmark(_), Shortcut::Escape) => self.update(Message::CloseBenchmark),
+                (Screen::Embeddings(_), Shortcut::Escape) => self.update(Message::CloseEmbeddings),
+                (Screen::Storage(_), Shortcut::Escape) => self.update(Message::CloseStorage),
+                (Screen::ModelForm(_), Shortcut::Escape) => self.update(Message::CloseModelForm),
+                (Screen::Chat, Shortcut::Find) => self.update(Message::OpenSearch),
+                (Screen::Chat, Shortcut::Escape) if self.search.is_some() => self.update(Message::CloseSearch),
+                (Screen::Chat, Shortcut::Escape) if self.show_model_info => {
+                    self.update(Message::ToggleModelInfo)
+                }
+                (Screen::Chat, Shortcut::Escape) if self.completion.running => {
+                    self.update(Message::StopCompletion)
+                }
+                (Screen::Chat, Shortcut::Escape) => self.update(Message::CancelGeneration),
+                (Screen::Chat, Shortcut::ClearChat) => self.update(Message::ClearChat),
+                (Screen::Chat, Shortcut::FocusModelPicker) => self.update(Message::FocusModelPicker),
+                (Screen::Chat, Shortcut::NewConversation) => self.update(Message::NewConversation),
+                (Screen::Chat, Shortcut::Copy) => self.update(Message::CopyHoveredMessage),
+                (Screen::Chat, Shortcut::AskClipboard) => self.update(Message::AskClipboard),
+                (Screen::Chat, Shortcut::PageUp) => self.update(Message::ScrollChat(-1.0)),
+                (Screen::Chat, Shortcut::PageDown) => self.update(Message::ScrollChat(1.0)),
+                _ => Task::none(),
+            },
+
+            // Focus and size only matter for the main window's polling
+            Message::WindowEvent(id, _) if self.main_window != Some(id) => Task::none(),
+
+            Message::WindowEvent(id, event) => match event {
+                window::Event::Focused => {
+                    let was_paused = self.window_minimized;
+                    self.window_focused = true;
+                    self.window_minimized = false;
+
+                    // Refresh right away instead of showing stale numbers
+                    if !was_paused {
+                        return Task::none();
+                    }
+                    let gpu = self.config.show_gpu_stats.then(|| Task::done(Message::GpuStatsTick));
+                    let system = self.config.show_system_stats.then(|| Task::done(Message::SystemStatsTick));
+                    let reconnect = (self.status == Status::Disconnected).then(|| Task::done(Message::CheckConnection));
+                    Task::batch(gpu.into_iter().chain(system).chain(reconnect))
+                }
+                window::Event::Unfocused => {
+                    self.window_focused = false;
+                    // Losing focus is the only hint we get on most compositors
+                    window::get_minimized(id).map(Message::WindowMinimized)
+                }
+                window::Event::Resized(size) => {
+                    // Some platforms report minimizing as a resize to zero
+                    self.window_minimized = size.width == 0.0 || size.height == 0.0;
+                    if self.window_minimized {
+                        return Task::none();
+                    }
+                    // Only a restored window's size is worth remembering
+                    window::get_maximized(id).map(move |maximized| Message::WindowResized(size, maximized))
+                }
+                window::Event::Moved(position) => {
+                    if !self.window_state.maximized {
+                        self.window_state.x = Some(position.x);
+                        self.window_state.y = Some(position.y);
+                    }
+                    Task::none()
+                }
+                _ => Task::none(),
+            },
+
+            Message::WindowResized(size, maximized) => {
+                self.window_state.maximized = maximized;
+                if !maximized {
+                    self.window_state.width = size.width;
+                    self.window_state.height = size.height;
+                }
+                Task::none()
+            }
+
+            Message::WindowMinimized(minimized) => {
+                self.window_minimized = minimized.unwrap_or(false);
+                Task::none()
+            }
+
+            Message::ShiftPressed => {
+                self.shift_held = true;
+                Task::none()
+            }
+            
+            Message::ShiftReleased => {
+                self.shift_held = false;
+                Task::none()
+            }
+
+            Message::CommandPressed => {
+                self.command_held = true;
+                Task::none()
+            }
+
+            Message::CommandReleased => {
+                self.command_held = false;
+                Task::none()
+            }
+        }
+    }
+
+    pub fn view(&self, window: window::Id) -> Element<'_, Message> {
+        if let Some(popup) = self.quick_ask.as_ref().filter(|q| q.window == window) {
+            let model = self.selected_model.as_deref().or(self.config.default_model.as_deref());
+            return popup.view(model);
+        }
+
+        let content = match &self.screen {
+            Screen::Chat if self.show_model_info && self.selected_model.is_some() => {
+                let model = self.selected_model.as_deref().unwrap_or_default();
+                let backdrop = |_: &Theme| container::Style {
+                    background: Some(iced::Color { a: 0.6, ..iced::Color::BLACK }.into()),
+                    ..Default::default()
+                };
+                stack![
+                    self.view_chat(),
+                    opaque(
+                        center(model_info::view(model, self.model_info.as_ref(), self.model_info_error.as_deref()))
+                            .style(backdrop)
+                    ),
+                ]
+                .into()
+            }
+            Screen::Chat => self.view_chat(),
+            Screen::Settings(form) => form.view(&self.available_models),
+            Screen::TemplateTests(run) => run.view(&self.config.prompt_templates, &self.available_models),
+            Screen::Logs(logs) => logs.view(),
+            Screen::Onboarding(setup) => setup.view(),
+            Screen::Benchmark(run) => run.view(&self.available_models),
+            Screen::Embeddings(playground) => playground.view(&self.available_models),
+            Screen::ModelForm(form) => form.view(&self.available_models),
+            Screen::Storage(storage) => storage.view(storage::Usage {
+                conversations: &self.conversations,
+                running: &self.running_models,
+                default_model: self.config.default_model.as_deref(),
+            }),
+        };
+        // Always stacked, even with no toasts, so the widget tree (and the
+        // chat's scroll position and focus) stays put when one comes or goes
+        let content: Element<Message> = stack![content, self.toasts.view()].into();
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(|theme: &Theme| {
+                let palette = theme.extended_palette();
+                container::Style {
+                    border: iced::Border {
+                        color: palette.primary.strong.color,
+                        width: 2.0,
+                        radius: 8.0.into(),
+                    },
+                    ..Default::default()
+                }
+            })
+            .into()
+    }
+
+    fn view_chat(&self) -> Element<'_, Message> {
+        // Model selector row
+        let choice = |name: &String| ModelChoice {
+            name: name.clone(),
+            fit: self.vram_fit(name),
+        };
+        let model_picker = pick_list(
+            self.available_models.iter().map(choice).collect::<Vec<_>>(),
+            self.selected_model.as_ref().map(choice),
+            |choice| Message::ModelSelected(choice.name),
+        )
+        .placeholder("Select model...")
+        .width(Length::FillPortion(3));
+
+        let profile_picker: Element<Message> = if self.config.profiles.is_empty() {
+            Column::new().into()
+        } else {
+            let names: Vec<String> = self.config.profiles.iter().map(|p| p.name.clone()).collect();
+            row![pick_list(names, self.active_profile.clone(), Message::ProfileSelected).placeholder("Profile")]
+                .push_maybe(self.active_profile.is_some().then(|| {
+                    button(text("✕").size(11))
+                        .style(button::text)
+                        .padding([2, 4])
+                        .on_press(Message::ClearProfile)
+                }))
+                .align_y(iced::Alignment::Center)
+                .into()
+        };
+
+        let info_btn = button("ⓘ")
+            .style(button::secondary)
+            .on_press_maybe(self.selected_model.is_some().then_some(Message::ToggleModelInfo));
+        let selected_loaded = self
+            .selected_model
+            .as_ref()
+            .is_some_and(|m| self.running_models.iter().any(|r| &r.name == m));
+        let unload_btn = button("⏏")
+            .style(button::secondary)
+            .on_press_maybe(selected_loaded.then_some(Message::UnloadModel));
+        let cached_text = if self.models_cached {
+            text("cached").size(11).style(text::secondary)
+        } else {
+            text("")
+        };
+        let refresh_btn: Element<Message> = {
+            const SPINNER: [&str; 8] = ["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"];
+            let label = match self.refreshing_models {
+                true if self.config.reduced_motion => "…",
+                true => SPINNER[self.spinner_frame % SPINNER.len()],
+                false => "↻",
+            };
+            let button = button(text(label).width(14).center())
+                .on_press_maybe((!self.refreshing_models).then_some(Message::RefreshModels));
+            match &self.models_error {
+                Some(e) => {
+                    let age = self
+                        .models_fetched_at
+                        .map(|at| format!("last refreshed {}", format_age(at.elapsed())))
+                        .unwrap_or_else(|| String::from("never refreshed"));
+                    let hint = format!("Model list may be stale ({age}): {e}");
+                    tooltip(
+                        row![button, text("⚠").style(text::danger)].spacing(4).align_y(iced::Alignment::Center),
+                        container(text(hint).size(12)).padding(6).style(container::rounded_box),
+                        tooltip::Position::Bottom,
+                    )
+                    .into()
+                }
+                None => button.into(),
+            }
+        };
+        let clear_btn = button("Clear").on_press_maybe((!self.locked).then_some(Message::ClearChat));
+        let lock_btn = button(if self.locked { "🔒" } else { "🔓" })
+            .style(if self.locked {
+                button::primary
+            } else {
+                button::secondary
+            })
+            .on_press(Message::ToggleLock);
+        let curate_btn = button("Curate")
+            .style(if self.curating {
+                button::primary
+            } else {
+                button::secondary
+            })
+            .on_press_maybe((!self.locked).then_some(Message::ToggleCurate));
+        let params_btn = button("Params")
+            .style(if self.show_params {
+                button::primary
+            } else {
+                button::secondary
+            })
+            .on_press(Message::ToggleParams);
+        let settings_btn = button("⚙").on_press(Message::OpenSettings);
+        let auto_copy = checkbox("Auto-copy", self.config.auto_copy)
+            .on_toggle(Message::AutoCopyToggled)
+            .size(16);
+
+        let sidebar_btn = button("☰")
+            .style(if self.show_sidebar {
+                button::primary
+            } else {
+                button::secondary
+            })
+            .on_press(Message::ToggleSidebar);
+        let search_btn = button("🔍")
+            .style(button::secondary)
+            .on_press(Message::OpenSearch);
+        let scratchpad_btn = tooltip(
+            button("🗒")
+                .style(if self.show_scratchpad {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press(Message::ToggleScratchpad),
+            container(text("Scratchpad").size(12))
+                .padding(6)
+                .style(container::rounded_box),
+            tooltip::Position::Bottom,
+        );
+        let completion_btn = tooltip(
+            button(if self.completion_mode { "Completion" } else { "Chat" })
+                .style(if self.completion_mode {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press(Message::ToggleCompletionMode),
+            container(text("Switch between chat and raw completion (/api/generate)").size(12))
+                .padding(6)
+                .style(container::rounded_box),
+            tooltip::Position::Bottom,
+        );
+        let copy_chat_btn = tooltip(
+            button("⧉")
+                .style(button::secondary)
+                .on_press_maybe((!self.chat_history.is_empty()).then_some(Message::CopyConversation)),
+            container(text("Copy conversation as markdown").size(12))
+                .padding(6)
+                .style(container::rounded_box),
+            tooltip::Position::Bottom,
+        );
+        let duplicate_btn = tooltip(
+            button("⑂")
+                .style(button::secondary)
+                .on_press_maybe(
+                    (!self.chat_history.is_empty() && self.status != Status::Generating)
+                        .then_some(Message::DuplicateConversation),
+                ),
+            container(text("Duplicate chat to explore another direction").size(12))
+                .padding(6)
+                .style(container::rounded_box),
+            tooltip::Position::Bottom,
+        );
+        let export_btn = tooltip(
+            button("🌐")
+                .style(button::secondary)
+                .on_press_maybe((!self.chat_history.is_empty()).then_some(Message::ExportHtml)),
+            container(text("Export as HTML to share").size(12))
+                .padding(6)
+                .style(container::rounded_box),
+            tooltip::Position::Bottom,
+        );
+        let clipboard_btn = tooltip(
+            button("📋")
+                .style(button::secondary)
+                .on_press_maybe((!self.locked).then_some(Message::AskClipboard)),
+            container(text("Ask about clipboard (Ctrl+Shift+V)").size(12))
+                .padding(6)
+                .style(container::rounded_box),
+            tooltip::Position::Bottom,
+        );
+
+        let toolbar = row![
+            sidebar_btn,
+            profile_picker,
+            model_picker,
+            cached_text,
+            info_btn,
+            unload_btn,
+            refresh_btn,
+            clear_btn,
+            curate_btn,
+            params_btn,
+            completion_btn,
+            horizontal_space(),
+            auto_copy,
+            scratchpad_btn,
+            duplicate_btn,
+            copy_chat_btn,
+            export_btn,
+            clipboard_btn,
+            search_btn,
+            lock_btn,
+            settings_btn,
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        // Chat history
+        let chat_content: Element<Message> = if self.chat_history.is_empty() && self.status != Status::Generating {
+            container(
+                text("Start a conversation...")
+                    .size(16)
+                    .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+            )
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .into()
+        } else {
+            let mut chat_column = Column::new().spacing(12).padding(8);
+            let start = self.summary.as_ref().map_or(0, |s| s.covers);
+            let window_start = self.history_window_start(start, self.chat_history.len());
+
+            // Messages far from the viewport are replaced by empty space of about their height
+            let built = self.built_range();
+            let mut y = 0.0;
+            let mut skipped = 0.0;
+
+            for (idx, entry) in self.chat_history.iter().enumerate() {
+                if !self.search_matches(idx) {
+                    continue;
+                }
+                if let Some((top, bottom)) = built {
+                    let height = self.estimated_height(entry);
+                    y += height;
+                    if y < top || y - height > bottom {
+                        skipped += height;
+                        continue;
+                    }
+                    if skipped > 0.0 {
+                        chat_column = chat_column.push(vertical_space().height(skipped - 12.0));
+                        skipped = 0.0;
+                    }
+                }
+                if idx == window_start && window_start > start {
+                    let limit = self.params.history_limit.trim();
+                    chat_column = chat_column.push(
+                        container(
+                            text(format!("⋯ Messages above aren't sent (history: {limit})"))
+                                .size(11)
+                                .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                        )
+                        .center_x(Length::Fill),
+                    );
+                }
+                if let Some(thinking) = entry.thinking.as_ref().filter(|_| self.config.show_thinking) {
+                    chat_column = chat_column.push(thinking_block(idx, thinking, entry.show_thinking));
+                }
+
+                let (content, hidden) = collapsed(&entry.content, entry.expanded || self.active_search().is_some());
+                let bubble = self.render_message(idx, &entry.role, content);
+                chat_column = chat_column.push(bubble);
+
+                if entry.content.lines().count() > COLLAPSE_LINES && self.active_search().is_none() {
+                    let label = if entry.expanded {
+                        String::from("Show less ▴")
+                    } else {
+                        format!("Show more ({hidden} lines) ▾")
+                    };
+                    let toggle = button(text(label).size(12))
+                        .style(button::text)
+                        .on_press(Message::ToggleExpanded(idx));
+                    chat_column = chat_column.push(if entry.role == "user" {
+                        container(toggle).align_right(Length::Fill)
+                    } else {
+                        container(toggle)
+                    });
+                }
+
+                if let Some(diff) = &entry.diff {
+                    chat_column = chat_column.push(diff_view::view(idx, diff));
+                }
+
+                if self.config.show_timestamps {
+                    if let Some(meta) = message_meta(entry, self.config.timestamp_format) {
+                        chat_column = chat_column.push(meta);
+                    }
+                }
+
+                if entry.excluded {
+                    let note = text("⊘ Left out of the context")
+                        .size(11)
+                        .color(iced::Color::from_rgb(0.5, 0.5, 0.5));
+                    chat_column = chat_column.push(if entry.role == "user" {
+                        container(note).align_right(Length::Fill)
+                    } else {
+                        container(note)
+                    });
+                }
+
+                if let Some(ref timing) = entry.timing {
+                    chat_column = chat_column.push(timing.view());
+                }
+
+                let is_last = idx + 1 == self.chat_history.len();
+                if entry.truncated && is_last && self.status != Status::Generating && !self.locked {
+                    chat_column = chat_column.push(
+                        row![
+                            text("Cut off at the token limit").size(12),
+                            button(text("Continue ▸").size(12))
+                                .style(button::secondary)
+                                .on_press(Message::Continue),
+                        ]
+                        .spacing(8)
+                        .align_y(iced::Alignment::Center),
+                    );
+                }
+
+                for (alt, alternative) in entry.alternatives.iter().enumerate() {
+                    chat_column = chat_column.push(alternative_view(idx, alt, alternative));
+                }
+                if self.compare_target == Some(idx) && self.status == Status::Generating {
+                    chat_column = chat_column.push(self.streaming_bubble());
+                }
+
+                if self.summary.as_ref().is_some_and(|s| s.covers == idx + 1) {
+                    chat_column = chat_column.push(
+                        container(
+                            text("Messages above are sent to the model as a summary")
+                                .size(12)
+                                .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                        )
+                        .center_x(Length::Fill),
+                    );
+                }
+            }
+
+            if skipped > 0.0 {
+                chat_column = chat_column.push(vertical_space().height(skipped - 12.0));
+            }
+
+            if let Some(ref report) = self.last_error {
+                chat_column = chat_column.push(report.view());
+            }
+
+            // The response so far, with a running length counter
+            // (a comparison streams under the message it's compared with)
+            let streaming_here = self.status == Status::Generating && self.compare_target.is_none();
+            if streaming_here {
+                chat_column = chat_column.push(self.streaming_bubble());
+            }
+            for (idx, prompt) in self.queued.iter().enumerate() {
+                chat_column = chat_column.push(self.queued_bubble(idx, prompt));
+            }
+
+            scrollable(chat_column)
+                .id(CHAT_SCROLL.clone())
+                .on_scroll(Message::ChatScrolled)
+                .height(Length::FillPortion(5))
+                .into()
+        };
+
+        // Input area
+        let is_generating = self.status == Status::Generating;
+        // Without an action handler the editor is read-only
+        let input = text_editor(&self.input_content);
+        #[cfg(feature = "spellcheck")]
+        let input = input
+            .highlight_with::<spellcheck::Highlighter>(spellcheck::Settings(self.dictionary.clone()), spellcheck::format);
+        let mut input = input
+            .placeholder(if self.locked {
+                "Conversation is locked"
+            } else {
+                "Type your message..."
+            })
+            .height(Length::Fixed(80.0));
+        if !self.locked {
+            input = input.on_action(Message::InputChanged);
+        }
+
+        let can_send = !is_generating && !self.locked && self.selected_model.is_some();
+        // While an answer streams, Send queues the prompt for after it
+        let send_btn = button(if is_generating { "Queue" } else { "Send" })
+            .on_press_maybe((!self.locked && self.selected_model.is_some()).then_some(Message::Submit));
+
+        let dictate_btn = (cfg!(feature = "dictation") && self.config.whisper_model.is_some()).then(|| {
+            let recording = self.recording.is_some();
+            tooltip(
+                button(if recording { "⏹" } else { "🎤" })
+                    .style(if recording { button::danger } else { button::secondary })
+                    .on_press_maybe((!self.locked && !self.transcribing).then_some(Message::ToggleDictation)),
+                container(text(if recording { "Stop and transcribe" } else { "Dictate" }).size(12))
+                    .padding(6)
+                    .style(container::rounded_box),
+                tooltip::Position::Top,
+            )
+        });
+
+        let quick_prompts: Element<Message> = if self.quick_prompts().is_empty() || self.locked {
+            Column::new().into()
+        } else {
+            let buttons = self.quick_prompts().iter().enumerate().map(|(idx, prompt)| {
+                button(text(&prompt.label).size(12))
+                    .style(button::secondary)
+                    .padding([2, 8])
+                    .on_press_maybe(can_send.then_some(Message::QuickPrompt(idx)))
+                    .into()
+            });
+            iced::widget::Row::with_children(buttons).spacing(6).wrap().into()
+        };
+
+        let input_row = row![input]
+            .push_maybe(dictate_btn)
+            .push(send_btn)
+            .spacing(8)
+            .align_y(iced::Alignment::End);
+
+        let dropped_files: Element<Message> = if self.dropped_files.is_empty() {
+            Column::new().into()
+        } else {
+            let chips = self.dropped_files.iter().enumerate().map(|(idx, attachment)| {
+                container(
+                    row![
+                        text(format!("📎 attached: {}", attachment.label())).size(12),
+                        button(text("✕").size(11))
+                            .style(button::text)
+                            .padding([0, 4])
+                            .on_press(Message::RemoveDroppedFile(idx)),
+                    ]
+                    .spacing(4)
+                    .align_y(iced::Alignment::Center),
+                )
+                .padding([2, 8])
+                .style(container::rounded_box)
+                .into()
+            });
+            row(chips).spacing(6).into()
+        };
+
+        let editing: Element<Message> = match self.editing {
+            Some(_) => row![
+                text("✎ Editing a message, the original stays as a branch").size(12),
+                button(text("Cancel").size(11))
+                    .style(button::text)
+                    .padding([0, 4])
+                    .on_press(Message::CancelEdit),
+            ]
+            .spacing(6)
+            .align_y(iced::Alignment::Center)
+            .into(),
+            None => Column::new().into(),
+        };
+
+        let input_text = self.input_content.text();
+        let input_stats: Element<Message> = if self.config.input_stats && !input_text.trim().is_empty() {
+            let words = input_text.split_whitespace().count();
+            let chars = input_text.trim_end().chars().count();
+            let stats = format!(
+                "{words} words · {chars} characters · ~{} tokens",
+                format_tokens(estimate_tokens(&input_text))
+            );
+            #[cfg(feature = "spellcheck")]
+            let stats = match self.dictionary.as_ref().map_or(0, |d| d.count_misspelled(&input_text)) {
+                0 => stats,
+                misspelled => format!("{stats} · {misspelled} possibly misspelled"),
+            };
+            container(text(stats).size(11).color(iced::Color::from_rgb(0.5, 0.5, 0.5)))
+                .align_right(Length::Fill)
+                .into()
+        } else {
+            Column::new().into()
+        };
+        let command_matches = commands::suggestions(
+            &input_text,
+            &self.available_models,
+            &self.config.conversation_templates,
+        );
+        let mention_matches = match (&self.workspace, workspace::mention_query(&input_text)) {
+            (Some(workspace), Some(query)) => workspace.matches(query, 8),
+            _ => Vec::new(),
+        };
+        let command_popup: Element<Message> = if self.locked {
+            Column::new().into()
+        } else if !command_matches.is_empty() {
+            commands::view_suggestions(command_matches)
+        } else if !mention_matches.is_empty() {
+            commands::view_mentions(mention_matches)
+        } else {
+            Column::new().into()
+        };
+
+        let context_warning: Element<Message> = if self.context_nearly_full() && !self.locked {
+            row![
+                text("Conversation is about to exceed the model's context window; older messages will be forgotten.")
+                    .size(12)
+                    .style(text::danger),
+                horizontal_space(),
+                button(text("Summarize").size(12))
+                    .style(button::secondary)
+                    .on_press_maybe((!self.summarizing).then_some(Message::SummarizeHistory)),
+                button(text("Trim oldest").size(12))
+                    .style(button::secondary)
+                    .on_press(Message::TrimHistory),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+            .into()
+        } else {
+            Column::new().into()
+        };
+
+        let budget_warning: Element<Message> = match self.config.token_budget {
+            Some(budget) if self.over_token_budget() && !self.budget_dismissed && !self.locked => row![
+                text(format!(
+                    "This conversation has used {} of its {} token budget. Summarize or start fresh to keep replies fast.",
+                    format_tokens(self.tokens_used),
+                    format_tokens(budget)
+                ))
+                .size(12)
+                .style(text::danger),
+                horizontal_space(),
+                button(text("Summarize").size(12))
+                    .style(button::secondary)
+                    .on_press_maybe((!self.summarizing).then_some(Message::SummarizeHistory)),
+                button(text("New chat").size(12))
+                    .style(button::secondary)
+                    .on_press(Message::ClearChat),
+                button(text("✕").size(12))
+                    .style(button::text)
+                    .on_press(Message::DismissBudgetAlert),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center)
+            .into(),
+            _ => Column::new().into(),
+        };
+
+        let model_warning: Element<Message> = match self.selected_model.as_deref() {
+            Some(model) if self.selected_model_missing() => {
+                let switch = closest_model(model, &self.available_models).map(|replacement| {
+                    button(text(format!("Switch to {replacement}")).size(12))
+                        .style(button::secondary)
+                        .on_press(Message::ModelSelected(replacement))
+                });
+                row![
+                    text(format!("⚠ {model} is no longer available on the server."))
+                        .size(12)
+                        .style(text::danger),
+                    horizontal_space(),
+                ]
+                .push_maybe(switch)
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .into()
+            }
+            _ => Column::new().into(),
+        };
+
+        // Status bar with GPU stats
+        let status_text = text(&self.status_message).size(12);
+
+        // Each stat with a sparkline of the last couple of minutes
+        let gpu_rows = self.gpu_stats.iter().zip(&self.gpu_history).enumerate().map(|(idx, (stats, history))| {
+            // Cards are only told apart once there's more than one
+            let name = (self.gpu_stats.len() > 1).then(|| text(format!("{}:", stats.label(idx))).size(12));
+            row![]
+                .push_maybe(name)
+                .push(gpu_stat(
+                    format!(
+                        "VRAM: {}/{}MB ({:.0}%)",
+                        stats.vram_used_mb,
+                        stats.vram_total_mb,
+                        stats.vram_usage_percent()
+                    ),
+                    Sparkline::new(&history.vram_percent).max(100.0),
+                ))
+                .push(gpu_stat(
+                    format!("GPU: {}%", stats.gpu_usage_percent),
+                    Sparkline::new(&history.usage_percent).max(100.0),
+                ))
+                .push_maybe(stats.temperature_c.map(|t| gpu_stat(format!("{t}°C"), Sparkline::new(&history.temperature_c))))
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+                .into()
+        });
+        let gpu_text = Column::with_children(gpu_rows).spacing(2);
+
+        let system_text = match self.system_stats.filter(|_| self.config.show_system_stats) {
+            Some(stats) => text(format!(
+                "RAM: {:.1}/{:.1}GB{}",
+                stats.ram_used_mb as f32 / 1024.0,
+                stats.ram_total_mb as f32 / 1024.0,
+                self.cpu_percent.map(|cpu| format!(" | CPU: {cpu:.0}%")).unwrap_or_default()
+            ))
+            .size(12),
+            None => text("").size(12),
+        };
+
+        let context_text = match self.context_length() {
+            Some(ctx) => format!(
+                "{} / {} ctx",
+                format_tokens(self.context_tokens()),
+                format_tokens(ctx)
+            ),
+            None if !self.chat_history.is_empty() => {
+                format!("~{} tokens", format_tokens(self.context_tokens()))
+            }
+            None => String::new(),
+        };
+        let context_text = match self.config.token_budget {
+            Some(budget) if self.tokens_used > 0 => format!(
+                "{context_text}  {} / {} budget",
+                format_tokens(self.tokens_used),
+                format_tokens(budget)
+            ),
+            _ => context_text,
+        };
+        let context_text = if self.context_nearly_full() || self.over_token_budget() {
+            text(context_text).size(12).style(text::danger)
+        } else {
+            text(context_text).size(12)
+        };
+
+        let loaded_text = if self.running_models.is_empty() {
+            text("").size(12)
+        } else {
+            // Share of the model in VRAM, anything below 100% is partially on the CPU
+            let names: Vec<String> = self
+                .running_models
+                .iter()
+                .map(|m| match (m.size_vram * 100).checked_div(m.size) {
+                    Some(gpu) if gpu < 100 => {
+                        format!("{} {} VRAM ({gpu}% GPU)", m.name, format_bytes(m.size_vram))
+                    }
+                    _ => format!("{} {} VRAM", m.name, format_bytes(m.size_vram)),
+                })
+                .collect();
+            text(format!("Loaded: {}", names.join(", "))).size(12)
+        };
+
+        let retry_btn = (self.status == Status::Disconnected).then(|| {
+            button(text("Retry now").size(12))
+                .style(button::text)
+                .padding([0, 4])
+                .on_press_maybe((!self.checking_connection).then_some(Message::CheckConnection))
+        });
+        let logs_btn = (self.status == Status::Disconnected).then(|| {
+            button(text("Logs").size(12))
+                .style(button::text)
+                .padding([0, 4])
+                .on_press(Message::OpenLogs)
+        });
+        let server_menu = (self.config.manage_server || self.server.is_some()).then(|| {
+            let (label, actions) = match &self.server {
+                Some(server) => (
+                    format!("🖥 ollama serve (pid {})", server.pid()),
+                    vec![ServerAction::Restart, ServerAction::Stop],
+                ),
+                None => (String::from("🖥 Server"), vec![ServerAction::Start]),
+            };
+            pick_list(actions, None::<ServerAction>, Message::ServerAction)
+                .placeholder(label)
+                .text_size(12)
+                .padding([0, 6])
+        });
+        let status_bar = row![status_text]
+            .push_maybe(retry_btn)
+            .push_maybe(logs_btn)
+            .push_maybe(server_menu)
+            .push(horizontal_space())
+            .push(context_text)
+            .push(loaded_text)
+            .push(system_text)
+            .push(gpu_text)
+            .spacing(16)
+            .align_y(iced::Alignment::Center);
+
+        let params_panel = if self.show_params {
+            self.params.view(
+                self.config
+                    .prompt_templates
+                    .iter()
+                    .map(|t| t.name.clone())
+                    .collect(),
+            )
+        } else {
+            Column::new().into()
+        };
+
+        let search_bar: Element<Message> = match &self.search {
+            Some(query) => {
+                let found = self.active_search().map(|_| {
+                    let count = (0..self.chat_history.len()).filter(|idx| self.search_matches(*idx)).count();
+                    text(format!("{count} of {} messages", self.chat_history.len())).size(12)
+                });
+                row![
+                    text_input("Search messages (Esc to close)", query)
+                        .id(SEARCH_INPUT.clone())
+                        .on_input(Message::SearchChanged)
+                        .width(Length::Fill),
+                ]
+                .push_maybe(found)
+                .push(button(text("✕").size(12)).style(button::text).on_press(Message::CloseSearch))
+                .spacing(8)
+                .align_y(iced::Alignment::Center)
+                .into()
+            }
+            None => Column::new().into(),
+        };
+
+        // Main layout
+        let chat_pane = if self.completion_mode {
+            let can_run = self.status != Status::Generating && self.selected_model.is_some();
+            column![
+                toolbar,
+                params_panel,
+                vertical_space().height(8),
+                self.completion.view(can_run),
+                vertical_space().height(4),
+                status_bar,
+            ]
+            .spacing(4)
+        } else {
+            column![
+                toolbar,
+                params_panel,
+                search_bar,
+                vertical_space().height(8),
+                chat_content,
+                vertical_space().height(8),
+                context_warning,
+                budget_warning,
+                model_warning,
+                command_popup,
+                editing,
+                dropped_files,
+                quick_prompts,
+                input_row,
+                input_stats,
+                vertical_space().height(4),
+                status_bar,
+            ]
+            .spacing(4)
+        };
+
+        let chat_pane: Element<Message> = row![chat_pane]
+            .push_maybe(self.show_scratchpad.then(|| self.scratchpad_panel()))
+            .push_maybe(self.params.inspect.then(|| self.inspector.view()))
+            .spacing(12)
+            .into();
+
+        if self.show_sidebar {
+            let sidebar = sidebar::view(
+                &self.conversations,
+                &self.config.conversation_templates,
+                &self.snippets,
+                self.show_snippets,
+                self.conversation_id.as_deref(),
+                self.renaming.as_ref().map(|(id, draft)| (id.as_str(), draft.as_str())),
+                self.active_search(),
+            );
+            row![sidebar, chat_pane].spacing(12).padding(16).into()
+        } else {
+            container(chat_pane).padding(16).into()
+        }
+    }
+
+    /// Guide a first run through connecting and downloading a model, unless
+    /// it was skipped or something else is open
+    fn start_onboarding(&mut self, reachable: bool) {
+        if self.onboarding_dismissed || !matches!(self.screen, Screen::Chat) || !self.chat_history.is_empty() {
+            return;
+        }
+        self.screen = Screen::Onboarding(Onboarding::new(self.config.ollama_url.clone(), reachable));
+    }
+
+    /// Notes panel to the right of the chat
+    fn scratchpad_panel(&self) -> Element<'_, Message> {
+        let header = row![
+            text("Scratchpad").size(14),
+            horizontal_space(),
+            checkbox("Send as context", self.scratchpad_in_context)
+                .on_toggle(Message::ScratchpadInContextToggled)
+                .size(14)
+                .text_size(12),
+        ]
+        .align_y(iced::Alignment::Center);
+        let notes = text_editor(&self.scratchpad)
+            .placeholder("Paste context or jot down results...")
+            .on_action(Message::ScratchpadAction)
+            .height(Length::Fill);
+        column![header, notes].spacing(8).width(Length::Fixed(300.0)).into()
+    }
+
+    /// Search text while the search bar is open and not empty
+    fn active_search(&self) -> Option<&str> {
+        self.search.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+
+    /// Whether the message at `idx` survives the search filter
+    fn search_matches(&self, idx: usize) -> bool {
+        let Some(query) = self.active_search() else {
+            return true;
+        };
+        self.chat_history
+            .get(idx)
+            .is_some_and(|entry| !history::find_matches(&entry.content, query).is_empty())
+    }
+
+    /// Redo the shown diffs whose question or answer changed under them
+    fn refresh_diffs(&mut self) {
+        for idx in 0..self.chat_history.len() {
+            let Some(diff) = &self.chat_history[idx].diff else {
+                continue;
+            };
+            let fresh = match self.diff_sides(idx) {
+                Some((original, revised)) if diff.is_of(original, revised) => continue,
+                Some((original, revised)) => Some(diff_view::Diff::new(original, revised)),
+                None => None,
+            };
+            self.chat_history[idx].diff = fresh;
+        }
+    }
+
+    /// The text a question asked to revise and the answer's version of it,
+    /// for an answer that follows such a question
+    fn diff_sides(&self, idx: usize) -> Option<(&str, &str)> {
+        let answer = self.chat_history.get(idx).filter(|e| e.role == "assistant")?;
+        let question = self.chat_history.get(idx.checked_sub(1)?).filter(|e| e.role == "user")?;
+        let original = diff_view::original(&question.content, &question.attachments)?;
+        Some((original, diff_view::revised(&answer.content)))
+    }
+
+    /// Save the chat under its conversation id, assigning one on the first save
+    fn save_conversation(&mut self) -> Task<Message> {
+        if self.chat_history.is_empty() {
+            // No conversation file yet, the notes are kept on their own like the draft
+            if !self.scratchpad_edited {
+                return Task::none();
+            }
+            self.scratchpad_edited = false;
+            return Task::perform(history::save_scratchpad(self.scratchpad_text()), Message::ConversationSaved);
+        }
+        let messages: Vec<SavedMessage> = self.chat_history.iter().map(ChatEntry::to_saved).collect();
+        // A new chat's draft and notes were kept on their own until now
+        let first_save = self.conversation_id.is_none();
+        let id = self.conversation_id.get_or_insert_with(history::new_id).clone();
+        let now = history::unix_now();
+
+        let existing = self
+            .conversations
+            .iter()
+            .position(|c| c.id == id)
+            .map(|i| self.conversations.remove(i));
+        let conversation = Conversation {
+            title: existing
+                .as_ref()
+                .map(|c| c.title.clone())
+                .unwrap_or_else(|| history::title_from(&messages)),
+            renamed: existing.as_ref().is_some_and(|c| c.renamed),
+            created_at: existing.as_ref().map_or(now, |c| c.created_at),
+            updated_at: now,
+            model: self.selected_model.clone(),
+            system_prompt: self.session_system_prompt.clone(),
+            options: self.params.to_options().ok(),
+            scratchpad: self.scratchpad_text(),
+            scratchpad_in_context: self.scratchpad_in_context,
+            draft: self.draft_text(),
+            messages,
+            id,
+        };
+        self.scratchpad_edited = false;
+        self.draft_edited_at = None;
+        self.conversations.insert(0, conversation.clone());
+        let save = Task::perform(history::save(conversation), Message::ConversationSaved);
+        if first_save {
+            Task::batch([
+                save,
+                Task::perform(history::save_draft(String::new()), Message::ConversationSaved),
+                Task::perform(history::save_scratchpad(String::new()), Message::ConversationSaved),
+            ])
+        } else {
+            save
+        }
+    }
+
+    /// Put the draft into the saved conversation as it is, without moving it
+    /// to the top of the sidebar the way a new message does
+    fn save_draft_in_conversation(&mut self) -> Task<Message> {
+        let draft = self.draft_text();
+        let id = self.conversation_id.as_deref();
+        match self.conversations.iter_mut().find(|c| Some(c.id.as_str()) == id) {
+            Some(conversation) if !self.scratchpad_edited => {
+                conversation.draft = draft;
+                Task::perform(history::save(conversation.clone()), Message::ConversationSaved)
+            }
+            _ => self.save_conversation(),
+        }
+    }
+
+    /// Unsent input, without the editor's trailing newline
+    fn draft_text(&self) -> String {
+        self.input_content.text().trim_end_matches('\n').to_string()
+    }
+
+    /// Checks shared by everything that sends the conversation to the model
+    fn request_settings(&mut self) -> Option<(String, ChatOptions)> {
+        if self.status == Status::Generating {
+            return None;
+        }
+
+        let Some(model) = self.selected_model.clone() else {
+            self.status_message = String::from("No model selected");
+            return None;
+        };
+        if self.selected_model_missing() {
+            self.status_message = format!("{model} is no longer installed, pick another model");
+            return None;
+        }
+
+        match self.params.to_options() {
+            Ok(options) => Some((model, options)),
+            Err(e) => {
+                self.show_params = true;
+                self.status_message = e;
+                None
+            }
+        }
+    }
+
+    /// Send the conversation as it stands and stream the reply into the history
+    fn generate(&mut self, model: String, options: ChatOptions) -> Task<Message> {
+        self.status = Status::Generating;
+        self.status_message = String::from("Generating...");
+
+        if self.config.inhibit_idle && inhibit::SUPPORTED && self.idle_inhibitor.is_none() {
+            self.idle_inhibitor = IdleInhibitor::start("Generating a response")
+                .inspect_err(|e| tracing::warn!("{e}"))
+                .ok();
+        }
+
+        let mut messages = match self.compare_target {
+            Some(target) => self.build_messages_before(target),
+            None => self.build_messages(),
+        };
+        if self.continuing {
+            messages.push(ChatMessage {
+                role: "user".to_string(),
+                content: String::from(
+                    "Continue exactly where you left off, without repeating anything or adding an introduction.",
+                ),
+                thinking: None,
+            });
+        }
+        self.generating_model = Some(model.clone());
+        self.generation_started = Some(std::time::Instant::now());
+        self.first_token_after = None;
+        self.model_was_loaded = self.running_models.iter().any(|m| m.name == model);
+        self.generating_format = options.format.clone();
+
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        self.last_error = None;
+        tracing::info!("Request #{request_id}: chat with {model}");
+
+        let profile = std::mem::take(&mut self.params.profile_next);
+        if profile {
+            self.profiler = Some(ProfileRecorder::new(
+                request_id,
+                model.clone(),
+                format!("{}/api/chat", self.client.base_url()),
+                options.clone(),
+                messages.len(),
+                messages.iter().map(|m| m.content.len()).sum(),
+            ));
+        }
+
+        self.streaming.clear();
+        self.streaming_thinking.clear();
+        self.streamed_chunks = 0;
+        let record_timing = self.config.record_token_timing || profile;
+        let mut client = self.client.clone();
+        // Chunks for the live bubble; the stream ends when the request future is dropped
+        let (ui_tx, mut ui_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut chunks = Task::run(
+            futures::stream::poll_fn(move |cx| ui_rx.poll_recv(cx)),
+            Message::ResponseChunk,
+        );
+        if self.params.inspect {
+            let payload = client.chat_payload(&model, messages.clone(), options.clone());
+            self.inspector.begin(format!("{}/api/chat", client.base_url()), &payload);
+            let (raw_tx, mut raw_rx) = tokio::sync::mpsc::unbounded_channel();
+            client = client.with_raw_lines(raw_tx);
+            chunks = Task::batch([
+                chunks,
+                Task::run(
+                    futures::stream::poll_fn(move |cx| raw_rx.poll_recv(cx)),
+                    Message::InspectorLine,
+                ),
+            ]);
+        }
+        let request = Task::perform(
+            async move {
+                let endpoint = format!("{}/api/chat", client.base_url());
+                let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+                // Run the request in this future, not a spawned task, so
+                // aborting it on Esc drops the connection and stops generation
+                let request = client.chat_stream(&model, messages, options, tx);
+
+                // Collect all tokens
+                let started = std::time::Instant::now();
+                let mut offsets = Vec::new();
+                let mut full_response = String::new();
+                let collect = async {
+                    while let Some(chunk) = rx.recv().await {
+                        if let StreamChunk::Content(ref token) = chunk {
+                            if record_timing {
+                                offsets.push(started.elapsed());
+                            }
+                            full_response.push_str(token);
+                        }
+                        let _ = ui_tx.send(chunk);
+                    }
+                    drop(ui_tx);
+                };
+                let (result, ()) = futures::join!(request, collect);
+                let timing = record_timing.then(|| TokenTiming::new(offsets));
+
+                match result {
+                    Ok(mut response) => {
+                        let separate = response.message.take().and_then(|m| m.thinking);
+                        let (inline, answer) = ollama::split_thinking(&full_response);
+                        response.message = Some(ChatMessage {
+                            role: "assistant".to_string(),
+                            content: answer.to_string(),
+                            thinking: separate.or(inline.map(String::from)).filter(|t| !t.is_empty()),
+                        });
+                        Ok((response, timing))
+                    }
+                    Err(e) => Err(ErrorReport::new(request_id, &e, endpoint, model)),
+                }
+            },
+            Message::ResponseComplete,
+        );
+        let (task, handle) = Task::batch([chunks, request]).abortable();
+        self.generation = Some(handle);
+        task
+    }
+
+    /// A prompt waiting for the current answer; after a failure or a cancel
+    /// the queue waits for Send on its first entry
+    fn queued_bubble<'a>(&self, idx: usize, prompt: &'a str) -> Element<'a, Message> {
+        let paused = idx == 0 && self.status != Status::Generating;
+        let header = row![
+            text(if paused { "⏸ queued" } else { "⏳ queued" }).size(11),
+            horizontal_space(),
+        ]
+        .push_maybe(paused.then(|| {
+            button(text("Send ▸").size(11))
+                .style(button::secondary)
+                .padding([2, 6])
+                .on_press(Message::SendQueued)
+        }))
+        .push(
+            button(text("✕").size(11))
+                .style(button::text)
+                .padding([2, 6])
+                .on_press(Message::RemoveQueued(idx)),
+        )
+        .spacing(4)
+        .align_y(iced::Alignment::Center);
+        let bubble = container(
+            column![
+                header,
+                text(prompt).size(14).color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+            ]
+            .spacing(6),
+        )
+        .padding(12)
+        .style(container::bordered_box)
+        .max_width(500);
+        row![horizontal_space(), bubble].width(Length::Fill).into()
+    }
+
+    /// The response being generated, labelled with its model when it's a comparison
+    fn streaming_bubble(&self) -> Element<'_, Message> {
+        let first_token = self
+            .first_token_after
+            .map(|after| format!(" · first token after {:.1}s", after.as_secs_f32()))
+            .unwrap_or_default();
+        let counter = match self.config.response_soft_cap {
+            Some(cap) => format!(
+                "{} chars · {} / {} tokens{first_token}",
+                self.streaming.chars().count(),
+                self.streamed_chunks,
+                cap
+            ),
+            None => format!(
+                "{} chars · {} tokens{first_token}",
+                self.streaming.chars().count(),
+                self.streamed_chunks
+            ),
+        };
+        let mut content = Column::new().spacing(6);
+        if let (Some(_), Some(model)) = (self.compare_target, &self.generating_model) {
+            content = content.push(text(format!("{model}…")).size(11));
+        }
+        if self.continuing {
+            content = content.push(text("Continuing…").size(11));
+        }
+        // Before the first token: a model that wasn't in memory is most likely
+        // still loading, otherwise Ollama is working through the prompt
+        if self.first_token_after.is_none() {
+            let elapsed = self.generation_started.map(|s| s.elapsed()).unwrap_or_default();
+            let phase = match &self.generating_model {
+                Some(model) if !self.model_was_loaded => format!("Loading {model}…"),
+                _ => String::from("Processing prompt…"),
+            };
+            let waiting = text(format!("{phase} {:.1}s", elapsed.as_secs_f32()))
+                .size(13)
+                .color(iced::Color::from_rgb(0.6, 0.6, 0.6));
+            let bubble = container(content.push(waiting))
+                .padding(12)
+                .style(container::bordered_box)
+                .max_width(500);
+            return row![bubble, horizontal_space()].width(Length::Fill).into();
+        }
+        let (inline, answer) = ollama::split_thinking(&self.streaming);
+        let thinking = inline.unwrap_or(&self.streaming_thinking);
+        if self.config.show_thinking && !thinking.is_empty() {
+            // Just the latest lines, the full reasoning is collapsible once it's done
+            const TAIL_CHARS: usize = 400;
+            let skip = thinking.chars().count().saturating_sub(TAIL_CHARS);
+            let tail: String = thinking.chars().skip(skip).collect();
+            let ellipsis = if skip > 0 { "…" } else { "" };
+            content = content.push(text("💭 Thinking…").size(12)).push(
+                text(format!("{ellipsis}{}", tail.trim_start()))
+                    .size(13)
+                    .color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+            );
+        }
+        let content = content
+            .push(text(answer).size(14))
+            .push(text(counter).size(11).color(iced::Color::from_rgb(0.6, 0.6, 0.6)));
+
+        let bubble = container(content)
+            .padding(12)
+            .style(container::bordered_box)
+            .max_width(500);
+        row![bubble, horizontal_space()].width(Length::Fill).into()
+    }
+
+    /// Stop a response that ran past `response_soft_cap`, keeping what it wrote so far
+    fn stop_at_soft_cap(&mut self) -> Task<Message> {
+        if let Some(handle) = self.generation.take() {
+            handle.abort();
+        }
+        let streamed = std::mem::take(&mut self.streaming);
+        let separate = std::mem::take(&mut self.streaming_thinking);
+        let (inline, content) = ollama::split_thinking(&streamed);
+        let thinking = Some(separate)
+            .filter(|t| !t.is_empty())
+            .or(inline.map(String::from));
+        let content = content.to_string();
+        tracing::info!("Response stopped at soft cap after {} chunks", self.streamed_chunks);
+        let model = self.generating_model.take();
+        let continued = std::mem::take(&mut self.continuing)
+            .then(|| self.chat_history.last_mut().filter(|e| e.role == "assistant"))
+            .flatten();
+        if let Some(entry) = continued {
+            entry.content.push_str(&content);
+            entry.truncated = true;
+        } else if let Some(target) = self.compare_target.take() {
+            if let Some(entry) = self.chat_history.get_mut(target) {
+                entry.alternatives.push(Alternative {
+                    model: model.unwrap_or_default(),
+                    content,
+                });
+            }
+        } else {
+            self.chat_history.push(ChatEntry {
+                role: "assistant".to_string(),
+                content,
+                timing: None,
+                thinking,
+                show_thinking: false,
+                expanded: false,
+                diff: None,
+                excluded: false,
+                attachments: Vec::new(),
+                sources: Vec::new(),
+                model,
+                alternatives: Vec::new(),
+                json: None,
+                truncated: true,
+                variants: Vec::new(),
+                variant_pos: 0,
+                created_at: Some(history::unix_now()),
+                duration: self.generation_started.map(|started| started.elapsed()),
+            });
+            self.attach_replaced();
+        }
+        self.status = Status::Connected;
+        self.status_message = format!(
+            "✂ Stopped at the soft cap of {} tokens",
+            self.config.response_soft_cap.unwrap_or_default()
+        );
+        self.idle_inhibitor = None;
+        self.profiler = None;
+        let next = if self.queued.is_empty() {
+            Task::none()
+        } else {
+            Task::done(Message::SendQueued)
+        };
+        Task::batch([self.save_conversation(), next])
+    }
+
+    fn run_command(&mut self, command: SlashCommand) -> Task<Message> {
+        match command {
+            SlashCommand::Clear => self.update(Message::ClearChat),
+            SlashCommand::Model(name) => {
+                let model = self
+                    .available_models
+                    .iter()
+                    .find(|m| **m == name)
+                    .or_else(|| self.available_models.iter().find(|m| m.starts_with(&name)))
+                    .cloned()
+                    .or_else(|| closest_model(&name, &self.available_models));
+                match model {
+                    Some(model) => {
+                        self.status_message = format!("Switched to {model}");
+                        self.update(Message::ModelSelected(model))
+                    }
+                    None => {
+                        self.status_message = format!("No installed model matches \"{name}\"");
+                        Task::none()
+                    }
+                }
+            }
+            SlashCommand::System(prompt) => {
+                self.status_message = match prompt {
+                    Some(_) => String::from("System prompt set for this session"),
+                    None => String::from("System prompt reset"),
+                };
+                self.session_system_prompt = prompt;
                 Task::none()
             }
-            
-            Message::ShiftPressed => {
-                self.shift_held = true;
+            SlashCommand::Retry => self.update(Message::Retry),
+            SlashCommand::Copy => {
+                match self.chat_history.iter().rposition(|e| e.role == "assistant") {
+                    Some(idx) => self.update(Message::CopyMessage(idx)),
+                    None => {
+                        self.status_message = String::from("No response to copy");
+                        Task::none()
+                    }
+                }
+            }
+            // An empty command lists them all in the autocomplete popup
+            SlashCommand::Workspace(Some(dir)) => {
+                self.status_message = format!("Scanning {dir}...");
+                Task::perform(Workspace::scan_async(expand_home(&dir)), Message::WorkspaceScanned)
+            }
+            SlashCommand::Workspace(None) => {
+                self.status_message = match self.workspace.take() {
+                    Some(workspace) => format!("Closed workspace {}", workspace.name()),
+                    None => String::from("No workspace open, use /workspace <dir>"),
+                };
+                Task::none()
+            }
+            SlashCommand::Template(name) => self.update(Message::StartConversationTemplate(name)),
+            SlashCommand::SaveTemplate(name) => {
+                let turns = self
+                    .chat_history
+                    .iter()
+                    .filter(|e| !e.excluded)
+                    .map(|e| SeedTurn {
+                        role: e.role.clone(),
+                        content: workspace::with_attachments(&e.content, &e.attachments),
+                    })
+                    .collect();
+                let template = ConversationTemplate {
+                    name: name.clone(),
+                    system_prompt: self.system_prompt().map(str::to_string),
+                    turns,
+                };
+                let templates = &mut self.config.conversation_templates;
+                match templates.iter_mut().find(|t| t.name == name) {
+                    Some(existing) => *existing = template,
+                    None => templates.push(template),
+                }
+                self.status_message = match self.config.save() {
+                    Ok(()) => format!("Saved conversation template \"{name}\""),
+                    Err(e) => format!("Template \"{name}\" kept for this session, not saved: {e}"),
+                };
                 Task::none()
             }
-            
-            Message::ShiftReleased => {
-                self.shift_held = false;
-                Task::none()
+            SlashCommand::Export => self.update(Message::ExportHtml),
+            SlashCommand::Duplicate => self.update(Message::DuplicateConversation),
+            SlashCommand::Help => self.update(Message::CompleteCommand("/")),
+        }
+    }
+
+    /// Prompt template selected in the parameters panel
+    fn active_template(&self) -> Option<&PromptTemplate> {
+        let name = self.params.template.as_ref()?;
+        self.config.prompt_templates.iter().find(|t| &t.name == name)
+    }
+
+    /// System prompt for the next turn; the active template can override the default
+    fn system_prompt(&self) -> Option<&str> {
+        self.active_template()
+            .and_then(|t| t.system_prompt.as_deref())
+            .or(self.session_system_prompt.as_deref())
+            .or(match &self.selected_model {
+                Some(model) => self.config.system_prompt_for(model),
+                None => self.config.system_prompt.as_deref(),
+            })
+    }
+
+    /// Scratchpad contents without the editor's trailing newline
+    fn scratchpad_text(&self) -> String {
+        let text = self.scratchpad.text();
+        text.strip_suffix('\n').unwrap_or(&text).to_string()
+    }
+
+    /// Assemble the message list sent to Ollama for the next turn
+    fn build_messages(&self) -> Vec<ChatMessage> {
+        self.build_messages_before(self.chat_history.len())
+    }
+
+    /// Context as it was when the message at `end` was generated
+    fn build_messages_before(&self, end: usize) -> Vec<ChatMessage> {
+        let mut messages: Vec<ChatMessage> = Vec::new();
+
+        // Add system prompt if configured
+        if let Some(sys) = self.system_prompt() {
+            messages.push(ChatMessage {
+                role: "system".to_string(),
+                content: sys.to_string(),
+                thinking: None,
+            });
+        }
+
+        if self.scratchpad_in_context {
+            let notes = self.scratchpad_text();
+            if !notes.trim().is_empty() {
+                messages.push(ChatMessage {
+                    role: "system".to_string(),
+                    content: format!("Notes for this conversation:\n{}", self.redactor.apply(notes.trim())),
+                    thinking: None,
+                });
+            }
+        }
+
+        // Summarized turns are replaced by their summary
+        let start = match self.summary {
+            Some(ref summary) => {
+                messages.push(ChatMessage {
+                    role: "system".to_string(),
+                    content: format!("Summary of the earlier conversation:\n{}", summary.text),
+                    thinking: None,
+                });
+                summary.covers
+            }
+            None => 0,
+        };
+
+        // Add chat history, or its most recent part with a history limit
+        let end = end.min(self.chat_history.len()).max(start);
+        let start = self.history_window_start(start, end);
+        // Only the question being answered gets its excerpts in full
+        let latest = (start..end)
+            .rev()
+            .find(|&idx| self.chat_history[idx].role == "user" && !self.chat_history[idx].excluded);
+        for (idx, entry) in self.chat_history[start..end].iter().enumerate().filter(|(_, e)| !e.excluded) {
+            let content = workspace::with_attachments(&entry.content, &entry.attachments);
+            messages.push(ChatMessage {
+                role: entry.role.clone(),
+                content: if Some(start + idx) == latest {
+                    rag::with_sources(&content, &entry.sources)
+                } else {
+                    rag::with_citations(&content, &entry.sources)
+                },
+                thinking: None,
+            });
+        }
+
+        messages
+    }
+
+    /// First message of `chat_history[start..end]` still sent under the
+    /// history limit; `start` when there's no limit or it isn't reached
+    fn history_window_start(&self, start: usize, end: usize) -> usize {
+        let Ok(Some(limit)) = self.params.history_limit() else {
+            return start;
+        };
+        let sent = (start..end).rev().filter(|&idx| !self.chat_history[idx].excluded);
+        match limit {
+            HistoryLimit::Turns(turns) => sent
+                .filter(|&idx| self.chat_history[idx].role == "user")
+                .nth(turns - 1)
+                .unwrap_or(start),
+            HistoryLimit::Tokens(budget) => {
+                // The newest message always goes, even when it alone is over
+                let mut used = 0;
+                let mut first = end;
+                for idx in sent {
+                    let tokens = estimate_tokens(&self.chat_history[idx].content);
+                    if used + tokens > budget && first < end {
+                        return first;
+                    }
+                    used += tokens;
+                    first = idx;
+                }
+                start
+            }
+        }
+    }
+
+    /// Ask the model to fold everything but the last couple of exchanges
+    /// into the running summary
+    fn summarize_history(&mut self) -> Task<Message> {
+        const KEEP_RECENT: usize = 4;
+
+        if self.summarizing {
+            return Task::none();
+        }
+        let Some(model) = self.selected_model.clone() else {
+            return Task::none();
+        };
+
+        let start = self.summary.as_ref().map_or(0, |s| s.covers);
+        let covers = self.chat_history.len().saturating_sub(KEEP_RECENT);
+        if covers <= start {
+            self.status_message = String::from("Nothing to summarize yet");
+            return Task::none();
+        }
+
+        let mut transcript = String::new();
+        if let Some(ref summary) = self.summary {
+            transcript.push_str(&format!("Earlier summary:\n{}\n\n", summary.text));
+        }
+        for entry in self.chat_history[start..covers].iter().filter(|e| !e.excluded) {
+            transcript.push_str(&format!("{}: {}\n\n", entry.role, entry.content));
+        }
+
+        let messages = vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: String::from(
+                    "Summarize the following conversation so it can replace the original \
+                     messages as context. Keep facts, decisions, names, code and open \
+                     questions. Be concise and write in the third person.",
+                ),
+                thinking: None,
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: transcript,
+                thinking: None,
+            },
+        ];
+
+        self.summarizing = true;
+        self.status_message = String::from("Summarizing older messages...");
+        let client = self.client.clone();
+        Task::perform(
+            async move {
+                client
+                    .chat(&model, messages, Default::default())
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|resp| {
+                        resp.message
+                            .map(|m| m.content.trim().to_string())
+                            .filter(|text| !text.is_empty())
+                            .ok_or_else(|| String::from("Model returned an empty summary"))
+                    })
+                    .map(|text| Summary { covers, text })
+            },
+            Message::SummaryComplete,
+        )
+    }
+
+    /// Ask the model for a short title for the conversation, from its first exchange
+    fn generate_title(&self) -> Task<Message> {
+        const MAX_CHARS: usize = 2000;
+
+        let (Some(id), Some(model)) = (self.conversation_id.clone(), self.selected_model.clone()) else {
+            return Task::none();
+        };
+        let excerpt = |role: &str| {
+            self.chat_history
+                .iter()
+                .find(|e| e.role == role)
+                .map(|e| e.content.chars().take(MAX_CHARS).collect::<String>())
+                .unwrap_or_default()
+        };
+        let messages = vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: String::from(
+                    "Write a title of 3 to 6 words for the conversation below. \
+                     Reply with the title only: no quotes, no punctuation at the end.",
+                ),
+                thinking: None,
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: format!("User: {}\n\nAssistant: {}", excerpt("user"), excerpt("assistant")),
+                thinking: None,
+            },
+        ];
+
+        let client = self.client.clone();
+        Task::perform(
+            async move {
+                client
+                    .chat(&model, messages, Default::default())
+                    .await
+                    .map_err(|e| e.to_string())
+                    .map(|resp| resp.message.map(|m| m.content).unwrap_or_default())
+            },
+            move |result| Message::TitleGenerated(id.clone(), result),
+        )
+    }
+
+    /// Keep looking for the server, it may just not have started yet.
+    /// Paused while minimized, like the other polling; restoring checks at once.
+    fn reconnect_subscription(&self) -> Subscription<Message> {
+        if self.status == Status::Disconnected && !self.checking_connection && !self.window_minimized {
+            iced::time::every(self.reconnect_delay).map(|_| Message::CheckConnection)
+        } else {
+            Subscription::none()
+        }
+    }
+
+    /// Take the messages from `idx` on out of the chat, to keep them as a branch.
+    /// A summary reaching past `idx` no longer describes the chat and is dropped.
+    fn split_history(&mut self, idx: usize) -> Vec<ChatEntry> {
+        self.drop_summary_covering(idx);
+        self.measured_tokens = None;
+        self.chat_history.split_off(idx)
+    }
+
+    /// The summary no longer describes the chat once a message it covers changes
+    fn drop_summary_covering(&mut self, idx: usize) {
+        if self.summary.as_ref().is_some_and(|s| s.covers > idx) {
+            self.summary = None;
+        }
+    }
+
+    /// The regenerated answer becomes a branch of the new one at the end of the chat
+    fn attach_replaced(&mut self) {
+        if let (Some(old), Some(entry)) = (self.replaced.take(), self.chat_history.last_mut()) {
+            add_variant(entry, old);
+        }
+    }
+
+    /// The regeneration failed or was cancelled, show the previous answer again
+    fn restore_replaced(&mut self) {
+        if let Some(old) = self.replaced.take() {
+            self.chat_history.extend(old);
+        }
+    }
+
+    /// Start `ollama serve`; the reconnect checks pick it up once it listens
+    fn start_server(&mut self) {
+        match OllamaServer::start(&self.config.ollama_binary, &self.config.server_env) {
+            Ok(server) => {
+                self.status_message = format!("Starting ollama serve (pid {})...", server.pid());
+                self.server = Some(server);
+                self.reconnect_delay = RECONNECT_MIN;
+            }
+            Err(e) => {
+                tracing::warn!("{e}");
+                self.status_message = e;
             }
         }
     }
 
-    pub fn view(&self) -> Element<'_, Message> {
-        // Model selector row
-        let model_picker = pick_list(
-            self.available_models.clone(),
-            self.selected_model.clone(),
-            Message::ModelSelected,
-        )
-        .placeholder("Select model...")
-        .width(Length::FillPortion(3));
+    /// Remove history entries from both the UI and the context sent to the model
+    fn remove_entries(&mut self, idx: usize, count: usize) {
+        if idx >= self.chat_history.len() {
+            return;
+        }
+        let end = (idx + count).min(self.chat_history.len());
+        match self.compare_target {
+            Some(target) if target >= end => self.compare_target = Some(target - (end - idx)),
+            Some(target) if target >= idx => {
+                let _ = self.update(Message::CancelGeneration);
+            }
+            _ => {}
+        }
+        self.chat_history.drain(idx..end);
+        self.measured_tokens = None;
+        self.editing = None;
+        self.refresh_diffs();
 
-        let refresh_btn = button("↻").on_press(Message::RefreshModels);
-        let clear_btn = button("Clear").on_press(Message::ClearChat);
+        self.drop_summary_covering(idx);
 
-        let toolbar = row![
-            model_picker,
-            refresh_btn,
-            clear_btn,
-            horizontal_space(),
-        ]
-        .spacing(8)
-        .align_y(iced::Alignment::Center);
+        self.status_message = match end - idx {
+            1 => String::from("Message deleted"),
+            n => format!("{n} messages deleted"),
+        };
+    }
 
-        // Chat history
-        let chat_content: Element<Message> = if self.chat_history.is_empty() && self.status != Status::Generating {
-            container(
-                text("Start a conversation...")
-                    .size(16)
-                    .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
-            )
-            .center_x(Length::Fill)
-            .center_y(Length::Fill)
-            .into()
-        } else {
-            let mut chat_column = Column::new().spacing(12).padding(8);
+    /// Fetch the selected model's details (context window etc.) in the background
+    fn load_model_info(&mut self) -> Task<Message> {
+        self.model_info = None;
+        self.model_info_error = None;
+        let Some(model) = self.selected_model.clone() else {
+            return Task::none();
+        };
 
-            for (idx, entry) in self.chat_history.iter().enumerate() {
-                let bubble = self.render_message(idx, &entry.role, &entry.content);
-                chat_column = chat_column.push(bubble);
+        let client = self.client.clone();
+        Task::perform(
+            async move {
+                let info = client.show_model(&model).await.map_err(|e| e.to_string());
+                (model, info)
+            },
+            |(model, info)| Message::ModelInfoLoaded(model, info),
+        )
+    }
+
+    fn load_models(
+        &self,
+        done: fn(Result<Vec<ollama::Model>, String>) -> Message,
+    ) -> Task<Message> {
+        let client = self.client.clone();
+        Task::perform(async move { client.list_models().await.map_err(|e| e.to_string()) }, done)
+    }
+
+    /// Switch to a new config: reconnect if the server changed, re-register
+    /// the hotkey and reload whatever depends on it
+    fn apply_config(&mut self, new_config: Config) -> Task<Message> {
+        let spellcheck_changed = new_config.spellcheck != self.config.spellcheck;
+        let url_changed = new_config.ollama_url != self.config.ollama_url
+            || new_config.proxy_bypass_local != self.config.proxy_bypass_local
+            || new_config.proxy != self.config.proxy
+            || new_config.api_key != self.config.api_key;
+        if new_config.quick_ask_hotkey != self.config.quick_ask_hotkey {
+            // Drop the old registration first so the same key can be re-grabbed
+            self.hotkey = None;
+            self.hotkey = new_config.quick_ask_hotkey.as_deref().and_then(|spec| {
+                GlobalHotkey::register(spec)
+                    .inspect_err(|e| tracing::warn!("{e}"))
+                    .ok()
+            });
+            self.background |= self.hotkey.is_some();
+        }
+        if new_config.theme != self.config.theme {
+            themes::ThemeChoice::warn_if_unknown(new_config.theme.as_deref());
+        }
+        self.config = new_config;
+        self.client = OllamaClient::from_config(&self.config);
+        self.redactor = Redactor::new(&self.config.redact_patterns);
+        let theme_task = if self.follows_system_theme() {
+            Task::done(Message::SystemThemeTick)
+        } else {
+            Task::none()
+        };
+        let theme_task = if spellcheck_changed {
+            #[cfg(feature = "spellcheck")]
+            {
+                self.dictionary = None;
             }
+            Task::batch([theme_task, self.load_dictionary()])
+        } else {
+            theme_task
+        };
 
-            // Show "thinking" indicator while generating
-            if self.status == Status::Generating {
-                let thinking = container(text("...").size(14))
-                    .padding(12)
-                    .style(container::bordered_box)
-                    .max_width(500);
-                chat_column = chat_column.push(
-                    row![thinking, horizontal_space()].width(Length::Fill)
-                );
+        if !url_changed {
+            return theme_task;
+        }
+
+        // Reconnect against the new server and reload its models
+        self.status = Status::Disconnected;
+        self.available_models.clear();
+        self.selected_model = None;
+        self.show_cached_models();
+        self.reconnect_delay = RECONNECT_MIN;
+        self.checking_connection = false;
+        Task::batch([theme_task, self.update(Message::CheckConnection)])
+    }
+
+    /// Keep the sizes for the VRAM estimate, returning just the names
+    fn record_model_sizes(&mut self, models: Vec<ollama::Model>) -> Vec<String> {
+        self.model_sizes = models.iter().map(|m| (m.name.clone(), m.size)).collect();
+        models.into_iter().map(|m| m.name).collect()
+    }
+
+    /// Whether a model should run on the GPU: measured if it's loaded,
+    /// estimated from its size and free VRAM otherwise
+    fn vram_fit(&self, model: &str) -> Option<Fit> {
+        if let Some(running) = self.running_models.iter().find(|r| r.name == model) {
+            return Some(vram_fit::loaded(running));
+        }
+        let size = *self.model_sizes.get(model)?;
+        let available = vram_fit::available(&self.gpu_stats, &self.running_models)?;
+        Some(vram_fit::estimate(size, available))
+    }
+
+    /// Fill the picker from the model cache until the server answers
+    fn show_cached_models(&mut self) {
+        self.available_models = model_cache::load(self.client.base_url());
+        self.models_cached = !self.available_models.is_empty();
+        if self.models_cached {
+            self.select_default_model();
+            self.status_message = format!("{} cached models, connecting to Ollama...", self.available_models.len());
+        }
+    }
+
+    /// The selected profile, if it's still in the config
+    fn profile(&self) -> Option<&Profile> {
+        let name = self.active_profile.as_ref()?;
+        self.config.profiles.iter().find(|p| &p.name == name)
+    }
+
+    /// The active profile's quick prompts if it has any, the global ones otherwise
+    fn quick_prompts(&self) -> &[QuickPrompt] {
+        match self.profile() {
+            Some(profile) if !profile.quick_prompts.is_empty() => &profile.quick_prompts,
+            _ => &self.config.quick_prompts,
+        }
+    }
+
+    /// The configured default model if installed, else the first one
+    fn select_default_model(&mut self) {
+        self.selected_model = self
+            .config
+            .default_model
+            .clone()
+            .filter(|m| self.available_models.contains(m))
+            .or_else(|| self.available_models.first().cloned());
+        self.params.think = self
+            .selected_model
+            .as_ref()
+            .is_some_and(|m| self.config.think_models.contains(m));
+        if let Some(model) = &self.selected_model {
+            self.params.set_options(&self.config.options_for(model));
+        }
+    }
+
+    /// Replace the installed model list, warning once if the selection vanished from it
+    fn set_available_models(&mut self, models: Vec<String>) {
+        if self.models_cached || models != self.available_models {
+            if let Err(e) = model_cache::save(self.client.base_url(), &models) {
+                tracing::warn!("Couldn't cache the model list: {e}");
             }
+        }
+        self.models_cached = false;
+        let was_missing = self.selected_model_missing();
+        self.available_models = models;
+        if self.selected_model_missing() && !was_missing {
+            let model = self.selected_model.as_deref().unwrap_or_default();
+            tracing::warn!("Selected model {model} was removed or renamed on the server");
+            self.status_message = format!("⚠ {model} is no longer available");
+        }
+    }
 
-            scrollable(chat_column)
-                .height(Length::FillPortion(5))
-                .into()
+    /// The selected model was deleted or renamed server-side
+    fn selected_model_missing(&self) -> bool {
+        self.selected_model
+            .as_ref()
+            .is_some_and(|model| !self.available_models.contains(model))
+    }
+
+    fn load_running_models(&self) -> Task<Message> {
+        let client = self.client.clone();
+        Task::perform(
+            async move { client.list_running().await.map_err(|e| e.to_string()) },
+            Message::RunningModelsLoaded,
+        )
+    }
+
+    /// Context window of the selected model, if known
+    fn context_length(&self) -> Option<u64> {
+        let num_ctx = self.params.num_ctx.trim().parse::<u64>().ok().filter(|n| *n > 0);
+        num_ctx.or_else(|| self.model_info.as_ref().and_then(|info| info.context_length()))
+    }
+
+    /// Tokens the next request will use: Ollama's own count where we have one,
+    /// plus an estimate for the system prompt and anything added since
+    fn context_tokens(&self) -> u64 {
+        let estimate_from = |start: usize| -> u64 {
+            self.chat_history[start..]
+                .iter()
+                .filter(|e| !e.excluded)
+                .map(|e| estimate_tokens(&e.content))
+                .sum()
         };
 
-        // Input area
-        let is_generating = self.status == Status::Generating;
-        let input = text_editor(&self.input_content)
-            .placeholder("Type your message...")
-            .on_action(Message::InputChanged)
-            .height(Length::Fixed(80.0));
+        let system = self.system_prompt().map(estimate_tokens).unwrap_or(0);
+        let start = self.summary.as_ref().map_or(0, |s| s.covers);
+        let first = self.history_window_start(start, self.chat_history.len());
+        let estimated = match self.summary {
+            Some(ref summary) => system + estimate_tokens(&summary.text) + estimate_from(first),
+            None => system + estimate_from(first),
+        };
 
-        let send_btn = button(if is_generating { "..." } else { "Send" })
-            .on_press_maybe((!is_generating && self.selected_model.is_some()).then_some(Message::Submit));
+        match self.measured_tokens {
+            // Ollama's count includes what the history limit now leaves out
+            Some((covered, measured)) if covered <= self.chat_history.len() && first == start => {
+                // Prompt caching can make Ollama under-report, never go below the estimate
+                (measured + estimate_from(covered)).max(estimated)
+            }
+            _ => estimated,
+        }
+    }
 
-        let input_row = row![input, send_btn].spacing(8).align_y(iced::Alignment::End);
+    fn over_token_budget(&self) -> bool {
+        self.config
+            .token_budget
+            .is_some_and(|budget| self.tokens_used >= budget)
+    }
 
-        // Status bar with GPU stats
-        let status_text = text(&self.status_message).size(12);
+    /// Whether the conversation is close to overflowing the context window
+    fn context_nearly_full(&self) -> bool {
+        self.context_length()
+            .is_some_and(|ctx| self.context_tokens() * 10 >= ctx * 9)
+    }
 
-        let gpu_text = if let Some(ref stats) = self.gpu_stats {
-            text(format!(
-                "VRAM: {}/{}MB ({:.0}%) | GPU: {}%{}",
-                stats.vram_used_mb,
-                stats.vram_total_mb,
-                stats.vram_usage_percent(),
-                stats.gpu_usage_percent,
-                stats.temperature_c.map(|t| format!(" | {}°C", t)).unwrap_or_default()
-            ))
-            .size(12)
-        } else {
-            text("").size(12)
+    fn load_dictionary(&self) -> Task<Message> {
+        match self.config.spellcheck.clone() {
+            #[cfg(feature = "spellcheck")]
+            Some(language) => Task::perform(spellcheck::load(language), Message::DictionaryLoaded),
+            #[cfg(not(feature = "spellcheck"))]
+            Some(_) => {
+                tracing::warn!("spellcheck is set, but this build has no spellcheck feature");
+                Task::none()
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// The input once a command ran: empty, or the draft Ctrl+K set aside
+    fn input_after_command(&mut self) -> text_editor::Content {
+        let mut content = text_editor::Content::with_text(&self.picker_draft.take().unwrap_or_default());
+        content.perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+        content
+    }
+
+    /// Up/Down in an empty input, or past the first or last line of a recalled
+    /// prompt, steps through sent prompts; false leaves the key to the editor
+    fn recall_prompt(&mut self, older: bool) -> bool {
+        if self.recall.is_none() && !self.input_content.text().trim().is_empty() {
+            return false;
+        }
+        let (line, _) = self.input_content.cursor_position();
+        let last_line = self.input_content.line_count().saturating_sub(1);
+        if self.recall.is_some() && ((older && line > 0) || (!older && line < last_line)) {
+            return false;
+        }
+        self.recall = match (self.recall, older) {
+            (None, false) => return false,
+            (None, true) => match self.prompts.len().checked_sub(1) {
+                Some(newest) => Some(newest),
+                None => return false,
+            },
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) => Some(i + 1).filter(|next| *next < self.prompts.len()),
+        };
+        // Down past the newest prompt leaves an empty box again
+        self.input_content = match self.recall.and_then(|i| self.prompts.get(i)) {
+            Some(prompt) => text_editor::Content::with_text(prompt),
+            None => text_editor::Content::new(),
         };
+        true
+    }
 
-        let status_bar = row![status_text, horizontal_space(), gpu_text]
-            .spacing(16)
-            .align_y(iced::Alignment::Center);
+    fn remember_prompt(&mut self, prompt: &str) {
+        self.recall = None;
+        prompt_history::push(&mut self.prompts, prompt);
+        if self.config.remember_prompts {
+            if let Err(e) = prompt_history::save(&self.prompts) {
+                tracing::warn!("Failed to save prompt history: {e}");
+            }
+        }
+    }
 
-        // Main layout
-        let content = column![
-            toolbar,
-            vertical_space().height(8),
-            chat_content,
-            vertical_space().height(8),
-            input_row,
-            vertical_space().height(4),
-            status_bar,
-        ]
-        .padding(16)
-        .spacing(4);
+    /// Vertical span of the chat, in content pixels, whose messages are built;
+    /// `None` builds them all. Before the first scroll the chat sits at the bottom.
+    fn built_range(&self) -> Option<(f32, f32)> {
+        if self.chat_history.len() <= VIRTUALIZE_ABOVE || self.active_search().is_some() {
+            return None;
+        }
+        match self.chat_viewport {
+            Some(viewport) => {
+                let top = viewport.absolute_offset().y;
+                Some((top - OVERSCAN, top + viewport.bounds().height + OVERSCAN))
+            }
+            None => {
+                let total: f32 = self.chat_history.iter().map(|e| self.estimated_height(e)).sum();
+                Some((total - 2.0 * OVERSCAN, f32::INFINITY))
+            }
+        }
+    }
 
-        container(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .style(|theme: &Theme| {
-                let palette = theme.extended_palette();
-                container::Style {
-                    border: iced::Border {
-                        color: palette.primary.strong.color,
-                        width: 2.0,
-                        radius: 8.0.into(),
-                    },
-                    ..Default::default()
-                }
-            })
-            .into()
+    /// Rough height of a message with everything drawn under it, column spacing included
+    fn estimated_height(&self, entry: &ChatEntry) -> f32 {
+        // A 500px bubble fits about 70 characters of 14px text
+        let (content, _) = collapsed(&entry.content, entry.expanded);
+        let lines: usize = content.lines().map(|line| line.chars().count() / 70 + 1).sum();
+        let mut height = lines.max(1) as f32 * 19.0 + 24.0 + 12.0;
+        if entry.content.lines().count() > COLLAPSE_LINES {
+            height += 30.0 + 12.0;
+        }
+        if entry.thinking.is_some() && self.config.show_thinking {
+            height += 26.0 + 12.0;
+        }
+        if self.config.show_timestamps {
+            height += 14.0 + 12.0;
+        }
+        if entry.timing.is_some() {
+            height += 60.0 + 12.0;
+        }
+        height + entry.alternatives.len() as f32 * (120.0 + 12.0)
     }
 
-    fn render_message(&self, idx: usize, role: &str, content: &str) -> Element<'_, Message> {
+    fn render_message<'a>(&'a self, idx: usize, role: &str, content: &'a str) -> Element<'a, Message> {
         let is_user = role == "user";
 
-        let msg_text = text(content.to_string()).size(14);
+        let segments = code_blocks::split(content);
+        let json = self.chat_history.get(idx).and_then(|e| e.json.as_ref());
+        let msg_text: Element<Message> = if let Some(query) = self.active_search() {
+            highlighted_text(content, query)
+        } else if let Some(check) = json {
+            let badge = match check {
+                JsonCheck::Valid => text("✓ Valid JSON").size(11),
+                JsonCheck::Invalid(problem) => text(format!("⚠ Answer is {problem}")).size(11).style(text::danger),
+            };
+            column![badge, code_blocks::view_json(content)].spacing(6).into()
+        } else if is_user {
+            text(content.to_string()).size(14).into()
+        } else if segments.iter().all(|s| matches!(s, code_blocks::Segment::Text(_))) {
+            prose_view(content)
+        } else {
+            let mut blocks = 0;
+            let mut parts = Column::new().spacing(8);
+            for segment in segments {
+                parts = parts.push(match segment {
+                    code_blocks::Segment::Text(prose) => prose_view(prose),
+                    code_blocks::Segment::Code { tag, code } => {
+                        blocks += 1;
+                        code_blocks::view(idx, blocks - 1, tag, code)
+                    }
+                });
+            }
+            parts.into()
+        };
+        // Label the original answer once there's something to compare it with
+        let msg_text: Element<Message> = match self.chat_history.get(idx) {
+            Some(ChatEntry { model: Some(model), alternatives, .. }) if !alternatives.is_empty() => {
+                column![text(model.as_str()).size(11), msg_text].spacing(6).into()
+            }
+            _ => msg_text,
+        };
+        let attachments = self.chat_history.get(idx).map_or(&[][..], |e| &e.attachments[..]);
+        let msg_text: Element<Message> = if attachments.is_empty() {
+            msg_text
+        } else {
+            let names: Vec<String> = attachments.iter().map(Attachment::label).collect();
+            column![msg_text, text(format!("📎 {}", names.join(", "))).size(11)]
+                .spacing(6)
+                .into()
+        };
+
+        // Cite the excerpts the question was sent with
+        let sources = match idx.checked_sub(1).and_then(|prev| self.chat_history.get(prev)) {
+            Some(question) if !is_user && question.role == "user" => &question.sources[..],
+            _ => &[][..],
+        };
+        let msg_text: Element<Message> = if sources.is_empty() {
+            msg_text
+        } else {
+            let cited: Vec<String> = sources
+                .iter()
+                .enumerate()
+                .map(|(n, source)| format!("[{}] {}", n + 1, source.name()))
+                .collect();
+            column![msg_text, text(format!("📚 {}", cited.join("  "))).size(11)]
+                .spacing(6)
+                .into()
+        };
 
         // Make the bubble a clickable button to copy
         let bubble = button(
@@ -495,20 +6310,428 @@ impl App {
                 .max_width(500)
         )
         .style(if is_user {
-            button::secondary
+            let accent = self.config.user_bubble_color.as_deref().and_then(themes::parse_color);
+            themes::bubble_style(accent, button::secondary)
         } else {
-            button::primary
+            let accent = self.config.assistant_bubble_color.as_deref().and_then(themes::parse_color);
+            themes::bubble_style(accent, button::primary)
         })
         .on_press(Message::CopyMessage(idx));
 
-        if is_user {
-            row![horizontal_space(), bubble]
-                .width(Length::Fill)
-                .into()
+        // Delete actions appear in the bubble's corner on hover
+        let bubble: Element<Message> = if self.locked {
+            bubble.into()
         } else {
-            row![bubble, horizontal_space()]
-                .width(Length::Fill)
-                .into()
+            let delete_btn = |label, message| {
+                button(text(label).size(11))
+                    .style(button::danger)
+                    .padding([2, 6])
+                    .on_press(message)
+            };
+
+            let mut actions = row![].spacing(4);
+            if !is_user && self.status != Status::Generating {
+                let answered_by = self.chat_history.get(idx).and_then(|e| e.model.as_deref());
+                let others: Vec<String> = self
+                    .available_models
+                    .iter()
+                    .filter(|m| Some(m.as_str()) != answered_by)
+                    .cloned()
+                    .collect();
+                if !others.is_empty() {
+                    actions = actions.push(
+                        pick_list(others, None::<String>, move |model| Message::RetryWith(idx, model))
+                            .placeholder("Retry with…")
+                            .text_size(11)
+                            .padding([2, 6]),
+                    );
+                }
+            }
+            if !is_user {
+                let starred = self
+                    .chat_history
+                    .get(idx)
+                    .is_some_and(|e| self.snippets.iter().any(|s| s.content == e.content));
+                actions = actions.push(
+                    button(text(if starred { "★" } else { "☆" }).size(11))
+                        .style(button::secondary)
+                        .padding([2, 6])
+                        .on_press(Message::ToggleStar(idx)),
+                );
+            }
+            if !is_user && self.diff_sides(idx).is_some() {
+                let showing = self.chat_history.get(idx).is_some_and(|e| e.diff.is_some());
+                actions = actions.push(
+                    button(text("± Diff").size(11))
+                        .style(if showing { button::primary } else { button::secondary })
+                        .padding([2, 6])
+                        .on_press(Message::ToggleDiff(idx)),
+                );
+            }
+            if is_user && self.status != Status::Generating {
+                actions = actions.push(
+                    button(text("✎").size(11))
+                        .style(button::secondary)
+                        .padding([2, 6])
+                        .on_press(Message::EditMessage(idx)),
+                );
+            }
+            if is_user && self.chat_history.get(idx + 1).is_some_and(|e| e.role == "assistant") {
+                actions = actions.push(delete_btn("🗑 Turn", Message::DeleteExchange(idx)));
+            }
+            actions = actions.push(delete_btn("🗑", Message::DeleteMessage(idx)));
+
+            hover(
+                bubble,
+                container(actions)
+                    .width(Length::Fill)
+                    .align_x(iced::alignment::Horizontal::Right)
+                    .padding(4),
+            )
+        };
+
+        // Hover marks the bubble Ctrl+C copies
+        let bubble = mouse_area(bubble)
+            .on_enter(Message::HoverMessage(Some(idx)))
+            .on_exit(Message::HoverMessage(None));
+
+        // ◀ 2/3 ▶ under a message with other versions
+        let bubble: Element<Message> = match self.chat_history.get(idx) {
+            Some(entry) if !entry.variants.is_empty() => {
+                let enabled = !self.locked && self.status != Status::Generating;
+                let arrow = |label, offset| {
+                    button(text(label).size(11))
+                        .style(button::text)
+                        .padding([0, 4])
+                        .on_press_maybe(enabled.then_some(Message::SwitchVariant(idx, offset)))
+                };
+                let position = format!("{}/{}", entry.variant_pos + 1, entry.variants.len() + 1);
+                let switcher = row![arrow("◀", -1), text(position).size(11), arrow("▶", 1)]
+                    .spacing(2)
+                    .align_y(iced::Alignment::Center);
+                column![bubble, switcher]
+                    .spacing(2)
+                    .align_x(if is_user { iced::Alignment::End } else { iced::Alignment::Start })
+                    .into()
+            }
+            _ => bubble.into(),
+        };
+
+        let bubble_row = if is_user {
+            row![horizontal_space(), bubble].width(Length::Fill)
+        } else {
+            row![bubble, horizontal_space()].width(Length::Fill)
+        };
+
+        if !self.curating {
+            return bubble_row.into();
+        }
+
+        let included = !self.chat_history.get(idx).is_some_and(|e| e.excluded);
+        let first_movable = self.summary.as_ref().map_or(0, |s| s.covers);
+        let movable = idx >= first_movable;
+        let move_btn = |label, offset: isize, enabled: bool| {
+            button(text(label).size(12))
+                .style(button::secondary)
+                .padding([2, 6])
+                .on_press_maybe(enabled.then_some(Message::MoveMessage(idx, offset)))
+        };
+        row![
+            checkbox("", included).on_toggle(move |include| Message::SetExcluded(idx, !include)),
+            move_btn("↑", -1, movable && idx > first_movable),
+            move_btn("↓", 1, movable && idx + 1 < self.chat_history.len()),
+            bubble_row,
+        ]
+        .spacing(6)
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+}
+
+/// A model picker entry, labelled with how well it fits in VRAM
+#[derive(Debug, Clone, PartialEq)]
+struct ModelChoice {
+    name: String,
+    fit: Option<Fit>,
+}
+
+impl std::fmt::Display for ModelChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.fit {
+            Some(fit) => write!(f, "{} · {fit}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// Human-readable byte size, e.g. "812MB" or "4.7GB"
+fn format_bytes(bytes: u64) -> String {
+    const MB: u64 = 1024 * 1024;
+    const GB: u64 = 1024 * MB;
+    if bytes >= GB {
+        format!("{:.1}GB", bytes as f64 / GB as f64)
+    } else {
+        format!("{}MB", bytes / MB)
+    }
+}
+
+/// How long ago, e.g. "just now" or "12 min ago"
+fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        0..60 => String::from("just now"),
+        secs @ 60..3600 => format!("{} min ago", secs / 60),
+        secs => format!("{} h ago", secs / 3600),
+    }
+}
+
+/// A GPU reading in the status bar, followed by its recent history
+fn gpu_stat<'a>(label: String, sparkline: Sparkline<'a>) -> Element<'a, Message> {
+    row![text(label).size(12), sparkline.view(40.0, 12.0)]
+        .spacing(4)
+        .align_y(iced::Alignment::Center)
+        .into()
+}
+
+/// "5 min ago · 4.2 s" under a message, on the side of its bubble
+fn message_meta(entry: &ChatEntry, format: TimestampFormat) -> Option<Element<'_, Message>> {
+    let mut parts = Vec::new();
+    if let Some(at) = entry.created_at {
+        parts.push(timestamps::format(at, history::unix_now(), format));
+    }
+    if let Some(duration) = entry.duration {
+        parts.push(timestamps::duration(duration));
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    let meta = text(parts.join(" · "))
+        .size(11)
+        .color(iced::Color::from_rgb(0.5, 0.5, 0.5));
+    Some(if entry.role == "user" {
+        container(meta).align_right(Length::Fill).into()
+    } else {
+        container(meta).into()
+    })
+}
+
+/// Collapsible reasoning shown above the answer it led to
+fn thinking_block(idx: usize, thinking: &str, expanded: bool) -> Element<'_, Message> {
+    let words = thinking.split_whitespace().count();
+    let toggle = button(
+        text(format!(
+            "💭 Thought for {words} words {}",
+            if expanded { "▾" } else { "▸" }
+        ))
+        .size(12),
+    )
+    .style(button::text)
+    .on_press(Message::ToggleThinking(idx));
+
+    let mut block = column![toggle].spacing(4);
+    if expanded {
+        block = block.push(
+            container(
+                text(thinking.trim())
+                    .size(13)
+                    .color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+            )
+            .padding([4, 12])
+            .max_width(500),
+        );
+    }
+    block.into()
+}
+
+/// Best replacement for a model that disappeared: another tag of the same model
+/// if there is one, otherwise the most similar name
+fn closest_model(missing: &str, available: &[String]) -> Option<String> {
+    let base = |name: &str| name.split(':').next().unwrap_or(name).to_string();
+    let missing_base = base(missing);
+
+    if let Some(same_base) = available.iter().find(|m| base(m) == missing_base) {
+        return Some(same_base.clone());
+    }
+
+    available
+        .iter()
+        .map(|m| (edit_distance(&missing_base, &base(m)), m))
+        .filter(|(distance, _)| *distance <= missing_base.len() / 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, m)| m.clone())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            current.push(substitute.min(prev[j + 1] + 1).min(current[j] + 1));
+        }
+        prev = current;
+    }
+    prev[b.len()]
+}
+
+/// Message text with every search match highlighted
+fn highlighted_text<'a>(content: &'a str, query: &str) -> Element<'a, Message> {
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (start, end) in history::find_matches(content, query) {
+        spans.push(span(&content[last..start]));
+        spans.push(
+            span(&content[start..end])
+                .color(iced::Color::BLACK)
+                .background(iced::Color::from_rgb(1.0, 0.85, 0.3)),
+        );
+        last = end;
+    }
+    spans.push(span(&content[last..]));
+    rich_text(spans).size(14).into()
+}
+
+/// The first [`COLLAPSE_LINES`] lines of a long message, unless it's expanded,
+/// and how many lines were left out
+fn collapsed(content: &str, expanded: bool) -> (&str, usize) {
+    if expanded {
+        return (content, 0);
+    }
+    match content.match_indices('\n').nth(COLLAPSE_LINES - 1) {
+        Some((end, _)) => (content[..end].trim_end(), content[end + 1..].lines().count()),
+        None => (content, 0),
+    }
+}
+
+/// Answer prose with its LaTeX math laid out: block math centered on its own
+/// line, inline math as italic Unicode where one line reads the same, else
+/// typeset in the flow of the text
+fn prose_view(prose: &str) -> Element<'_, Message> {
+    let pieces = math::split(prose);
+    if pieces.iter().all(|p| matches!(p, math::Piece::Text(_))) {
+        return text(prose).size(14).into();
+    }
+    let italic = iced::Font {
+        style: iced::font::Style::Italic,
+        ..iced::Font::DEFAULT
+    };
+    // Rich text can't hold a fraction, such paragraphs are rows of words instead
+    let typeset = |latex: &str| !math::parse(latex).is_flat();
+    let in_flow = pieces.iter().any(|p| matches!(p, math::Piece::Inline(latex) if typeset(latex)));
+
+    let mut parts = Column::new().spacing(6);
+    let mut spans = Vec::new();
+    let mut words: Vec<Element<'_, Message>> = Vec::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        match piece {
+            math::Piece::Text(t) => {
+                // Block math gets its own line, so drop the line breaks around it
+                let mut t = *t;
+                if i > 0 && matches!(pieces[i - 1], math::Piece::Block(_)) {
+                    t = t.trim_start_matches('\n');
+                }
+                if matches!(pieces.get(i + 1), Some(math::Piece::Block(_))) {
+                    t = t.trim_end_matches('\n');
+                }
+                if t.is_empty() {
+                    continue;
+                }
+                if in_flow {
+                    for (n, line) in t.split('\n').enumerate() {
+                        if n > 0 {
+                            parts = parts.push(iced::widget::Row::from_vec(std::mem::take(&mut words)).wrap());
+                        }
+                        words.extend(line.split_inclusive(' ').map(|word| text(word).size(14).into()));
+                    }
+                } else {
+                    spans.push(span(t));
+                }
+            }
+            math::Piece::Inline(latex) if in_flow && typeset(latex) => words.push(math_view::view(latex, 14.0)),
+            math::Piece::Inline(latex) if in_flow => {
+                words.push(text(math::to_unicode(latex)).size(14).font(italic).into());
+            }
+            math::Piece::Inline(latex) => spans.push(span(math::to_unicode(latex)).font(italic)),
+            math::Piece::Block(latex) => {
+                if !spans.is_empty() {
+                    parts = parts.push(rich_text(std::mem::take(&mut spans)).size(14));
+                }
+                if !words.is_empty() {
+                    parts = parts.push(iced::widget::Row::from_vec(std::mem::take(&mut words)).wrap());
+                }
+                parts = parts.push(container(math_view::view(latex, 16.0)).center_x(Length::Fill));
+            }
         }
     }
+    if !spans.is_empty() {
+        parts = parts.push(rich_text(spans).size(14));
+    }
+    if !words.is_empty() {
+        parts = parts.push(iced::widget::Row::from_vec(words).wrap());
+    }
+    parts.into()
+}
+
+/// Another model's answer to the same prompt, stacked under the original
+fn alternative_view(idx: usize, alt: usize, alternative: &Alternative) -> Element<'_, Message> {
+    let header = row![
+        text(alternative.model.as_str()).size(11),
+        horizontal_space(),
+        button(text("Use this").size(11))
+            .style(button::secondary)
+            .padding([2, 6])
+            .on_press(Message::UseAlternative(idx, alt)),
+        button(text("✕").size(11))
+            .style(button::text)
+            .padding([2, 6])
+            .on_press(Message::DismissAlternative(idx, alt)),
+    ]
+    .spacing(4)
+    .align_y(iced::Alignment::Center);
+
+    let bubble = container(column![header, text(alternative.content.as_str()).size(14)].spacing(6))
+        .padding(12)
+        .style(container::bordered_box)
+        .max_width(500);
+    row![bubble, horizontal_space()].width(Length::Fill).into()
+}
+
+/// `~/src/app` to an absolute path
+fn expand_home(dir: &str) -> PathBuf {
+    match dir.strip_prefix("~/") {
+        Some(rest) => directories::BaseDirs::new()
+            .map(|dirs| dirs.home_dir().join(rest))
+            .unwrap_or_else(|| PathBuf::from(dir)),
+        None => PathBuf::from(dir),
+    }
+}
+
+/// Open the main window where it was last closed
+fn open_main_window(state: &WindowState) -> (window::Id, Task<Message>) {
+    let (id, task) = window::open(main_window_settings(state));
+    let task = if state.maximized {
+        task.then(|id| window::maximize(id, true))
+    } else {
+        task.discard()
+    };
+    (id, task)
+}
+
+/// The main chat window
+fn main_window_settings(state: &WindowState) -> window::Settings {
+    let position = match (state.x, state.y) {
+        (Some(x), Some(y)) => window::Position::Specific(iced::Point::new(x, y)),
+        _ => window::Position::default(),
+    };
+    window::Settings {
+        size: iced::Size::new(state.width, state.height),
+        position,
+        min_size: Some(iced::Size::new(500.0, 400.0)),
+        resizable: true,
+        platform_specific: window::settings::PlatformSpecific {
+            application_id: String::from("locallm"),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
 }
@@ -0,0 +1,274 @@
+use super::{code_blocks, Message};
+use crate::history;
+use crate::ollama::{ChatMessage, ChatOptions, OllamaClient, StreamChunk};
+use futures::StreamExt;
+use iced::widget::{button, checkbox, column, container, horizontal_space, row, scrollable, text, text_editor, Column};
+use iced::{Element, Length, Task};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const DEFAULT_PROMPT: &str = "Explain how a hash map works, in three short paragraphs.";
+
+/// State of the benchmark screen
+pub struct BenchmarkRun {
+    /// One prompt per line
+    pub prompts: text_editor::Content,
+    pub models: BTreeSet<String>,
+    pub results: Vec<BenchResult>,
+    pub running: bool,
+    /// Jobs in the current run, for the progress count
+    pub total: usize,
+    pub handle: Option<iced::task::Handle>,
+}
+
+/// One prompt answered by one model
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub model: String,
+    pub prompt: String,
+    /// Time to the first streamed token, reasoning included
+    pub first_token: Option<Duration>,
+    pub total: Duration,
+    /// Ollama's own timings
+    pub load: Option<Duration>,
+    pub tokens: Option<u64>,
+    pub tokens_per_sec: Option<f64>,
+    pub error: Option<String>,
+}
+
+impl BenchmarkRun {
+    pub fn new(selected: Option<String>) -> Self {
+        Self {
+            prompts: text_editor::Content::with_text(DEFAULT_PROMPT),
+            models: selected.into_iter().collect(),
+            results: Vec::new(),
+            running: false,
+            total: 0,
+            handle: None,
+        }
+    }
+
+    fn prompt_list(&self) -> Vec<String> {
+        self.prompts
+            .text()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Run every prompt on every selected model, one request at a time so
+    /// they don't compete for the GPU
+    pub fn start(&mut self, client: OllamaClient) -> Task<Message> {
+        let prompts = self.prompt_list();
+        let jobs: Vec<(String, String)> = self
+            .models
+            .iter()
+            .flat_map(|model| prompts.iter().map(move |prompt| (model.clone(), prompt.clone())))
+            .collect();
+        if jobs.is_empty() {
+            return Task::none();
+        }
+        self.results.clear();
+        self.total = jobs.len();
+        self.running = true;
+
+        let stream = futures::stream::iter(jobs).then(move |(model, prompt)| {
+            let client = client.clone();
+            async move { run_one(client, model, prompt).await }
+        });
+        let (task, handle) = Task::run(stream, Message::BenchmarkFinished)
+            .chain(Task::done(Message::BenchmarkDone))
+            .abortable();
+        self.handle = Some(handle);
+        task
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+        self.running = false;
+    }
+
+    pub fn view<'a>(&'a self, available_models: &'a [String]) -> Element<'a, Message> {
+        let can_run = !self.running && !self.models.is_empty() && !self.prompt_list().is_empty();
+        let header = row![
+            text("Benchmark").size(20),
+            horizontal_space(),
+            if self.running {
+                button("Stop").style(button::danger).on_press(Message::StopBenchmark)
+            } else {
+                button("Run").on_press_maybe(can_run.then_some(Message::RunBenchmark))
+            },
+            button("Export CSV")
+                .style(button::secondary)
+                .on_press_maybe((!self.results.is_empty()).then_some(Message::ExportBenchmark)),
+            button("Close")
+                .style(button::secondary)
+                .on_press(Message::CloseBenchmark),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let mut models = Column::new().spacing(4);
+        for model in available_models {
+            models = models.push(
+                checkbox(model.as_str(), self.models.contains(model))
+                    .on_toggle(|on| Message::BenchmarkModelToggled(model.clone(), on))
+                    .text_size(13)
+                    .size(14),
+            );
+        }
+        let setup = row![
+            column![text("Models").size(14), scrollable(models).height(Length::Fixed(140.0))]
+                .spacing(6)
+                .width(Length::FillPortion(1)),
+            column![
+                text("Prompts, one per line").size(14),
+                text_editor(&self.prompts)
+                    .on_action(Message::BenchmarkPromptsAction)
+                    .height(Length::Fixed(140.0)),
+            ]
+            .spacing(6)
+            .width(Length::FillPortion(2)),
+        ]
+        .spacing(16);
+
+        let progress = if self.running {
+            text(format!("Running {} of {}...", self.results.len() + 1, self.total)).size(13)
+        } else if self.results.is_empty() {
+            text("Models run one after another; the first request to each includes loading it").size(13)
+        } else {
+            text(format!("{} runs", self.results.len())).size(13)
+        };
+
+        column![header, setup, progress, self.table()]
+            .spacing(12)
+            .padding(8)
+            .into()
+    }
+
+    fn table(&self) -> Element<'_, Message> {
+        let cell = |s: String, width: f32| text(s).size(12).width(Length::Fixed(width));
+        let header = row![
+            text("Model").size(12).width(Length::Fill),
+            text("Prompt").size(12).width(Length::Fill),
+            cell(String::from("First token"), 90.0),
+            cell(String::from("Tokens/s"), 80.0),
+            cell(String::from("Tokens"), 70.0),
+            cell(String::from("Load"), 70.0),
+            cell(String::from("Total"), 70.0),
+        ]
+        .spacing(8);
+
+        let mut rows = Column::new().spacing(4).push(header);
+        for result in &self.results {
+            let prompt: String = result.prompt.chars().take(50).collect();
+            let row = match &result.error {
+                Some(error) => row![
+                    text(&result.model).size(12).width(Length::Fill),
+                    text(prompt).size(12).width(Length::Fill),
+                    text(error).size(12).style(text::danger).width(Length::FillPortion(2)),
+                ]
+                .spacing(8),
+                None => row![
+                    text(&result.model).size(12).width(Length::Fill),
+                    text(prompt).size(12).width(Length::Fill),
+                    cell(seconds(result.first_token), 90.0),
+                    cell(result.tokens_per_sec.map(|t| format!("{t:.1}")).unwrap_or_default(), 80.0),
+                    cell(result.tokens.map(|t| t.to_string()).unwrap_or_default(), 70.0),
+                    cell(seconds(result.load), 70.0),
+                    cell(seconds(Some(result.total)), 70.0),
+                ]
+                .spacing(8),
+            };
+            rows = rows.push(container(row).padding([4, 0]));
+        }
+        scrollable(rows).height(Length::Fill).into()
+    }
+}
+
+fn seconds(duration: Option<Duration>) -> String {
+    duration
+        .map(|d| format!("{:.2}s", d.as_secs_f64()))
+        .unwrap_or_default()
+}
+
+async fn run_one(client: OllamaClient, model: String, prompt: String) -> BenchResult {
+    let messages = vec![ChatMessage {
+        role: "user".to_string(),
+        content: prompt.clone(),
+        thinking: None,
+    }];
+    let started = Instant::now();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let request = client.chat_stream(&model, messages, ChatOptions::default(), tx);
+    let first = async {
+        let mut first = None;
+        while let Some(chunk) = rx.recv().await {
+            if first.is_none() && matches!(chunk, StreamChunk::Content(_) | StreamChunk::Thinking(_)) {
+                first = Some(started.elapsed());
+            }
+        }
+        first
+    };
+    let (response, first_token) = futures::join!(request, first);
+    let total = started.elapsed();
+
+    let nanos = |n: Option<u64>| n.map(Duration::from_nanos);
+    match response {
+        Ok(response) => BenchResult {
+            model,
+            prompt,
+            first_token,
+            total,
+            load: nanos(response.load_duration),
+            tokens: response.eval_count,
+            tokens_per_sec: response
+                .eval_count
+                .zip(response.eval_duration)
+                .filter(|(_, ns)| *ns > 0)
+                .map(|(count, ns)| count as f64 / (ns as f64 / 1e9)),
+            error: None,
+        },
+        Err(e) => BenchResult {
+            model,
+            prompt,
+            first_token: None,
+            total,
+            load: None,
+            tokens: None,
+            tokens_per_sec: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// Results as CSV, in Downloads like saved code blocks
+pub async fn export_csv(results: Vec<BenchResult>) -> Result<PathBuf, String> {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    let secs = |d: Option<Duration>| d.map(|d| format!("{:.3}", d.as_secs_f64())).unwrap_or_default();
+    let mut csv = String::from("model,prompt,first_token_s,tokens_per_s,tokens,load_s,total_s,error\n");
+    for r in &results {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            quote(&r.model),
+            quote(&r.prompt),
+            secs(r.first_token),
+            r.tokens_per_sec.map(|t| format!("{t:.2}")).unwrap_or_default(),
+            r.tokens.map(|t| t.to_string()).unwrap_or_default(),
+            secs(r.load),
+            secs(Some(r.total)),
+            r.error.as_deref().map(quote).unwrap_or_default(),
+        ));
+    }
+
+    let path = code_blocks::save_dir().join(format!("locallm-benchmark-{}.csv", history::unix_now()));
+    tokio::fs::write(&path, csv)
+        .await
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    Ok(path)
+}
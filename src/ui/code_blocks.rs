@@ -0,0 +1,359 @@
+use super::Message;
+use iced::widget::{button, column, container, horizontal_space, rich_text, row, span, text};
+use iced::{Color, Element, Font, Length};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+
+/// A piece of a message: prose, or a fenced code block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Code { tag: Option<&'a str>, code: &'a str },
+}
+
+/// Split markdown on ``` fences. An unterminated fence (still streaming)
+/// runs to the end of the message.
+pub fn split(content: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = find_fence(rest) {
+        let before = rest[..start].trim_end_matches([' ', '\t']);
+        if !before.trim().is_empty() {
+            segments.push(Segment::Text(before.trim_end()));
+        }
+
+        let after_fence = &rest[start + 3..];
+        let (info, body) = after_fence.split_once('\n').unwrap_or((after_fence, ""));
+        let tag = info.split_whitespace().next();
+
+        let (code, remaining) = match find_fence(body) {
+            Some(end) => {
+                let after = &body[end + 3..];
+                let after = after.split_once('\n').map_or("", |(_, next)| next);
+                (&body[..end], after)
+            }
+            None => (body, ""),
+        };
+        segments.push(Segment::Code {
+            tag,
+            code: code.trim_end_matches(['\n', ' ', '\t']),
+        });
+        rest = remaining;
+    }
+
+    if !rest.trim().is_empty() {
+        segments.push(Segment::Text(rest.trim()));
+    }
+    segments
+}
+
+/// Byte offset of the next ``` that starts a line
+fn find_fence(s: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in s.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        if line[indent..].starts_with("```") {
+            return Some(offset + indent);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The `n`th code block of a message
+pub fn nth_block(content: &str, n: usize) -> Option<(Option<&str>, &str)> {
+    split(content)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Code { tag, code } => Some((tag, code)),
+            Segment::Text(_) => None,
+        })
+        .nth(n)
+}
+
+/// What we know about a block's language, from its tag or from a guess
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+    pub name: &'static str,
+    pub extension: &'static str,
+    /// How to run a saved file, `{file}` is replaced with its path
+    pub run: Option<&'static str>,
+}
+
+const LANGUAGES: &[(&[&str], Language)] = &[
+    (&["rust", "rs"], lang("rust", "rs", Some("rustc {file}"))),
+    (&["python", "py", "python3"], lang("python", "py", Some("python3 {file}"))),
+    (&["javascript", "js", "node", "jsx"], lang("javascript", "js", Some("node {file}"))),
+    (&["typescript", "ts", "tsx"], lang("typescript", "ts", Some("npx tsx {file}"))),
+    (&["bash", "sh", "shell", "zsh", "console"], lang("bash", "sh", Some("bash {file}"))),
+    (&["go", "golang"], lang("go", "go", Some("go run {file}"))),
+    (&["c", "h"], lang("c", "c", Some("cc {file} && ./a.out"))),
+    (&["cpp", "c++", "cc", "hpp", "cxx"], lang("cpp", "cpp", Some("c++ {file} && ./a.out"))),
+    (&["java"], lang("java", "java", Some("java {file}"))),
+    (&["ruby", "rb"], lang("ruby", "rb", Some("ruby {file}"))),
+    (&["lua"], lang("lua", "lua", Some("lua {file}"))),
+    (&["sql"], lang("sql", "sql", None)),
+    (&["json"], lang("json", "json", None)),
+    (&["yaml", "yml"], lang("yaml", "yaml", None)),
+    (&["toml"], lang("toml", "toml", None)),
+    (&["html", "xml", "svg"], lang("html", "html", None)),
+    (&["css"], lang("css", "css", None)),
+    (&["markdown", "md"], lang("markdown", "md", None)),
+    (&["diff", "patch"], lang("diff", "diff", None)),
+    (&["dockerfile", "docker"], lang("dockerfile", "Dockerfile", None)),
+    (&["makefile", "make"], lang("makefile", "Makefile", Some("make -f {file}"))),
+];
+
+const fn lang(name: &'static str, extension: &'static str, run: Option<&'static str>) -> Language {
+    Language { name, extension, run }
+}
+
+const TEXT: Language = lang("text", "txt", None);
+
+impl Language {
+    /// Look up a fence tag like "py" or "Rust"
+    pub fn from_tag(tag: &str) -> Option<Language> {
+        let tag = tag.to_ascii_lowercase();
+        LANGUAGES
+            .iter()
+            .find(|(aliases, _)| aliases.contains(&tag.as_str()))
+            .map(|(_, language)| *language)
+    }
+
+    fn named(name: &str) -> Language {
+        Self::from_tag(name).unwrap_or(TEXT)
+    }
+}
+
+/// Language of a block: the fence tag when known, otherwise a guess.
+/// The flag is true for guesses.
+pub fn language(tag: Option<&str>, code: &str) -> (Option<Language>, bool) {
+    match tag.and_then(Language::from_tag) {
+        Some(language) => (Some(language), false),
+        None if tag.is_some() => (None, false),
+        None => (detect(code), true),
+    }
+}
+
+/// Guess the language of an untagged block from telltale lines and keywords
+pub fn detect(code: &str) -> Option<Language> {
+    let trimmed = code.trim_start();
+    let first_line = trimmed.lines().next().unwrap_or_default();
+
+    if let Some(shebang) = first_line.strip_prefix("#!") {
+        return Some(match shebang {
+            s if s.contains("python") => Language::named("python"),
+            s if s.contains("node") => Language::named("javascript"),
+            s if s.contains("ruby") => Language::named("ruby"),
+            _ => Language::named("bash"),
+        });
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(code).is_ok()
+    {
+        return Some(Language::named("json"));
+    }
+    if trimmed.starts_with("<!DOCTYPE") || trimmed.starts_with("<html") || trimmed.starts_with("<?xml") {
+        return Some(Language::named("html"));
+    }
+    if trimmed.starts_with("--- ") || trimmed.starts_with("diff --git") || trimmed.starts_with("@@ ") {
+        return Some(Language::named("diff"));
+    }
+    if trimmed.starts_with("FROM ") {
+        return Some(Language::named("dockerfile"));
+    }
+
+    // Score each language by how many of its markers appear
+    const MARKERS: &[(&str, &[&str])] = &[
+        ("rust", &["fn ", "let mut ", "impl ", "pub fn", "use std::", "::new(", "println!", "&mut ", "-> Result<", "#[derive"]),
+        ("python", &["def ", "import ", "elif ", "self.", "print(", "__name__", "None", "    return ", "from "]),
+        ("javascript", &["const ", "function ", "=> ", "console.log", "require(", "document.", "let ", "export "]),
+        ("typescript", &["interface ", ": string", ": number", "export type ", "as const", "<T>"]),
+        ("go", &["package ", "func ", ":= ", "fmt.", "import (", "err != nil"]),
+        ("c", &["#include <", "int main(", "printf(", "malloc(", "->", "NULL"]),
+        ("cpp", &["std::", "#include <iostream>", "cout <<", "template<", "namespace "]),
+        ("java", &["public class ", "public static void main", "System.out.", "private "]),
+        ("bash", &["echo ", "sudo ", "$(", "fi\n", "apt ", "cd ", "export ", "&& ", "--"]),
+        ("sql", &["SELECT ", "FROM ", "WHERE ", "INSERT INTO", "CREATE TABLE", "JOIN "]),
+        ("css", &["{\n", "px;", "color:", "margin:", "display:"]),
+        ("yaml", &[":\n  ", "- name:", "version: "]),
+        ("toml", &["[package]", "[dependencies]", " = \"", "[["]),
+    ];
+
+    MARKERS
+        .iter()
+        .map(|(name, markers)| (*name, markers.iter().filter(|m| code.contains(*m)).count()))
+        .filter(|(_, score)| *score >= 2)
+        .max_by_key(|(_, score)| *score)
+        .map(|(name, _)| Language::named(name))
+}
+
+/// File name for saving a block: one named in a leading comment like
+/// `// src/main.rs`, or `snippet-<n>.<ext>`
+pub fn suggested_filename(code: &str, language: Option<Language>, n: usize) -> String {
+    let extension = language.unwrap_or(TEXT).extension;
+    let first_line = code.lines().next().unwrap_or_default().trim();
+    let comment = ["//", "#", "--", "/*", "<!--"]
+        .iter()
+        .find_map(|prefix| first_line.strip_prefix(prefix));
+
+    let named = comment.and_then(|comment| {
+        comment
+            .split_whitespace()
+            .filter_map(|word| Path::new(word.trim_end_matches([':', '*', '/'])).file_name())
+            .filter_map(|name| name.to_str())
+            .find(|name| name.contains('.') && !name.starts_with('.'))
+            .map(str::to_string)
+    });
+    named.unwrap_or_else(|| match extension {
+        "Dockerfile" | "Makefile" => extension.to_string(),
+        _ => format!("snippet-{n}.{extension}"),
+    })
+}
+
+/// Save `code` under `dir` without overwriting an existing file
+pub async fn save(dir: PathBuf, filename: String, code: String) -> Result<PathBuf, String> {
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+
+    let path = Path::new(&filename);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("snippet");
+    let extension = path.extension().and_then(|s| s.to_str());
+    let mut target = dir.join(&filename);
+    let mut copy = 1;
+    while tokio::fs::try_exists(&target).await.unwrap_or(false) {
+        copy += 1;
+        target = dir.join(match extension {
+            Some(ext) => format!("{stem}-{copy}.{ext}"),
+            None => format!("{stem}-{copy}"),
+        });
+    }
+
+    let mut contents = code;
+    contents.push('\n');
+    tokio::fs::write(&target, contents)
+        .await
+        .map_err(|e| format!("Failed to save {}: {e}", target.display()))?;
+    Ok(target)
+}
+
+/// Where "Save as file" puts blocks: Downloads, falling back to the home directory
+pub fn save_dir() -> PathBuf {
+    directories::UserDirs::new()
+        .map(|dirs| {
+            dirs.download_dir()
+                .unwrap_or_else(|| dirs.home_dir())
+                .to_path_buf()
+        })
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// Highlighted lines keyed by a hash of language and code, so redraws don't
+/// re-run syntect over the whole history
+type Highlighted = Arc<Vec<(String, Color)>>;
+static HIGHLIGHT_CACHE: LazyLock<Mutex<HashMap<u64, Highlighted>>> = LazyLock::new(Default::default);
+const HIGHLIGHT_CACHE_LIMIT: usize = 256;
+
+fn highlight(code: &str, language: &str) -> Option<Highlighted> {
+    let mut hasher = DefaultHasher::new();
+    (language, code).hash(&mut hasher);
+    let key = hasher.finish();
+
+    if let Some(cached) = HIGHLIGHT_CACHE.lock().ok()?.get(&key) {
+        return Some(cached.clone());
+    }
+
+    let syntax = SYNTAXES
+        .find_syntax_by_token(language)
+        .or_else(|| SYNTAXES.find_syntax_by_extension(language))?;
+    let theme = THEMES.themes.get("base16-ocean.dark")?;
+    let mut highlighter = HighlightLines::new(syntax, theme);
+
+    let mut spans = Vec::new();
+    for line in syntect::util::LinesWithEndings::from(code) {
+        for (style, piece) in highlighter.highlight_line(line, &SYNTAXES).ok()? {
+            let c = style.foreground;
+            spans.push((piece.to_string(), Color::from_rgb8(c.r, c.g, c.b)));
+        }
+    }
+
+    let spans = Arc::new(spans);
+    let mut cache = HIGHLIGHT_CACHE.lock().ok()?;
+    if cache.len() >= HIGHLIGHT_CACHE_LIMIT {
+        cache.clear();
+    }
+    cache.insert(key, spans.clone());
+    Some(spans)
+}
+
+/// A code block with its language and Copy / Save as file actions.
+/// `message` and `block` identify it for the actions.
+pub fn view<'a>(message: usize, block: usize, tag: Option<&'a str>, code: &'a str) -> Element<'a, Message> {
+    let (language, detected) = language(tag, code);
+    let label = match (language, tag) {
+        (Some(language), _) if detected => format!("{} (detected)", language.name),
+        (Some(language), _) => language.name.to_string(),
+        (None, Some(tag)) => tag.to_string(),
+        (None, None) => String::from("code"),
+    };
+
+    let action = |label, message| {
+        button(text(label).size(11))
+            .style(button::text)
+            .padding([2, 6])
+            .on_press(message)
+    };
+    let header = row![
+        text(label).size(11),
+        horizontal_space(),
+        action("Copy", Message::CopyCode(message, block)),
+        action("Save as file", Message::SaveCode(message, block)),
+    ]
+    .spacing(4)
+    .align_y(iced::Alignment::Center);
+
+    let body = body(code, language.map(|language| language.name));
+    panel(column![header, body].spacing(4).into())
+}
+
+/// A whole answer that was requested as JSON, highlighted without block actions
+/// (clicking the bubble copies it)
+pub fn view_json(json: &str) -> Element<'_, Message> {
+    panel(body(json, Some("json")))
+}
+
+fn body<'a>(code: &'a str, language: Option<&str>) -> Element<'a, Message> {
+    match language.and_then(|language| highlight(code, language)) {
+        Some(spans) => rich_text(
+            spans
+                .iter()
+                .map(|(piece, color)| span(piece.clone()).color(*color))
+                .collect::<Vec<_>>(),
+        )
+        .font(Font::MONOSPACE)
+        .size(13)
+        .into(),
+        None => text(code).font(Font::MONOSPACE).size(13).into(),
+    }
+}
+
+fn panel(content: Element<'_, Message>) -> Element<'_, Message> {
+    container(content)
+        .padding(8)
+        .width(Length::Fill)
+        .style(|_theme| container::Style {
+            background: Some(Color::from_rgb8(0x2b, 0x30, 0x3b).into()),
+            text_color: Some(Color::from_rgb8(0xc0, 0xc5, 0xce)),
+            border: iced::Border::default().rounded(4),
+            ..Default::default()
+        })
+        .into()
+}
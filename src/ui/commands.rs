@@ -0,0 +1,234 @@
+use super::Message;
+use crate::templates::ConversationTemplate;
+use iced::widget::{button, container, row, text, Column};
+use iced::{Element, Length};
+
+/// A command typed into the input box instead of a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlashCommand {
+    Clear,
+    Model(String),
+    /// Session system prompt, None resets to the configured one
+    System(Option<String>),
+    Retry,
+    Copy,
+    /// Open a project for `#file` mentions, None closes it
+    Workspace(Option<String>),
+    /// Start a new chat from a conversation template
+    Template(String),
+    /// Save the current chat as a conversation template
+    SaveTemplate(String),
+    /// Save the conversation as a standalone HTML page
+    Export,
+    /// Continue in a copy of the chat, keeping the original
+    Duplicate,
+    Help,
+}
+
+/// Name, argument hint and description, in the order the popup lists them
+pub const COMMANDS: &[(&str, &str, &str)] = &[
+    ("/clear", "", "Clear the conversation"),
+    ("/model", "<name>", "Switch model, a prefix like \"llama3\" is enough"),
+    ("/system", "<prompt>", "Set the system prompt for this session, empty resets it"),
+    ("/retry", "", "Regenerate the last response"),
+    ("/copy", "", "Copy the last response"),
+    ("/workspace", "<dir>", "Open a project to mention its files with #, empty closes it"),
+    ("/template", "<name>", "New chat from a conversation template, or `save <name>` to save this one"),
+    ("/export", "", "Save the conversation as an HTML page to share"),
+    ("/duplicate", "", "Continue in a copy of this chat, the original is kept"),
+    ("/help", "", "List commands"),
+];
+
+/// Parse input starting with `/`; None means it's a regular message
+pub fn parse(input: &str) -> Option<Result<SlashCommand, String>> {
+    let input = input.trim();
+    let rest = input.strip_prefix('/')?;
+    // "//" escapes a message that starts with a slash
+    if rest.starts_with('/') {
+        return None;
+    }
+
+    let (name, arg) = match rest.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (rest, ""),
+    };
+
+    let command = match name {
+        "clear" => SlashCommand::Clear,
+        "model" if arg.is_empty() => return Some(Err(String::from("Usage: /model <name>"))),
+        "model" => SlashCommand::Model(arg.to_string()),
+        "system" => SlashCommand::System(Some(arg.to_string()).filter(|a| !a.is_empty())),
+        "retry" => SlashCommand::Retry,
+        "copy" => SlashCommand::Copy,
+        "workspace" => SlashCommand::Workspace(Some(arg.to_string()).filter(|a| !a.is_empty())),
+        "template" if arg.is_empty() => {
+            return Some(Err(String::from("Usage: /template <name> or /template save <name>")))
+        }
+        "template" => match arg.strip_prefix("save") {
+            Some(name) if name.is_empty() || name.starts_with(char::is_whitespace) => match name.trim() {
+                "" => return Some(Err(String::from("Usage: /template save <name>"))),
+                name => SlashCommand::SaveTemplate(name.to_string()),
+            },
+            _ => SlashCommand::Template(arg.to_string()),
+        },
+        "export" => SlashCommand::Export,
+        "duplicate" | "fork" => SlashCommand::Duplicate,
+        "help" => SlashCommand::Help,
+        _ => return Some(Err(format!("Unknown command /{name}, try /help"))),
+    };
+    Some(Ok(command))
+}
+
+/// A message as sent: "//" at the start stands for a single slash
+pub fn unescape(message: &str) -> &str {
+    match message.strip_prefix("//") {
+        Some(_) => &message[1..],
+        None => message,
+    }
+}
+
+/// An entry in the autocomplete popup
+pub enum Suggestion<'a> {
+    Command(&'static (&'static str, &'static str, &'static str)),
+    /// Argument for `/model`
+    Model(&'a str),
+    /// Argument for `/template`
+    Template(&'a str),
+}
+
+/// Commands matching the name typed so far, or the argument's options once
+/// `/model ` or `/template ` is typed
+pub fn suggestions<'a>(
+    input: &str,
+    models: &'a [String],
+    templates: &'a [ConversationTemplate],
+) -> Vec<Suggestion<'a>> {
+    // The editor's text always ends in a newline
+    let input = input.trim_end_matches('\n');
+    let Some(typed) = input.strip_prefix('/') else {
+        return Vec::new();
+    };
+    if let Some(model) = typed.strip_prefix("model ") {
+        let model = model.trim();
+        return models
+            .iter()
+            .filter(|m| m.contains(model))
+            .map(|m| Suggestion::Model(m))
+            .collect();
+    }
+    if let Some(name) = typed.strip_prefix("template ") {
+        let name = name.trim();
+        return templates
+            .iter()
+            .filter(|t| t.name.contains(name))
+            .map(|t| Suggestion::Template(&t.name))
+            .collect();
+    }
+    if typed.contains(char::is_whitespace) || typed.starts_with('/') {
+        return Vec::new();
+    }
+    COMMANDS
+        .iter()
+        .filter(|(name, _, _)| name[1..].starts_with(typed))
+        .map(Suggestion::Command)
+        .collect()
+}
+
+/// Autocomplete list shown above the input, click to fill in the command
+pub fn view_suggestions(matches: Vec<Suggestion<'_>>) -> Element<'_, Message> {
+    let mut list = Column::new().spacing(2);
+    for suggestion in matches {
+        let (label, description, message) = match suggestion {
+            Suggestion::Command((name, args, description)) => (
+                format!("{name} {args}"),
+                *description,
+                Message::CompleteCommand(name),
+            ),
+            Suggestion::Model(model) => (
+                model.to_string(),
+                "",
+                Message::ModelCommandPicked(model.to_string()),
+            ),
+            Suggestion::Template(name) => (
+                name.to_string(),
+                "",
+                Message::StartConversationTemplate(name.to_string()),
+            ),
+        };
+        list = list.push(
+            button(
+                row![
+                    text(label).size(13).font(iced::Font::MONOSPACE).width(Length::Fixed(160.0)),
+                    text(description).size(12),
+                ]
+                .spacing(8),
+            )
+            .style(button::text)
+            .width(Length::Fill)
+            .on_press(message),
+        );
+    }
+
+    container(list)
+        .padding(4)
+        .width(Length::Fill)
+        .style(container::bordered_box)
+        .into()
+}
+
+/// Workspace files matching the `#mention` being typed, click to complete it
+pub fn view_mentions(matches: Vec<&str>) -> Element<'_, Message> {
+    let mut list = Column::new().spacing(2);
+    for path in matches {
+        list = list.push(
+            button(text(format!("#{path}")).size(13).font(iced::Font::MONOSPACE))
+                .style(button::text)
+                .width(Length::Fill)
+                .on_press(Message::MentionPicked(path.to_string())),
+        );
+    }
+
+    container(list)
+        .padding(4)
+        .width(Length::Fill)
+        .style(container::bordered_box)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(input: &str) -> Vec<&'static str> {
+        suggestions(input, &[], &[])
+            .into_iter()
+            .filter_map(|s| match s {
+                Suggestion::Command((name, _, _)) => Some(*name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn command_names_complete_from_editor_text() {
+        assert!(commands("/he\n").contains(&"/help"));
+        assert!(commands("/he").contains(&"/help"));
+        assert!(commands("/help me\n").is_empty());
+        assert!(commands("//he\n").is_empty());
+    }
+
+    #[test]
+    fn double_slash_sends_a_slash() {
+        assert!(parse("//etc/hosts is empty\n").is_none());
+        assert_eq!(unescape("//etc/hosts is empty"), "/etc/hosts is empty");
+        assert_eq!(unescape("a // b"), "a // b");
+        assert_eq!(unescape("/// three"), "// three");
+    }
+
+    #[test]
+    fn model_argument_completes() {
+        let models = [String::from("llama3.2:3b"), String::from("qwen3:8b")];
+        let matches = suggestions("/model qw\n", &models, &[]);
+        assert!(matches!(matches[..], [Suggestion::Model("qwen3:8b")]));
+    }
+}
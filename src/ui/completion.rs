@@ -0,0 +1,178 @@
+use super::Message;
+use crate::ollama::{ChatOptions, GenerateResponse, OllamaClient, StreamChunk};
+use futures::SinkExt;
+use iced::widget::{button, checkbox, column, container, row, scrollable, text, text_editor, text_input};
+use iced::{Element, Font, Length, Task};
+
+/// Completion mode: a prompt continued through `/api/generate`, optionally
+/// without the prompt template, for code-completion style experiments
+pub struct CompletionPad {
+    pub prompt: text_editor::Content,
+    /// Text after the insertion point, for fill-in-the-middle
+    pub suffix: String,
+    /// Skip the model's prompt template. Ollama ignores the suffix then, it
+    /// takes the template to place it
+    pub raw: bool,
+    pub output: String,
+    pub running: bool,
+    pub error: Option<String>,
+    /// Tokens, speed and why the last run stopped
+    pub stats: Option<String>,
+    pub generation: Option<iced::task::Handle>,
+}
+
+/// Progress of a completion, streamed back to the UI
+#[derive(Debug, Clone)]
+pub enum CompletionEvent {
+    Token(String),
+    Done(Result<GenerateResponse, String>),
+}
+
+impl Default for CompletionPad {
+    fn default() -> Self {
+        Self {
+            prompt: text_editor::Content::new(),
+            suffix: String::new(),
+            raw: false,
+            output: String::new(),
+            running: false,
+            error: None,
+            stats: None,
+            generation: None,
+        }
+    }
+}
+
+impl CompletionPad {
+    pub fn start(&mut self, client: OllamaClient, model: String, prompt: String, options: ChatOptions) -> Task<Message> {
+        if self.running {
+            return Task::none();
+        }
+        self.output.clear();
+        self.error = None;
+        self.stats = None;
+        self.running = true;
+
+        let suffix = Some(self.suffix.clone()).filter(|s| !s.is_empty() && !self.raw);
+        let raw = self.raw;
+        let stream = iced::stream::channel(64, move |mut output| async move {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let request = client.generate_stream(&model, prompt, suffix, raw, options, tx);
+            let forward = async {
+                while let Some(chunk) = rx.recv().await {
+                    if let StreamChunk::Content(token) = chunk {
+                        let _ = output.send(CompletionEvent::Token(token)).await;
+                    }
+                }
+            };
+            let (result, ()) = futures::join!(request, forward);
+            let _ = output
+                .send(CompletionEvent::Done(result.map_err(|e| e.to_string())))
+                .await;
+        });
+
+        let (task, handle) = Task::run(stream, Message::CompletionEvent).abortable();
+        self.generation = Some(handle);
+        task
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.generation.take() {
+            handle.abort();
+        }
+        self.running = false;
+    }
+
+    pub fn handle(&mut self, event: CompletionEvent) {
+        match event {
+            CompletionEvent::Token(token) => self.output.push_str(&token),
+            CompletionEvent::Done(result) => {
+                self.running = false;
+                self.generation = None;
+                match result {
+                    Ok(response) => self.stats = Some(stats(&response)),
+                    Err(e) => self.error = Some(e),
+                }
+            }
+        }
+    }
+
+    /// Append the completion to the prompt, to keep going from there
+    pub fn accept(&mut self) {
+        let prompt = self.prompt.text();
+        // The editor adds a trailing newline that isn't part of the prompt
+        let prompt = prompt.strip_suffix('\n').unwrap_or(&prompt);
+        self.prompt = text_editor::Content::with_text(&format!("{prompt}{}", self.output));
+        self.prompt
+            .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+        self.output.clear();
+        self.stats = None;
+    }
+
+    pub fn view(&self, can_run: bool) -> Element<'_, Message> {
+        let prompt = text_editor(&self.prompt)
+            .placeholder("def fibonacci(n):")
+            .on_action(Message::CompletionPromptAction)
+            .font(Font::MONOSPACE)
+            .height(Length::FillPortion(1));
+        let placeholder = if self.raw {
+            "Suffix (not used in raw mode)"
+        } else {
+            "Suffix (text after the cursor, optional)"
+        };
+        let suffix = text_input(placeholder, &self.suffix)
+            .on_input_maybe((!self.raw).then_some(Message::CompletionSuffixChanged))
+            .font(Font::MONOSPACE);
+
+        let run_btn = if self.running {
+            button("Stop").style(button::danger).on_press(Message::StopCompletion)
+        } else {
+            button("Complete").on_press_maybe(can_run.then_some(Message::RunCompletion))
+        };
+        let controls = row![
+            checkbox("Raw (no prompt template)", self.raw).on_toggle(Message::CompletionRawToggled),
+            iced::widget::horizontal_space(),
+            button("Append to prompt")
+                .style(button::secondary)
+                .on_press_maybe((!self.running && !self.output.is_empty()).then_some(Message::AcceptCompletion)),
+            run_btn,
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let output: Element<Message> = match (&self.error, self.output.is_empty()) {
+            (Some(e), _) => text(format!("Error: {e}")).style(text::danger).into(),
+            (None, true) if self.running => text("...").into(),
+            (None, true) => text("The completion appears here").size(13).into(),
+            (None, false) => scrollable(text(&self.output).font(Font::MONOSPACE).size(14))
+                .height(Length::Fill)
+                .into(),
+        };
+        let output = container(output)
+            .padding(8)
+            .width(Length::Fill)
+            .height(Length::FillPortion(1))
+            .style(container::rounded_box);
+
+        let mut pad = column![prompt, suffix, controls, output].spacing(8);
+        if self.raw && !self.suffix.is_empty() {
+            pad = pad.push(text("⚠ Raw mode ignores the suffix, turn it off for fill-in-the-middle").size(12).style(text::danger));
+        }
+        if let Some(stats) = &self.stats {
+            pad = pad.push(text(stats).size(12));
+        }
+        pad.height(Length::Fill).into()
+    }
+}
+
+fn stats(response: &GenerateResponse) -> String {
+    let tokens = response.eval_count.unwrap_or(0);
+    let mut parts = vec![format!("{tokens} tokens")];
+    if let Some(ns) = response.eval_duration.filter(|ns| *ns > 0) {
+        parts.push(format!("{:.1} tok/s", tokens as f64 / (ns as f64 / 1e9)));
+    }
+    if let Some(reason) = &response.done_reason {
+        parts.push(format!("stopped: {reason}"));
+    }
+    parts.join(" · ")
+}
@@ -0,0 +1,171 @@
+//! What a model changed when asked to revise pasted text or code: a line
+//! diff against the original, with the changed words marked inside lines.
+
+use super::code_blocks::{self, Segment};
+use super::Message;
+use crate::workspace::Attachment;
+use iced::widget::{button, column, container, horizontal_space, rich_text, row, span, text, Column};
+use iced::{Color, Element, Font, Length};
+use similar::{ChangeTag, TextDiff};
+
+/// Unchanged lines kept around each change
+const CONTEXT: usize = 3;
+
+const ADDED: Color = Color::from_rgb(0.3, 0.72, 0.4);
+const REMOVED: Color = Color::from_rgb(0.88, 0.36, 0.36);
+const MUTED: Color = Color::from_rgb(0.5, 0.5, 0.5);
+
+/// Which side of the diff to copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    Original,
+    Revised,
+}
+
+/// The text the question asked to have revised: an attached file, else the
+/// largest code block, else everything after the first paragraph (the
+/// instruction). A plain one-paragraph question has nothing to diff.
+pub fn original<'a>(question: &'a str, attachments: &'a [Attachment]) -> Option<&'a str> {
+    if let Some(attachment) = attachments.first() {
+        return Some(&attachment.content);
+    }
+    largest_block(question).or_else(|| {
+        question
+            .trim()
+            .split_once("\n\n")
+            .map(|(_, body)| body.trim())
+            .filter(|body| !body.is_empty())
+    })
+}
+
+/// The model's version: its largest code block, or the answer without a
+/// leading "Here's the revised text:" line
+pub fn revised(answer: &str) -> &str {
+    largest_block(answer).unwrap_or_else(|| {
+        let answer = answer.trim();
+        match answer.split_once("\n\n") {
+            Some((intro, rest)) if intro.trim_end().ends_with(':') && !intro.contains('\n') => rest.trim(),
+            _ => answer,
+        }
+    })
+}
+
+fn largest_block(content: &str) -> Option<&str> {
+    code_blocks::split(content)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Code { code, .. } => Some(code),
+            Segment::Text(_) => None,
+        })
+        .max_by_key(|code| code.len())
+}
+
+/// A line diff worked out once, when it's shown, rather than every frame
+#[derive(Debug, Clone)]
+pub struct Diff {
+    original: String,
+    revised: String,
+    lines: Vec<Line>,
+    added: usize,
+    removed: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+    /// Unchanged lines left out
+    Fold,
+    /// A line and its parts, the changed words marked
+    Change { tag: ChangeTag, parts: Vec<(bool, String)> },
+}
+
+impl Diff {
+    pub fn new(original: &str, revised: &str) -> Self {
+        let diff = TextDiff::from_lines(original, revised);
+        let (mut added, mut removed) = (0, 0);
+        let mut lines = Vec::new();
+        for (n, group) in diff.grouped_ops(CONTEXT).iter().enumerate() {
+            if n > 0 {
+                lines.push(Line::Fold);
+            }
+            for op in group {
+                for change in diff.iter_inline_changes(op) {
+                    match change.tag() {
+                        ChangeTag::Delete => removed += 1,
+                        ChangeTag::Insert => added += 1,
+                        ChangeTag::Equal => {}
+                    }
+                    let parts = change
+                        .iter_strings_lossy()
+                        .map(|(emphasized, part)| (emphasized, part.trim_end_matches('\n').to_string()))
+                        .collect();
+                    lines.push(Line::Change { tag: change.tag(), parts });
+                }
+            }
+        }
+        Self {
+            original: original.to_string(),
+            revised: revised.to_string(),
+            lines,
+            added,
+            removed,
+        }
+    }
+
+    /// Still the diff of these two texts
+    pub fn is_of(&self, original: &str, revised: &str) -> bool {
+        self.original == original && self.revised == revised
+    }
+}
+
+/// Removed lines in red, added in green, the changed words highlighted;
+/// long unchanged stretches fold to ⋯
+pub fn view(message: usize, diff: &Diff) -> Element<'_, Message> {
+    let mut lines = Column::new();
+    for line in &diff.lines {
+        let Line::Change { tag, parts } = line else {
+            lines = lines.push(text("⋯").size(13).color(MUTED));
+            continue;
+        };
+        let (prefix, color) = match tag {
+            ChangeTag::Delete => ("- ", Some(REMOVED)),
+            ChangeTag::Insert => ("+ ", Some(ADDED)),
+            ChangeTag::Equal => ("  ", None),
+        };
+        let mut spans = vec![span(prefix).color_maybe(color)];
+        for (emphasized, part) in parts {
+            let part = span(part.as_str()).color_maybe(color);
+            spans.push(match color.filter(|_| *emphasized) {
+                Some(color) => part.background(Color { a: 0.25, ..color }),
+                None => part,
+            });
+        }
+        lines = lines.push(rich_text(spans).font(Font::MONOSPACE).size(13));
+    }
+    let (added, removed) = (diff.added, diff.removed);
+
+    let summary = if added + removed == 0 {
+        String::from("No changes")
+    } else {
+        format!("+{added} −{removed} lines")
+    };
+    let action = |label, version| {
+        button(text(label).size(11))
+            .style(button::text)
+            .padding([2, 6])
+            .on_press(Message::CopyDiffVersion(message, version))
+    };
+    let header = row![
+        text(summary).size(11),
+        horizontal_space(),
+        action("Copy original", Version::Original),
+        action("Copy revised", Version::Revised),
+    ]
+    .spacing(4)
+    .align_y(iced::Alignment::Center);
+
+    let panel = container(column![header, lines].spacing(6))
+        .padding(12)
+        .style(container::bordered_box)
+        .max_width(700);
+    row![panel, horizontal_space()].width(Length::Fill).into()
+}
@@ -0,0 +1,177 @@
+use super::Message;
+use crate::ollama::OllamaClient;
+use crate::rag;
+use iced::widget::{button, column, container, horizontal_space, pick_list, row, scrollable, text, text_editor, Column};
+use iced::{Color, Element, Length, Task};
+use std::time::{Duration, Instant};
+
+const SAMPLE: &str = "The cat sat on the mat.\n\nA kitten was resting on the rug.\n\nQuarterly revenue grew by 12 percent.";
+
+/// Pairs listed under the matrix, most similar first
+const TOP_PAIRS: usize = 10;
+
+/// State of the embeddings screen: texts separated by blank lines,
+/// compared pairwise by cosine similarity
+pub struct EmbeddingPlayground {
+    pub model: Option<String>,
+    pub texts: text_editor::Content,
+    pub running: bool,
+    pub result: Option<Result<Comparison, String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub texts: Vec<String>,
+    /// `similarity[i][j]` between text i and text j
+    pub similarity: Vec<Vec<f32>>,
+    pub dimensions: usize,
+    pub took: Duration,
+}
+
+impl EmbeddingPlayground {
+    pub fn new(model: Option<String>) -> Self {
+        Self {
+            model,
+            texts: text_editor::Content::with_text(SAMPLE),
+            running: false,
+            result: None,
+        }
+    }
+
+    fn text_list(&self) -> Vec<String> {
+        self.texts
+            .text()
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn start(&mut self, client: OllamaClient) -> Task<Message> {
+        let texts = self.text_list();
+        let Some(model) = self.model.clone().filter(|_| texts.len() >= 2) else {
+            return Task::none();
+        };
+        self.running = true;
+        Task::perform(compare(client, model, texts), Message::EmbeddingsCompared)
+    }
+
+    pub fn view<'a>(&'a self, available_models: &'a [String]) -> Element<'a, Message> {
+        let can_run = !self.running && self.model.is_some() && self.text_list().len() >= 2;
+        let header = row![
+            text("Embeddings").size(20),
+            horizontal_space(),
+            pick_list(available_models, self.model.as_ref(), Message::EmbeddingsModelPicked)
+                .placeholder("Embedding model"),
+            button(if self.running { "Embedding…" } else { "Compare" })
+                .on_press_maybe(can_run.then_some(Message::RunEmbeddings)),
+            button("Close")
+                .style(button::secondary)
+                .on_press(Message::CloseEmbeddings),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let input = column![
+            text("Texts, separated by a blank line").size(14),
+            text_editor(&self.texts)
+                .on_action(Message::EmbeddingsTextsAction)
+                .height(Length::Fixed(160.0)),
+        ]
+        .spacing(6);
+
+        let results: Element<Message> = match &self.result {
+            None => text("Paste two or more texts and compare them with an embedding model, e.g. nomic-embed-text")
+                .size(13)
+                .into(),
+            Some(Err(e)) => text(e).size(13).style(text::danger).into(),
+            Some(Ok(comparison)) => comparison.view(),
+        };
+
+        column![header, input, results].spacing(12).padding(8).into()
+    }
+}
+
+impl Comparison {
+    fn view(&self) -> Element<'_, Message> {
+        let summary = text(format!(
+            "{} texts · {} dimensions · {} ms",
+            self.texts.len(),
+            self.dimensions,
+            self.took.as_millis()
+        ))
+        .size(13);
+
+        const CELL: f32 = 56.0;
+        let mut matrix = Column::new().spacing(2).push(
+            (0..self.texts.len()).fold(row![horizontal_space().width(Length::Fixed(CELL))].spacing(2), |r, j| {
+                r.push(text(format!("#{}", j + 1)).size(12).width(Length::Fixed(CELL)))
+            }),
+        );
+        for (i, scores) in self.similarity.iter().enumerate() {
+            let mut cells = row![text(format!("#{}", i + 1)).size(12).width(Length::Fixed(CELL))].spacing(2);
+            for &score in scores {
+                cells = cells.push(
+                    container(text(format!("{score:.3}")).size(12))
+                        .width(Length::Fixed(CELL))
+                        .padding([2, 4])
+                        .style(move |_| container::Style::default().background(shade(score))),
+                );
+            }
+            matrix = matrix.push(cells);
+        }
+
+        let mut legend = Column::new().spacing(2);
+        for (i, t) in self.texts.iter().enumerate() {
+            legend = legend.push(text(format!("#{}  {}", i + 1, preview(t))).size(12));
+        }
+
+        let mut pairs: Vec<(usize, usize, f32)> = (0..self.texts.len())
+            .flat_map(|i| (i + 1..self.texts.len()).map(move |j| (i, j)))
+            .map(|(i, j)| (i, j, self.similarity[i][j]))
+            .collect();
+        pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+        let mut ranked = Column::new().spacing(2).push(text("Most similar").size(14));
+        for (i, j, score) in pairs.into_iter().take(TOP_PAIRS) {
+            ranked = ranked.push(text(format!("{score:.3}  #{} ↔ #{}", i + 1, j + 1)).size(12));
+        }
+
+        scrollable(column![summary, matrix, legend, ranked].spacing(12))
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+/// Greener the more similar; scores under 0.2 stay clear
+fn shade(score: f32) -> Color {
+    let strength = ((score - 0.2) / 0.8).clamp(0.0, 1.0);
+    Color::from_rgba(0.3, 0.72, 0.4, strength * 0.6)
+}
+
+fn preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    match line.char_indices().nth(80) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None if text.contains('\n') => format!("{line}…"),
+        None => line.to_string(),
+    }
+}
+
+async fn compare(client: OllamaClient, model: String, texts: Vec<String>) -> Result<Comparison, String> {
+    let started = Instant::now();
+    let embeddings = client.embed(&model, &texts).await.map_err(|e| e.to_string())?;
+    if embeddings.len() != texts.len() {
+        return Err(format!("{model} returned {} embeddings for {} texts", embeddings.len(), texts.len()));
+    }
+    let similarity = embeddings
+        .iter()
+        .map(|a| embeddings.iter().map(|b| rag::cosine(a, b)).collect())
+        .collect();
+    Ok(Comparison {
+        dimensions: embeddings.first().map_or(0, Vec::len),
+        texts,
+        similarity,
+        took: started.elapsed(),
+    })
+}
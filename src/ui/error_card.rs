@@ -0,0 +1,122 @@
+use super::Message;
+use crate::ollama::OllamaError;
+use iced::widget::{button, column, container, horizontal_space, row, text};
+use iced::{Element, Length};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Everything needed to describe a failed request in a bug report
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub request_id: u64,
+    pub kind: String,
+    pub message: String,
+    pub endpoint: String,
+    pub model: String,
+    /// What the user can do about it
+    pub hint: Option<&'static str>,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl ErrorReport {
+    pub fn new(request_id: u64, error: &OllamaError, endpoint: String, model: String) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            request_id,
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+            endpoint,
+            model,
+            hint: error.hint(),
+            timestamp,
+        }
+    }
+
+    /// The one-click fix offered for this kind of failure
+    fn recovery(&self) -> Option<(&'static str, Message)> {
+        Some(match self.kind.as_str() {
+            "ConnectionFailed" | "NotRunning" => ("Reconnect", Message::CheckConnection),
+            "ModelNotFound" => ("Refresh models", Message::RefreshModels),
+            "Unauthorized" => ("Open settings", Message::OpenSettings),
+            "OutOfMemory" => ("Unload models", Message::UnloadAllModels),
+            "ContextExceeded" => ("Summarize history", Message::SummarizeHistory),
+            "Timeout" | "ServerError" | "Http" => ("Retry", Message::Retry),
+            _ => return None,
+        })
+    }
+
+    /// Plain-text report for pasting into an issue
+    pub fn diagnostics(&self) -> String {
+        format!(
+            "LocalLM {version} error report\n\
+             Request: #{id}\n\
+             Kind: {kind}\n\
+             Endpoint: {endpoint}\n\
+             Model: {model}\n\
+             Time: {timestamp} (unix)\n\
+             OS: {os}/{arch}\n\
+             Error: {message}\n\
+             Hint: {hint}\n",
+            version = env!("CARGO_PKG_VERSION"),
+            id = self.request_id,
+            kind = self.kind,
+            endpoint = self.endpoint,
+            model = self.model,
+            timestamp = self.timestamp,
+            os = std::env::consts::OS,
+            arch = std::env::consts::ARCH,
+            message = self.message,
+            hint = self.hint.unwrap_or("-"),
+        )
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let detail = |label: &'static str, value: String| {
+            row![
+                text(label).size(12).width(Length::Fixed(70.0)),
+                text(value).size(12),
+            ]
+            .spacing(8)
+        };
+
+        let recovery = self.recovery().map(|(label, message)| {
+            button(text(label).size(12)).on_press(message)
+        });
+        let header = row![
+            text(format!("⚠ Request failed: {}", self.kind))
+                .size(14)
+                .style(text::danger),
+            horizontal_space(),
+        ]
+        .push_maybe(recovery)
+        .push(
+            button(text("Copy diagnostics").size(12))
+                .style(button::secondary)
+                .on_press(Message::CopyDiagnostics),
+        )
+        .push(
+            button(text("✕").size(12))
+                .style(button::text)
+                .on_press(Message::DismissError),
+        )
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let card = column![header, text(&self.message).size(13)]
+            .push_maybe(self.hint.map(|hint| text(hint).size(13)))
+            .push(detail("Endpoint", self.endpoint.clone()))
+            .push(detail("Model", self.model.clone()))
+            .push(detail("Request", format!("#{}", self.request_id)))
+            .spacing(6);
+
+        container(card)
+            .padding(12)
+            .width(Length::Fill)
+            .style(container::bordered_box)
+            .into()
+    }
+}
@@ -0,0 +1,99 @@
+use super::Message;
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text};
+use iced::{Element, Length};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InspectorTab {
+    Request,
+    Response,
+}
+
+/// The last chat request exactly as posted, and the lines streamed back
+#[derive(Debug)]
+pub struct Inspector {
+    pub tab: InspectorTab,
+    url: String,
+    /// Pretty-printed request body
+    request: String,
+    /// Raw NDJSON lines of the response, one per line
+    response: String,
+    lines: usize,
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self {
+            tab: InspectorTab::Request,
+            url: String::new(),
+            request: String::new(),
+            response: String::new(),
+            lines: 0,
+        }
+    }
+}
+
+impl Inspector {
+    /// Start over for a new request
+    pub fn begin(&mut self, url: String, payload: &serde_json::Value) {
+        self.url = url;
+        self.request = serde_json::to_string_pretty(payload).unwrap_or_default();
+        self.response.clear();
+        self.lines = 0;
+    }
+
+    pub fn push_line(&mut self, line: &str) {
+        self.response.push_str(line);
+        self.response.push('\n');
+        self.lines += 1;
+    }
+
+    /// What the Copy button copies: the open tab
+    pub fn current(&self) -> &str {
+        match self.tab {
+            InspectorTab::Request => &self.request,
+            InspectorTab::Response => &self.response,
+        }
+    }
+
+    /// Panel to the right of the chat
+    pub fn view(&self) -> Element<'_, Message> {
+        let tab_button = |label: String, tab: InspectorTab| {
+            button(text(label).size(12))
+                .style(if self.tab == tab { button::primary } else { button::secondary })
+                .padding([2, 8])
+                .on_press(Message::InspectorTab(tab))
+        };
+        let header = row![
+            text("Inspector").size(14),
+            horizontal_space(),
+            tab_button(String::from("Request"), InspectorTab::Request),
+            tab_button(format!("Response ({})", self.lines), InspectorTab::Response),
+            button(text("Copy").size(12))
+                .style(button::text)
+                .on_press_maybe((!self.current().is_empty()).then_some(Message::CopyInspector)),
+        ]
+        .spacing(6)
+        .align_y(iced::Alignment::Center);
+
+        let body: Element<Message> = if self.request.is_empty() {
+            text("Send a message to see the request posted to /api/chat")
+                .size(12)
+                .into()
+        } else {
+            let mut body = column![].spacing(6);
+            if self.tab == InspectorTab::Request {
+                body = body.push(text(format!("POST {}", self.url)).size(11));
+            }
+            let content = container(text(self.current()).size(11).font(iced::Font::MONOSPACE)).width(Length::Fill);
+            let scroll = scrollable(content).height(Length::Fill);
+            let scroll = if self.tab == InspectorTab::Response {
+                scroll.anchor_bottom()
+            } else {
+                scroll
+            };
+            body.push(scroll).into()
+        };
+
+        column![header, body].spacing(8).width(Length::Fixed(420.0)).into()
+    }
+}
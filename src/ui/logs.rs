@@ -0,0 +1,77 @@
+use super::settings::SettingsForm;
+use super::Message;
+use crate::logging;
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text};
+use iced::{Element, Length, Task};
+
+/// State of the log viewer screen
+pub struct LogView {
+    pub text: String,
+    pub error: Option<String>,
+    pub loading: bool,
+    /// Settings form to go back to, when opened from there
+    pub settings: Option<Box<SettingsForm>>,
+}
+
+impl LogView {
+    pub fn new(settings: Option<Box<SettingsForm>>) -> Self {
+        Self {
+            text: String::new(),
+            error: None,
+            loading: false,
+            settings,
+        }
+    }
+
+    pub fn load(&mut self) -> Task<Message> {
+        self.loading = true;
+        Task::perform(logging::read_tail(), Message::LogsLoaded)
+    }
+
+    pub fn loaded(&mut self, result: Result<String, String>) {
+        self.loading = false;
+        match result {
+            Ok(text) => {
+                self.text = text;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let path = logging::log_path()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let header = row![
+            text("Logs").size(20),
+            text(path).size(12),
+            horizontal_space(),
+            button(if self.loading { "Loading..." } else { "Refresh" })
+                .on_press_maybe((!self.loading).then_some(Message::RefreshLogs)),
+            button("Copy")
+                .style(button::secondary)
+                .on_press_maybe((!self.text.is_empty()).then_some(Message::CopyLogs)),
+            button("Close")
+                .style(button::secondary)
+                .on_press(Message::CloseLogs),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let body: Element<Message> = match &self.error {
+            Some(error) => text(error).size(13).style(text::danger).into(),
+            None if self.text.is_empty() && !self.loading => text("The log is empty").size(13).into(),
+            None => scrollable(
+                container(text(&self.text).size(12).font(iced::Font::MONOSPACE))
+                    .width(Length::Fill)
+                    .padding([0, 12]),
+            )
+            .anchor_bottom()
+            .height(Length::Fill)
+            .into(),
+        };
+
+        column![header, body].spacing(12).padding(8).into()
+    }
+}
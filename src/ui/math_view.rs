@@ -0,0 +1,234 @@
+//! Math laid out from [`math::Node`]s: fractions and radicals get their
+//! rule, scripts stack beside their base and matrices line up in columns.
+
+use super::Message;
+use crate::math::{self, Node};
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer::{self, Quad};
+use iced::advanced::widget::{self, Tree, Widget};
+use iced::widget::{column, row, text, Column, Row, Space};
+use iced::{mouse, Alignment, Element, Font, Length, Point, Rectangle, Renderer, Size, Theme};
+
+const ITALIC: Font = Font {
+    style: iced::font::Style::Italic,
+    ..Font::DEFAULT
+};
+
+/// Big operators whose limits go above and below them, not beside
+const LIMITS_ABOVE: &[&str] = &["∑", "∏", "∐", "⋃", "⋂", "lim", "max", "min", "sup", "inf", "argmax", "argmin"];
+
+/// Fractions, scripts and the degree of a root are set smaller, down to this
+const MIN_SIZE: f32 = 9.0;
+
+fn smaller(size: f32) -> f32 {
+    (size * 0.75).max(MIN_SIZE)
+}
+
+/// `latex` laid out at `size`
+pub fn view(latex: &str, size: f32) -> Element<'static, Message> {
+    typeset(&math::parse(latex), size)
+}
+
+fn typeset(node: &Node, size: f32) -> Element<'static, Message> {
+    match node {
+        Node::Text(t) => text(t.clone()).size(size).font(ITALIC).into(),
+        Node::Row(nodes) => Row::with_children(nodes.iter().map(|n| typeset(n, size)))
+            .align_y(Alignment::Center)
+            .into(),
+        Node::Frac(numerator, denominator) => {
+            Bar::new(typeset(numerator, smaller(size)), typeset(denominator, smaller(size))).into()
+        }
+        Node::Sqrt { degree, radicand } => {
+            let degree = degree.as_ref().map(|d| {
+                column![typeset(d, smaller(smaller(size))), Space::with_height(size * 0.5)]
+            });
+            let radical = Bar::new(Space::new(0, 0), typeset(radicand, size));
+            row![]
+                .push_maybe(degree)
+                .push(text("√").size(size * 1.2))
+                .push(radical)
+                .align_y(Alignment::Center)
+                .into()
+        }
+        Node::Binom(n, k) => delimited(
+            "(",
+            ")",
+            column![typeset(n, smaller(size)), typeset(k, smaller(size))]
+                .align_x(Alignment::Center)
+                .into(),
+            2,
+            size,
+        ),
+        Node::Scripts { base, sub, sup } => {
+            let script = |s: &Option<Box<Node>>| s.as_ref().map(|s| typeset(s, smaller(size)));
+            let above = matches!(**base, Node::Text(ref t) if LIMITS_ABOVE.contains(&t.trim()));
+            if above {
+                return column![]
+                    .push_maybe(script(sup))
+                    .push(typeset(base, size * 1.3))
+                    .push_maybe(script(sub))
+                    .align_x(Alignment::Center)
+                    .into();
+            }
+            // A lone script sits above or below the middle of the base
+            let gap = || Space::with_height(size * 0.5);
+            let scripts = match (script(sup), script(sub)) {
+                (Some(sup), Some(sub)) => column![sup, sub],
+                (Some(sup), None) => column![sup, gap()],
+                (None, Some(sub)) => column![gap(), sub],
+                (None, None) => column![],
+            };
+            row![typeset(base, size), scripts].align_y(Alignment::Center).into()
+        }
+        Node::Table { open, close, rows } => {
+            let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+            // `aligned`-like tables line up on their `&`: right, then left
+            let align = |c: usize| match (open.is_empty() && close.is_empty(), c % 2) {
+                (true, 0) if columns > 1 => Alignment::End,
+                (true, _) => Alignment::Start,
+                (false, _) => Alignment::Center,
+            };
+            let grid = Row::with_children((0..columns).map(|c| {
+                Column::with_children(rows.iter().map(|cells| match cells.get(c) {
+                    Some(cell) => typeset(cell, size),
+                    None => text("").size(size).into(),
+                }))
+                .spacing(size * 0.3)
+                .align_x(align(c))
+                .into()
+            }))
+            .spacing(size * 0.8);
+            delimited(open, close, grid.into(), rows.len(), size)
+        }
+    }
+}
+
+/// Content between delimiters stretched to about `lines` lines of math
+fn delimited(
+    open: &'static str,
+    close: &'static str,
+    content: Element<'static, Message>,
+    lines: usize,
+    size: f32,
+) -> Element<'static, Message> {
+    let delimiter = |d: &'static str| (!d.is_empty()).then(|| text(d).size(size * 1.2 * lines.max(1) as f32));
+    row![]
+        .push_maybe(delimiter(open))
+        .push(content)
+        .push_maybe(delimiter(close))
+        .spacing(size * 0.2)
+        .align_y(Alignment::Center)
+        .into()
+}
+
+/// One element centered over another with a rule between them as wide as
+/// the wider one: a fraction, or with nothing over it, a radicand
+struct Bar {
+    over: Element<'static, Message>,
+    under: Element<'static, Message>,
+}
+
+impl Bar {
+    /// Space between the rule and what's above and below it
+    const GAP: f32 = 2.0;
+
+    fn new(over: impl Into<Element<'static, Message>>, under: impl Into<Element<'static, Message>>) -> Self {
+        Self {
+            over: over.into(),
+            under: under.into(),
+        }
+    }
+}
+
+impl Widget<Message, Theme, Renderer> for Bar {
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Shrink, Length::Shrink)
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.over), Tree::new(&self.under)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(&[&self.over, &self.under]);
+    }
+
+    fn layout(&self, tree: &mut Tree, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.loose();
+        let over = self.over.as_widget().layout(&mut tree.children[0], renderer, &limits);
+        let under = self.under.as_widget().layout(&mut tree.children[1], renderer, &limits);
+        let width = over.size().width.max(under.size().width) + 2.0 * Self::GAP;
+        let rule = over.size().height + Self::GAP;
+        let height = rule + 1.0 + Self::GAP + under.size().height;
+
+        let over_x = (width - over.size().width) / 2.0;
+        let under_x = (width - under.size().width) / 2.0;
+        layout::Node::with_children(
+            Size::new(width, height),
+            vec![
+                over.move_to(Point::new(over_x, 0.0)),
+                under.move_to(Point::new(under_x, rule + 1.0 + Self::GAP)),
+            ],
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let mut children = layout.children();
+        let (Some(over), Some(under)) = (children.next(), children.next()) else {
+            return;
+        };
+        self.over
+            .as_widget()
+            .draw(&tree.children[0], renderer, theme, style, over, cursor, viewport);
+        self.under
+            .as_widget()
+            .draw(&tree.children[1], renderer, theme, style, under, cursor, viewport);
+
+        let rule = Rectangle {
+            x: bounds.x,
+            y: over.bounds().y + over.bounds().height + Self::GAP,
+            width: bounds.width,
+            height: 1.0,
+        };
+        <Renderer as renderer::Renderer>::fill_quad(
+            renderer,
+            Quad {
+                bounds: rule,
+                ..Quad::default()
+            },
+            style.text_color,
+        );
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation,
+    ) {
+        for ((child, state), layout) in [&self.over, &self.under]
+            .into_iter()
+            .zip(&mut tree.children)
+            .zip(layout.children())
+        {
+            child.as_widget().operate(state, layout, renderer, operation);
+        }
+    }
+}
+
+impl From<Bar> for Element<'static, Message> {
+    fn from(bar: Bar) -> Self {
+        Element::new(bar)
+    }
+}
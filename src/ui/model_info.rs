@@ -0,0 +1,78 @@
+use super::Message;
+use crate::ollama::ModelShow;
+use crate::tokens::format_tokens;
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text};
+use iced::{Element, Font, Length};
+
+/// Card with the selected model's /api/show details, shown over the chat
+pub fn view<'a>(name: &'a str, info: Option<&'a ModelShow>, error: Option<&'a str>) -> Element<'a, Message> {
+    let header = row![
+        text(name).size(18),
+        horizontal_space(),
+        button(text("Customize…").size(12))
+            .style(button::secondary)
+            .on_press(Message::OpenModelForm(Some(name.to_string()))),
+        button(text("✕").size(14))
+            .style(button::text)
+            .on_press(Message::ToggleModelInfo),
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center);
+
+    let Some(info) = info else {
+        let status = match error {
+            Some(e) => text(format!("Couldn't load the model details: {e}")).size(13).style(text::danger),
+            None => text("Loading model details...").size(13),
+        };
+        return card(column![header, status].spacing(12));
+    };
+
+    let details = info.details.as_ref();
+    let field = |label: &'static str, value: Option<String>| {
+        row![
+            text(label).size(13).width(Length::Fixed(110.0)),
+            text(value.unwrap_or_else(|| String::from("—"))).size(13),
+        ]
+        .spacing(8)
+    };
+
+    let mut content = column![
+        header,
+        field("Context length", info.context_length().map(format_tokens)),
+        field("Family", details.and_then(|d| d.family.clone())),
+        field("Parameters", details.and_then(|d| d.parameter_size.clone())),
+        field("Quantization", details.and_then(|d| d.quantization_level.clone())),
+    ]
+    .spacing(6);
+
+    if let Some(ref template) = info.template {
+        content = content.push(block("Template", template));
+    }
+    if let Some(ref license) = info.license {
+        content = content.push(block("License", license));
+    }
+
+    card(content)
+}
+
+fn block<'a>(label: &'a str, body: &'a str) -> Element<'a, Message> {
+    column![
+        text(label).size(13).color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+        container(
+            scrollable(text(body).size(12).font(Font::MONOSPACE)).height(Length::Fixed(120.0)),
+        )
+        .padding(8)
+        .width(Length::Fill)
+        .style(container::bordered_box),
+    ]
+    .spacing(4)
+    .into()
+}
+
+fn card<'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    container(content)
+        .padding(16)
+        .max_width(560)
+        .style(container::bordered_box)
+        .into()
+}
@@ -0,0 +1,229 @@
+use super::onboarding::PullEvent;
+use super::Message;
+use crate::ollama::{CreateRequest, ModelShow, OllamaClient};
+use futures::SinkExt;
+use iced::widget::{button, column, horizontal_space, pick_list, row, scrollable, text, text_editor, text_input};
+use iced::{Element, Font, Length, Task};
+
+/// Form for a model derived from an installed one, sent to `/api/create`
+/// instead of a hand-written Modelfile
+pub struct ModelForm {
+    pub name: String,
+    pub base: Option<String>,
+    pub system: text_editor::Content,
+    /// One `name value` per line, like Modelfile PARAMETER lines
+    pub parameters: text_editor::Content,
+    pub template: text_editor::Content,
+    /// Step reported by the server while creating
+    pub status: Option<String>,
+    pub error: Option<String>,
+    handle: Option<iced::task::Handle>,
+}
+
+impl ModelForm {
+    pub fn new(base: Option<String>) -> Self {
+        Self {
+            name: base.as_deref().map(|b| format!("{}-custom", b.split(':').next().unwrap_or(b))).unwrap_or_default(),
+            base,
+            system: text_editor::Content::new(),
+            parameters: text_editor::Content::new(),
+            template: text_editor::Content::new(),
+            status: None,
+            error: None,
+            handle: None,
+        }
+    }
+
+    pub fn creating(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    /// Start from what the base model already has, so editing a derived
+    /// model means changing only what differs
+    pub fn fill_from(&mut self, show: &ModelShow) {
+        self.system = text_editor::Content::with_text(show.system.as_deref().unwrap_or_default());
+        self.parameters = text_editor::Content::with_text(show.parameters.as_deref().unwrap_or_default());
+        self.template = text_editor::Content::with_text(show.template.as_deref().unwrap_or_default());
+    }
+
+    fn request(&self) -> Result<CreateRequest, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(String::from("Give the new model a name"));
+        }
+        let from = self.base.clone().ok_or("Pick a base model")?;
+        let field = |content: &text_editor::Content| {
+            Some(content.text().trim().to_string()).filter(|text| !text.is_empty())
+        };
+        Ok(CreateRequest {
+            model: name.to_string(),
+            from,
+            system: field(&self.system),
+            template: field(&self.template),
+            parameters: parse_parameters(&self.parameters.text())?,
+        })
+    }
+
+    pub fn start(&mut self, client: OllamaClient) -> Task<Message> {
+        if self.creating() {
+            return Task::none();
+        }
+        let request = match self.request() {
+            Ok(request) => request,
+            Err(e) => {
+                self.error = Some(e);
+                return Task::none();
+            }
+        };
+        self.error = None;
+        self.status = Some(String::from("Starting…"));
+
+        let stream = iced::stream::channel(64, move |mut output| async move {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let create = client.create_stream(&request, tx);
+            let forward = async {
+                while let Some(progress) = rx.recv().await {
+                    let _ = output.send(PullEvent::Progress(progress)).await;
+                }
+            };
+            let (result, ()) = futures::join!(create, forward);
+            let _ = output.send(PullEvent::Done(result.map_err(|e| e.to_string()))).await;
+        });
+        let (task, handle) = Task::run(stream, Message::CreateModelEvent).abortable();
+        self.handle = Some(handle);
+        task
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+        self.status = None;
+    }
+
+    /// The new model's name once it's created
+    pub fn handle(&mut self, event: PullEvent) -> Option<String> {
+        match event {
+            PullEvent::Progress(progress) => {
+                self.status = Some(progress.status);
+                None
+            }
+            PullEvent::Done(result) => {
+                self.handle = None;
+                match result {
+                    Ok(()) => {
+                        self.status = Some(format!("Created {}", self.name.trim()));
+                        Some(self.name.trim().to_string())
+                    }
+                    Err(e) => {
+                        self.status = None;
+                        self.error = Some(e);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn view<'a>(&'a self, available_models: &'a [String]) -> Element<'a, Message> {
+        let header = row![
+            text("Create model").size(20),
+            horizontal_space(),
+            if self.creating() {
+                button("Stop").style(button::danger).on_press(Message::CancelCreateModel)
+            } else {
+                button("Create").on_press(Message::CreateModel)
+            },
+            button("Close")
+                .style(button::secondary)
+                .on_press(Message::CloseModelForm),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let label = |s| text(s).size(14);
+        let names = row![
+            column![
+                label("Base model"),
+                pick_list(available_models, self.base.as_ref(), Message::ModelFormBasePicked)
+                    .placeholder("Installed model")
+                    .width(Length::Fill),
+            ]
+            .spacing(6)
+            .width(Length::Fill),
+            column![
+                label("Name"),
+                text_input("my-assistant", &self.name).on_input(Message::ModelFormNameChanged),
+            ]
+            .spacing(6)
+            .width(Length::Fill),
+        ]
+        .spacing(16);
+
+        let editor = |content, on_action: fn(text_editor::Action) -> Message, height| {
+            text_editor(content)
+                .on_action(on_action)
+                .font(Font::MONOSPACE)
+                .size(13)
+                .height(Length::Fixed(height))
+        };
+        let mut form = column![
+            names,
+            text("Filled in from the base model; using the base's own name updates it in place").size(12),
+            label("System prompt"),
+            editor(&self.system, Message::ModelFormSystemAction, 90.0),
+            label("Parameters, one per line (e.g. temperature 0.7, num_ctx 8192, stop <|eot_id|>)"),
+            editor(&self.parameters, Message::ModelFormParametersAction, 90.0),
+            label("Template (Go template syntax, empty keeps the base model's)"),
+            editor(&self.template, Message::ModelFormTemplateAction, 120.0),
+        ]
+        .spacing(8);
+        if let Some(status) = &self.status {
+            form = form.push(text(status).size(13));
+        }
+        if let Some(error) = &self.error {
+            form = form.push(text(error).size(13).style(text::danger));
+        }
+
+        column![header, scrollable(form).height(Length::Fill)]
+            .spacing(12)
+            .padding(8)
+            .into()
+    }
+}
+
+/// Modelfile-style `name value` lines to the JSON `parameters` object.
+/// Numbers and booleans keep their type and repeated names (like `stop`)
+/// collect into a list.
+fn parse_parameters(text: &str) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    use serde_json::Value;
+    let mut parameters = serde_json::Map::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let line = line.strip_prefix("PARAMETER ").unwrap_or(line);
+        let (name, raw) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("\"{line}\" needs a value, like \"temperature 0.7\""))?;
+        let raw = raw.trim();
+        let value = if let Ok(n) = raw.parse::<i64>() {
+            Value::from(n)
+        } else if let Ok(n) = raw.parse::<f64>() {
+            Value::from(n)
+        } else if let Ok(b) = raw.parse::<bool>() {
+            Value::from(b)
+        } else {
+            Value::from(raw.trim_matches('"'))
+        };
+        match parameters.get_mut(name) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            // `stop` is always a list
+            None if name == "stop" => {
+                parameters.insert(name.to_string(), Value::Array(vec![value]));
+            }
+            None => {
+                parameters.insert(name.to_string(), value);
+            }
+        }
+    }
+    Ok(parameters)
+}
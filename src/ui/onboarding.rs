@@ -0,0 +1,222 @@
+use super::{format_bytes, Message};
+use crate::ollama::{OllamaClient, PullProgress};
+use futures::SinkExt;
+use iced::widget::{
+    button, column, container, horizontal_space, progress_bar, row, scrollable, text, text_input, Column,
+};
+use iced::{Element, Length, Task};
+
+/// Models offered on first run: name, download size and what it's good for
+const RECOMMENDED: &[(&str, &str, &str)] = &[
+    ("llama3.2:3b", "2.0 GB", "Small all-rounder, fast on most hardware"),
+    ("qwen2.5:7b", "4.7 GB", "Strong general model, good at many languages"),
+    ("qwen2.5-coder:7b", "4.7 GB", "Writing and explaining code"),
+    ("gemma3:4b", "3.3 GB", "Compact model that also reads images"),
+    ("deepseek-r1:8b", "5.2 GB", "Reasons step by step before answering"),
+    ("llama3.2:1b", "1.3 GB", "Smallest, usable without a GPU"),
+];
+
+/// First-run setup: find the server, then download a model
+pub struct Onboarding {
+    pub url: String,
+    pub checking: bool,
+    pub reachable: Option<bool>,
+    pub error: Option<String>,
+    /// Name typed for a model that isn't in the list
+    pub custom: String,
+    pub pull: Option<Pull>,
+}
+
+/// A download in progress
+pub struct Pull {
+    pub model: String,
+    pub status: String,
+    pub completed: u64,
+    pub total: u64,
+    handle: iced::task::Handle,
+}
+
+#[derive(Debug, Clone)]
+pub enum PullEvent {
+    Progress(PullProgress),
+    Done(Result<(), String>),
+}
+
+impl Onboarding {
+    pub fn new(url: String, reachable: bool) -> Self {
+        Self {
+            url,
+            checking: false,
+            reachable: Some(reachable),
+            error: None,
+            custom: String::new(),
+            pull: None,
+        }
+    }
+
+    pub fn start_pull(&mut self, client: OllamaClient, model: String) -> Task<Message> {
+        if self.pull.is_some() {
+            return Task::none();
+        }
+        self.error = None;
+
+        let name = model.clone();
+        let stream = iced::stream::channel(64, move |mut output| async move {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let request = client.pull_stream(&name, tx);
+            let forward = async {
+                while let Some(progress) = rx.recv().await {
+                    let _ = output.send(PullEvent::Progress(progress)).await;
+                }
+            };
+            let (result, ()) = futures::join!(request, forward);
+            let _ = output.send(PullEvent::Done(result.map_err(|e| e.to_string()))).await;
+        });
+
+        let (task, handle) = Task::run(stream, Message::PullEvent).abortable();
+        self.pull = Some(Pull {
+            model,
+            status: String::from("Starting download..."),
+            completed: 0,
+            total: 0,
+            handle,
+        });
+        task
+    }
+
+    pub fn cancel_pull(&mut self) {
+        if let Some(pull) = self.pull.take() {
+            pull.handle.abort();
+        }
+    }
+
+    /// The finished model's name, once a download succeeds
+    pub fn handle(&mut self, event: PullEvent) -> Option<String> {
+        let pull = self.pull.as_mut()?;
+        match event {
+            PullEvent::Progress(progress) => {
+                pull.status = progress.status;
+                // Counts only come with layer downloads, keep the last while verifying
+                if let (Some(completed), Some(total)) = (progress.completed, progress.total) {
+                    pull.completed = completed;
+                    pull.total = total;
+                }
+                None
+            }
+            PullEvent::Done(result) => {
+                let pull = self.pull.take()?;
+                match result {
+                    Ok(()) => Some(pull.model),
+                    Err(e) => {
+                        self.error = Some(format!("Couldn't download {}: {e}", pull.model));
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let header = row![
+            text("Welcome to LocalLM").size(22),
+            horizontal_space(),
+            button("Skip")
+                .style(button::secondary)
+                .on_press(Message::CloseOnboarding),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        // Step 1: the server
+        let reachability = match (self.checking, self.reachable) {
+            (true, _) => text("Checking...").size(13),
+            (false, Some(true)) => text("✓ Ollama is running").size(13).style(text::success),
+            (false, Some(false)) => text(
+                "✗ Ollama isn't reachable here. Install it from https://ollama.com and start it \
+                 with `ollama serve`, or enter the address of the machine running it.",
+            )
+            .size(13)
+            .style(text::danger),
+            (false, None) => text("").size(13),
+        };
+        let server = column![
+            text("1. Ollama server").size(16),
+            row![
+                text_input("http://127.0.0.1:11434", &self.url)
+                    .on_input(Message::OnboardingUrlChanged)
+                    .on_submit(Message::OnboardingCheck)
+                    .width(Length::Fill),
+                button("Check").on_press_maybe((!self.checking).then_some(Message::OnboardingCheck)),
+            ]
+            .spacing(8),
+            reachability,
+        ]
+        .spacing(8);
+
+        // Step 2: a model
+        let can_pull = self.reachable == Some(true) && self.pull.is_none();
+        let mut models = Column::new().spacing(6);
+        for &(name, size, description) in RECOMMENDED {
+            models = models.push(
+                row![
+                    text(name).size(14).width(Length::Fixed(160.0)),
+                    text(size).size(13).width(Length::Fixed(70.0)),
+                    text(description).size(13).width(Length::Fill),
+                    button(text("Download").size(13))
+                        .on_press_maybe(can_pull.then(|| Message::OnboardingPull(name.to_string()))),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            );
+        }
+        let custom = self.custom.trim();
+        models = models.push(
+            row![
+                text_input("Another model from ollama.com/library, e.g. mistral:7b", &self.custom)
+                    .on_input(Message::OnboardingCustomChanged)
+                    .width(Length::Fill),
+                button(text("Download").size(13)).on_press_maybe(
+                    (can_pull && !custom.is_empty()).then(|| Message::OnboardingPull(custom.to_string()))
+                ),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+        );
+
+        let mut download = column![text("2. Download a model").size(16), models].spacing(8);
+        if let Some(pull) = &self.pull {
+            let fraction = if pull.total > 0 {
+                pull.completed as f32 / pull.total as f32
+            } else {
+                0.0
+            };
+            let amount = if pull.total > 0 {
+                format!(" ({} of {})", format_bytes(pull.completed), format_bytes(pull.total))
+            } else {
+                String::new()
+            };
+            download = download.push(
+                column![
+                    row![
+                        text(format!("{}: {}{amount}", pull.model, pull.status)).size(13),
+                        horizontal_space(),
+                        button(text("Cancel").size(13))
+                            .style(button::secondary)
+                            .on_press(Message::OnboardingCancelPull),
+                    ]
+                    .align_y(iced::Alignment::Center),
+                    progress_bar(0.0..=1.0, fraction).height(8),
+                ]
+                .spacing(6),
+            );
+        }
+        if let Some(error) = &self.error {
+            download = download.push(text(error).size(13).style(text::danger));
+        }
+
+        let body = column![server, download].spacing(24).max_width(760);
+        column![header, scrollable(container(body).center_x(Length::Fill)).height(Length::Fill)]
+            .spacing(20)
+            .padding(16)
+            .into()
+    }
+}
@@ -0,0 +1,319 @@
+use super::Message;
+use crate::config::HistoryLimit;
+use crate::ollama::ChatOptions;
+use iced::widget::{button, checkbox, column, pick_list, row, text, text_input};
+use iced::{Element, Length};
+use std::fmt;
+
+/// Per-session generation parameters, seeded from the config
+pub struct ParamsForm {
+    pub num_predict: String,
+    /// One entry per stop sequence, with `\n` and `\t` escaped
+    pub stop: Vec<String>,
+    pub temperature: String,
+    pub num_ctx: String,
+    /// `HistoryLimit` as typed, empty sends the whole conversation
+    pub history_limit: String,
+    /// Name of the prompt template wrapped around each message
+    pub template: Option<String>,
+    /// Write a profiling report for the next request only
+    pub profile_next: bool,
+    /// Show the request/response inspector next to the chat
+    pub inspect: bool,
+    /// Request separate reasoning, remembered per model in `think_models`
+    pub think: bool,
+    pub format: OutputFormat,
+    /// JSON schema for `OutputFormat::Schema`
+    pub schema: String,
+    /// Add the closest excerpts from the indexed documents to each question
+    pub use_documents: bool,
+}
+
+/// What the answer must be, Ollama's `format` field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Schema,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 3] = [OutputFormat::Text, OutputFormat::Json, OutputFormat::Schema];
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OutputFormat::Text => "Text",
+            OutputFormat::Json => "JSON",
+            OutputFormat::Schema => "JSON schema",
+        })
+    }
+}
+
+/// Result of checking an answer requested as JSON
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonCheck {
+    Valid,
+    Invalid(String),
+}
+
+/// Pretty-print an answer requested as JSON and check it: it must parse and,
+/// with a schema, have the schema's top-level `required` fields. Answers that
+/// don't parse are returned unchanged.
+pub fn check_json(content: &str, format: Option<&serde_json::Value>) -> (String, JsonCheck) {
+    let value: serde_json::Value = match serde_json::from_str(content.trim()) {
+        Ok(value) => value,
+        Err(e) => return (content.to_string(), JsonCheck::Invalid(format!("not valid JSON: {e}"))),
+    };
+    let pretty = serde_json::to_string_pretty(&value).unwrap_or_else(|_| content.to_string());
+
+    let required = format
+        .and_then(|schema| schema.get("required"))
+        .and_then(|required| required.as_array())
+        .map(|fields| fields.iter().filter_map(|f| f.as_str()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let missing: Vec<&str> = required
+        .into_iter()
+        .filter(|field| value.get(field).is_none())
+        .collect();
+    if missing.is_empty() {
+        (pretty, JsonCheck::Valid)
+    } else {
+        (pretty, JsonCheck::Invalid(format!("missing required {}", missing.join(", "))))
+    }
+}
+
+impl ParamsForm {
+    pub fn from_options(options: &ChatOptions) -> Self {
+        let mut form = Self {
+            num_predict: String::new(),
+            stop: Vec::new(),
+            temperature: String::new(),
+            num_ctx: String::new(),
+            history_limit: String::new(),
+            template: None,
+            profile_next: false,
+            inspect: false,
+            think: false,
+            format: OutputFormat::Text,
+            schema: String::new(),
+            use_documents: false,
+        };
+        form.set_options(options);
+        form
+    }
+
+    /// Fill in the option fields, leaving toggles and the template alone
+    pub fn set_options(&mut self, options: &ChatOptions) {
+        let show = |n: Option<String>| n.unwrap_or_default();
+        self.num_predict = show(options.num_predict.map(|n| n.to_string()));
+        self.stop = options.stop.iter().map(|s| escape(s)).collect();
+        self.temperature = show(options.temperature.map(|t| t.to_string()));
+        self.num_ctx = show(options.num_ctx.map(|n| n.to_string()));
+    }
+
+    pub fn history_limit(&self) -> Result<Option<HistoryLimit>, String> {
+        HistoryLimit::parse(&self.history_limit)
+    }
+
+    /// Parse the form into request options
+    pub fn to_options(&self) -> Result<ChatOptions, String> {
+        // Not a request option, but checked with them before anything is sent
+        self.history_limit()?;
+
+        let num_predict = match self.num_predict.trim() {
+            "" => None,
+            n => Some(
+                n.parse::<i32>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("Max tokens must be a positive number, got \"{n}\""))?,
+            ),
+        };
+
+        let temperature = match self.temperature.trim() {
+            "" => None,
+            t => Some(
+                t.parse::<f32>()
+                    .ok()
+                    .filter(|t| (0.0..=2.0).contains(t))
+                    .ok_or_else(|| format!("Temperature must be between 0 and 2, got \"{t}\""))?,
+            ),
+        };
+
+        let num_ctx = match self.num_ctx.trim() {
+            "" => None,
+            n => Some(
+                n.parse::<u32>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("Context must be a positive number of tokens, got \"{n}\""))?,
+            ),
+        };
+
+        let stop = self
+            .stop
+            .iter()
+            .map(|s| unescape(s))
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let format = match self.format {
+            OutputFormat::Text => None,
+            OutputFormat::Json => Some(serde_json::Value::from("json")),
+            OutputFormat::Schema => {
+                let schema: serde_json::Value = serde_json::from_str(&self.schema)
+                    .map_err(|e| format!("JSON schema isn't valid JSON: {e}"))?;
+                if !schema.is_object() {
+                    return Err(String::from("JSON schema must be an object"));
+                }
+                Some(schema)
+            }
+        };
+
+        Ok(ChatOptions {
+            num_predict,
+            stop,
+            temperature,
+            num_ctx,
+            think: self.think,
+            format,
+        })
+    }
+
+    pub fn view(&self, template_names: Vec<String>) -> Element<'_, Message> {
+        let clear_template = button(text("✕").size(12))
+            .style(button::text)
+            .on_press_maybe(self.template.is_some().then_some(Message::TemplateSelected(None)));
+
+        let schema: Element<'_, Message> = if self.format == OutputFormat::Schema {
+            text_input(r#"{"type": "object", "properties": {...}, "required": [...]}"#, &self.schema)
+                .on_input(Message::FormatSchemaChanged)
+                .size(12)
+                .width(Length::Fill)
+                .into()
+        } else {
+            row![].into()
+        };
+
+        let numbers = row![
+            text("Max tokens").size(12),
+            text_input("unlimited", &self.num_predict)
+                .on_input(Message::NumPredictChanged)
+                .size(12)
+                .width(Length::Fixed(90.0)),
+            text("Temperature").size(12),
+            text_input("model's", &self.temperature)
+                .on_input(Message::TemperatureChanged)
+                .size(12)
+                .width(Length::Fixed(70.0)),
+            text("Context").size(12),
+            text_input("model's", &self.num_ctx)
+                .on_input(Message::NumCtxChanged)
+                .size(12)
+                .width(Length::Fixed(80.0)),
+            text("History").size(12),
+            text_input("all", &self.history_limit)
+                .on_input(Message::HistoryLimitChanged)
+                .size(12)
+                .width(Length::Fixed(90.0)),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let stops = self.stop.iter().enumerate().fold(
+            row![text("Stop").size(12)].spacing(8).align_y(iced::Alignment::Center),
+            |stops, (i, stop)| {
+                stops.push(
+                    row![
+                        text_input("e.g. ### or \\n\\n", stop)
+                            .on_input(move |value| Message::StopSequenceChanged(i, value))
+                            .size(12)
+                            .width(Length::Fixed(110.0)),
+                        button(text("✕").size(12))
+                            .style(button::text)
+                            .on_press(Message::RemoveStopSequence(i)),
+                    ]
+                    .align_y(iced::Alignment::Center),
+                )
+            },
+        );
+        let stops = stops.push(
+            button(text("+").size(12))
+                .style(button::secondary)
+                .on_press(Message::AddStopSequence),
+        );
+
+        let toggles = row![
+            checkbox("Think", self.think)
+                .on_toggle(Message::ThinkToggled)
+                .text_size(12)
+                .size(14),
+            checkbox("Use my documents", self.use_documents)
+                .on_toggle(Message::UseDocumentsToggled)
+                .text_size(12)
+                .size(14),
+            text("Output").size(12),
+            pick_list(OutputFormat::ALL, Some(self.format), Message::OutputFormatSelected).text_size(12),
+            schema,
+            text("Template").size(12),
+            pick_list(template_names, self.template.clone(), |name| {
+                Message::TemplateSelected(Some(name))
+            })
+            .placeholder("none")
+            .text_size(12),
+            clear_template,
+            button(text("Test templates").size(12))
+                .style(button::secondary)
+                .on_press(Message::OpenTemplateTests),
+            button(text("Benchmark").size(12))
+                .style(button::secondary)
+                .on_press(Message::OpenBenchmark),
+            button(text("Embeddings").size(12))
+                .style(button::secondary)
+                .on_press(Message::OpenEmbeddings),
+            button(text("Storage").size(12))
+                .style(button::secondary)
+                .on_press(Message::OpenStorage),
+            checkbox("Profile next", self.profile_next)
+                .on_toggle(Message::ProfileNextToggled)
+                .text_size(12)
+                .size(14),
+            checkbox("Inspector", self.inspect)
+                .on_toggle(Message::InspectToggled)
+                .text_size(12)
+                .size(14),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        column![numbers, stops, toggles].spacing(6).into()
+    }
+}
+
+/// Show control characters as escapes so they survive a single-line input
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
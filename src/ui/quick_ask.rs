@@ -0,0 +1,152 @@
+use super::Message;
+use crate::ollama::{self, ChatMessage, ChatOptions, OllamaClient, StreamChunk};
+use crate::redact::Redactor;
+use futures::SinkExt;
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text, text_input};
+use iced::{window, Element, Length, Task};
+use std::sync::LazyLock;
+
+static INPUT_ID: LazyLock<text_input::Id> = LazyLock::new(|| text_input::Id::new("quick-ask"));
+
+/// Streamed progress of a quick-ask answer
+#[derive(Debug, Clone)]
+pub enum QuickAskEvent {
+    Token(String),
+    Done(Result<(), String>),
+}
+
+/// The frameless popup opened by the global hotkey: one question, one answer
+pub struct QuickAsk {
+    pub window: window::Id,
+    pub input: String,
+    pub answer: String,
+    pub generating: bool,
+    pub error: Option<String>,
+    pub copied: bool,
+}
+
+impl QuickAsk {
+    /// Open the popup window, focusing the input once it's up
+    pub fn open() -> (Self, Task<Message>) {
+        let (window, opened) = window::open(window::Settings {
+            size: iced::Size::new(640.0, 320.0),
+            position: window::Position::Centered,
+            resizable: false,
+            decorations: false,
+            level: window::Level::AlwaysOnTop,
+            platform_specific: window::settings::PlatformSpecific {
+                application_id: String::from("locallm-quick"),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let popup = Self {
+            window,
+            input: String::new(),
+            answer: String::new(),
+            generating: false,
+            error: None,
+            copied: false,
+        };
+        let focus = opened.then(|_| text_input::focus(INPUT_ID.clone()));
+        (popup, focus)
+    }
+
+    /// Send the question and stream the answer back as `QuickAskEvent`s
+    pub fn ask(
+        &mut self,
+        client: OllamaClient,
+        model: String,
+        system_prompt: Option<String>,
+        redactor: &Redactor,
+    ) -> Task<Message> {
+        let question = redactor.apply(self.input.trim());
+        if question.is_empty() || self.generating {
+            return Task::none();
+        }
+        self.answer.clear();
+        self.error = None;
+        self.copied = false;
+        self.generating = true;
+
+        let mut messages = Vec::new();
+        if let Some(system) = system_prompt {
+            messages.push(ChatMessage {
+                role: "system".to_string(),
+                content: system,
+                thinking: None,
+            });
+        }
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content: question,
+            thinking: None,
+        });
+
+        let stream = iced::stream::channel(64, move |mut output| async move {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let request = client.chat_stream(&model, messages, ChatOptions::default(), tx);
+            let forward = async {
+                // Only the answer, a quick question doesn't need the reasoning
+                while let Some(token) = rx.recv().await {
+                    if let StreamChunk::Content(token) = token {
+                        let _ = output.send(QuickAskEvent::Token(token)).await;
+                    }
+                }
+            };
+            let (result, ()) = futures::join!(request, forward);
+            let _ = output
+                .send(QuickAskEvent::Done(result.map(|_| ()).map_err(|e| e.to_string())))
+                .await;
+        });
+
+        Task::run(stream, Message::QuickAskEvent)
+    }
+
+    pub fn view<'a>(&'a self, model: Option<&'a str>) -> Element<'a, Message> {
+        let input = text_input("Ask anything...", &self.input)
+            .id(INPUT_ID.clone())
+            .on_input(Message::QuickAskInputChanged)
+            .on_submit(Message::QuickAskSubmit)
+            .size(18)
+            .padding(10);
+
+        // Inline <think> reasoning is left out here too
+        let answer = ollama::split_thinking(&self.answer).1;
+        let body: Element<Message> = match (&self.error, answer.is_empty()) {
+            (Some(e), _) => text(format!("Error: {e}")).style(text::danger).into(),
+            (None, true) if self.generating => text("...").into(),
+            (None, true) => text("").into(),
+            (None, false) => scrollable(text(answer).size(15))
+                .height(Length::Fill)
+                .into(),
+        };
+
+        let hint = if self.copied {
+            "📋 Copied · Esc to close"
+        } else {
+            "Enter to ask · Esc to close"
+        };
+        let footer = row![
+            text(model.unwrap_or("No model")).size(12),
+            horizontal_space(),
+            text(hint).size(12),
+            button(text("Open LocalLM").size(12))
+                .style(button::text)
+                .on_press(Message::OpenMainWindow),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        container(
+            column![input, container(body).height(Length::Fill), footer]
+                .spacing(10)
+                .padding(12),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(container::bordered_box)
+        .into()
+    }
+}
@@ -0,0 +1,532 @@
+use super::themes::{self, ThemeChoice};
+use super::Message;
+use crate::config::{Config, SubmitKey, TimestampFormat, UI_SCALE_RANGE};
+use iced::widget::{
+    button, checkbox, column, horizontal_space, pick_list, row, scrollable, text, text_editor,
+    text_input,
+};
+use iced::{Element, Length};
+
+/// Editable draft of the config, applied on "Apply"
+pub struct SettingsForm {
+    pub ollama_url: String,
+    pub proxy_bypass_local: bool,
+    pub api_key: String,
+    pub proxy: String,
+    pub manage_server: bool,
+    pub ollama_binary: String,
+    pub default_model: String,
+    pub system_prompt: text_editor::Content,
+    pub auto_copy: bool,
+    pub show_gpu_stats: bool,
+    pub show_system_stats: bool,
+    pub show_timestamps: bool,
+    pub timestamp_format: TimestampFormat,
+    pub submit_key: SubmitKey,
+    pub input_stats: bool,
+    pub spellcheck: String,
+    pub theme: ThemeChoice,
+    /// Percent
+    pub ui_scale: String,
+    pub user_bubble_color: String,
+    pub assistant_bubble_color: String,
+    pub high_contrast: bool,
+    pub reduced_motion: bool,
+    pub summarize_after_tokens: String,
+    pub token_budget: String,
+    pub response_soft_cap: String,
+    pub notify_after_secs: String,
+    pub record_token_timing: bool,
+    pub remember_prompts: bool,
+    pub show_thinking: bool,
+    pub keep_alive: String,
+    pub inhibit_idle: bool,
+    pub quick_ask_hotkey: String,
+    pub clipboard_prompt: String,
+    /// One folder per line
+    pub document_folders: text_editor::Content,
+    pub embedding_model: String,
+    pub document_top_k: String,
+
+    /// Outcome of the last "Index now", None before the first
+    pub index_status: Option<String>,
+    pub indexing: bool,
+    /// Result of the last "Test" click, None while untested or in flight
+    pub connection_ok: Option<bool>,
+    pub testing: bool,
+    pub error: Option<String>,
+}
+
+impl SettingsForm {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            ollama_url: config.ollama_url.clone(),
+            proxy_bypass_local: config.proxy_bypass_local,
+            api_key: config.api_key.clone().unwrap_or_default(),
+            proxy: config.proxy.clone().unwrap_or_default(),
+            manage_server: config.manage_server,
+            ollama_binary: config.ollama_binary.clone(),
+            default_model: config.default_model.clone().unwrap_or_default(),
+            system_prompt: text_editor::Content::with_text(
+                config.system_prompt.as_deref().unwrap_or_default(),
+            ),
+            auto_copy: config.auto_copy,
+            show_gpu_stats: config.show_gpu_stats,
+            show_system_stats: config.show_system_stats,
+            show_timestamps: config.show_timestamps,
+            timestamp_format: config.timestamp_format,
+            submit_key: config.submit_key,
+            input_stats: config.input_stats,
+            spellcheck: config.spellcheck.clone().unwrap_or_default(),
+            theme: ThemeChoice::from_config(config.theme.as_deref()),
+            ui_scale: format!("{:.0}", config.ui_scale * 100.0),
+            user_bubble_color: config.user_bubble_color.clone().unwrap_or_default(),
+            assistant_bubble_color: config.assistant_bubble_color.clone().unwrap_or_default(),
+            high_contrast: config.high_contrast,
+            reduced_motion: config.reduced_motion,
+            summarize_after_tokens: config
+                .summarize_after_tokens
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+            token_budget: config.token_budget.map(|n| n.to_string()).unwrap_or_default(),
+            response_soft_cap: config
+                .response_soft_cap
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+            notify_after_secs: config.notify_after_secs.map(|n| n.to_string()).unwrap_or_default(),
+            record_token_timing: config.record_token_timing,
+            remember_prompts: config.remember_prompts,
+            show_thinking: config.show_thinking,
+            keep_alive: config.keep_alive.clone().unwrap_or_default(),
+            inhibit_idle: config.inhibit_idle,
+            quick_ask_hotkey: config.quick_ask_hotkey.clone().unwrap_or_default(),
+            clipboard_prompt: config.clipboard_prompt.clone(),
+            document_folders: text_editor::Content::with_text(&config.document_folders.join("\n")),
+            embedding_model: config.embedding_model.clone(),
+            document_top_k: config.document_top_k.to_string(),
+            index_status: None,
+            indexing: false,
+            connection_ok: None,
+            testing: false,
+            error: None,
+        }
+    }
+
+    /// Build a new config from the form, keeping fields the form doesn't edit
+    pub fn to_config(&self, base: &Config) -> Result<Config, String> {
+        let ollama_url = validate_url(&self.ollama_url)?;
+        let default_model = Some(self.default_model.trim().to_string()).filter(|m| !m.is_empty());
+        let system_prompt = self.system_prompt.text();
+        let system_prompt = Some(system_prompt.trim().to_string()).filter(|p| !p.is_empty());
+        let summarize_after_tokens = match self.summarize_after_tokens.trim() {
+            "" => None,
+            n => Some(
+                n.parse::<u64>()
+                    .map_err(|_| format!("Summarize threshold must be a number, got \"{n}\""))?,
+            ),
+        };
+        let token_budget = match self.token_budget.trim() {
+            "" => None,
+            n => Some(
+                n.parse::<u64>()
+                    .map_err(|_| format!("Token budget must be a number, got \"{n}\""))?,
+            ),
+        };
+        let response_soft_cap = match self.response_soft_cap.trim() {
+            "" => None,
+            n => Some(
+                n.parse::<u64>()
+                    .map_err(|_| format!("Response soft cap must be a number, got \"{n}\""))?,
+            ),
+        };
+        let notify_after_secs = match self.notify_after_secs.trim() {
+            "" => None,
+            n => Some(
+                n.parse::<u64>()
+                    .map_err(|_| format!("Notification delay must be a number of seconds, got \"{n}\""))?,
+            ),
+        };
+        let ui_scale = match self.ui_scale.trim().trim_end_matches('%').parse::<f32>() {
+            Ok(percent) if UI_SCALE_RANGE.contains(&(percent / 100.0)) => percent / 100.0,
+            _ => {
+                return Err(format!(
+                    "UI scale must be a percentage between {:.0} and {:.0}",
+                    UI_SCALE_RANGE.start() * 100.0,
+                    UI_SCALE_RANGE.end() * 100.0
+                ))
+            }
+        };
+        let embedding_model = match self.embedding_model.trim() {
+            "" => return Err(String::from("Embedding model can't be empty")),
+            model => model.to_string(),
+        };
+        let document_top_k = self
+            .document_top_k
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|k| *k > 0)
+            .ok_or_else(|| format!("Excerpts per question must be a positive number, got \"{}\"", self.document_top_k.trim()))?;
+        let user_bubble_color = bubble_color(&self.user_bubble_color)?;
+        let assistant_bubble_color = bubble_color(&self.assistant_bubble_color)?;
+
+        let mut config = base.clone();
+        config.ollama_url = ollama_url;
+        config.proxy_bypass_local = self.proxy_bypass_local;
+        config.default_model = default_model;
+        config.system_prompt = system_prompt;
+        config.auto_copy = self.auto_copy;
+        config.show_gpu_stats = self.show_gpu_stats;
+        config.show_system_stats = self.show_system_stats;
+        config.show_timestamps = self.show_timestamps;
+        config.timestamp_format = self.timestamp_format;
+        config.submit_key = self.submit_key;
+        config.input_stats = self.input_stats;
+        config.spellcheck = Some(self.spellcheck.trim().to_string()).filter(|s| !s.is_empty());
+        config.theme = self.theme.to_config();
+        config.ui_scale = ui_scale;
+        config.user_bubble_color = user_bubble_color;
+        config.assistant_bubble_color = assistant_bubble_color;
+        config.high_contrast = self.high_contrast;
+        config.reduced_motion = self.reduced_motion;
+        config.summarize_after_tokens = summarize_after_tokens;
+        config.token_budget = token_budget;
+        config.response_soft_cap = response_soft_cap;
+        config.notify_after_secs = notify_after_secs;
+        config.record_token_timing = self.record_token_timing;
+        config.remember_prompts = self.remember_prompts;
+        config.show_thinking = self.show_thinking;
+        config.inhibit_idle = self.inhibit_idle;
+        config.keep_alive = Some(self.keep_alive.trim().to_string()).filter(|k| !k.is_empty());
+        config.api_key = Some(self.api_key.trim().to_string()).filter(|k| !k.is_empty());
+        config.proxy = Some(self.proxy.trim().to_string()).filter(|p| !p.is_empty());
+        config.manage_server = self.manage_server;
+        config.ollama_binary = match self.ollama_binary.trim() {
+            "" => String::from("ollama"),
+            binary => binary.to_string(),
+        };
+        config.quick_ask_hotkey =
+            Some(self.quick_ask_hotkey.trim().to_string()).filter(|k| !k.is_empty());
+        config.clipboard_prompt = self.clipboard_prompt.trim().to_string();
+        config.document_folders = self.folders();
+        config.embedding_model = embedding_model;
+        config.document_top_k = document_top_k;
+        Ok(config)
+    }
+
+    /// Non-empty lines of the folders box
+    pub fn folders(&self) -> Vec<String> {
+        self.document_folders
+            .text()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn view<'a>(&'a self, available_models: &'a [String]) -> Element<'a, Message> {
+        let header = row![
+            text("Settings").size(20),
+            horizontal_space(),
+            button("View logs")
+                .style(button::text)
+                .on_press(Message::OpenLogs),
+            button("Cancel")
+                .style(button::secondary)
+                .on_press(Message::CloseSettings),
+            button("Apply").on_press(Message::ApplySettings),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let connection_label = match (self.testing, self.connection_ok) {
+            (true, _) => text("Testing...").size(12),
+            (false, Some(true)) => text("✓ Ollama reachable").size(12),
+            (false, Some(false)) => text("✗ Ollama not reachable").size(12),
+            (false, None) => text("").size(12),
+        };
+
+        let url_row = row![
+            text_input("http://127.0.0.1:11434", &self.ollama_url)
+                .on_input(Message::SettingsUrlChanged)
+                .width(Length::Fill),
+            button("Test").on_press_maybe((!self.testing).then_some(Message::TestConnection)),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let model_row = row![
+            text_input("e.g. llama3.2:3b", &self.default_model)
+                .on_input(Message::SettingsModelChanged)
+                .width(Length::FillPortion(3)),
+            pick_list(
+                available_models,
+                Some(self.default_model.clone()).filter(|m| available_models.contains(m)),
+                Message::SettingsModelChanged,
+            )
+            .placeholder("Installed...")
+            .width(Length::FillPortion(2)),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let system_prompt = text_editor(&self.system_prompt)
+            .placeholder("You are a helpful assistant.")
+            .on_action(Message::SettingsSystemPromptAction)
+            .height(Length::Fixed(120.0));
+
+        let mut form = column![
+            header,
+            section("Ollama URL"),
+            url_row,
+            connection_label,
+            checkbox("Bypass the system proxy for a localhost server", self.proxy_bypass_local)
+                .on_toggle(Message::SettingsProxyBypassToggled),
+            row![
+                text("Proxy").size(14),
+                text_input("system (HTTP_PROXY)", &self.proxy)
+                    .on_input(Message::SettingsProxyChanged)
+                    .width(Length::Fixed(280.0)),
+                text("http://, https:// or socks5://").size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("API key").size(14),
+                text_input("none", &self.api_key)
+                    .secure(true)
+                    .on_input(Message::SettingsApiKeyChanged)
+                    .width(Length::Fixed(280.0)),
+                text("sent as a bearer token").size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                checkbox("Start", self.manage_server).on_toggle(Message::SettingsManageServerToggled),
+                text_input("ollama", &self.ollama_binary)
+                    .on_input(Message::SettingsOllamaBinaryChanged)
+                    .width(Length::Fixed(200.0)),
+                text("serve when Ollama isn't running").size(14),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            section("Default model"),
+            model_row,
+            row![
+                text("Keep model loaded for").size(14),
+                text_input("server default (5m)", &self.keep_alive)
+                    .on_input(Message::SettingsKeepAliveChanged)
+                    .width(Length::Fixed(160.0)),
+                text("e.g. 30m, 1h, -1 = forever").size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            section("System prompt"),
+            system_prompt,
+            section("Behavior"),
+            checkbox("Copy responses to clipboard automatically", self.auto_copy)
+                .on_toggle(Message::SettingsAutoCopyToggled),
+            checkbox("Show GPU stats", self.show_gpu_stats)
+                .on_toggle(Message::SettingsGpuStatsToggled),
+            checkbox("Show RAM and CPU usage", self.show_system_stats)
+                .on_toggle(Message::SettingsSystemStatsToggled),
+            row![
+                checkbox("Show message times", self.show_timestamps)
+                    .on_toggle(Message::SettingsTimestampsToggled),
+                pick_list(TimestampFormat::ALL, Some(self.timestamp_format), Message::SettingsTimestampFormatPicked),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("Send with").size(14),
+                pick_list(SubmitKey::ALL, Some(self.submit_key), Message::SettingsSubmitKeyPicked),
+                text(match self.submit_key {
+                    SubmitKey::Enter => "Shift+Enter for a new line",
+                    SubmitKey::CtrlEnter => "Enter for a new line, Cmd+Enter on macOS",
+                })
+                .size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            checkbox("Word and token count under the input", self.input_stats)
+                .on_toggle(Message::SettingsInputStatsToggled),
+            row![
+                text("Spellcheck").size(14),
+                text_input(
+                    if cfg!(feature = "spellcheck") { "off, e.g. en_US" } else { "not in this build" },
+                    &self.spellcheck,
+                )
+                .on_input_maybe(cfg!(feature = "spellcheck").then_some(Message::SettingsSpellcheckChanged))
+                .width(Length::Fixed(200.0)),
+                text("hunspell language or path to a .dic file").size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("Summarize older messages after").size(14),
+                text_input("off", &self.summarize_after_tokens)
+                    .on_input(Message::SettingsSummarizeAfterChanged)
+                    .width(Length::Fixed(90.0)),
+                text("tokens").size(14),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("Suggest a fresh chat after").size(14),
+                text_input("off", &self.token_budget)
+                    .on_input(Message::SettingsTokenBudgetChanged)
+                    .width(Length::Fixed(90.0)),
+                text("tokens used in a conversation").size(14),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("Stop responses after").size(14),
+                text_input("off", &self.response_soft_cap)
+                    .on_input(Message::SettingsResponseSoftCapChanged)
+                    .width(Length::Fixed(90.0)),
+                text("tokens, keeping what was written").size(14),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("Notify when a response took over").size(14),
+                text_input("off", &self.notify_after_secs)
+                    .on_input(Message::SettingsNotifyAfterChanged)
+                    .width(Length::Fixed(90.0)),
+                text("seconds and the window is in the background").size(14),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            checkbox("Keep the machine awake while generating (Linux, systemd)", self.inhibit_idle)
+                .on_toggle_maybe(crate::inhibit::SUPPORTED.then_some(Message::SettingsInhibitIdleToggled)),
+            checkbox("Show reasoning from thinking models", self.show_thinking)
+                .on_toggle(Message::SettingsShowThinkingToggled),
+            checkbox("Record token timing (latency chart under replies)", self.record_token_timing)
+                .on_toggle(Message::SettingsTokenTimingToggled),
+            checkbox("Remember sent prompts across restarts (Up/Down in an empty input)", self.remember_prompts)
+                .on_toggle(Message::SettingsRememberPromptsToggled),
+            row![
+                text("Quick-ask hotkey").size(14),
+                text_input("off, e.g. Super+Space", &self.quick_ask_hotkey)
+                    .on_input(Message::SettingsQuickAskHotkeyChanged)
+                    .width(Length::Fixed(200.0)),
+                text("on Wayland, bind `locallm --quick` instead").size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("Ask about clipboard with").size(14),
+                text_input("nothing, just the clipboard", &self.clipboard_prompt)
+                    .on_input(Message::SettingsClipboardPromptChanged)
+                    .width(Length::Fixed(240.0)),
+                text("Ctrl+Shift+V").size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            section("Documents"),
+            text("Folders indexed for \"Use my documents\", one per line (.txt, .md, .pdf)").size(12),
+            text_editor(&self.document_folders)
+                .placeholder("~/Documents/notes")
+                .on_action(Message::SettingsDocumentFoldersAction)
+                .height(Length::Fixed(80.0)),
+            row![
+                text("Embedding model").size(14),
+                text_input("nomic-embed-text", &self.embedding_model)
+                    .on_input(Message::SettingsEmbeddingModelChanged)
+                    .width(Length::Fixed(200.0)),
+                text("Excerpts per question").size(14),
+                text_input("4", &self.document_top_k)
+                    .on_input(Message::SettingsDocumentTopKChanged)
+                    .width(Length::Fixed(50.0)),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                button("Index now").on_press_maybe((!self.indexing).then_some(Message::IndexDocuments)),
+                text(match (self.indexing, &self.index_status) {
+                    (true, _) => "Indexing...",
+                    (false, Some(status)) => status.as_str(),
+                    (false, None) => "",
+                })
+                .size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            section("Appearance"),
+            row![
+                text("Theme").size(14),
+                pick_list(ThemeChoice::all(), Some(self.theme.clone()), Message::SettingsThemePicked)
+                    .width(Length::Fixed(240.0)),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("UI scale").size(14),
+                text_input("100", &self.ui_scale)
+                    .on_input(Message::SettingsUiScaleChanged)
+                    .width(Length::Fixed(70.0)),
+                text("% (Ctrl+= / Ctrl+- / Ctrl+0)").size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            row![
+                text("Bubble colors").size(14),
+                text_input("yours, theme default", &self.user_bubble_color)
+                    .on_input(Message::SettingsUserColorChanged)
+                    .width(Length::Fixed(170.0)),
+                text_input("responses, theme default", &self.assistant_bubble_color)
+                    .on_input(Message::SettingsAssistantColorChanged)
+                    .width(Length::Fixed(170.0)),
+                text("#rrggbb").size(12),
+            ]
+            .spacing(8)
+            .align_y(iced::Alignment::Center),
+            section("Accessibility"),
+            checkbox("High-contrast colors", self.high_contrast)
+                .on_toggle(Message::SettingsHighContrastToggled),
+            checkbox("Reduce motion (no typing indicator)", self.reduced_motion)
+                .on_toggle(Message::SettingsReducedMotionToggled),
+        ]
+        .spacing(8)
+        .padding(8);
+
+        if let Some(ref error) = self.error {
+            form = form.push(
+                text(error)
+                    .size(12)
+                    .color(iced::Color::from_rgb(0.9, 0.4, 0.4)),
+            );
+        }
+
+        scrollable(form).height(Length::Fill).into()
+    }
+}
+
+fn section(label: &str) -> Element<'_, Message> {
+    text(label)
+        .size(14)
+        .color(iced::Color::from_rgb(0.6, 0.6, 0.6))
+        .into()
+}
+
+/// Empty means the theme's default, otherwise it has to be a hex color
+fn bubble_color(color: &str) -> Result<Option<String>, String> {
+    match color.trim() {
+        "" => Ok(None),
+        c if themes::parse_color(c).is_some() => Ok(Some(c.to_string())),
+        c => Err(format!("Bubble color must look like #3b4261, got \"{c}\"")),
+    }
+}
+
+/// Check the URL parses and uses a scheme reqwest can talk to
+pub fn validate_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {e}"))?;
+
+    match parsed.scheme() {
+        "http" | "https" => Ok(url.to_string()),
+        scheme => Err(format!("Unsupported URL scheme: {scheme}")),
+    }
+}
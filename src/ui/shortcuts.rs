@@ -0,0 +1,62 @@
+use super::Message;
+use iced::keyboard::{self, key::Named, Key, Modifiers};
+use iced::Subscription;
+
+/// Keyboard shortcuts, mapped to app messages in `App::update`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    /// Ctrl+L
+    ClearChat,
+    /// Ctrl+K
+    FocusModelPicker,
+    /// Ctrl+N
+    NewConversation,
+    /// Ctrl+F
+    Find,
+    /// Esc: cancel generation, close the popup or the current screen
+    Escape,
+    /// Ctrl+C without a selection in the input copies the hovered message
+    Copy,
+    /// Ctrl+Shift+V
+    AskClipboard,
+    PageUp,
+    PageDown,
+    /// Ctrl+= / Ctrl+- / Ctrl+0
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+}
+
+/// Key presses from every window, tagged with the window they happened in.
+///
+/// Deliberately ignores whether a widget captured the key: the input box
+/// has focus nearly all the time and swallows PageUp/Down and Ctrl+C.
+pub fn listen() -> Subscription<Message> {
+    iced::event::listen_with(|event, _status, window| match event {
+        iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+            shortcut(key.as_ref(), modifiers).map(|shortcut| Message::Shortcut(window, shortcut))
+        }
+        _ => None,
+    })
+}
+
+fn shortcut(key: Key<&str>, modifiers: Modifiers) -> Option<Shortcut> {
+    match key {
+        Key::Named(Named::Escape) => Some(Shortcut::Escape),
+        Key::Named(Named::PageUp) => Some(Shortcut::PageUp),
+        Key::Named(Named::PageDown) => Some(Shortcut::PageDown),
+        Key::Character(c) if modifiers.command() => match c.to_ascii_lowercase().as_str() {
+            "l" => Some(Shortcut::ClearChat),
+            "k" => Some(Shortcut::FocusModelPicker),
+            "n" => Some(Shortcut::NewConversation),
+            "f" => Some(Shortcut::Find),
+            "c" => Some(Shortcut::Copy),
+            "v" if modifiers.shift() => Some(Shortcut::AskClipboard),
+            "=" | "+" => Some(Shortcut::ZoomIn),
+            "-" => Some(Shortcut::ZoomOut),
+            "0" => Some(Shortcut::ZoomReset),
+            _ => None,
+        },
+        _ => None,
+    }
+}
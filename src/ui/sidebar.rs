@@ -0,0 +1,157 @@
+use super::Message;
+use crate::history::{self, Conversation};
+use crate::snippets::Snippet;
+use crate::templates::ConversationTemplate;
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text, text_input, Column};
+use iced::{Element, Length};
+use std::sync::LazyLock;
+
+pub static RENAME_INPUT: LazyLock<text_input::Id> = LazyLock::new(|| text_input::Id::new("rename"));
+
+const WIDTH: f32 = 230.0;
+const MAX_HITS: usize = 100;
+
+/// Saved conversations, or search hits across them while a search is active;
+/// the Snippets tab lists starred answers instead
+pub fn view<'a>(
+    conversations: &'a [Conversation],
+    templates: &'a [ConversationTemplate],
+    snippets: &'a [Snippet],
+    show_snippets: bool,
+    current: Option<&str>,
+    renaming: Option<(&str, &'a str)>,
+    query: Option<&str>,
+) -> Element<'a, Message> {
+    let tab = |label, snippets| {
+        button(text(label).size(13))
+            .style(if show_snippets == snippets { button::secondary } else { button::text })
+            .padding([2, 8])
+            .on_press(Message::ShowSnippets(snippets))
+    };
+    let header = row![tab("Chats", false), tab("★ Snippets", true), horizontal_space()]
+        .spacing(2)
+        .align_y(iced::Alignment::Center);
+    if show_snippets {
+        return frame(column![header, snippet_list(snippets)].spacing(8));
+    }
+    let header = header.push(
+        button(text("New").size(12))
+            .style(button::secondary)
+            .padding([2, 8])
+            .on_press(Message::NewConversation),
+    );
+
+    let mut list = Column::new().spacing(2);
+    if query.is_none() && !templates.is_empty() {
+        list = list.push(text("Start from a template").size(11));
+        for template in templates {
+            list = list.push(
+                button(text(format!("▶ {}", template.name)).size(12))
+                    .style(button::text)
+                    .width(Length::Fill)
+                    .on_press(Message::StartConversationTemplate(template.name.clone())),
+            );
+        }
+        list = list.push(text("Recent").size(11));
+    }
+    match query {
+        Some(query) => {
+            let hits = history::search(conversations, query, MAX_HITS);
+            if hits.is_empty() {
+                list = list.push(text("No matches in saved conversations").size(12));
+            }
+            for hit in hits {
+                list = list.push(
+                    button(column![text(hit.title).size(12), text(hit.snippet).size(11)].spacing(2))
+                        .style(button::text)
+                        .width(Length::Fill)
+                        .on_press(Message::OpenConversation(hit.conversation, Some(hit.message))),
+                );
+            }
+        }
+        None => {
+            if conversations.is_empty() {
+                list = list.push(text("Conversations are saved here as you chat").size(12));
+            }
+            for conversation in conversations {
+                let is_current = current == Some(conversation.id.as_str());
+                if let Some((_, draft)) = renaming.filter(|(id, _)| *id == conversation.id) {
+                    list = list.push(
+                        row![
+                            text_input("Title", draft)
+                                .id(RENAME_INPUT.clone())
+                                .on_input(Message::RenameChanged)
+                                .on_submit(Message::SubmitRename)
+                                .size(12)
+                                .padding(4),
+                            button(text("✕").size(11))
+                                .style(button::text)
+                                .padding([2, 6])
+                                .on_press(Message::CancelRename),
+                        ]
+                        .align_y(iced::Alignment::Center),
+                    );
+                    continue;
+                }
+                list = list.push(
+                    row![
+                        button(text(conversation.title.as_str()).size(12))
+                            .style(if is_current { button::secondary } else { button::text })
+                            .width(Length::Fill)
+                            .on_press(Message::OpenConversation(conversation.id.clone(), None)),
+                        button(text("✎").size(11))
+                            .style(button::text)
+                            .padding([2, 6])
+                            .on_press(Message::StartRename(conversation.id.clone())),
+                        button(text("✕").size(11))
+                            .style(button::text)
+                            .padding([2, 6])
+                            .on_press(Message::DeleteConversation(conversation.id.clone())),
+                    ]
+                    .align_y(iced::Alignment::Center),
+                );
+            }
+        }
+    }
+
+    frame(column![header, scrollable(list).height(Length::Fill)].spacing(8))
+}
+
+fn snippet_list(snippets: &[Snippet]) -> Element<'_, Message> {
+    let mut list = Column::new().spacing(6);
+    if snippets.is_empty() {
+        list = list.push(text("Star an answer (☆ on hover) to keep it here").size(12));
+    }
+    for snippet in snippets {
+        let action = |label, message| {
+            button(text(label).size(11))
+                .style(button::text)
+                .padding([2, 6])
+                .on_press(message)
+        };
+        let mut details = column![text(snippet.preview()).size(12)].spacing(2);
+        if let Some(source) = snippet.source.as_deref().or(snippet.model.as_deref()) {
+            details = details.push(text(source).size(10));
+        }
+        list = list.push(column![
+            details,
+            row![
+                action("Insert", Message::InsertSnippet(snippet.id.clone())),
+                action("Copy", Message::CopySnippet(snippet.id.clone())),
+                horizontal_space(),
+                action("✕", Message::RemoveSnippet(snippet.id.clone())),
+            ]
+            .align_y(iced::Alignment::Center),
+        ]);
+    }
+    scrollable(list).height(Length::Fill).into()
+}
+
+fn frame<'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    container(content)
+        .padding(8)
+        .width(Length::Fixed(WIDTH))
+        .height(Length::Fill)
+        .style(container::bordered_box)
+        .into()
+}
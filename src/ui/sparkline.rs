@@ -0,0 +1,81 @@
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke};
+use iced::widget::canvas::path::Builder;
+use iced::{mouse, Element, Length, Point, Rectangle, Renderer, Theme};
+
+/// Tiny line chart of a series of values, scaled to fit its bounds
+pub struct Sparkline<'a> {
+    values: &'a [f32],
+    /// Fixed top of the scale, e.g. 100 for percentages
+    max: Option<f32>,
+}
+
+impl<'a> Sparkline<'a> {
+    pub fn new(values: &'a [f32]) -> Self {
+        Self { values, max: None }
+    }
+
+    pub fn max(mut self, max: f32) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn view<Message: 'a>(self, width: f32, height: f32) -> Element<'a, Message> {
+        canvas::Canvas::new(self)
+            .width(Length::Fixed(width))
+            .height(Length::Fixed(height))
+            .into()
+    }
+}
+
+impl<Message> canvas::Program<Message> for Sparkline<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        if self.values.len() >= 2 {
+            let max = self
+                .max
+                .unwrap_or_else(|| self.values.iter().copied().fold(0.0, f32::max))
+                .max(f32::EPSILON);
+            let step = bounds.width / (self.values.len() - 1) as f32;
+            let point = |i: usize, value: f32| {
+                let y = bounds.height - (value / max).clamp(0.0, 1.0) * (bounds.height - 1.0);
+                Point::new(i as f32 * step, y)
+            };
+
+            let mut builder = Builder::new();
+            builder.move_to(point(0, self.values[0]));
+            for (i, value) in self.values.iter().enumerate().skip(1) {
+                builder.line_to(point(i, *value));
+            }
+
+            frame.stroke(
+                &builder.build(),
+                Stroke::default()
+                    .with_color(theme.extended_palette().primary.strong.color)
+                    .with_width(1.5),
+            );
+        } else {
+            // Baseline so an empty chart still reads as a chart
+            frame.stroke(
+                &Path::line(
+                    Point::new(0.0, bounds.height - 0.5),
+                    Point::new(bounds.width, bounds.height - 0.5),
+                ),
+                Stroke::default()
+                    .with_color(theme.extended_palette().background.strong.color)
+                    .with_width(1.0),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,479 @@
+//! Spelling in the input box against a hunspell dictionary: the `.dic`
+//! word list together with the prefix and suffix rules of its `.aff` file,
+//! so "walked" and "unwalked" are known from `walk/DU`. Compound words and
+//! the rarer `.aff` options aren't supported.
+
+use iced::advanced::text::highlighter::{self, Format};
+use iced::{Font, Theme};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Where distributions and macOS put hunspell dictionaries
+const DICTIONARY_DIRS: &[&str] = &[
+    "/usr/share/hunspell",
+    "/usr/share/myspell",
+    "/usr/share/myspell/dicts",
+    "/usr/local/share/hunspell",
+    "/Library/Spelling",
+];
+
+type Flag = u32;
+
+/// How flags are written, the `.aff` file's `FLAG` option
+#[derive(Debug, Clone, Copy, Default)]
+enum FlagType {
+    /// One character each, the default
+    #[default]
+    Char,
+    /// Two characters each, `FLAG long`
+    Long,
+    /// Comma-separated numbers, `FLAG num`
+    Numeric,
+}
+
+impl FlagType {
+    fn parse(self, flags: &str) -> Vec<Flag> {
+        match self {
+            FlagType::Char => flags.chars().map(Flag::from).collect(),
+            FlagType::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars
+                    .chunks(2)
+                    .map(|pair| pair.iter().fold(0, |flag, &c| (flag << 16) | Flag::from(c)))
+                    .collect()
+            }
+            FlagType::Numeric => flags.split(',').filter_map(|n| n.trim().parse().ok()).collect(),
+        }
+    }
+}
+
+/// One character of an affix condition
+#[derive(Debug)]
+enum CharClass {
+    Any,
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Any => true,
+            CharClass::OneOf(chars) => chars.contains(&c),
+            CharClass::NoneOf(chars) => !chars.contains(&c),
+        }
+    }
+}
+
+/// What a stem has to look like for an affix to apply: `[^aeiou]y` is
+/// matched against the end of the stem for a suffix, the start for a prefix
+#[derive(Debug)]
+struct Condition(Vec<CharClass>);
+
+impl Condition {
+    fn parse(condition: &str) -> Self {
+        let mut classes = Vec::new();
+        let mut chars = condition.chars();
+        while let Some(c) = chars.next() {
+            classes.push(match c {
+                '.' => CharClass::Any,
+                '[' => {
+                    let set: Vec<char> = chars.by_ref().take_while(|&c| c != ']').collect();
+                    match set.split_first() {
+                        Some(('^', rest)) => CharClass::NoneOf(rest.to_vec()),
+                        _ => CharClass::OneOf(set),
+                    }
+                }
+                c => CharClass::OneOf(vec![c]),
+            });
+        }
+        Self(classes)
+    }
+
+    fn matches_end(&self, stem: &str) -> bool {
+        let mut chars = stem.chars().rev();
+        self.0.iter().rev().all(|class| chars.next().is_some_and(|c| class.matches(c)))
+    }
+
+    fn matches_start(&self, stem: &str) -> bool {
+        let mut chars = stem.chars();
+        self.0.iter().all(|class| chars.next().is_some_and(|c| class.matches(c)))
+    }
+}
+
+/// A `PFX` or `SFX` rule: take `strip` off the stem, then add the affix
+#[derive(Debug)]
+struct Affix {
+    flag: Flag,
+    /// Combines with affixes of the other kind
+    cross_product: bool,
+    strip: String,
+    condition: Condition,
+}
+
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    /// Lowercased words and their flags
+    words: HashMap<String, Vec<Flag>>,
+    /// Rules by the text they add
+    prefixes: HashMap<String, Vec<Affix>>,
+    suffixes: HashMap<String, Vec<Affix>>,
+    /// Words with this flag only count with an affix
+    need_affix: Option<Flag>,
+    /// Words with this flag are never right
+    forbidden: Option<Flag>,
+}
+
+impl Dictionary {
+    fn parse(dic: &str, aff: &str) -> Self {
+        let mut dictionary = Self::default();
+        let mut flag_type = FlagType::default();
+        let mut cross_products: HashMap<Flag, bool> = HashMap::new();
+
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["FLAG", kind, ..] => {
+                    flag_type = match kind {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Numeric,
+                        _ => FlagType::Char,
+                    }
+                }
+                ["NEEDAFFIX", flag, ..] => dictionary.need_affix = flag_type.parse(flag).first().copied(),
+                ["FORBIDDENWORD", flag, ..] => dictionary.forbidden = flag_type.parse(flag).first().copied(),
+                // The header of a rule group: flag, cross product, count
+                ["PFX" | "SFX", flag, cross, count] if count.parse::<usize>().is_ok() => {
+                    if let Some(&flag) = flag_type.parse(flag).first() {
+                        cross_products.insert(flag, cross == "Y");
+                    }
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, condition, ..] => {
+                    let Some(&flag) = flag_type.parse(flag).first() else {
+                        continue;
+                    };
+                    let empty = |text: &str| if text == "0" { String::new() } else { text.to_lowercase() };
+                    // Flags after the added text chain further affixes, not followed here
+                    let add = empty(add.split('/').next().unwrap_or_default());
+                    let affix = Affix {
+                        flag,
+                        cross_product: cross_products.get(&flag).copied().unwrap_or(false),
+                        strip: empty(strip),
+                        condition: Condition::parse(&condition.to_lowercase()),
+                    };
+                    let rules = if kind == "PFX" {
+                        &mut dictionary.prefixes
+                    } else {
+                        &mut dictionary.suffixes
+                    };
+                    rules.entry(add).or_default().push(affix);
+                }
+                _ => {}
+            }
+        }
+
+        // The first line is the word count; morphological fields follow a tab or space
+        for line in dic.lines().skip(1) {
+            let Some(entry) = line.split(['\t', ' ']).next().filter(|e| !e.is_empty()) else {
+                continue;
+            };
+            let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            dictionary
+                .words
+                .entry(word.to_lowercase())
+                .or_default()
+                .extend(flag_type.parse(flags));
+        }
+        dictionary
+    }
+
+    fn knows(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.listed(&word, &[]) || self.suffixed(&word, None) || self.prefixed(&word)
+    }
+
+    /// `stem` is in the word list and carries all of `flags`; with none,
+    /// it has to be a word on its own
+    fn listed(&self, stem: &str, flags: &[Flag]) -> bool {
+        self.words.get(stem).is_some_and(|own| {
+            let has = |flag: Option<Flag>| flag.is_some_and(|flag| own.contains(&flag));
+            !has(self.forbidden)
+                && if flags.is_empty() {
+                    !has(self.need_affix)
+                } else {
+                    flags.iter().all(|flag| own.contains(flag))
+                }
+        })
+    }
+
+    /// `word` is a listed stem plus a suffix, and the prefix already taken
+    /// off if there was one
+    fn suffixed(&self, word: &str, prefix: Option<&Affix>) -> bool {
+        word.char_indices().skip(1).map(|(i, _)| i).chain([word.len()]).any(|i| {
+            let (rest, added) = word.split_at(i);
+            self.suffixes.get(added).is_some_and(|rules| {
+                rules.iter().any(|rule| {
+                    if prefix.is_some() && !rule.cross_product {
+                        return false;
+                    }
+                    let stem = format!("{rest}{}", rule.strip);
+                    let flags: Vec<Flag> = std::iter::once(rule.flag).chain(prefix.map(|p| p.flag)).collect();
+                    rule.condition.matches_end(&stem) && self.listed(&stem, &flags)
+                })
+            })
+        })
+    }
+
+    /// `word` is a prefix plus a listed stem, maybe with a suffix as well
+    fn prefixed(&self, word: &str) -> bool {
+        word.char_indices().map(|(i, _)| i).any(|i| {
+            let (added, rest) = word.split_at(i);
+            self.prefixes.get(added).is_some_and(|rules| {
+                rules.iter().any(|rule| {
+                    let stem = format!("{}{rest}", rule.strip);
+                    rule.condition.matches_start(&stem)
+                        && (self.listed(&stem, &[rule.flag]) || (rule.cross_product && self.suffixed(&stem, Some(rule))))
+                })
+            })
+        })
+    }
+
+    /// Byte ranges of the words in a line that aren't in the dictionary.
+    /// Code, paths, URLs, acronyms and identifiers are left alone.
+    pub fn misspelled(&self, line: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut in_code = false;
+        for (chunk_start, chunk) in chunks(line) {
+            // Inline `code` can span several chunks
+            let ticks = chunk.matches('`').count();
+            let skip = in_code
+                || ticks > 0
+                || chunk.contains(|c: char| c.is_ascii_digit() || "/\\@_<>{}[]=#$%&*|~^".contains(c))
+                || chunk.trim_end_matches(|c: char| c.is_ascii_punctuation()).contains('.');
+            in_code ^= ticks % 2 == 1;
+            if skip {
+                continue;
+            }
+            for (start, word) in words(chunk) {
+                let acronym = word.chars().all(|c| !c.is_lowercase());
+                let identifier = word.chars().skip(1).any(char::is_uppercase);
+                if word.chars().count() > 1 && !acronym && !identifier && !self.knows(word) {
+                    ranges.push(chunk_start + start..chunk_start + start + word.len());
+                }
+            }
+        }
+        ranges
+    }
+
+    pub fn count_misspelled(&self, text: &str) -> usize {
+        text.lines().map(|line| self.misspelled(line).len()).sum()
+    }
+}
+
+/// Whitespace-separated chunks with their byte offsets
+fn chunks(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split(char::is_whitespace)
+        .filter(|chunk| !chunk.is_empty())
+        .map(move |chunk| (chunk.as_ptr() as usize - line.as_ptr() as usize, chunk))
+}
+
+/// Runs of letters and inner apostrophes with their byte offsets
+fn words(chunk: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in chunk.char_indices().chain(std::iter::once((chunk.len(), ' '))) {
+        let letter = c.is_alphabetic() || (c == '\'' && start.is_some());
+        match (start, letter) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let word = chunk[s..i].trim_end_matches('\'');
+                words.push((s, word));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// A language like "en_US" from the usual dictionary folders, or a path to a `.dic` file
+pub async fn load(language: String) -> Result<Arc<Dictionary>, String> {
+    let candidates: Vec<PathBuf> = if language.ends_with(".dic") || language.contains('/') {
+        vec![PathBuf::from(&language)]
+    } else {
+        let user_dir = directories::BaseDirs::new().map(|dirs| dirs.data_dir().join("hunspell"));
+        user_dir
+            .into_iter()
+            .chain(DICTIONARY_DIRS.iter().map(|dir| PathBuf::from(*dir)))
+            .map(|dir| dir.join(format!("{language}.dic")))
+            .collect()
+    };
+    for path in &candidates {
+        match tokio::fs::read(path).await {
+            // Older dictionaries aren't UTF-8; their ASCII words still work
+            Ok(bytes) => {
+                let aff_path = path.with_extension("aff");
+                let aff = match tokio::fs::read(&aff_path).await {
+                    Ok(aff) => aff,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                    Err(e) => return Err(format!("Failed to read {}: {e}", aff_path.display())),
+                };
+                let dictionary = Dictionary::parse(&String::from_utf8_lossy(&bytes), &String::from_utf8_lossy(&aff));
+                return Ok(Arc::new(dictionary));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        }
+    }
+    Err(format!(
+        "No hunspell dictionary for \"{language}\" (install hunspell-{} or set a path to a .dic file)",
+        language.split(['_', '-']).next().unwrap_or(&language)
+    ))
+}
+
+/// The dictionary the input is checked against, compared by identity
+#[derive(Debug, Clone)]
+pub struct Settings(pub Option<Arc<Dictionary>>);
+
+impl PartialEq for Settings {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+pub struct Misspelled;
+
+/// Colors unknown words in the input editor
+pub struct Highlighter {
+    dictionary: Option<Arc<Dictionary>>,
+    line: usize,
+}
+
+impl highlighter::Highlighter for Highlighter {
+    type Settings = Settings;
+    type Highlight = Misspelled;
+    type Iterator<'a> = std::vec::IntoIter<(Range<usize>, Misspelled)>;
+
+    fn new(settings: &Settings) -> Self {
+        Self {
+            dictionary: settings.0.clone(),
+            line: 0,
+        }
+    }
+
+    fn update(&mut self, settings: &Settings) {
+        self.dictionary = settings.0.clone();
+        self.line = 0;
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.line = self.line.min(line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        self.line += 1;
+        let ranges = self.dictionary.as_ref().map(|d| d.misspelled(line)).unwrap_or_default();
+        ranges
+            .into_iter()
+            .map(|range| (range, Misspelled))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.line
+    }
+}
+
+pub fn format(_: &Misspelled, theme: &Theme) -> Format<Font> {
+    Format {
+        color: Some(theme.extended_palette().danger.base.color),
+        font: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8
+TRY esianrtolcdugmphbyfvkwz
+
+PFX U Y 1
+PFX U   0     un         .
+
+SFX D Y 4
+SFX D   0     d          e
+SFX D   y     ied        [^aeiou]y
+SFX D   0     ed         [^ey]
+SFX D   0     ed         [aeiou]y
+
+SFX S Y 2
+SFX S   y     ies        [^aeiou]y
+SFX S   0     s          [^sxzhy]
+
+NEEDAFFIX X
+";
+
+    const DIC: &str = "5
+walk/DSU
+try/DS
+hello
+Paris
+ful/XS
+";
+
+    fn dictionary() -> Dictionary {
+        Dictionary::parse(DIC, AFF)
+    }
+
+    #[test]
+    fn suffixes_follow_their_conditions() {
+        let dictionary = dictionary();
+        for word in ["walk", "walked", "walks", "tried", "tries", "hello", "paris"] {
+            assert!(dictionary.knows(word), "{word}");
+        }
+        for word in ["tryed", "trys", "helloed", "walkies"] {
+            assert!(!dictionary.knows(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn prefixes_combine_with_suffixes() {
+        let dictionary = dictionary();
+        assert!(dictionary.knows("unwalk"));
+        assert!(dictionary.knows("Unwalked"));
+        assert!(!dictionary.knows("untried"));
+        assert!(!dictionary.knows("unhello"));
+    }
+
+    #[test]
+    fn need_affix_words_only_count_with_one() {
+        let dictionary = dictionary();
+        assert!(!dictionary.knows("ful"));
+        assert!(dictionary.knows("fuls"));
+    }
+
+    #[test]
+    fn long_and_numeric_flags() {
+        let long = Dictionary::parse("1\nwalk/AaBb\n", "FLAG long\nSFX Bb Y 1\nSFX Bb 0 ed .\n");
+        assert!(long.knows("walked"));
+        let numeric = Dictionary::parse("1\nwalk/7,12\n", "FLAG num\nSFX 12 Y 1\nSFX 12 0 ing .\n");
+        assert!(numeric.knows("walking"));
+        assert!(!numeric.knows("walked"));
+    }
+
+    #[test]
+    fn code_and_names_are_skipped() {
+        let dictionary = dictionary();
+        let misspelled = dictionary.misspelled("walkd `fo bar` src/mian.rs HTTP getUser");
+        assert_eq!(misspelled.len(), 1);
+        assert_eq!(misspelled[0], 0..5);
+    }
+}
@@ -0,0 +1,228 @@
+use super::{format_age, format_bytes, Message};
+use crate::history::{self, Conversation};
+use crate::ollama::{Model, RunningModel};
+use iced::widget::{button, column, container, horizontal_space, pick_list, row, scrollable, text, Column};
+use iced::{Element, Length};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortBy {
+    #[default]
+    Size,
+    Name,
+    LastUsed,
+}
+
+impl SortBy {
+    pub const ALL: [SortBy; 3] = [SortBy::Size, SortBy::Name, SortBy::LastUsed];
+}
+
+impl fmt::Display for SortBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SortBy::Size => "Size",
+            SortBy::Name => "Name",
+            SortBy::LastUsed => "Last used",
+        })
+    }
+}
+
+/// Installed models grouped by family, with what they take on disk
+#[derive(Default)]
+pub struct StorageView {
+    pub models: Vec<Model>,
+    pub sort: SortBy,
+    pub loading: bool,
+    pub error: Option<String>,
+    /// Asked to delete, waiting for the second click
+    pub confirm_delete: Option<String>,
+    pub deleting: Option<String>,
+}
+
+/// What the view needs to know about how models are used
+pub struct Usage<'a> {
+    pub conversations: &'a [Conversation],
+    pub running: &'a [RunningModel],
+    pub default_model: Option<&'a str>,
+}
+
+impl Usage<'_> {
+    /// Unix seconds a saved conversation last used each model
+    fn last_used(&self) -> HashMap<&str, u64> {
+        let mut last = HashMap::new();
+        for conversation in self.conversations {
+            if let Some(model) = conversation.model.as_deref() {
+                let at = last.entry(model).or_insert(0);
+                *at = conversation.updated_at.max(*at);
+            }
+        }
+        last
+    }
+
+    /// Never chatted with, not the default and not in memory right now
+    fn unused(&self, model: &str, last_used: &HashMap<&str, u64>) -> bool {
+        !last_used.contains_key(model)
+            && self.default_model != Some(model)
+            && !self.running.iter().any(|r| r.name == model)
+    }
+}
+
+impl StorageView {
+    pub fn new() -> Self {
+        Self {
+            loading: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn view<'a>(&'a self, usage: Usage<'a>) -> Element<'a, Message> {
+        let header = row![
+            text("Storage").size(20),
+            horizontal_space(),
+            text("Sort by").size(13),
+            pick_list(SortBy::ALL, Some(self.sort), Message::StorageSortPicked),
+            button("New model…")
+                .style(button::secondary)
+                .on_press(Message::OpenModelForm(None)),
+            button("↻")
+                .style(button::secondary)
+                .on_press_maybe((!self.loading).then_some(Message::RefreshStorage)),
+            button("Close")
+                .style(button::secondary)
+                .on_press(Message::CloseStorage),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let total = disk_size(&self.models);
+        let summary = match &self.error {
+            Some(e) => text(e).size(13).style(text::danger),
+            None if self.loading => text("Loading models…").size(13),
+            None => text(format!("{} models using {} on disk", self.models.len(), format_bytes(total))).size(13),
+        };
+
+        let last_used = usage.last_used();
+        let mut families: BTreeMap<&str, Vec<&Model>> = BTreeMap::new();
+        for model in &self.models {
+            let family = model
+                .details
+                .as_ref()
+                .and_then(|d| d.family.as_deref())
+                .unwrap_or("other");
+            families.entry(family).or_default().push(model);
+        }
+        let mut families: Vec<(&str, Vec<&Model>)> = families.into_iter().collect();
+        for (_, models) in &mut families {
+            match self.sort {
+                SortBy::Size => models.sort_by_key(|m| std::cmp::Reverse(m.size)),
+                SortBy::Name => models.sort_by(|a, b| a.name.cmp(&b.name)),
+                SortBy::LastUsed => models.sort_by_key(|m| std::cmp::Reverse(last_used.get(m.name.as_str()))),
+            }
+        }
+        let family_size = |models: &[&Model]| disk_size(models.iter().copied());
+        match self.sort {
+            SortBy::Size => families.sort_by_key(|(_, models)| std::cmp::Reverse(family_size(models))),
+            SortBy::Name => {}
+            SortBy::LastUsed => families.sort_by_key(|(_, models)| {
+                std::cmp::Reverse(models.iter().filter_map(|m| last_used.get(m.name.as_str())).max().copied())
+            }),
+        }
+
+        let now = history::unix_now();
+        let mut list = Column::new().spacing(4);
+        for (family, models) in families {
+            list = list.push(
+                row![
+                    text(family).size(15),
+                    text(format!("{} · {} models", format_bytes(family_size(&models)), models.len())).size(12),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center),
+            );
+            for model in models {
+                let shared = self
+                    .models
+                    .iter()
+                    .filter(|other| other.digest == model.digest && other.name != model.name)
+                    .count();
+                let used = match last_used.get(model.name.as_str()) {
+                    Some(at) => format!("used {}", format_age(Duration::from_secs(now.saturating_sub(*at)))),
+                    None if usage.unused(&model.name, &last_used) => String::from("unused"),
+                    None => String::from("no saved chats"),
+                };
+                let details = model
+                    .details
+                    .as_ref()
+                    .map(|d| {
+                        [d.parameter_size.as_deref(), d.quantization_level.as_deref()]
+                            .into_iter()
+                            .flatten()
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .unwrap_or_default();
+                let size = if shared > 0 {
+                    format!("{} (shared with {shared} other tag{})", format_bytes(model.size), if shared == 1 { "" } else { "s" })
+                } else {
+                    format_bytes(model.size)
+                };
+                list = list.push(
+                    container(
+                        row![
+                            text(&model.name).size(13).width(Length::FillPortion(3)),
+                            text(details).size(12).width(Length::FillPortion(2)),
+                            text(size).size(12).width(Length::FillPortion(2)),
+                            text(used).size(12).width(Length::FillPortion(2)),
+                            self.delete_button(&model.name),
+                        ]
+                        .spacing(8)
+                        .align_y(iced::Alignment::Center),
+                    )
+                    .padding([2, 12]),
+                );
+            }
+        }
+
+        column![header, summary, scrollable(list).height(Length::Fill)]
+            .spacing(12)
+            .padding(8)
+            .into()
+    }
+
+    fn delete_button(&self, model: &str) -> Element<'_, Message> {
+        if self.deleting.as_deref() == Some(model) {
+            return text("Deleting…").size(12).into();
+        }
+        if self.confirm_delete.as_deref() == Some(model) {
+            return row![
+                button(text("Delete").size(12))
+                    .style(button::danger)
+                    .padding([2, 8])
+                    .on_press(Message::DeleteModel(model.to_string())),
+                button(text("Keep").size(12))
+                    .style(button::secondary)
+                    .padding([2, 8])
+                    .on_press(Message::CancelDeleteModel),
+            ]
+            .spacing(4)
+            .into();
+        }
+        button(text("🗑").size(12))
+            .style(button::secondary)
+            .padding([2, 8])
+            .on_press_maybe(self.deleting.is_none().then(|| Message::ConfirmDeleteModel(model.to_string())))
+            .into()
+    }
+}
+
+/// Space taken on disk: tags of the same model share a digest and count once
+fn disk_size<'a>(models: impl IntoIterator<Item = &'a Model>) -> u64 {
+    let mut seen = HashSet::new();
+    models
+        .into_iter()
+        .filter(|m| seen.insert(m.digest.as_str()))
+        .map(|m| m.size)
+        .sum()
+}
@@ -0,0 +1,256 @@
+use super::Message;
+use crate::ollama::{ChatMessage, ChatOptions, OllamaClient};
+use crate::templates::PromptTemplate;
+use futures::StreamExt;
+use iced::widget::{
+    button, column, container, horizontal_space, pick_list, row, scrollable, text, Column,
+};
+use iced::{Element, Length, Task};
+use std::time::{Duration, Instant};
+
+/// State of the template test screen
+pub struct TemplateTestRun {
+    pub model: Option<String>,
+    pub results: Vec<TestOutcome>,
+    pub running: bool,
+    /// The run in progress, aborted when the screen is left
+    tests: Option<iced::task::Handle>,
+}
+
+/// Result of running one template test against the model
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    pub template: usize,
+    pub test: usize,
+    pub output: String,
+    /// Failed assertions, or the request error if the model couldn't be reached
+    pub result: Result<Vec<String>, String>,
+    pub duration: Duration,
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        matches!(self.result, Ok(ref failures) if failures.is_empty())
+    }
+}
+
+impl TemplateTestRun {
+    pub fn new(model: Option<String>) -> Self {
+        Self {
+            model,
+            results: Vec::new(),
+            running: false,
+            tests: None,
+        }
+    }
+
+    /// Run every test of every template sequentially, reporting each as it finishes
+    pub fn start(
+        &mut self,
+        client: OllamaClient,
+        templates: Vec<PromptTemplate>,
+        default_system: Option<String>,
+    ) -> Task<Message> {
+        let Some(model) = self.model.clone() else {
+            return Task::none();
+        };
+        self.results.clear();
+        self.running = true;
+
+        let jobs: Vec<_> = templates
+            .into_iter()
+            .enumerate()
+            .flat_map(|(t, template)| {
+                let system = template.system_prompt.clone().or(default_system.clone());
+                template
+                    .tests
+                    .iter()
+                    .enumerate()
+                    .map(|(i, test)| (t, i, template.render(&test.input), system.clone(), test.assertions.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let stream = futures::stream::iter(jobs).then(move |(template, test, prompt, system, assertions)| {
+            let client = client.clone();
+            let model = model.clone();
+            async move {
+                let mut messages = Vec::new();
+                if let Some(system) = system {
+                    messages.push(ChatMessage {
+                        role: "system".to_string(),
+                        content: system,
+                        thinking: None,
+                    });
+                }
+                messages.push(ChatMessage {
+                    role: "user".to_string(),
+                    content: prompt,
+                    thinking: None,
+                });
+
+                let started = Instant::now();
+                let response = client.chat(&model, messages, ChatOptions::default()).await;
+                let duration = started.elapsed();
+
+                match response {
+                    Ok(resp) => {
+                        let output = resp.message.map(|m| m.content).unwrap_or_default();
+                        let failures = assertions
+                            .iter()
+                            .filter_map(|a| a.check(&output).err())
+                            .collect();
+                        TestOutcome {
+                            template,
+                            test,
+                            output,
+                            result: Ok(failures),
+                            duration,
+                        }
+                    }
+                    Err(e) => TestOutcome {
+                        template,
+                        test,
+                        output: String::new(),
+                        result: Err(e.to_string()),
+                        duration,
+                    },
+                }
+            }
+        });
+
+        let (task, handle) = Task::run(stream, Message::TemplateTestFinished)
+            .chain(Task::done(Message::TemplateTestsDone))
+            .abortable();
+        // Dropped with the screen too, whichever way it's left
+        self.tests = Some(handle.abort_on_drop());
+        task
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.tests.take() {
+            handle.abort();
+        }
+        self.running = false;
+    }
+
+    pub fn finished(&mut self) {
+        self.tests = None;
+        self.running = false;
+    }
+
+    pub fn view<'a>(
+        &'a self,
+        templates: &'a [PromptTemplate],
+        available_models: &'a [String],
+    ) -> Element<'a, Message> {
+        let total: usize = templates.iter().map(|t| t.tests.len()).sum();
+        let passed = self.results.iter().filter(|r| r.passed()).count();
+
+        let header = row![
+            text("Template tests").size(20),
+            horizontal_space(),
+            pick_list(available_models, self.model.clone(), Message::TemplateTestModelSelected)
+                .placeholder("Model..."),
+            button(if self.running { "Running..." } else { "Run all" }).on_press_maybe(
+                (!self.running && self.model.is_some() && total > 0).then_some(Message::RunTemplateTests)
+            ),
+            button("Close")
+                .style(button::secondary)
+                .on_press(Message::CloseTemplateTests),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center);
+
+        let summary = if self.results.is_empty() {
+            text(format!("{total} tests in {} templates", templates.len())).size(13)
+        } else if passed == self.results.len() {
+            text(format!("{passed}/{total} passed")).size(13).style(text::success)
+        } else {
+            text(format!("{passed}/{} passed ({total} total)", self.results.len()))
+                .size(13)
+                .style(text::danger)
+        };
+
+        if total == 0 {
+            let hint = text(
+                "No template tests defined. Add them to config.toml:\n\n\
+                 [[prompt_templates]]\n\
+                 name = \"Fix grammar\"\n\
+                 template = \"Fix the grammar, reply with the text only:\\n\\n{input}\"\n\n\
+                 [[prompt_templates.tests]]\n\
+                 input = \"he go to school\"\n\
+                 assert = [{ type = \"contains\", value = \"goes\" }]\n\n\
+                 Assertion types: contains, not_contains, regex (pattern), json_valid",
+            )
+            .size(13)
+            .font(iced::Font::MONOSPACE);
+            return column![header, hint].spacing(16).padding(8).into();
+        }
+
+        let mut rows = Column::new().spacing(8);
+        for (t, template) in templates.iter().enumerate() {
+            rows = rows.push(text(&template.name).size(15));
+
+            for (i, test) in template.tests.iter().enumerate() {
+                let outcome = self.results.iter().find(|r| r.template == t && r.test == i);
+                rows = rows.push(test_row(&test.input, outcome));
+            }
+        }
+
+        column![header, summary, scrollable(rows).height(Length::Fill)]
+            .spacing(12)
+            .padding(8)
+            .into()
+    }
+}
+
+fn test_row<'a>(input: &'a str, outcome: Option<&'a TestOutcome>) -> Element<'a, Message> {
+    let input_preview: String = input.lines().next().unwrap_or_default().chars().take(60).collect();
+
+    let (status, details): (Element<Message>, Option<String>) = match outcome {
+        None => (text("pending").size(12).into(), None),
+        Some(outcome) => match outcome.result {
+            Ok(ref failures) if failures.is_empty() => (
+                text(format!("PASS {:.1}s", outcome.duration.as_secs_f32()))
+                    .size(12)
+                    .style(text::success)
+                    .into(),
+                None,
+            ),
+            Ok(ref failures) => (
+                text("FAIL").size(12).style(text::danger).into(),
+                Some(format!(
+                    "{}\nOutput: {}",
+                    failures.join("\n"),
+                    outcome.output.chars().take(300).collect::<String>()
+                )),
+            ),
+            Err(ref e) => (
+                text("ERROR").size(12).style(text::danger).into(),
+                Some(e.clone()),
+            ),
+        },
+    };
+
+    let mut content = column![row![
+        text(input_preview).size(13).width(Length::Fill),
+        status
+    ]
+    .spacing(8)]
+    .spacing(4);
+
+    if let Some(details) = details {
+        content = content.push(
+            text(details)
+                .size(12)
+                .color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+        );
+    }
+
+    container(content)
+        .padding(8)
+        .width(Length::Fill)
+        .style(container::bordered_box)
+        .into()
+}
@@ -0,0 +1,451 @@
+//! `App::update` driven without a window against the mock Ollama server:
+//! the tasks it returns run here, and every message they produce is fed
+//! back in, the way the iced runtime would.
+
+use super::*;
+use futures::stream::{BoxStream, SelectAll, StreamExt};
+use iced_runtime::Action;
+use locallm_core::mock::{MockOllama, Reply, MODEL};
+use std::sync::Once;
+
+/// Long enough for a slow machine, short enough to fail a hung test quickly
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Config, conversations and caches go to a temporary directory rather
+/// than the user's
+fn isolate() {
+    static ISOLATE: Once = Once::new();
+    ISOLATE.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("locallm-tests-{}", std::process::id()));
+        std::env::set_var("XDG_CONFIG_HOME", dir.join("config"));
+        std::env::set_var("XDG_DATA_HOME", dir.join("data"));
+        std::env::set_var("XDG_CACHE_HOME", dir.join("cache"));
+    });
+}
+
+struct Harness {
+    app: App,
+    server: MockOllama,
+    running: SelectAll<BoxStream<'static, Action<Message>>>,
+}
+
+impl Harness {
+    /// The app pointed at a fresh mock server, once it has connected and
+    /// picked the model
+    async fn start() -> Self {
+        isolate();
+        let server = MockOllama::start().await;
+        let config = Config {
+            ollama_url: server.url().to_string(),
+            default_model: Some(MODEL.to_string()),
+            ..Config::default()
+        };
+        let (app, task) = App::new(config, false, None);
+        let mut harness = Self {
+            app,
+            server,
+            running: SelectAll::new(),
+        };
+        harness.spawn(task);
+        harness
+            .until("the model list", |app| app.selected_model.is_some() && !app.refreshing_models)
+            .await;
+        harness
+    }
+
+    fn spawn(&mut self, task: Task<Message>) {
+        if let Some(stream) = iced_runtime::task::into_stream(task) {
+            self.running.push(stream);
+        }
+    }
+
+    fn send(&mut self, message: Message) {
+        let task = self.app.update(message);
+        self.spawn(task);
+    }
+
+    /// Ask `prompt` the way the Send button does
+    fn submit(&mut self, prompt: &str) {
+        self.app.input_content = text_editor::Content::with_text(prompt);
+        self.send(Message::Submit);
+    }
+
+    /// Run tasks until `done` holds for the app
+    async fn until(&mut self, what: &str, done: impl Fn(&App) -> bool) {
+        let deadline = tokio::time::Instant::now() + TIMEOUT;
+        while !done(&self.app) {
+            match tokio::time::timeout_at(deadline, self.running.next()).await {
+                Ok(Some(Action::Output(message))) => self.send(message),
+                // Windows, the clipboard and widget operations need the real runtime
+                Ok(Some(_)) => {}
+                Ok(None) => panic!("nothing left running while waiting for {what}"),
+                Err(_) => panic!("timed out waiting for {what}"),
+            }
+        }
+    }
+
+    /// Run whatever is still going for `period`, to see that nothing more happens
+    async fn settle(&mut self, period: Duration) {
+        let deadline = tokio::time::Instant::now() + period;
+        while let Ok(Some(action)) = tokio::time::timeout_at(deadline, self.running.next()).await {
+            if let Action::Output(message) = action {
+                self.send(message);
+            }
+        }
+    }
+
+    async fn answered(&mut self) {
+        self.until("the answer", |app| app.status != Status::Generating).await;
+    }
+
+    /// Streamed chat requests, leaving out the one-off title request
+    fn chats(&self) -> Vec<locallm_core::mock::Request> {
+        let mut sent = self.server.requests_to("/api/chat");
+        sent.retain(|r| r.body["stream"] == true);
+        sent
+    }
+
+    fn roles(&self) -> Vec<&str> {
+        self.app.chat_history.iter().map(|e| e.role.as_str()).collect()
+    }
+}
+
+fn asked(request: &locallm_core::mock::Request) -> Vec<&str> {
+    request.body["messages"]
+        .as_array()
+        .map(|messages| messages.iter().filter_map(|m| m["content"].as_str()).collect())
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn connects_and_picks_the_model() {
+    let harness = Harness::start().await;
+    assert_eq!(harness.app.status, Status::Connected);
+    assert_eq!(harness.app.available_models, [MODEL]);
+    assert_eq!(harness.app.selected_model.as_deref(), Some(MODEL));
+}
+
+#[tokio::test]
+async fn streams_an_answer() {
+    let mut harness = Harness::start().await;
+    harness
+        .server
+        .route("/api/chat", Reply::chat(MODEL, &["Hello", " from", " the mock"]).paced(Duration::from_millis(20)));
+
+    harness.submit("Hi there");
+    assert_eq!(harness.app.status, Status::Generating);
+    harness.until("the first chunk", |app| !app.streaming.is_empty()).await;
+    assert_eq!(harness.app.status, Status::Generating);
+    assert!("Hello from the mock".starts_with(harness.app.streaming.as_str()));
+
+    harness.answered().await;
+    assert_eq!(harness.roles(), ["user", "assistant"]);
+    assert_eq!(harness.app.chat_history[1].content, "Hello from the mock");
+    assert_eq!(harness.app.chat_history[1].model.as_deref(), Some(MODEL));
+    assert!(harness.app.last_error.is_none());
+
+    let sent = harness.chats();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].body["model"], MODEL);
+    assert_eq!(asked(&sent[0]).last(), Some(&"Hi there"));
+}
+
+#[tokio::test]
+async fn follow_up_sends_the_history() {
+    let mut harness = Harness::start().await;
+    harness.submit("First");
+    harness.answered().await;
+    harness.submit("Second");
+    harness.answered().await;
+
+    assert_eq!(harness.roles(), ["user", "assistant", "user", "assistant"]);
+    let sent = harness.chats();
+    assert_eq!(sent.len(), 2);
+    let second = asked(&sent[1]);
+    assert_eq!(&second[second.len() - 3..], ["First", "Hello from the mock", "Second"]);
+}
+
+#[tokio::test]
+async fn cancel_stops_the_stream() {
+    let mut harness = Harness::start().await;
+    let chunks = ["one ", "two ", "three ", "four ", "five "];
+    harness
+        .server
+        .route("/api/chat", Reply::chat(MODEL, &chunks).paced(Duration::from_millis(100)));
+
+    harness.submit("Count");
+    harness.until("the first chunk", |app| !app.streaming.is_empty()).await;
+    harness.send(Message::CancelGeneration);
+    assert_eq!(harness.app.status, Status::Connected);
+    assert!(harness.app.streaming.is_empty());
+
+    // The rest of the stream never shows up
+    harness.settle(Duration::from_millis(600)).await;
+    assert_eq!(harness.app.status, Status::Connected);
+    assert_eq!(harness.roles(), ["user"]);
+    assert!(harness.app.streaming.is_empty());
+}
+
+#[tokio::test]
+async fn double_slash_sends_a_slash() {
+    let mut harness = Harness::start().await;
+    harness.submit("//etc/hosts is empty, why?");
+    harness.answered().await;
+    assert_eq!(harness.roles(), ["user", "assistant"]);
+    assert_eq!(harness.app.chat_history[0].content, "/etc/hosts is empty, why?");
+    assert_eq!(asked(&harness.chats()[0]).last(), Some(&"/etc/hosts is empty, why?"));
+}
+
+#[tokio::test]
+async fn model_picker_keeps_the_draft() {
+    let mut harness = Harness::start().await;
+    harness.app.input_content = text_editor::Content::with_text("Half a question");
+    harness.send(Message::FocusModelPicker);
+    assert_eq!(harness.app.input_content.text().trim_end(), "/model");
+
+    harness.send(Message::ModelCommandPicked(MODEL.to_string()));
+    assert_eq!(harness.app.input_content.text().trim_end(), "Half a question");
+    assert_eq!(harness.app.selected_model.as_deref(), Some(MODEL));
+}
+
+#[tokio::test]
+async fn notes_of_a_new_chat_are_kept() {
+    let mut harness = Harness::start().await;
+    harness.send(Message::ToggleScratchpad);
+    let notes = std::sync::Arc::new(String::from("Ask about the release date"));
+    harness.send(Message::ScratchpadAction(text_editor::Action::Edit(text_editor::Edit::Paste(notes))));
+    harness.send(Message::ToggleScratchpad);
+    harness.settle(Duration::from_millis(200)).await;
+    assert_eq!(history::load_scratchpad().await.as_deref(), Some("Ask about the release date"));
+
+    let mut restarted = Harness::start().await;
+    restarted.until("the notes", |app| !app.scratchpad_text().is_empty()).await;
+    assert_eq!(restarted.app.scratchpad_text(), "Ask about the release date");
+    assert!(restarted.app.show_scratchpad);
+
+    // Filed with the conversation once there is one
+    harness.submit("Hi");
+    harness.answered().await;
+    harness.settle(Duration::from_millis(200)).await;
+    assert_eq!(history::load_scratchpad().await, None);
+}
+
+#[tokio::test]
+async fn saving_a_draft_keeps_the_sidebar_order() {
+    let mut harness = Harness::start().await;
+    harness.submit("First chat");
+    harness.answered().await;
+    let first = harness.app.conversation_id.clone().unwrap();
+    harness.send(Message::NewConversation);
+    harness.submit("Second chat");
+    harness.answered().await;
+    let second = harness.app.conversation_id.clone().unwrap();
+
+    harness.send(Message::OpenConversation(first.clone(), None));
+    harness.app.input_content = text_editor::Content::with_text("Unsent");
+    harness.send(Message::SaveDraft);
+    let position = |id: &str| harness.app.conversations.iter().position(|c| c.id == id).unwrap();
+    assert!(position(&second) < position(&first));
+    assert_eq!(harness.app.conversations[position(&first)].draft, "Unsent");
+}
+
+#[tokio::test]
+async fn edited_message_moves_with_the_edit() {
+    let mut harness = Harness::start().await;
+    harness.submit("First");
+    harness.answered().await;
+    harness.submit("Second");
+    harness.answered().await;
+
+    harness.send(Message::EditMessage(2));
+    harness.send(Message::MoveMessage(2, -2));
+    assert_eq!(harness.app.editing, Some(0));
+    assert_eq!(harness.app.chat_history[0].content, "Second");
+    harness.send(Message::MoveMessage(2, -1));
+    assert_eq!(harness.app.editing, Some(0));
+}
+
+#[tokio::test]
+async fn prompt_sent_while_generating_is_queued() {
+    let mut harness = Harness::start().await;
+    harness
+        .server
+        .route("/api/chat", Reply::chat(MODEL, &["a", "b", "c"]).paced(Duration::from_millis(50)));
+
+    harness.submit("First");
+    harness.submit("Second");
+    assert_eq!(harness.app.queued, ["Second"]);
+
+    harness
+        .until("the queued prompt's answer", |app| {
+            app.chat_history.len() == 4 && app.status != Status::Generating
+        })
+        .await;
+    assert!(harness.app.queued.is_empty());
+    let sent = harness.chats();
+    assert_eq!(asked(&sent[1]).last(), Some(&"Second"));
+}
+
+#[tokio::test]
+async fn failed_request_shows_a_recovery() {
+    let mut harness = Harness::start().await;
+    harness.server.route(
+        "/api/chat",
+        Reply::error(500, "model requires more system memory (48.0 GiB) than is available (31.2 GiB)"),
+    );
+
+    harness.submit("Hi");
+    harness.until("the error", |app| app.last_error.is_some()).await;
+    let report = harness.app.last_error.as_ref().unwrap();
+    assert_eq!(report.kind, "OutOfMemory");
+    assert!(report.hint.is_some());
+    assert_eq!(harness.app.status, Status::Connected);
+    assert_eq!(harness.roles(), ["user"]);
+
+    // Fixed on the server side, Retry answers the same question
+    harness.server.route("/api/chat", Reply::chat(MODEL, &["Fits now"]));
+    harness.send(Message::Retry);
+    harness.answered().await;
+    assert_eq!(harness.roles(), ["user", "assistant"]);
+    assert_eq!(harness.app.chat_history[1].content, "Fits now");
+    assert!(harness.app.last_error.is_none());
+}
+
+#[tokio::test]
+async fn missing_model_refreshes_the_list() {
+    let mut harness = Harness::start().await;
+    harness
+        .server
+        .route("/api/chat", Reply::error(404, "model \"mock:latest\" not found, try pulling it first"));
+    harness.server.route("/api/tags", Reply::tags(&["other:latest"]));
+
+    harness.submit("Hi");
+    harness.until("the error", |app| app.last_error.is_some()).await;
+    assert_eq!(harness.app.last_error.as_ref().unwrap().kind, "ModelNotFound");
+    harness
+        .until("the new model list", |app| app.available_models == ["other:latest"])
+        .await;
+}
+
+#[tokio::test]
+async fn failed_model_details_show_the_error() {
+    let mut harness = Harness::start().await;
+    harness.server.route("/api/show", Reply::error(500, "failed to read the manifest"));
+    harness.send(Message::ModelSelected(MODEL.to_string()));
+    harness.until("the error", |app| app.model_info_error.is_some()).await;
+    assert!(harness.app.model_info.is_none());
+    assert!(harness.app.model_info_error.as_ref().unwrap().contains("manifest"));
+}
+
+#[tokio::test]
+async fn closing_the_template_tests_stops_them() {
+    let mut harness = Harness::start().await;
+    let test = crate::templates::TemplateTest {
+        input: String::from("Hi"),
+        assertions: Vec::new(),
+    };
+    harness.app.config.prompt_templates = vec![crate::templates::PromptTemplate {
+        name: String::from("Greeting"),
+        template: String::from("{input}"),
+        system_prompt: None,
+        tests: vec![test; 3],
+    }];
+    harness
+        .server
+        .route("/api/chat", Reply::chat(MODEL, &["Hel", "lo"]).paced(Duration::from_millis(100)));
+
+    harness.send(Message::OpenTemplateTests);
+    harness.send(Message::RunTemplateTests);
+    harness.settle(Duration::from_millis(50)).await;
+    harness.send(Message::CloseTemplateTests);
+    harness.settle(Duration::from_millis(500)).await;
+    assert_eq!(harness.server.requests_to("/api/chat").len(), 1);
+}
+
+#[tokio::test]
+async fn locking_stops_the_answer() {
+    let mut harness = Harness::start().await;
+    let chunks = ["one ", "two ", "three "];
+    harness
+        .server
+        .route("/api/chat", Reply::chat(MODEL, &chunks).paced(Duration::from_millis(100)));
+
+    harness.submit("Count");
+    harness.until("the first chunk", |app| !app.streaming.is_empty()).await;
+    harness.send(Message::ToggleLock);
+    harness.settle(Duration::from_millis(500)).await;
+    assert!(harness.app.locked);
+    assert_eq!(harness.app.status, Status::Connected);
+    assert_eq!(harness.roles(), ["user"]);
+}
+
+#[tokio::test]
+async fn changing_a_summarized_message_drops_the_summary() {
+    let mut harness = Harness::start().await;
+    for prompt in ["One", "Two"] {
+        harness.submit(prompt);
+        harness.answered().await;
+    }
+    let summarized = || Summary {
+        covers: 2,
+        text: String::from("They said one"),
+    };
+
+    harness.app.summary = Some(summarized());
+    harness.send(Message::DeleteMessage(3));
+    assert!(harness.app.summary.is_some());
+
+    harness.send(Message::SetExcluded(0, true));
+    assert!(harness.app.summary.is_none());
+
+    harness.app.summary = Some(summarized());
+    harness.send(Message::DeleteMessage(1));
+    assert!(harness.app.summary.is_none());
+}
+
+#[tokio::test]
+async fn think_toggle_saves_only_that_setting() {
+    let mut harness = Harness::start().await;
+    harness.app.config.system_prompt = Some(String::from("Only for this session"));
+    harness.send(Message::ThinkToggled(true));
+
+    let saved = std::fs::read_to_string(Config::config_path().unwrap()).unwrap();
+    let saved: Config = toml::from_str(&saved).unwrap();
+    assert!(saved.think_models.iter().any(|m| m == MODEL));
+    assert!(saved.system_prompt.is_none());
+}
+
+#[tokio::test]
+async fn no_reconnecting_while_minimized() {
+    use iced::advanced::subscription::into_recipes;
+
+    let mut harness = Harness::start().await;
+    harness.server.route("/api/tags", Reply::error(500, "unavailable"));
+    harness.send(Message::CheckConnection);
+    harness.until("the lost connection", |app| app.status == Status::Disconnected).await;
+    assert_eq!(into_recipes(harness.app.reconnect_subscription()).len(), 1);
+
+    harness.send(Message::WindowMinimized(Some(true)));
+    assert!(into_recipes(harness.app.reconnect_subscription()).is_empty());
+}
+
+#[tokio::test]
+async fn only_the_latest_question_gets_its_excerpts() {
+    let mut harness = Harness::start().await;
+    for prompt in ["First", "Second"] {
+        harness.submit(prompt);
+        harness.answered().await;
+    }
+    let source = |text: &str| Source {
+        path: String::from("/docs/notes.md"),
+        text: text.to_string(),
+    };
+    harness.app.chat_history[0].sources = vec![source("old excerpt")];
+    harness.app.chat_history[2].sources = vec![source("new excerpt")];
+
+    let messages = harness.app.build_messages();
+    let user: Vec<&str> = messages.iter().filter(|m| m.role == "user").map(|m| m.content.as_str()).collect();
+    assert!(!user[0].contains("old excerpt"));
+    assert!(user[0].contains("[1] notes.md"));
+    assert!(user[1].contains("new excerpt"));
+}
@@ -0,0 +1,153 @@
+use iced::widget::button;
+use iced::{Color, Theme};
+use std::fmt;
+use std::sync::LazyLock;
+
+/// `theme` value that switches between light and dark with the desktop
+pub const SYSTEM: &str = "system";
+
+/// Pure black/white palette with saturated accents for low-vision users
+static HIGH_CONTRAST: LazyLock<Theme> = LazyLock::new(|| {
+    Theme::custom(
+        String::from("High Contrast"),
+        iced::theme::Palette {
+            background: Color::BLACK,
+            text: Color::WHITE,
+            primary: Color::from_rgb(1.0, 0.85, 0.0),
+            success: Color::from_rgb(0.0, 1.0, 0.4),
+            danger: Color::from_rgb(1.0, 0.3, 0.3),
+        },
+    )
+});
+
+/// An entry in the settings theme picker
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThemeChoice {
+    System,
+    Builtin(Theme),
+}
+
+impl ThemeChoice {
+    pub fn all() -> Vec<ThemeChoice> {
+        std::iter::once(ThemeChoice::System)
+            .chain(Theme::ALL.iter().cloned().map(ThemeChoice::Builtin))
+            .collect()
+    }
+
+    /// Parse the config's `theme`, unset or unknown names give the default
+    pub fn from_config(theme: Option<&str>) -> ThemeChoice {
+        let Some(name) = theme else {
+            return ThemeChoice::default();
+        };
+        if name.eq_ignore_ascii_case(SYSTEM) {
+            return ThemeChoice::System;
+        }
+        match Theme::ALL.iter().find(|t| normalize(&t.to_string()) == normalize(name)) {
+            Some(theme) => ThemeChoice::Builtin(theme.clone()),
+            None => ThemeChoice::default(),
+        }
+    }
+
+    /// Log an unknown `theme` once, when the config is loaded, rather than
+    /// on every frame that resolves it
+    pub fn warn_if_unknown(theme: Option<&str>) {
+        if let Some(name) = theme {
+            let known = name.eq_ignore_ascii_case(SYSTEM)
+                || Theme::ALL.iter().any(|t| normalize(&t.to_string()) == normalize(name));
+            if !known {
+                tracing::warn!("Unknown theme {name:?}, using the default");
+            }
+        }
+    }
+
+    /// Value written to the config, None for the default
+    pub fn to_config(&self) -> Option<String> {
+        match self {
+            choice if *choice == ThemeChoice::default() => None,
+            ThemeChoice::System => Some(SYSTEM.to_string()),
+            ThemeChoice::Builtin(theme) => Some(theme.to_string()),
+        }
+    }
+
+    pub fn resolve(&self, system_dark: bool) -> Theme {
+        match self {
+            ThemeChoice::System if system_dark => Theme::TokyoNightStorm,
+            ThemeChoice::System => Theme::TokyoNightLight,
+            ThemeChoice::Builtin(theme) => theme.clone(),
+        }
+    }
+}
+
+impl Default for ThemeChoice {
+    fn default() -> Self {
+        ThemeChoice::Builtin(Theme::TokyoNightStorm)
+    }
+}
+
+impl fmt::Display for ThemeChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeChoice::System => write!(f, "Follow system (light/dark)"),
+            ThemeChoice::Builtin(theme) => write!(f, "{theme}"),
+        }
+    }
+}
+
+/// "Tokyo Night Storm", "tokyo-night-storm" and "TokyoNightStorm" all match
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Theme for the current config; high contrast overrides the chosen theme
+pub fn resolve(theme: Option<&str>, high_contrast: bool, system_dark: bool) -> Theme {
+    if high_contrast {
+        HIGH_CONTRAST.clone()
+    } else {
+        ThemeChoice::from_config(theme).resolve(system_dark)
+    }
+}
+
+/// Whether the desktop prefers a dark color scheme. Blocks on D-Bus on Linux.
+pub fn detect_system_dark() -> bool {
+    !matches!(dark_light::detect(), dark_light::Mode::Light)
+}
+
+/// Parse "#rrggbb" or "rrggbb"
+pub fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Bubble button style: `base` with the configured accent as background
+pub fn bubble_style(
+    accent: Option<Color>,
+    base: fn(&Theme, button::Status) -> button::Style,
+) -> impl Fn(&Theme, button::Status) -> button::Style {
+    move |theme, status| {
+        let style = base(theme, status);
+        let Some(accent) = accent else {
+            return style;
+        };
+        let background = match status {
+            button::Status::Hovered | button::Status::Pressed => Color {
+                a: 0.85,
+                ..accent
+            },
+            _ => accent,
+        };
+        // Dark text on light accents
+        let luminance = 0.299 * accent.r + 0.587 * accent.g + 0.114 * accent.b;
+        button::Style {
+            background: Some(background.into()),
+            text_color: if luminance > 0.6 { Color::BLACK } else { Color::WHITE },
+            ..style
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use super::sparkline::Sparkline;
+use super::Message;
+use iced::widget::{row, text};
+use iced::Element;
+use std::time::Duration;
+
+/// Number of buckets the response duration is split into for the sparkline
+const BUCKETS: usize = 48;
+
+/// Arrival time of every streamed chunk of a response, relative to the request
+#[derive(Debug, Clone, Default)]
+pub struct TokenTiming {
+    offsets: Vec<Duration>,
+    /// Chunks per bucket between the first and last chunk, precomputed for drawing
+    throughput: Vec<f32>,
+}
+
+impl TokenTiming {
+    pub fn new(offsets: Vec<Duration>) -> Self {
+        let mut timing = Self {
+            offsets,
+            throughput: Vec::new(),
+        };
+        timing.throughput = timing.buckets();
+        timing
+    }
+
+    pub fn offsets(&self) -> &[Duration] {
+        &self.offsets
+    }
+
+    /// Time from sending the request until the first chunk arrived
+    pub fn first_token(&self) -> Option<Duration> {
+        self.offsets.first().copied()
+    }
+
+    pub fn total(&self) -> Option<Duration> {
+        self.offsets.last().copied()
+    }
+
+    /// Longest gap between two consecutive chunks
+    pub fn max_stall(&self) -> Duration {
+        self.offsets
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn buckets(&self) -> Vec<f32> {
+        let (Some(first), Some(last)) = (self.first_token(), self.total()) else {
+            return Vec::new();
+        };
+        let span = (last - first).as_secs_f32();
+        if span <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut buckets = vec![0.0; BUCKETS];
+        for offset in &self.offsets {
+            let position = (*offset - first).as_secs_f32() / span;
+            let idx = ((position * BUCKETS as f32) as usize).min(BUCKETS - 1);
+            buckets[idx] += 1.0;
+        }
+        buckets
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let summary = format!(
+            "first token {:.1}s · {} chunks in {:.1}s · max stall {:.2}s",
+            self.first_token().unwrap_or_default().as_secs_f32(),
+            self.offsets.len(),
+            self.total().unwrap_or_default().as_secs_f32(),
+            self.max_stall().as_secs_f32(),
+        );
+
+        row![
+            Sparkline::new(&self.throughput).view(120.0, 16.0),
+            text(summary)
+                .size(11)
+                .color(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+        ]
+        .spacing(8)
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+}
@@ -0,0 +1,168 @@
+use super::Message;
+use iced::widget::{button, column, container, horizontal_space, row, text, Column};
+use iced::{Color, Element, Length, Theme};
+use std::time::{Duration, Instant};
+
+/// More than this and the oldest is dropped
+const MAX_TOASTS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Success,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Errors stay up longer, there's usually something to read
+    fn lifetime(self) -> Duration {
+        match self {
+            Severity::Success | Severity::Info => Duration::from_secs(3),
+            Severity::Warning => Duration::from_secs(6),
+            Severity::Error => Duration::from_secs(10),
+        }
+    }
+
+    fn color(self, theme: &Theme) -> Color {
+        let palette = theme.extended_palette();
+        match self {
+            Severity::Success => palette.success.base.color,
+            Severity::Info => palette.primary.base.color,
+            Severity::Warning => Color::from_rgb(0.9, 0.6, 0.1),
+            Severity::Error => palette.danger.base.color,
+        }
+    }
+}
+
+/// A notification in the corner of the chat that goes away by itself
+#[derive(Debug, Clone)]
+pub struct Toast {
+    id: u64,
+    severity: Severity,
+    message: String,
+    /// Longer text shown when the toast is expanded, e.g. the full error
+    detail: Option<String>,
+    expanded: bool,
+    shown_at: Instant,
+}
+
+/// Stack of toasts, newest at the bottom
+#[derive(Debug, Default)]
+pub struct Toasts {
+    items: Vec<Toast>,
+    next_id: u64,
+}
+
+impl Toasts {
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) {
+        self.push_with_detail(severity, message, None);
+    }
+
+    pub fn push_with_detail(&mut self, severity: Severity, message: impl Into<String>, detail: Option<String>) {
+        let message = message.into();
+        // The same notice again just restarts its timer
+        if let Some(toast) = self
+            .items
+            .iter_mut()
+            .find(|t| t.message == message && t.severity == severity)
+        {
+            toast.shown_at = Instant::now();
+            toast.detail = detail;
+            return;
+        }
+        self.items.push(Toast {
+            id: self.next_id,
+            severity,
+            message,
+            detail,
+            expanded: false,
+            shown_at: Instant::now(),
+        });
+        self.next_id += 1;
+        if self.items.len() > MAX_TOASTS {
+            self.items.remove(0);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.items.retain(|t| t.id != id);
+    }
+
+    pub fn toggle_detail(&mut self, id: u64) {
+        if let Some(toast) = self.items.iter_mut().find(|t| t.id == id) {
+            toast.expanded = !toast.expanded;
+        }
+    }
+
+    /// Drop expired toasts; an expanded one stays until it's closed
+    pub fn expire(&mut self) {
+        self.items
+            .retain(|t| t.expanded || t.shown_at.elapsed() < t.severity.lifetime());
+    }
+
+    /// The stack, bottom right, above the status bar
+    pub fn view(&self) -> Element<'_, Message> {
+        let toasts = self.items.iter().map(toast_view);
+        container(Column::with_children(toasts).spacing(6).width(Length::Fixed(340.0)))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_right(Length::Fill)
+            .align_bottom(Length::Fill)
+            .padding(iced::Padding {
+                top: 16.0,
+                right: 24.0,
+                bottom: 64.0,
+                left: 16.0,
+            })
+            .into()
+    }
+}
+
+fn toast_view(toast: &Toast) -> Element<'_, Message> {
+    let severity = toast.severity;
+    let mut header = row![text(&toast.message).size(13).width(Length::Fill)]
+        .spacing(6)
+        .align_y(iced::Alignment::Center);
+    if toast.detail.is_some() {
+        header = header.push(
+            button(text(if toast.expanded { "Less" } else { "Details" }).size(11))
+                .style(button::text)
+                .padding([0, 4])
+                .on_press(Message::ToggleToastDetail(toast.id)),
+        );
+    }
+    header = header.push(
+        button(text("✕").size(11))
+            .style(button::text)
+            .padding([0, 4])
+            .on_press(Message::DismissToast(toast.id)),
+    );
+
+    let mut body = column![header].spacing(4);
+    if let Some(detail) = toast.detail.as_ref().filter(|_| toast.expanded) {
+        body = body.push(row![text(detail).size(11), horizontal_space()]);
+    }
+
+    container(body)
+        .padding([6, 10])
+        .width(Length::Fill)
+        .style(move |theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                background: Some(palette.background.weak.color.into()),
+                text_color: Some(palette.background.weak.text),
+                border: iced::Border {
+                    color: severity.color(theme),
+                    width: 2.0,
+                    radius: 6.0.into(),
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+}
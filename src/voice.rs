@@ -0,0 +1,213 @@
+//! Dictation: the microphone is captured in-process, written to a 16 kHz
+//! mono WAV file, then transcribed locally by a whisper.cpp binary
+//! (`whisper_binary` with the ggml model in `whisper_model`).
+//!
+//! Capture needs the `dictation` cargo feature, which on Linux builds
+//! against the ALSA headers (`libasound2-dev`/`alsa-lib-devel`). Without it
+//! there's no mic button.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+
+#[cfg(feature = "dictation")]
+pub use capture::Recording;
+
+/// Stand-in for builds without the `dictation` feature, never started
+#[cfg(not(feature = "dictation"))]
+pub struct Recording;
+
+#[cfg(not(feature = "dictation"))]
+impl Recording {
+    pub fn start() -> Result<Self, String> {
+        Err("Built without the dictation feature".to_string())
+    }
+
+    pub async fn finish(self) -> Result<PathBuf, String> {
+        Err("Built without the dictation feature".to_string())
+    }
+}
+
+#[cfg(feature = "dictation")]
+mod capture {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+
+    /// What whisper.cpp expects
+    const SAMPLE_RATE: u32 = 16_000;
+
+    /// Mono samples as the microphone delivered them
+    struct Captured {
+        samples: Vec<f32>,
+        rate: u32,
+    }
+
+    /// A recording in progress. The audio stream lives on its own thread,
+    /// it isn't `Send` on every platform; dropping this stops it.
+    pub struct Recording {
+        stop: mpsc::Sender<()>,
+        captured: tokio::sync::oneshot::Receiver<Captured>,
+    }
+
+    impl Recording {
+        /// Start capturing from the default input device
+        pub fn start() -> Result<Self, String> {
+            let (stop, stopped) = mpsc::channel::<()>();
+            let (started_tx, started) = mpsc::channel();
+            let (done, captured) = tokio::sync::oneshot::channel();
+            std::thread::spawn(move || {
+                let samples = Arc::new(Mutex::new(Vec::new()));
+                let (stream, rate) = match open(samples.clone()) {
+                    Ok(opened) => opened,
+                    Err(e) => {
+                        let _ = started_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = started_tx.send(Ok(()));
+                // Until `finish`, or the recording is dropped
+                let _ = stopped.recv();
+                drop(stream);
+                let samples = std::mem::take(&mut *samples.lock().unwrap());
+                let _ = done.send(Captured { samples, rate });
+            });
+            started
+                .recv()
+                .map_err(|_| "The recording thread stopped".to_string())??;
+            Ok(Self { stop, captured })
+        }
+
+        /// Stop recording and write what was heard to a fresh WAV file
+        pub async fn finish(self) -> Result<PathBuf, String> {
+            let _ = self.stop.send(());
+            let Captured { samples, rate } = self
+                .captured
+                .await
+                .map_err(|_| "The recording thread stopped".to_string())?;
+            if samples.is_empty() {
+                return Err("No audio was captured".to_string());
+            }
+            let path = wav_path();
+            let file = path.clone();
+            tokio::task::spawn_blocking(move || write_wav(&file, &resample(&samples, rate)))
+                .await
+                .map_err(|e| e.to_string())??;
+            Ok(path)
+        }
+    }
+
+    fn open(samples: Arc<Mutex<Vec<f32>>>) -> Result<(cpal::Stream, u32), String> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or("No microphone found")?;
+        let supported = device
+            .default_input_config()
+            .map_err(|e| format!("Microphone unavailable: {e}"))?;
+        let config = supported.config();
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => build::<f32>(&device, &config, samples),
+            cpal::SampleFormat::I16 => build::<i16>(&device, &config, samples),
+            cpal::SampleFormat::U16 => build::<u16>(&device, &config, samples),
+            cpal::SampleFormat::I32 => build::<i32>(&device, &config, samples),
+            format => return Err(format!("Unsupported microphone sample format {format}")),
+        }
+        .map_err(|e| format!("Failed to open the microphone: {e}"))?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start the microphone: {e}"))?;
+        Ok((stream, config.sample_rate.0))
+    }
+
+    fn build<T>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        samples: Arc<Mutex<Vec<f32>>>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError>
+    where
+        T: cpal::SizedSample,
+        f32: cpal::FromSample<T>,
+    {
+        let channels = usize::from(config.channels.max(1));
+        device.build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                // Mixed down to mono
+                let mono = data
+                    .chunks(channels)
+                    .map(|frame| frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() / frame.len() as f32);
+                samples.lock().unwrap().extend(mono);
+            },
+            |e| tracing::warn!("Microphone: {e}"),
+            None,
+        )
+    }
+
+    /// Linear resampling to 16 kHz, good enough for speech
+    fn resample(samples: &[f32], rate: u32) -> Vec<i16> {
+        let step = f64::from(rate) / f64::from(SAMPLE_RATE);
+        let len = (samples.len() as f64 / step) as usize;
+        (0..len)
+            .map(|i| {
+                let pos = i as f64 * step;
+                let at = pos as usize;
+                let next = samples.get(at + 1).copied().unwrap_or(samples[at]);
+                let sample = samples[at] + (next - samples[at]) * (pos - at as f64) as f32;
+                (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16
+            })
+            .collect()
+    }
+
+    fn write_wav(path: &Path, samples: &[i16]) -> Result<(), String> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let failed = |e: hound::Error| format!("Failed to write {}: {e}", path.display());
+        let mut writer = hound::WavWriter::create(path, spec).map_err(failed)?;
+        for &sample in samples {
+            writer.write_sample(sample).map_err(failed)?;
+        }
+        writer.finalize().map_err(failed)
+    }
+
+    /// A WAV file of its own per recording, so instances never share one
+    fn wav_path() -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!("locallm-dictation-{}-{n}.wav", std::process::id()))
+    }
+}
+
+/// Transcribe a recording with whisper.cpp and delete it. The spoken
+/// language is detected.
+pub async fn transcribe(binary: String, model: String, path: PathBuf) -> Result<String, String> {
+    let output = tokio::process::Command::new(&binary)
+        .args(["-m", &model, "-l", "auto", "-nt", "-np", "-f"])
+        .arg(&path)
+        .stdin(Stdio::null())
+        .output()
+        .await;
+    let _ = tokio::fs::remove_file(&path).await;
+
+    let output = output.map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("{binary} not found, install whisper.cpp or set whisper_binary"),
+        _ => format!("Failed to run {binary}: {e}"),
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or_default();
+        return Err(format!("{binary} failed ({}): {reason}", output.status));
+    }
+
+    // One line per segment
+    let text = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(text)
+}
@@ -0,0 +1,66 @@
+//! Rough guess at whether a model will run on the GPU, from its size on disk
+//! and the VRAM the GPU stats report as free.
+
+use crate::gpu_stats::GpuStats;
+use crate::ollama::RunningModel;
+use std::fmt;
+
+/// Weights plus KV cache and runtime buffers, relative to the file size
+const OVERHEAD: f64 = 1.2;
+/// With less than this share on the GPU a model is effectively CPU-bound
+const MIN_OFFLOAD: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fit {
+    Fits,
+    Partial,
+    CpuOnly,
+}
+
+impl fmt::Display for Fit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Fit::Fits => "fits in VRAM",
+            Fit::Partial => "partial offload likely",
+            Fit::CpuOnly => "CPU only",
+        })
+    }
+}
+
+/// Bytes of VRAM a model could get: what's free plus what loaded models hold,
+/// since Ollama evicts idle models to make room. `None` without GPU stats.
+pub fn available(gpus: &[GpuStats], running: &[RunningModel]) -> Option<u64> {
+    const MB: u64 = 1024 * 1024;
+    let total: u64 = gpus.iter().map(|gpu| gpu.vram_total_mb).sum();
+    if total == 0 {
+        return None;
+    }
+    let free: u64 = gpus
+        .iter()
+        .map(|gpu| gpu.vram_total_mb.saturating_sub(gpu.vram_used_mb))
+        .sum();
+    let loaded: u64 = running.iter().map(|model| model.size_vram).sum();
+    Some((free * MB + loaded).min(total * MB))
+}
+
+pub fn estimate(size: u64, available: u64) -> Fit {
+    let needed = size as f64 * OVERHEAD;
+    if needed <= available as f64 {
+        Fit::Fits
+    } else if available as f64 >= needed * MIN_OFFLOAD {
+        Fit::Partial
+    } else {
+        Fit::CpuOnly
+    }
+}
+
+/// What Ollama actually did with a loaded model
+pub fn loaded(model: &RunningModel) -> Fit {
+    if model.size_vram == 0 {
+        Fit::CpuOnly
+    } else if model.size_vram >= model.size {
+        Fit::Fits
+    } else {
+        Fit::Partial
+    }
+}
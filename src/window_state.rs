@@ -0,0 +1,60 @@
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Main window geometry, remembered between runs in `window.json` in the data dir
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    /// Size before maximizing, so un-maximizing goes back to it
+    pub width: f32,
+    pub height: f32,
+    /// Unknown on Wayland, where clients can't see or pick their position
+    #[serde(default)]
+    pub x: Option<f32>,
+    #[serde(default)]
+    pub y: Option<f32>,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        Self {
+            width: 800.0,
+            height: 700.0,
+            x: None,
+            y: None,
+            maximized: false,
+        }
+    }
+}
+
+impl WindowState {
+    fn path() -> Option<PathBuf> {
+        Config::data_dir().ok().map(|dir| dir.join("window.json"))
+    }
+
+    /// Last saved geometry, or the defaults if there is none or it's unreadable
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .inspect_err(|e| tracing::warn!("Ignoring {}: {e}", path.display()))
+                .unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Written synchronously: this runs as the window closes, right before the app may exit
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("No data directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+}